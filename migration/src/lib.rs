@@ -22,6 +22,24 @@ mod m20250702_115754_create_enum_soil_type_sensor_profile;
 mod m20260226_000000_add_flux_redox_websites;
 mod m20260227_000000_precompute_sensor_averages;
 mod m20260302_000000_add_6h_continuous_aggregate;
+mod m20261018_000001_add_sensor_type_registry;
+mod m20261018_000002_add_groundwater_data;
+mod m20261018_000003_add_weather_data;
+mod m20261018_000004_add_flux_fit_window;
+mod m20261018_000005_add_flux_model_fits;
+mod m20261018_000006_add_flux_qc;
+mod m20261018_000007_add_batch_idempotency;
+mod m20261018_000008_add_flux_collar_offset;
+mod m20261018_000009_add_redox_channels;
+mod m20261018_000010_add_redox_reference_electrode;
+mod m20261018_000011_redox_hypertable;
+mod m20261018_000012_add_channel_integration_method;
+mod m20261018_000013_add_channel_baseline_method;
+mod m20261018_000014_add_experiment_sample_names;
+mod m20261018_000015_add_channel_calibration;
+mod m20261018_000016_add_plotsample_value_source;
+mod m20261018_000017_channel_arrays_to_float8;
+mod m20261018_000018_add_gnss_fix_quality;
 
 pub struct Migrator;
 
@@ -51,6 +69,24 @@ impl MigratorTrait for Migrator {
             Box::new(m20260226_000000_add_flux_redox_websites::Migration),
             Box::new(m20260227_000000_precompute_sensor_averages::Migration),
             Box::new(m20260302_000000_add_6h_continuous_aggregate::Migration),
+            Box::new(m20261018_000001_add_sensor_type_registry::Migration),
+            Box::new(m20261018_000002_add_groundwater_data::Migration),
+            Box::new(m20261018_000003_add_weather_data::Migration),
+            Box::new(m20261018_000004_add_flux_fit_window::Migration),
+            Box::new(m20261018_000005_add_flux_model_fits::Migration),
+            Box::new(m20261018_000006_add_flux_qc::Migration),
+            Box::new(m20261018_000007_add_batch_idempotency::Migration),
+            Box::new(m20261018_000008_add_flux_collar_offset::Migration),
+            Box::new(m20261018_000009_add_redox_channels::Migration),
+            Box::new(m20261018_000010_add_redox_reference_electrode::Migration),
+            Box::new(m20261018_000011_redox_hypertable::Migration),
+            Box::new(m20261018_000012_add_channel_integration_method::Migration),
+            Box::new(m20261018_000013_add_channel_baseline_method::Migration),
+            Box::new(m20261018_000014_add_experiment_sample_names::Migration),
+            Box::new(m20261018_000015_add_channel_calibration::Migration),
+            Box::new(m20261018_000016_add_plotsample_value_source::Migration),
+            Box::new(m20261018_000017_channel_arrays_to_float8::Migration),
+            Box::new(m20261018_000018_add_gnss_fix_quality::Migration),
         ]
     }
 }
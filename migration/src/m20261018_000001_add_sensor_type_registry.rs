@@ -0,0 +1,148 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // 1. Sensor type registry: logger models, their file parser and channels
+        db.execute_unprepared(
+            r#"
+            DO $$ BEGIN
+                CREATE TYPE sensor_parser_enum AS ENUM ('tms', 'hobo_csv', 'meter_zl6', 'generic_csv');
+            EXCEPTION WHEN duplicate_object THEN null;
+            END $$;
+
+            CREATE TABLE IF NOT EXISTS sensortype (
+                id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+                name VARCHAR NOT NULL UNIQUE,
+                manufacturer VARCHAR,
+                description TEXT,
+                parser sensor_parser_enum NOT NULL,
+                delimiter VARCHAR,
+                header_rows INTEGER,
+                timestamp_column INTEGER,
+                timestamp_format VARCHAR,
+                utc_offset_minutes INTEGER NOT NULL DEFAULT 0,
+                last_updated TIMESTAMPTZ NOT NULL DEFAULT now()
+            );
+
+            CREATE TABLE IF NOT EXISTS sensortype_channel (
+                id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+                sensortype_id UUID NOT NULL REFERENCES sensortype(id) ON DELETE CASCADE,
+                name VARCHAR NOT NULL,
+                units VARCHAR NOT NULL,
+                column_index INTEGER NOT NULL,
+                description TEXT,
+                UNIQUE(sensortype_id, name)
+            );
+
+            DO $$ BEGIN ALTER TABLE sensor ADD COLUMN sensortype_id UUID REFERENCES sensortype(id);
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+
+            -- The TMS layout keeps using the dedicated sensordata table
+            INSERT INTO sensortype (name, manufacturer, description, parser)
+            VALUES ('TMS-4', 'TOMST', 'TOMST TMS-4 logger (three temperatures and moisture count)', 'tms')
+            ON CONFLICT (name) DO NOTHING;
+            "#,
+        )
+        .await?;
+
+        // 2. Generic measurement hypertable (one row per sensor, channel and timestamp)
+        db.execute_unprepared(
+            r#"
+            CREATE TABLE IF NOT EXISTS sensor_measurement (
+                sensor_id UUID NOT NULL REFERENCES sensor(id),
+                channel_id UUID NOT NULL REFERENCES sensortype_channel(id) ON DELETE CASCADE,
+                time_utc TIMESTAMPTZ NOT NULL,
+                value DOUBLE PRECISION NOT NULL,
+                PRIMARY KEY (sensor_id, channel_id, time_utc)
+            );
+
+            SELECT create_hypertable('sensor_measurement', 'time_utc',
+                chunk_time_interval => INTERVAL '7 days',
+                migrate_data => true);
+
+            CREATE INDEX IF NOT EXISTS idx_sensor_measurement_sensor_channel_time
+            ON sensor_measurement (sensor_id, channel_id, time_utc DESC);
+
+            -- Hourly continuous aggregate
+            CREATE MATERIALIZED VIEW sensor_measurement_hourly
+            WITH (timescaledb.continuous) AS
+            SELECT
+                time_bucket('1 hour', time_utc) AS bucket,
+                sensor_id,
+                channel_id,
+                AVG(value) AS avg_value,
+                MIN(value) AS min_value,
+                MAX(value) AS max_value,
+                COUNT(*) AS sample_count
+            FROM sensor_measurement
+            GROUP BY time_bucket('1 hour', time_utc), sensor_id, channel_id
+            WITH NO DATA;
+
+            SELECT add_continuous_aggregate_policy('sensor_measurement_hourly',
+                start_offset => INTERVAL '3 hours',
+                end_offset => INTERVAL '1 hour',
+                schedule_interval => INTERVAL '1 hour');
+
+            CREATE INDEX ON sensor_measurement_hourly (sensor_id, channel_id, bucket);
+
+            -- Hierarchical 6-hour aggregate on top of the hourly one (sample_count weighted)
+            CREATE MATERIALIZED VIEW sensor_measurement_6h
+            WITH (timescaledb.continuous) AS
+            SELECT
+                time_bucket('6 hours', bucket) AS bucket,
+                sensor_id,
+                channel_id,
+                SUM(avg_value * sample_count) / NULLIF(SUM(sample_count), 0) AS avg_value,
+                MIN(min_value) AS min_value,
+                MAX(max_value) AS max_value,
+                SUM(sample_count) AS sample_count
+            FROM sensor_measurement_hourly
+            GROUP BY time_bucket('6 hours', bucket), sensor_id, channel_id
+            WITH NO DATA;
+
+            SELECT add_continuous_aggregate_policy('sensor_measurement_6h',
+                start_offset => INTERVAL '18 hours',
+                end_offset   => INTERVAL '6 hours',
+                schedule_interval => INTERVAL '6 hours');
+
+            CREATE INDEX ON sensor_measurement_6h (sensor_id, channel_id, bucket);
+
+            ALTER TABLE sensor_measurement SET (
+                timescaledb.compress,
+                timescaledb.compress_segmentby = 'sensor_id, channel_id'
+            );
+            SELECT add_compression_policy('sensor_measurement', INTERVAL '30 days');
+            "#,
+        )
+        .await?;
+
+        // NOTE: The aggregates are refreshed over the full range on startup (see main.rs).
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            r#"
+            DROP MATERIALIZED VIEW IF EXISTS sensor_measurement_6h CASCADE;
+            DROP MATERIALIZED VIEW IF EXISTS sensor_measurement_hourly CASCADE;
+            DROP TABLE IF EXISTS sensor_measurement;
+            ALTER TABLE sensor DROP COLUMN IF EXISTS sensortype_id;
+            DROP TABLE IF EXISTS sensortype_channel;
+            DROP TABLE IF EXISTS sensortype;
+            DROP TYPE IF EXISTS sensor_parser_enum;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
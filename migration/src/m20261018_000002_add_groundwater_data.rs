@@ -0,0 +1,74 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // 1. Profile types for piezometers and the barologgers used to compensate them
+        db.execute_unprepared(
+            r#"
+            ALTER TYPE profile_type_enum ADD VALUE IF NOT EXISTS 'groundwater';
+            ALTER TYPE profile_type_enum ADD VALUE IF NOT EXISTS 'barologger';
+            "#,
+        )
+        .await?;
+
+        // 2. Groundwater-specific profile fields
+        db.execute_unprepared(
+            r#"
+            -- Depth of the logger's pressure sensor below the ground surface (coord_z)
+            DO $$ BEGIN ALTER TABLE sensorprofile ADD COLUMN logger_depth_m DOUBLE PRECISION;
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+            -- Reference barologger; when NULL the nearest barologger in the area is used
+            DO $$ BEGIN ALTER TABLE sensorprofile ADD COLUMN baro_profile_id UUID REFERENCES sensorprofile(id) ON DELETE SET NULL;
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+            "#,
+        )
+        .await?;
+
+        // 3. Logger readings. Barologger profiles only fill pressure/temperature,
+        //    piezometer readings additionally get the compensated levels.
+        db.execute_unprepared(
+            r#"
+            CREATE TABLE IF NOT EXISTS groundwater_data (
+                id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+                sensorprofile_id UUID NOT NULL REFERENCES sensorprofile(id) ON DELETE CASCADE,
+                measured_on TIMESTAMPTZ NOT NULL,
+                pressure_kpa DOUBLE PRECISION NOT NULL,
+                temp_c DOUBLE PRECISION,
+                baro_pressure_kpa DOUBLE PRECISION,
+                water_column_m DOUBLE PRECISION,
+                depth_below_surface_m DOUBLE PRECISION,
+                water_level_masl DOUBLE PRECISION,
+                UNIQUE(sensorprofile_id, measured_on)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_groundwater_data_profile_time
+            ON groundwater_data (sensorprofile_id, measured_on);
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Enum values cannot be dropped from profile_type_enum; they are left in place.
+        db.execute_unprepared(
+            r#"
+            DROP TABLE IF EXISTS groundwater_data;
+            ALTER TABLE sensorprofile DROP COLUMN IF EXISTS baro_profile_id;
+            ALTER TABLE sensorprofile DROP COLUMN IF EXISTS logger_depth_m;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // 1. Profile type for weather stations
+        db.execute_unprepared(
+            r#"
+            ALTER TYPE profile_type_enum ADD VALUE IF NOT EXISTS 'weather';
+            "#,
+        )
+        .await?;
+
+        // 2. Station time series as a hypertable
+        db.execute_unprepared(
+            r#"
+            CREATE TABLE IF NOT EXISTS weather_data (
+                sensorprofile_id UUID NOT NULL REFERENCES sensorprofile(id) ON DELETE CASCADE,
+                time_utc TIMESTAMPTZ NOT NULL,
+                air_temperature_c DOUBLE PRECISION,
+                precipitation_mm DOUBLE PRECISION,
+                snow_depth_cm DOUBLE PRECISION,
+                relative_humidity_pct DOUBLE PRECISION,
+                PRIMARY KEY (sensorprofile_id, time_utc)
+            );
+
+            SELECT create_hypertable('weather_data', 'time_utc',
+                chunk_time_interval => INTERVAL '30 days',
+                migrate_data => true);
+
+            CREATE INDEX IF NOT EXISTS idx_weather_data_profile_time
+            ON weather_data (sensorprofile_id, time_utc DESC);
+            "#,
+        )
+        .await?;
+
+        // 3. Continuous aggregates matching the sensordata resolutions. Precipitation
+        //    is summed, the other variables are averaged.
+        db.execute_unprepared(
+            r#"
+            CREATE MATERIALIZED VIEW weather_data_hourly
+            WITH (timescaledb.continuous) AS
+            SELECT
+                time_bucket('1 hour', time_utc) AS bucket,
+                sensorprofile_id,
+                AVG(air_temperature_c) AS avg_air_temperature_c,
+                MIN(air_temperature_c) AS min_air_temperature_c,
+                MAX(air_temperature_c) AS max_air_temperature_c,
+                SUM(precipitation_mm) AS sum_precipitation_mm,
+                AVG(snow_depth_cm) AS avg_snow_depth_cm,
+                AVG(relative_humidity_pct) AS avg_relative_humidity_pct,
+                COUNT(*) AS sample_count
+            FROM weather_data
+            GROUP BY time_bucket('1 hour', time_utc), sensorprofile_id
+            WITH NO DATA;
+
+            SELECT add_continuous_aggregate_policy('weather_data_hourly',
+                start_offset => INTERVAL '3 hours',
+                end_offset => INTERVAL '1 hour',
+                schedule_interval => INTERVAL '1 hour');
+
+            CREATE INDEX ON weather_data_hourly (sensorprofile_id, bucket);
+
+            CREATE MATERIALIZED VIEW weather_data_6h
+            WITH (timescaledb.continuous) AS
+            SELECT
+                time_bucket('6 hours', bucket) AS bucket,
+                sensorprofile_id,
+                SUM(avg_air_temperature_c * sample_count) / NULLIF(SUM(sample_count), 0) AS avg_air_temperature_c,
+                MIN(min_air_temperature_c) AS min_air_temperature_c,
+                MAX(max_air_temperature_c) AS max_air_temperature_c,
+                SUM(sum_precipitation_mm) AS sum_precipitation_mm,
+                SUM(avg_snow_depth_cm * sample_count) / NULLIF(SUM(sample_count), 0) AS avg_snow_depth_cm,
+                SUM(avg_relative_humidity_pct * sample_count) / NULLIF(SUM(sample_count), 0) AS avg_relative_humidity_pct,
+                SUM(sample_count) AS sample_count
+            FROM weather_data_hourly
+            GROUP BY time_bucket('6 hours', bucket), sensorprofile_id
+            WITH NO DATA;
+
+            SELECT add_continuous_aggregate_policy('weather_data_6h',
+                start_offset => INTERVAL '18 hours',
+                end_offset   => INTERVAL '6 hours',
+                schedule_interval => INTERVAL '6 hours');
+
+            CREATE INDEX ON weather_data_6h (sensorprofile_id, bucket);
+            "#,
+        )
+        .await?;
+
+        // NOTE: The aggregates are refreshed over the full range on startup (see main.rs).
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Enum values cannot be dropped from profile_type_enum; 'weather' is left in place.
+        db.execute_unprepared(
+            r#"
+            DROP MATERIALIZED VIEW IF EXISTS weather_data_6h CASCADE;
+            DROP MATERIALIZED VIEW IF EXISTS weather_data_hourly CASCADE;
+            DROP TABLE IF EXISTS weather_data;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Seconds since the start of the closure between which the stored raw readings
+        // are fitted; NULL uses the first/last reading (no dead band).
+        db.execute_unprepared(
+            r#"
+            DO $$ BEGIN ALTER TABLE flux_data ADD COLUMN fit_start_s DOUBLE PRECISION;
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+            DO $$ BEGIN ALTER TABLE flux_data ADD COLUMN fit_end_s DOUBLE PRECISION;
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+            "#,
+        )
+        .await?;
+
+        // Whether the analyser logged CH4, so recomputes keep fitting it even when
+        // the stored CH4 flux is empty. Existing records come from CH4 analysers.
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE flux_data ADD COLUMN IF NOT EXISTS has_ch4 BOOLEAN NOT NULL DEFAULT TRUE;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE flux_data DROP COLUMN IF EXISTS has_ch4;
+            ALTER TABLE flux_data DROP COLUMN IF EXISTS fit_end_s;
+            ALTER TABLE flux_data DROP COLUMN IF EXISTS fit_start_s;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
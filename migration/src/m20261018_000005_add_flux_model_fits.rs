@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // 1. Models a chamber flux can be fitted with
+        db.execute_unprepared(
+            r#"
+            DO $$ BEGIN
+                CREATE TYPE flux_model_enum AS ENUM ('linear', 'exponential', 'hmr');
+            EXCEPTION WHEN duplicate_object THEN null;
+            END $$;
+            "#,
+        )
+        .await?;
+
+        // 2. Selected model per gas (flux_* and r2_* hold its values) and the flux,
+        //    R² and AIC of every fitted model. Existing records were fitted linearly.
+        db.execute_unprepared(
+            r#"
+            DO $$ BEGIN ALTER TABLE flux_data ADD COLUMN flux_model_co2 flux_model_enum;
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+            DO $$ BEGIN ALTER TABLE flux_data ADD COLUMN flux_model_ch4 flux_model_enum;
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+            DO $$ BEGIN ALTER TABLE flux_data ADD COLUMN flux_model_h2o flux_model_enum;
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+            DO $$ BEGIN ALTER TABLE flux_data ADD COLUMN flux_fits JSONB;
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+
+            UPDATE flux_data SET flux_model_co2 = 'linear' WHERE flux_co2_umol_m2_s IS NOT NULL AND flux_model_co2 IS NULL;
+            UPDATE flux_data SET flux_model_ch4 = 'linear' WHERE flux_ch4_nmol_m2_s IS NOT NULL AND flux_model_ch4 IS NULL;
+            UPDATE flux_data SET flux_model_h2o = 'linear' WHERE flux_h2o_umol_m2_s IS NOT NULL AND flux_model_h2o IS NULL;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE flux_data DROP COLUMN IF EXISTS flux_fits;
+            ALTER TABLE flux_data DROP COLUMN IF EXISTS flux_model_h2o;
+            ALTER TABLE flux_data DROP COLUMN IF EXISTS flux_model_ch4;
+            ALTER TABLE flux_data DROP COLUMN IF EXISTS flux_model_co2;
+            DROP TYPE IF EXISTS flux_model_enum;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // 1. QC thresholds per gas; a NULL threshold disables that check
+        db.execute_unprepared(
+            r#"
+            DO $$ BEGIN
+                CREATE TYPE flux_gas_enum AS ENUM ('co2', 'ch4', 'h2o');
+            EXCEPTION WHEN duplicate_object THEN null;
+            END $$;
+
+            CREATE TABLE IF NOT EXISTS flux_qc_threshold (
+                id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+                gas flux_gas_enum NOT NULL UNIQUE,
+                min_r2 DOUBLE PRECISION,
+                min_readings INTEGER,
+                max_pressure_drift_kpa DOUBLE PRECISION,
+                max_temperature_drift_c DOUBLE PRECISION,
+                last_updated TIMESTAMPTZ NOT NULL DEFAULT now()
+            );
+
+            INSERT INTO flux_qc_threshold (gas, min_r2, min_readings, max_pressure_drift_kpa, max_temperature_drift_c)
+            VALUES
+                ('co2', 0.9, 10, 0.5, 2.0),
+                ('ch4', 0.7, 10, 0.5, 2.0),
+                ('h2o', 0.7, 10, 0.5, 2.0)
+            ON CONFLICT (gas) DO NOTHING;
+            "#,
+        )
+        .await?;
+
+        // 2. QC flags per gas (NULL until evaluated), the failed checks, and whether
+        //    an admin has set the flags by hand
+        db.execute_unprepared(
+            r#"
+            DO $$ BEGIN ALTER TABLE flux_data ADD COLUMN qc_flagged_co2 BOOLEAN;
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+            DO $$ BEGIN ALTER TABLE flux_data ADD COLUMN qc_flagged_ch4 BOOLEAN;
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+            DO $$ BEGIN ALTER TABLE flux_data ADD COLUMN qc_flagged_h2o BOOLEAN;
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+            DO $$ BEGIN ALTER TABLE flux_data ADD COLUMN qc_reasons JSONB;
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+            DO $$ BEGIN ALTER TABLE flux_data ADD COLUMN qc_override BOOLEAN NOT NULL DEFAULT false;
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE flux_data DROP COLUMN IF EXISTS qc_override;
+            ALTER TABLE flux_data DROP COLUMN IF EXISTS qc_reasons;
+            ALTER TABLE flux_data DROP COLUMN IF EXISTS qc_flagged_h2o;
+            ALTER TABLE flux_data DROP COLUMN IF EXISTS qc_flagged_ch4;
+            ALTER TABLE flux_data DROP COLUMN IF EXISTS qc_flagged_co2;
+            DROP TABLE IF EXISTS flux_qc_threshold;
+            DROP TYPE IF EXISTS flux_gas_enum;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
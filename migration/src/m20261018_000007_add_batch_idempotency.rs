@@ -0,0 +1,70 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // 1. Natural keys for upserts. Tables created before the UNIQUE constraints
+        //    were added may hold duplicates; the most recently written row is kept.
+        //    The index names match the ones Postgres gives the table constraints, so
+        //    nothing is created where the constraint already exists.
+        db.execute_unprepared(
+            r#"
+            DELETE FROM flux_data a USING flux_data b
+            WHERE a.sensorprofile_id = b.sensorprofile_id
+              AND a.measured_on = b.measured_on
+              AND a.replicate = b.replicate
+              AND a.ctid < b.ctid;
+            CREATE UNIQUE INDEX IF NOT EXISTS flux_data_sensorprofile_id_measured_on_replicate_key
+            ON flux_data (sensorprofile_id, measured_on, replicate);
+
+            DELETE FROM redox_data a USING redox_data b
+            WHERE a.sensorprofile_id = b.sensorprofile_id
+              AND a.measured_on = b.measured_on
+              AND a.ctid < b.ctid;
+            CREATE UNIQUE INDEX IF NOT EXISTS redox_data_sensorprofile_id_measured_on_key
+            ON redox_data (sensorprofile_id, measured_on);
+            "#,
+        )
+        .await?;
+
+        // 2. Responses of batch requests sent with an Idempotency-Key header, with
+        //    the hash of the request the key was first used with so that reusing
+        //    the key for a different request is rejected
+        db.execute_unprepared(
+            r#"
+            CREATE TABLE IF NOT EXISTS idempotency_key (
+                scope VARCHAR NOT NULL,
+                key VARCHAR NOT NULL,
+                request_hash VARCHAR NOT NULL,
+                response JSONB NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                PRIMARY KEY (scope, key)
+            );
+            CREATE INDEX IF NOT EXISTS idx_idempotency_key_created_at
+            ON idempotency_key (created_at);
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // The natural-key constraints predate this migration and are kept
+        db.execute_unprepared(
+            r#"
+            DROP TABLE IF EXISTS idempotency_key;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Collar height above the soil or measured headspace volume of each
+        // measurement, and the volume its fluxes were computed with
+        db.execute_unprepared(
+            r#"
+            DO $$ BEGIN ALTER TABLE flux_data ADD COLUMN collar_offset_cm DOUBLE PRECISION;
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+            DO $$ BEGIN ALTER TABLE flux_data ADD COLUMN volume_ml DOUBLE PRECISION;
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+            DO $$ BEGIN ALTER TABLE flux_data ADD COLUMN effective_volume_ml DOUBLE PRECISION;
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE flux_data DROP COLUMN IF EXISTS effective_volume_ml;
+            ALTER TABLE flux_data DROP COLUMN IF EXISTS volume_ml;
+            ALTER TABLE flux_data DROP COLUMN IF EXISTS collar_offset_cm;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
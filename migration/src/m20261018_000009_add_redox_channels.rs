@@ -0,0 +1,119 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // 1. Electrode channels per redox profile and potentials in long format
+        db.execute_unprepared(
+            r#"
+            CREATE TABLE IF NOT EXISTS redox_channel (
+                id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+                sensorprofile_id UUID NOT NULL REFERENCES sensorprofile(id) ON DELETE CASCADE,
+                name VARCHAR NOT NULL,
+                electrode_id VARCHAR,
+                depth_cm INTEGER NOT NULL,
+                installed_on TIMESTAMPTZ,
+                last_updated TIMESTAMPTZ NOT NULL DEFAULT now(),
+                UNIQUE (sensorprofile_id, name)
+            );
+
+            CREATE TABLE IF NOT EXISTS redox_measurement (
+                redox_data_id UUID NOT NULL REFERENCES redox_data(id) ON DELETE CASCADE,
+                channel_id UUID NOT NULL REFERENCES redox_channel(id) ON DELETE CASCADE,
+                potential_mv DOUBLE PRECISION NOT NULL,
+                PRIMARY KEY (redox_data_id, channel_id)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_redox_measurement_channel
+            ON redox_measurement (channel_id);
+            "#,
+        )
+        .await?;
+
+        // 2. Move the fixed 5/15/25/35 cm columns into channels ch1..ch4
+        db.execute_unprepared(
+            r#"
+            DO $$ BEGIN
+            IF EXISTS (
+                SELECT 1 FROM information_schema.columns
+                WHERE table_name = 'redox_data' AND column_name = 'ch1_5cm_mv'
+            ) THEN
+                INSERT INTO redox_channel (sensorprofile_id, name, depth_cm)
+                SELECT p.id, c.name, c.depth_cm
+                FROM (
+                    SELECT id FROM sensorprofile WHERE profile_type = 'redox'
+                    UNION
+                    SELECT DISTINCT sensorprofile_id FROM redox_data
+                ) AS p
+                CROSS JOIN (VALUES ('ch1', 5), ('ch2', 15), ('ch3', 25), ('ch4', 35))
+                    AS c(name, depth_cm)
+                ON CONFLICT (sensorprofile_id, name) DO NOTHING;
+
+                INSERT INTO redox_measurement (redox_data_id, channel_id, potential_mv)
+                SELECT d.id, c.id, v.potential_mv
+                FROM redox_data AS d
+                CROSS JOIN LATERAL (VALUES
+                    ('ch1', d.ch1_5cm_mv),
+                    ('ch2', d.ch2_15cm_mv),
+                    ('ch3', d.ch3_25cm_mv),
+                    ('ch4', d.ch4_35cm_mv)
+                ) AS v(name, potential_mv)
+                JOIN redox_channel AS c
+                  ON c.sensorprofile_id = d.sensorprofile_id AND c.name = v.name
+                WHERE v.potential_mv IS NOT NULL
+                ON CONFLICT DO NOTHING;
+
+                ALTER TABLE redox_data
+                    DROP COLUMN ch1_5cm_mv,
+                    DROP COLUMN ch2_15cm_mv,
+                    DROP COLUMN ch3_25cm_mv,
+                    DROP COLUMN ch4_35cm_mv;
+            END IF;
+            END $$;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Channels other than ch1..ch4 cannot be represented and are dropped
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE redox_data
+                ADD COLUMN IF NOT EXISTS ch1_5cm_mv DOUBLE PRECISION,
+                ADD COLUMN IF NOT EXISTS ch2_15cm_mv DOUBLE PRECISION,
+                ADD COLUMN IF NOT EXISTS ch3_25cm_mv DOUBLE PRECISION,
+                ADD COLUMN IF NOT EXISTS ch4_35cm_mv DOUBLE PRECISION;
+
+            UPDATE redox_data AS d SET
+                ch1_5cm_mv = (SELECT m.potential_mv FROM redox_measurement m
+                    JOIN redox_channel c ON c.id = m.channel_id
+                    WHERE m.redox_data_id = d.id AND c.name = 'ch1'),
+                ch2_15cm_mv = (SELECT m.potential_mv FROM redox_measurement m
+                    JOIN redox_channel c ON c.id = m.channel_id
+                    WHERE m.redox_data_id = d.id AND c.name = 'ch2'),
+                ch3_25cm_mv = (SELECT m.potential_mv FROM redox_measurement m
+                    JOIN redox_channel c ON c.id = m.channel_id
+                    WHERE m.redox_data_id = d.id AND c.name = 'ch3'),
+                ch4_35cm_mv = (SELECT m.potential_mv FROM redox_measurement m
+                    JOIN redox_channel c ON c.id = m.channel_id
+                    WHERE m.redox_data_id = d.id AND c.name = 'ch4');
+
+            DROP TABLE IF EXISTS redox_measurement;
+            DROP TABLE IF EXISTS redox_channel;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
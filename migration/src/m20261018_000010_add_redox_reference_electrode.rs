@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Reference electrode of a redox profile, needed to convert potentials to Eh
+        db.execute_unprepared(
+            r#"
+            DO $$ BEGIN
+                CREATE TYPE reference_electrode_enum AS ENUM (
+                    'ag_agcl_sat_kcl', 'ag_agcl_3m_kcl', 'calomel_sat_kcl', 'she'
+                );
+            EXCEPTION WHEN duplicate_object THEN NULL; END $$;
+
+            DO $$ BEGIN ALTER TABLE sensorprofile ADD COLUMN reference_electrode reference_electrode_enum;
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE sensorprofile DROP COLUMN IF EXISTS reference_electrode;
+            DROP TYPE IF EXISTS reference_electrode_enum;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // 1. Logger column holding each channel in imported files (defaults to the name)
+        db.execute_unprepared(
+            r#"
+            DO $$ BEGIN ALTER TABLE redox_channel ADD COLUMN column_name VARCHAR;
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+            "#,
+        )
+        .await?;
+
+        // 2. Hypertables need the time column in every unique index and cannot be
+        //    referenced by foreign keys. Potentials carry the record time and are
+        //    keyed by channel and time; deleting or moving a record reaches them
+        //    through a trigger instead of the foreign key.
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE redox_measurement
+                DROP CONSTRAINT IF EXISTS redox_measurement_redox_data_id_fkey;
+            ALTER TABLE redox_data DROP CONSTRAINT IF EXISTS redox_data_pkey;
+            ALTER TABLE redox_data ADD PRIMARY KEY (id, measured_on);
+
+            ALTER TABLE redox_measurement ADD COLUMN IF NOT EXISTS measured_on TIMESTAMPTZ;
+            UPDATE redox_measurement AS m SET measured_on = d.measured_on
+            FROM redox_data AS d WHERE d.id = m.redox_data_id;
+            DELETE FROM redox_measurement WHERE measured_on IS NULL;
+            ALTER TABLE redox_measurement ALTER COLUMN measured_on SET NOT NULL;
+            ALTER TABLE redox_measurement DROP CONSTRAINT IF EXISTS redox_measurement_pkey;
+            ALTER TABLE redox_measurement ADD PRIMARY KEY (channel_id, measured_on);
+            DROP INDEX IF EXISTS idx_redox_measurement_channel;
+            CREATE INDEX IF NOT EXISTS idx_redox_measurement_record
+            ON redox_measurement (redox_data_id);
+
+            SELECT create_hypertable('redox_data', 'measured_on',
+                chunk_time_interval => INTERVAL '30 days',
+                migrate_data => true);
+            SELECT create_hypertable('redox_measurement', 'measured_on',
+                chunk_time_interval => INTERVAL '30 days',
+                migrate_data => true);
+
+            CREATE OR REPLACE FUNCTION redox_data_propagate() RETURNS trigger AS $$
+            BEGIN
+                IF TG_OP = 'DELETE' THEN
+                    DELETE FROM redox_measurement WHERE redox_data_id = OLD.id;
+                    RETURN OLD;
+                END IF;
+                UPDATE redox_measurement SET measured_on = NEW.measured_on
+                WHERE redox_data_id = NEW.id;
+                RETURN NEW;
+            END $$ LANGUAGE plpgsql;
+
+            DROP TRIGGER IF EXISTS redox_data_propagate ON redox_data;
+            CREATE TRIGGER redox_data_propagate
+            AFTER DELETE OR UPDATE OF measured_on ON redox_data
+            FOR EACH ROW EXECUTE FUNCTION redox_data_propagate();
+            "#,
+        )
+        .await?;
+
+        // 3. Records are still addressed by id alone, but the hypertable cannot
+        //    have a unique index on it, so a trigger keeps it unique instead.
+        db.execute_unprepared(
+            r#"
+            CREATE OR REPLACE FUNCTION redox_data_unique_id() RETURNS trigger AS $$
+            BEGIN
+                IF TG_OP = 'UPDATE' AND NEW.id = OLD.id THEN
+                    RETURN NEW;
+                END IF;
+                IF EXISTS (SELECT 1 FROM redox_data WHERE id = NEW.id) THEN
+                    RAISE unique_violation USING
+                        MESSAGE = format('redox_data id %s already exists', NEW.id);
+                END IF;
+                RETURN NEW;
+            END $$ LANGUAGE plpgsql;
+
+            DROP TRIGGER IF EXISTS redox_data_unique_id ON redox_data;
+            CREATE TRIGGER redox_data_unique_id
+            BEFORE INSERT OR UPDATE OF id ON redox_data
+            FOR EACH ROW EXECUTE FUNCTION redox_data_unique_id();
+            "#,
+        )
+        .await?;
+
+        // 4. Hourly and daily aggregates of the potentials per channel and of the
+        //    logger temperature per profile
+        db.execute_unprepared(
+            r#"
+            CREATE MATERIALIZED VIEW redox_measurement_hourly
+            WITH (timescaledb.continuous) AS
+            SELECT
+                time_bucket('1 hour', measured_on) AS bucket,
+                channel_id,
+                AVG(potential_mv) AS avg_potential_mv,
+                MIN(potential_mv) AS min_potential_mv,
+                MAX(potential_mv) AS max_potential_mv,
+                COUNT(*) AS sample_count
+            FROM redox_measurement
+            GROUP BY time_bucket('1 hour', measured_on), channel_id
+            WITH NO DATA;
+
+            SELECT add_continuous_aggregate_policy('redox_measurement_hourly',
+                start_offset => INTERVAL '3 hours',
+                end_offset => INTERVAL '1 hour',
+                schedule_interval => INTERVAL '1 hour');
+
+            CREATE INDEX ON redox_measurement_hourly (channel_id, bucket);
+
+            CREATE MATERIALIZED VIEW redox_measurement_daily
+            WITH (timescaledb.continuous) AS
+            SELECT
+                time_bucket('1 day', bucket) AS bucket,
+                channel_id,
+                SUM(avg_potential_mv * sample_count) / NULLIF(SUM(sample_count), 0) AS avg_potential_mv,
+                MIN(min_potential_mv) AS min_potential_mv,
+                MAX(max_potential_mv) AS max_potential_mv,
+                SUM(sample_count) AS sample_count
+            FROM redox_measurement_hourly
+            GROUP BY time_bucket('1 day', bucket), channel_id
+            WITH NO DATA;
+
+            SELECT add_continuous_aggregate_policy('redox_measurement_daily',
+                start_offset => INTERVAL '3 days',
+                end_offset => INTERVAL '1 day',
+                schedule_interval => INTERVAL '1 day');
+
+            CREATE INDEX ON redox_measurement_daily (channel_id, bucket);
+
+            CREATE MATERIALIZED VIEW redox_data_hourly
+            WITH (timescaledb.continuous) AS
+            SELECT
+                time_bucket('1 hour', measured_on) AS bucket,
+                sensorprofile_id,
+                AVG(temp_c) AS avg_temp_c,
+                MIN(temp_c) AS min_temp_c,
+                MAX(temp_c) AS max_temp_c,
+                COUNT(temp_c) AS sample_count
+            FROM redox_data
+            GROUP BY time_bucket('1 hour', measured_on), sensorprofile_id
+            WITH NO DATA;
+
+            SELECT add_continuous_aggregate_policy('redox_data_hourly',
+                start_offset => INTERVAL '3 hours',
+                end_offset => INTERVAL '1 hour',
+                schedule_interval => INTERVAL '1 hour');
+
+            CREATE INDEX ON redox_data_hourly (sensorprofile_id, bucket);
+
+            CREATE MATERIALIZED VIEW redox_data_daily
+            WITH (timescaledb.continuous) AS
+            SELECT
+                time_bucket('1 day', bucket) AS bucket,
+                sensorprofile_id,
+                SUM(avg_temp_c * sample_count) / NULLIF(SUM(sample_count), 0) AS avg_temp_c,
+                MIN(min_temp_c) AS min_temp_c,
+                MAX(max_temp_c) AS max_temp_c,
+                SUM(sample_count) AS sample_count
+            FROM redox_data_hourly
+            GROUP BY time_bucket('1 day', bucket), sensorprofile_id
+            WITH NO DATA;
+
+            SELECT add_continuous_aggregate_policy('redox_data_daily',
+                start_offset => INTERVAL '3 days',
+                end_offset => INTERVAL '1 day',
+                schedule_interval => INTERVAL '1 day');
+
+            CREATE INDEX ON redox_data_daily (sensorprofile_id, bucket);
+            "#,
+        )
+        .await?;
+
+        // NOTE: The aggregates are refreshed over the full range on startup (see main.rs).
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // The hypertables are left in place; records then no longer cascade to
+        // their potentials.
+        db.execute_unprepared(
+            r#"
+            DROP MATERIALIZED VIEW IF EXISTS redox_data_daily CASCADE;
+            DROP MATERIALIZED VIEW IF EXISTS redox_data_hourly CASCADE;
+            DROP MATERIALIZED VIEW IF EXISTS redox_measurement_daily CASCADE;
+            DROP MATERIALIZED VIEW IF EXISTS redox_measurement_hourly CASCADE;
+            DROP TRIGGER IF EXISTS redox_data_propagate ON redox_data;
+            DROP FUNCTION IF EXISTS redox_data_propagate();
+            DROP TRIGGER IF EXISTS redox_data_unique_id ON redox_data;
+            DROP FUNCTION IF EXISTS redox_data_unique_id();
+            ALTER TABLE redox_channel DROP COLUMN IF EXISTS column_name;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Integration rule used for the integral results of an instrument channel
+        db.execute_unprepared(
+            r#"
+            DO $$ BEGIN
+                CREATE TYPE integration_method_enum AS ENUM ('trapezoid', 'simpson', 'cumulative');
+            EXCEPTION WHEN duplicate_object THEN NULL; END $$;
+
+            DO $$ BEGIN ALTER TABLE instrumentexperimentchannel
+                ADD COLUMN integration_method integration_method_enum NOT NULL DEFAULT 'simpson';
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE instrumentexperimentchannel DROP COLUMN IF EXISTS integration_method;
+            DROP TYPE IF EXISTS integration_method_enum;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
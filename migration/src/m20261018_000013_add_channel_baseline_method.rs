@@ -0,0 +1,47 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Baseline algorithm of an instrument channel and its tuning parameters
+        db.execute_unprepared(
+            r#"
+            DO $$ BEGIN
+                CREATE TYPE baseline_method_enum AS ENUM (
+                    'linear', 'cubic_spline', 'pchip', 'polynomial', 'als', 'rolling_ball'
+                );
+            EXCEPTION WHEN duplicate_object THEN NULL; END $$;
+
+            DO $$ BEGIN ALTER TABLE instrumentexperimentchannel
+                ADD COLUMN baseline_method baseline_method_enum NOT NULL DEFAULT 'linear';
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+
+            ALTER TABLE instrumentexperimentchannel
+                ADD COLUMN IF NOT EXISTS baseline_parameters jsonb;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE instrumentexperimentchannel DROP COLUMN IF EXISTS baseline_parameters;
+            ALTER TABLE instrumentexperimentchannel DROP COLUMN IF EXISTS baseline_method;
+            DROP TYPE IF EXISTS baseline_method_enum;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Names of the injected samples in run order, used to label detected peaks
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE instrumentexperiment ADD COLUMN IF NOT EXISTS sample_names jsonb;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE instrumentexperiment DROP COLUMN IF EXISTS sample_names;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
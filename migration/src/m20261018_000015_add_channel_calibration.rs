@@ -0,0 +1,44 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Calibration curve fitted to the standards of an instrument channel
+        db.execute_unprepared(
+            r#"
+            DO $$ BEGIN
+                CREATE TYPE calibration_model_enum AS ENUM ('linear', 'quadratic');
+            EXCEPTION WHEN duplicate_object THEN NULL; END $$;
+
+            DO $$ BEGIN ALTER TABLE instrumentexperimentchannel
+                ADD COLUMN calibration_model calibration_model_enum NOT NULL DEFAULT 'linear';
+            EXCEPTION WHEN duplicate_column THEN NULL; END $$;
+
+            ALTER TABLE instrumentexperimentchannel ADD COLUMN IF NOT EXISTS calibration jsonb;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE instrumentexperimentchannel DROP COLUMN IF EXISTS calibration;
+            ALTER TABLE instrumentexperimentchannel DROP COLUMN IF EXISTS calibration_model;
+            DROP TYPE IF EXISTS calibration_model_enum;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Provenance of plot sample values written from instrument results
+        db.execute_unprepared(
+            r#"
+            CREATE TABLE IF NOT EXISTS public.plotsample_value_source (
+                id uuid DEFAULT gen_random_uuid() NOT NULL,
+                plotsample_id uuid NOT NULL,
+                field varchar NOT NULL,
+                value double precision NOT NULL,
+                previous_value double precision,
+                experiment_id uuid,
+                channel_ids jsonb NOT NULL DEFAULT '[]'::jsonb,
+                sample_name varchar NOT NULL,
+                result_value varchar NOT NULL,
+                written_on timestamptz DEFAULT now() NOT NULL,
+                CONSTRAINT plotsample_value_source_pkey PRIMARY KEY (id),
+                CONSTRAINT plotsample_value_source_plotsample_id_fkey FOREIGN KEY (plotsample_id)
+                    REFERENCES public.plotsample(id) ON DELETE CASCADE,
+                CONSTRAINT plotsample_value_source_experiment_id_fkey FOREIGN KEY (experiment_id)
+                    REFERENCES public.instrumentexperiment(id) ON DELETE SET NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_plotsample_value_source_plotsample
+                ON public.plotsample_value_source(plotsample_id, field);
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared("DROP TABLE IF EXISTS public.plotsample_value_source;")
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const COLUMNS: [&str; 4] = [
+    "time_values",
+    "raw_values",
+    "baseline_values",
+    "baseline_spline",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Subqueries are not allowed in ALTER COLUMN ... USING, so the JSON
+        // arrays are converted through a temporary function.
+        db.execute_unprepared(
+            r#"
+            CREATE OR REPLACE FUNCTION pg_temp.json_to_float8_array(value json)
+            RETURNS double precision[] LANGUAGE sql IMMUTABLE AS $$
+                SELECT CASE WHEN json_typeof(value) = 'array' THEN
+                    ARRAY(SELECT element::double precision
+                          FROM json_array_elements_text(value) AS element)
+                END
+            $$;
+            "#,
+        )
+        .await?;
+
+        // Channel signals as native arrays instead of JSON
+        for column in COLUMNS {
+            db.execute_unprepared(&format!(
+                r#"
+                DO $$ BEGIN
+                    IF (SELECT data_type FROM information_schema.columns
+                        WHERE table_name = 'instrumentexperimentchannel'
+                        AND column_name = '{column}') = 'json' THEN
+                        ALTER TABLE instrumentexperimentchannel
+                            ALTER COLUMN {column} TYPE double precision[]
+                            USING pg_temp.json_to_float8_array({column});
+                    END IF;
+                END $$;
+                "#
+            ))
+            .await?;
+        }
+
+        db.execute_unprepared("DROP FUNCTION IF EXISTS pg_temp.json_to_float8_array(json);")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        for column in COLUMNS {
+            db.execute_unprepared(&format!(
+                r#"
+                ALTER TABLE instrumentexperimentchannel
+                    ALTER COLUMN {column} TYPE json USING to_json({column});
+                "#
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Solution quality and precision reported by RTK receivers, in metres
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE public.gnss ADD COLUMN IF NOT EXISTS fix_quality character varying;
+            ALTER TABLE public.gnss ADD COLUMN IF NOT EXISTS horizontal_precision double precision;
+            ALTER TABLE public.gnss ADD COLUMN IF NOT EXISTS vertical_precision double precision;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE public.gnss DROP COLUMN IF EXISTS vertical_precision;
+            ALTER TABLE public.gnss DROP COLUMN IF EXISTS horizontal_precision;
+            ALTER TABLE public.gnss DROP COLUMN IF EXISTS fix_quality;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,16 @@
+use axum::{Json, http::StatusCode};
+use sea_orm::DbErr;
+
+/// Response for an error of a model function: missing records are 404,
+/// `DbErr::Custom` carries a validation message and is 422, and anything else
+/// is a 500 without details.
+pub fn db_error_response(e: DbErr) -> (StatusCode, Json<String>) {
+    match e {
+        DbErr::RecordNotFound(msg) => (StatusCode::NOT_FOUND, Json(msg)),
+        DbErr::Custom(msg) => (StatusCode::UNPROCESSABLE_ENTITY, Json(msg)),
+        _ => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json("Internal Server Error".to_string()),
+        ),
+    }
+}
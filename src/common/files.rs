@@ -0,0 +1,100 @@
+use base64::{Engine as _, engine::general_purpose};
+
+/// Decode an uploaded file given as a base64 data URL (or plain base64) into
+/// text. Files that are not valid UTF-8 are read as ISO-8859-1, the encoding
+/// of most logger exports (e.g. the degree sign).
+pub fn decode_base64_text(data: &str) -> Result<String, String> {
+    let encoded = data.split_once(',').map_or(data, |(_, data)| data);
+    let bytes = general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| format!("Invalid base64 data: {e}"))?;
+    Ok(String::from_utf8(bytes)
+        .unwrap_or_else(|e| e.into_bytes().iter().map(|&b| char::from(b)).collect()))
+}
+
+/// Whether the text is a Campbell Scientific TOA5 ASCII table
+pub fn is_toa5(text: &str) -> bool {
+    text.trim_start().starts_with("\"TOA5\"")
+}
+
+/// Field names, units and data rows of a delimited logger table
+#[derive(Debug, Clone, Default)]
+pub struct DelimitedTable {
+    pub names: Vec<String>,
+    /// Units line of TOA5 files, empty for plain delimited files
+    pub units: Vec<String>,
+    pub rows: Vec<csv::StringRecord>,
+}
+
+impl DelimitedTable {
+    pub fn column(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Records of a delimited text, with quoted fields (which may contain the
+/// delimiter) unquoted.
+pub fn read_records(text: &str, delimiter: u8) -> Result<Vec<csv::StringRecord>, String> {
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .delimiter(delimiter)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes())
+        .into_records()
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Invalid delimited file: {e}"))
+}
+
+fn is_blank(record: &csv::StringRecord) -> bool {
+    record.iter().all(str::is_empty)
+}
+
+/// Read a TOA5 file: environment, field names, units and processing header
+/// lines, then data rows.
+pub fn read_toa5(text: &str) -> Result<DelimitedTable, String> {
+    if !is_toa5(text) {
+        return Err("Not a TOA5 file".into());
+    }
+    let mut records = read_records(text, b',')?.into_iter();
+    let (Some(_), Some(names), Some(units), Some(_)) = (
+        records.next(),
+        records.next(),
+        records.next(),
+        records.next(),
+    ) else {
+        return Err("Not a TOA5 file".into());
+    };
+    Ok(DelimitedTable {
+        names: names.iter().map(str::to_string).collect(),
+        units: units.iter().map(str::to_string).collect(),
+        rows: records.filter(|record| !is_blank(record)).collect(),
+    })
+}
+
+/// The most frequent of `,`, `;` and tab in the first non-empty line of a
+/// delimited text, `,` if there is none.
+pub fn sniff_delimiter(text: &str) -> u8 {
+    let header = text
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or_default();
+    [b',', b';', b'\t']
+        .into_iter()
+        .max_by_key(|d| header.bytes().filter(|b| b == d).count())
+        .unwrap_or(b',')
+}
+
+/// Read a delimited file whose first non-empty line holds the column names,
+/// with the delimiter from [`sniff_delimiter`].
+pub fn read_delimited(text: &str) -> Result<DelimitedTable, String> {
+    let mut records = read_records(text, sniff_delimiter(text))?
+        .into_iter()
+        .filter(|record| !is_blank(record));
+    let names = records.next().ok_or("Empty file")?;
+    Ok(DelimitedTable {
+        names: names.iter().map(str::to_string).collect(),
+        units: Vec::new(),
+        rows: records.collect(),
+    })
+}
@@ -0,0 +1,150 @@
+//! Replay of batch responses for requests re-sent with the same `Idempotency-Key`
+//! header, e.g. after a network error.
+//!
+//! A request claims its key with `claim` inside the transaction of the batch and
+//! stores its response with `store` before committing. A concurrent request with
+//! the same key waits on the claimed row and then replays the stored response,
+//! or takes over the key if the first request rolled back.
+
+pub mod db;
+
+use axum::http::HeaderMap;
+use chrono::{Duration, Utc};
+use sea_orm::{
+    ActiveValue, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter,
+    sea_query::{Expr, OnConflict},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use sha2::{Digest, Sha256};
+
+pub const HEADER: &str = "Idempotency-Key";
+/// Hours a stored response is replayed for
+const RETENTION_HOURS: i64 = 24;
+const MAX_KEY_LENGTH: usize = 255;
+pub const MISMATCH_MESSAGE: &str = "Idempotency-Key was already used with a different request";
+
+/// The `Idempotency-Key` header of a request, if sent.
+pub fn key_from_headers(headers: &HeaderMap) -> Result<Option<String>, String> {
+    let Some(value) = headers.get(HEADER) else {
+        return Ok(None);
+    };
+    let key = value
+        .to_str()
+        .map_err(|_| format!("{HEADER} must be visible ASCII"))?
+        .trim();
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+        return Err(format!(
+            "{HEADER} must be between 1 and {MAX_KEY_LENGTH} characters"
+        ));
+    }
+    Ok(Some(key.to_string()))
+}
+
+/// Outcome of claiming an idempotency key.
+#[derive(Debug, PartialEq)]
+pub enum Claim<T> {
+    /// First use of the key; process the request and `store` its response
+    New,
+    /// The key was already used with the same request; its stored response
+    Replay(T),
+    /// The key was already used with a different request
+    Mismatch,
+}
+
+/// Hex SHA-256 of the JSON form of a request.
+fn request_hash<R: Serialize>(request: &R) -> Result<String, DbErr> {
+    let body = serde_json::to_vec(request)
+        .map_err(|e| DbErr::Custom(format!("Failed to serialize request: {e}")))?;
+    Ok(format!("{:x}", Sha256::digest(body)))
+}
+
+/// Claim a key for a request, dropping expired keys first. Must run in the
+/// transaction that processes the request, so that the claim is released when
+/// it rolls back.
+pub async fn claim<C: ConnectionTrait, R: Serialize, T: DeserializeOwned>(
+    db: &C,
+    scope: &str,
+    key: &str,
+    request: &R,
+) -> Result<Claim<T>, DbErr> {
+    let expired = Utc::now() - Duration::hours(RETENTION_HOURS);
+    db::Entity::delete_many()
+        .filter(db::Column::CreatedAt.lte(expired))
+        .exec(db)
+        .await?;
+
+    let request_hash = request_hash(request)?;
+    let claimed = db::Entity::insert(db::ActiveModel {
+        scope: ActiveValue::Set(scope.to_string()),
+        key: ActiveValue::Set(key.to_string()),
+        request_hash: ActiveValue::Set(request_hash.clone()),
+        // Replaced by `store` before the claim is committed
+        response: ActiveValue::Set(serde_json::Value::Null),
+        created_at: ActiveValue::Set(Utc::now()),
+    })
+    .on_conflict(
+        OnConflict::columns([db::Column::Scope, db::Column::Key])
+            .do_nothing()
+            .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await?;
+    if claimed > 0 {
+        return Ok(Claim::New);
+    }
+
+    let stored = db::Entity::find_by_id((scope.to_string(), key.to_string()))
+        .one(db)
+        .await?
+        .ok_or_else(|| DbErr::Custom(format!("{HEADER} '{key}' could not be claimed")))?;
+    if stored.request_hash != request_hash {
+        return Ok(Claim::Mismatch);
+    }
+    serde_json::from_value(stored.response)
+        .map(Claim::Replay)
+        .map_err(|e| DbErr::Custom(format!("Invalid stored response: {e}")))
+}
+
+/// Store the response of a request whose key was claimed in the same transaction.
+pub async fn store<C: ConnectionTrait, T: Serialize>(
+    db: &C,
+    scope: &str,
+    key: &str,
+    response: &T,
+) -> Result<(), DbErr> {
+    let response = serde_json::to_value(response)
+        .map_err(|e| DbErr::Custom(format!("Failed to serialize response: {e}")))?;
+    db::Entity::update_many()
+        .col_expr(db::Column::Response, Expr::value(response))
+        .filter(db::Column::Scope.eq(scope))
+        .filter(db::Column::Key.eq(key))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_and_validates_key_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(key_from_headers(&headers), Ok(None));
+
+        headers.insert(HEADER, " batch-42 ".parse().unwrap());
+        assert_eq!(key_from_headers(&headers), Ok(Some("batch-42".to_string())));
+
+        headers.insert(HEADER, "x".repeat(MAX_KEY_LENGTH + 1).parse().unwrap());
+        assert!(key_from_headers(&headers).is_err());
+    }
+
+    #[test]
+    fn request_hash_depends_on_body() {
+        let first = request_hash(&(Some(true), vec![1, 2])).unwrap();
+        assert_eq!(first.len(), 64);
+        assert_eq!(first, request_hash(&(Some(true), vec![1, 2])).unwrap());
+        assert_ne!(first, request_hash(&(Some(true), vec![1, 3])).unwrap());
+        assert_ne!(first, request_hash(&(None::<bool>, vec![1, 2])).unwrap());
+    }
+}
@@ -1,4 +1,8 @@
 pub mod auth;
+pub mod errors;
+pub mod files;
 pub mod geometry;
+pub mod idempotency;
 pub mod models;
 pub mod views;
+pub mod xlsx;
@@ -33,3 +33,9 @@ pub struct DateRangeQuery {
     pub start: Option<chrono::DateTime<chrono::Utc>>,
     pub end: Option<chrono::DateTime<chrono::Utc>>,
 }
+
+#[derive(Deserialize)]
+pub struct BatchQuery {
+    /// Insert all entries in one transaction, rolling back on the first error
+    pub atomic: Option<bool>,
+}
@@ -0,0 +1,102 @@
+//! XLSX workbooks of plain numeric and string cells on one or more sheets.
+
+use rust_xlsxwriter::{Workbook, XlsxError};
+
+/// Value of one spreadsheet cell
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Number(f64),
+    Text(String),
+    Empty,
+}
+
+impl From<&str> for Cell {
+    /// Numbers are stored as numbers; `nan`, `N/A` and blanks as empty cells.
+    fn from(value: &str) -> Self {
+        match value.trim() {
+            "" | "nan" | "NaN" | "N/A" => Self::Empty,
+            trimmed => match trimmed.parse::<f64>() {
+                Ok(number) if number.is_finite() => Self::Number(number),
+                _ => Self::Text(value.to_string()),
+            },
+        }
+    }
+}
+
+impl From<&serde_json::Value> for Cell {
+    fn from(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Number(n) => n.as_f64().map_or(Self::Empty, Self::Number),
+            serde_json::Value::String(s) => Self::from(s.as_str()),
+            serde_json::Value::Null => Self::Empty,
+            other => Self::Text(other.to_string()),
+        }
+    }
+}
+
+/// A named sheet of rows
+pub struct Sheet {
+    pub name: String,
+    pub rows: Vec<Vec<Cell>>,
+}
+
+/// Builds an XLSX workbook with the sheets in order.
+pub fn write_workbook(sheets: &[Sheet]) -> Result<Vec<u8>, XlsxError> {
+    let mut workbook = Workbook::new();
+    for sheet in sheets {
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(&sheet.name)?;
+        for (r, row) in sheet.rows.iter().enumerate() {
+            let r = u32::try_from(r).map_err(|_| XlsxError::RowColumnLimitError)?;
+            for (c, cell) in row.iter().enumerate() {
+                let c = u16::try_from(c).map_err(|_| XlsxError::RowColumnLimitError)?;
+                match cell {
+                    Cell::Number(n) => {
+                        worksheet.write_number(r, c, *n)?;
+                    }
+                    Cell::Text(t) => {
+                        worksheet.write_string(r, c, t)?;
+                    }
+                    Cell::Empty => {}
+                }
+            }
+        }
+    }
+    workbook.save_to_buffer()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calamine::{Data, Reader, Xlsx};
+    use std::io::Cursor;
+
+    #[test]
+    fn workbook_reads_back_with_its_sheets_and_cells() {
+        let sheets = [
+            Sheet {
+                name: "Raw & filtered".into(),
+                rows: vec![
+                    vec![Cell::from("Time/s"), Cell::from("i1")],
+                    vec![Cell::from("0.1"), Cell::from("N/A")],
+                    vec![Cell::from("0.2"), Cell::from("<a & b>")],
+                ],
+            },
+            Sheet {
+                name: "Summary".into(),
+                rows: vec![vec![Cell::Number(2.5)]],
+            },
+        ];
+        let archive = write_workbook(&sheets).unwrap();
+
+        let mut workbook: Xlsx<_> = Xlsx::new(Cursor::new(archive)).unwrap();
+        assert_eq!(workbook.sheet_names(), ["Raw & filtered", "Summary"]);
+        let raw = workbook.worksheet_range("Raw & filtered").unwrap();
+        assert_eq!(raw.get_value((0, 0)), Some(&Data::String("Time/s".into())));
+        assert_eq!(raw.get_value((1, 0)), Some(&Data::Float(0.1)));
+        assert_eq!(raw.get_value((1, 1)), Some(&Data::Empty));
+        assert_eq!(raw.get_value((2, 1)), Some(&Data::String("<a & b>".into())));
+        let summary = workbook.worksheet_range("Summary").unwrap();
+        assert_eq!(summary.get_value((0, 0)), Some(&Data::Float(2.5)));
+    }
+}
@@ -38,8 +38,19 @@ async fn main() {
     println!("DB migrations complete");
 
     // Refresh continuous aggregates over the full time range on startup.
-    // Hourly must run before 6h since 6h is a hierarchical aggregate built on top of hourly.
-    for view in ["sensordata_hourly", "sensordata_6h"] {
+    // Hourly must run before 6h and daily since those are hierarchical aggregates built on top of hourly.
+    for view in [
+        "sensordata_hourly",
+        "sensordata_6h",
+        "sensor_measurement_hourly",
+        "sensor_measurement_6h",
+        "weather_data_hourly",
+        "weather_data_6h",
+        "redox_measurement_hourly",
+        "redox_measurement_daily",
+        "redox_data_hourly",
+        "redox_data_daily",
+    ] {
         let sql = format!("CALL refresh_continuous_aggregate('{view}', NULL, NULL)");
         match db.execute(Statement::from_string(db.get_database_backend(), sql)).await {
             Ok(_) => println!("Refreshed {view} (full range)"),
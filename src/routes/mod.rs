@@ -19,6 +19,8 @@ use utoipa::OpenApi;
 use utoipa_axum::router::OpenApiRouter;
 use utoipa_scalar::{Scalar, Servable};
 
+// `#[derive(OpenApi)]` expands to a `for_each` over the nested paths
+#[allow(clippy::needless_for_each)]
 pub fn build_router(db: &DatabaseConnection) -> Router {
     #[derive(OpenApi)]
     #[openapi(
@@ -79,10 +81,48 @@ pub fn build_router(db: &DatabaseConnection) -> Router {
             "/api/plot_samples",
             private::samples::views::router(db, Some(keycloak_instance.clone())),
         )
+        .merge(sensor_routes(db, &keycloak_instance))
+        .nest(
+            "/api/transects",
+            private::transects::views::router(db, Some(keycloak_instance.clone())),
+        )
+        .merge(instrument_routes(db, &keycloak_instance))
+        .nest(
+            "/api/soil_types",
+            private::soil::types::views::router(db, Some(keycloak_instance.clone())),
+        )
+        .nest(
+            "/api/soil_profiles",
+            private::soil::profiles::views::router(db, Some(keycloak_instance.clone())),
+        )
+        .nest(
+            "/api/soil_classifications",
+            private::soil::classification::views::router(db, Some(keycloak_instance.clone())),
+        )
+        .merge(website_routes(db, &keycloak_instance))
+        .nest("/api/public", public::router(db))
+        .layer(DefaultBodyLimit::max(30 * 1024 * 1024))
+        .split_for_parts();
+
+    router
+        .merge(Scalar::with_url("/api/docs", api))
+        .layer(tower_http::compression::CompressionLayer::new())
+}
+
+/// Routes of sensors, their profiles and the data of each profile type
+fn sensor_routes(
+    db: &DatabaseConnection,
+    keycloak_instance: &Arc<KeycloakAuthInstance>,
+) -> OpenApiRouter {
+    OpenApiRouter::new()
         .nest(
             "/api/sensors",
             private::sensors::views::router(db, Some(keycloak_instance.clone())),
         )
+        .nest(
+            "/api/sensor_types",
+            private::sensors::types::views::router(db, Some(keycloak_instance.clone())),
+        )
         .nest(
             "/api/sensor_profiles",
             private::sensors::profile::views::router(db, Some(keycloak_instance.clone())),
@@ -98,14 +138,37 @@ pub fn build_router(db: &DatabaseConnection) -> Router {
             "/api/flux_data",
             private::sensors::flux_data::views::router(db, Some(keycloak_instance.clone())),
         )
+        .nest(
+            "/api/flux_qc_thresholds",
+            private::sensors::flux_data::qc::views::router(db, Some(keycloak_instance.clone())),
+        )
+        .nest(
+            "/api/groundwater_data",
+            private::sensors::groundwater_data::views::router(db, Some(keycloak_instance.clone())),
+        )
+        .nest(
+            "/api/weather_data",
+            private::sensors::weather_data::views::router(db, Some(keycloak_instance.clone())),
+        )
         .nest(
             "/api/redox_data",
             private::sensors::redox_data::views::router(db, Some(keycloak_instance.clone())),
         )
         .nest(
-            "/api/transects",
-            private::transects::views::router(db, Some(keycloak_instance.clone())),
+            "/api/redox_channels",
+            private::sensors::redox_data::channels::views::router(
+                db,
+                Some(keycloak_instance.clone()),
+            ),
         )
+}
+
+/// Routes of lab instrument experiments and their channels
+fn instrument_routes(
+    db: &DatabaseConnection,
+    keycloak_instance: &Arc<KeycloakAuthInstance>,
+) -> OpenApiRouter {
+    OpenApiRouter::new()
         .nest(
             "/api/instruments",
             private::instrument_experiments::views::router(db, Some(keycloak_instance.clone())),
@@ -117,18 +180,14 @@ pub fn build_router(db: &DatabaseConnection) -> Router {
                 Some(keycloak_instance.clone()),
             ),
         )
-        .nest(
-            "/api/soil_types",
-            private::soil::types::views::router(db, Some(keycloak_instance.clone())),
-        )
-        .nest(
-            "/api/soil_profiles",
-            private::soil::profiles::views::router(db, Some(keycloak_instance.clone())),
-        )
-        .nest(
-            "/api/soil_classifications",
-            private::soil::classification::views::router(db, Some(keycloak_instance.clone())),
-        )
+}
+
+/// Routes of websites and the areas, plots and sensors they show
+fn website_routes(
+    db: &DatabaseConnection,
+    keycloak_instance: &Arc<KeycloakAuthInstance>,
+) -> OpenApiRouter {
+    OpenApiRouter::new()
         .nest(
             "/api/websites",
             private::websites::views::router(db, Some(keycloak_instance.clone())),
@@ -145,11 +204,4 @@ pub fn build_router(db: &DatabaseConnection) -> Router {
             "/api/website_sensor_exclusions",
             private::website_sensor_exclusions::views::router(db, Some(keycloak_instance.clone())),
         )
-        .nest("/api/public", public::router(db))
-        .layer(DefaultBodyLimit::max(30 * 1024 * 1024))
-        .split_for_parts();
-
-    router
-        .merge(Scalar::with_url("/api/docs", api))
-        .layer(tower_http::compression::CompressionLayer::new())
 }
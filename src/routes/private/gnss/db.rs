@@ -18,6 +18,9 @@ pub struct Model {
     pub coord_x: Option<f64>,
     pub coord_y: Option<f64>,
     pub coord_srid: Option<i32>,
+    pub fix_quality: Option<String>,
+    pub horizontal_precision: Option<f64>,
+    pub vertical_precision: Option<f64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
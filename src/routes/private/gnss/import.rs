@@ -0,0 +1,677 @@
+//! Readers for the GNSS files accepted on upload: GPX (waypoints, tracks and
+//! routes), CSV point exports of Emlid Reach and Trimble receivers, and raw
+//! NMEA 0183 logs.
+
+use super::models::GnssCreate;
+use crate::common::files::sniff_delimiter;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::Serialize;
+use std::fmt;
+use utoipa::ToSchema;
+
+/// A point of an uploaded file that could not be read
+#[derive(ToSchema, Serialize, Debug, Clone, PartialEq)]
+pub struct GnssPointError {
+    /// Where the point is in the file, e.g. `line 12` or `track 1, point 4`
+    pub location: String,
+    pub name: Option<String>,
+    pub message: String,
+}
+
+/// Why an uploaded file was rejected. Nothing is imported if any point fails.
+#[derive(ToSchema, Serialize, Debug, Clone, PartialEq)]
+pub struct GnssImportError {
+    pub message: String,
+    pub points: Vec<GnssPointError>,
+}
+
+impl GnssImportError {
+    pub fn file(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            points: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for GnssImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        for point in &self.points {
+            write!(f, "; {}: {}", point.location, point.message)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GnssFormat {
+    Gpx,
+    Csv,
+    Nmea,
+}
+
+impl GnssFormat {
+    /// From the file extension, or from the content for other extensions
+    /// (NMEA logs are often saved as `.txt` or `.log`).
+    pub fn detect(filename: &str, text: &str) -> Self {
+        let extension = filename
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("gpx") => Self::Gpx,
+            Some("csv") => Self::Csv,
+            Some("nmea" | "nma") => Self::Nmea,
+            _ => {
+                let start = text.trim_start();
+                if start.starts_with('<') {
+                    Self::Gpx
+                } else if start.starts_with('$') {
+                    Self::Nmea
+                } else {
+                    Self::Csv
+                }
+            }
+        }
+    }
+}
+
+/// Points read from a file and the points that failed
+#[derive(Default)]
+struct Parsed {
+    points: Vec<GnssCreate>,
+    errors: Vec<GnssPointError>,
+}
+
+impl Parsed {
+    fn push(&mut self, point: Result<GnssCreate, String>, location: String, name: Option<String>) {
+        match point {
+            Ok(point) => self.points.push(point),
+            Err(message) => self.errors.push(GnssPointError {
+                location,
+                name,
+                message,
+            }),
+        }
+    }
+}
+
+/// Reads the points of an uploaded file, detecting its format from the file
+/// name and content.
+pub fn parse_gnss_file(text: &str, filename: &str) -> Result<Vec<GnssCreate>, GnssImportError> {
+    let text = text.trim_start_matches('\u{feff}');
+    let format = GnssFormat::detect(filename, text);
+    let parsed = match format {
+        GnssFormat::Gpx => parse_gpx(text)?,
+        GnssFormat::Csv => parse_csv(text)?,
+        GnssFormat::Nmea => parse_nmea(text),
+    };
+    if !parsed.errors.is_empty() {
+        return Err(GnssImportError {
+            message: format!(
+                "{} of {} points could not be read",
+                parsed.errors.len(),
+                parsed.errors.len() + parsed.points.len()
+            ),
+            points: parsed.errors,
+        });
+    }
+    if parsed.points.is_empty() {
+        return Err(GnssImportError::file(format!(
+            "No points found in the {format:?} file"
+        )));
+    }
+    let original_filename = Some(filename.to_string()).filter(|name| !name.is_empty());
+    Ok(parsed
+        .points
+        .into_iter()
+        .map(|point| GnssCreate {
+            original_filename: original_filename.clone(),
+            ..point
+        })
+        .collect())
+}
+
+fn new_point(latitude: f64, longitude: f64) -> Result<GnssCreate, String> {
+    if !(latitude.is_finite() && (-90.0..=90.0).contains(&latitude)) {
+        return Err(format!("Latitude {latitude} is out of range"));
+    }
+    if !(longitude.is_finite() && (-180.0..=180.0).contains(&longitude)) {
+        return Err(format!("Longitude {longitude} is out of range"));
+    }
+    Ok(GnssCreate {
+        time: None,
+        name: None,
+        comment: None,
+        original_filename: None,
+        elevation_gps: None,
+        latitude: Some(latitude),
+        longitude: Some(longitude),
+        coord_x: None,
+        coord_y: None,
+        fix_quality: None,
+        horizontal_precision: None,
+        vertical_precision: None,
+        data_base64: None,
+        filename: None,
+    })
+}
+
+/// Maps the solution status of the different receivers and the NMEA GGA
+/// quality indicator onto one vocabulary, e.g. `FIX`, `RTK Fixed` and `4`
+/// all become `rtk_fixed`. Unknown values are kept in snake case.
+pub fn normalise_fix_quality(value: &str) -> Option<String> {
+    let value = value.trim().to_lowercase();
+    let quality = match value.as_str() {
+        "" => return None,
+        "0" | "none" | "no fix" | "invalid" => "none",
+        "1" | "single" | "autonomous" | "standalone" | "gps" => "single",
+        "2" | "dgps" | "dgnss" | "differential" => "dgps",
+        "3" | "pps" => "pps",
+        "4" | "fix" | "fixed" | "rtk fix" | "rtk fixed" | "rtk_fixed" => "rtk_fixed",
+        "5" | "float" | "rtk float" | "rtk_float" => "rtk_float",
+        "6" | "estimated" | "dead reckoning" => "estimated",
+        "7" | "manual" => "manual",
+        "8" | "simulation" => "simulation",
+        other => return Some(other.split_whitespace().collect::<Vec<_>>().join("_")),
+    };
+    Some(quality.to_string())
+}
+
+fn parse_gpx(text: &str) -> Result<Parsed, GnssImportError> {
+    let gpx = gpx::read(text.as_bytes())
+        .map_err(|e| GnssImportError::file(format!("Invalid GPX file: {e}")))?;
+
+    let mut parsed = Parsed::default();
+    for (index, waypoint) in gpx.waypoints.iter().enumerate() {
+        parsed.push(
+            gpx_point(waypoint, waypoint.name.clone()),
+            format!("waypoint {}", index + 1),
+            waypoint.name.clone(),
+        );
+    }
+    // Track and route points are rarely named, so they are numbered after
+    // their track or route.
+    for (track_index, track) in gpx.tracks.iter().enumerate() {
+        let track_name = track
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("Track {}", track_index + 1));
+        let points = track.segments.iter().flat_map(|s| s.points.iter());
+        for (index, waypoint) in points.enumerate() {
+            let name = waypoint
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("{track_name} {}", index + 1));
+            parsed.push(
+                gpx_point(waypoint, Some(name.clone())),
+                format!("track {}, point {}", track_index + 1, index + 1),
+                Some(name),
+            );
+        }
+    }
+    for (route_index, route) in gpx.routes.iter().enumerate() {
+        let route_name = route
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("Route {}", route_index + 1));
+        for (index, waypoint) in route.points.iter().enumerate() {
+            let name = waypoint
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("{route_name} {}", index + 1));
+            parsed.push(
+                gpx_point(waypoint, Some(name.clone())),
+                format!("route {}, point {}", route_index + 1, index + 1),
+                Some(name),
+            );
+        }
+    }
+    Ok(parsed)
+}
+
+fn gpx_point(waypoint: &gpx::Waypoint, name: Option<String>) -> Result<GnssCreate, String> {
+    // Time is structured as: 2023-07-20T09:32:34.000000000Z
+    let time = waypoint
+        .time
+        .as_ref()
+        .map(|time| {
+            time.format()
+                .map_err(|e| e.to_string())
+                .and_then(|time| DateTime::parse_from_rfc3339(&time).map_err(|e| e.to_string()))
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|e| format!("Invalid time: {e}"))
+        })
+        .transpose()?;
+    let fix_quality = waypoint.fix.as_ref().and_then(|fix| match fix {
+        gpx::Fix::None => normalise_fix_quality("none"),
+        gpx::Fix::TwoDimensional => Some("2d".to_string()),
+        gpx::Fix::ThreeDimensional => Some("3d".to_string()),
+        gpx::Fix::DGPS => normalise_fix_quality("dgps"),
+        gpx::Fix::PPS => normalise_fix_quality("pps"),
+        gpx::Fix::Other(other) => normalise_fix_quality(other),
+    });
+    Ok(GnssCreate {
+        time,
+        name,
+        comment: waypoint
+            .comment
+            .clone()
+            .or_else(|| waypoint.description.clone()),
+        elevation_gps: waypoint.elevation,
+        fix_quality,
+        ..new_point(waypoint.point().y(), waypoint.point().x())?
+    })
+}
+
+// Header aliases of the CSV columns, compared after `normalise_header`. The
+// first alias found wins, e.g. Emlid's `Elevation` over `Ellipsoidal height`.
+const NAME_COLUMNS: &[&str] = &["name", "pointname", "pointid", "point", "id"];
+const LATITUDE_COLUMNS: &[&str] = &["latitude", "lat", "wgs84latitude"];
+const LONGITUDE_COLUMNS: &[&str] = &["longitude", "lon", "long", "lng", "wgs84longitude"];
+const ELEVATION_COLUMNS: &[&str] = &[
+    "elevation",
+    "ellipsoidalheight",
+    "height",
+    "altitude",
+    "wgs84height",
+    "wgs84ellipsoidalheight",
+];
+const TIME_COLUMNS: &[&str] = &[
+    "averagingstart",
+    "timestamp",
+    "datetime",
+    "collectedat",
+    "measuredat",
+    "time",
+    "utctime",
+];
+const DATE_COLUMNS: &[&str] = &["date", "surveydate"];
+const COMMENT_COLUMNS: &[&str] = &["description", "comment", "comments", "notes", "code"];
+const FIX_COLUMNS: &[&str] = &[
+    "solutionstatus",
+    "fixquality",
+    "fixtype",
+    "solutiontype",
+    "solution",
+    "gnssfix",
+    "status",
+];
+const HORIZONTAL_PRECISION_COLUMNS: &[&str] = &[
+    "lateralrms",
+    "horizontalprecision",
+    "hprecision",
+    "horizontalaccuracy",
+    "hrms",
+];
+const VERTICAL_PRECISION_COLUMNS: &[&str] = &[
+    "elevationrms",
+    "verticalprecision",
+    "vprecision",
+    "verticalaccuracy",
+    "vrms",
+];
+
+/// `Ellipsoidal height (m)` becomes `ellipsoidalheight`
+fn normalise_header(header: &str) -> String {
+    header
+        .split(['(', '['])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// Parses a CSV date and time, e.g. Emlid's `2023-07-20 09:32:34.0 UTC` or
+/// Trimble's `2023/07/20 09:32:34`. Times without a zone are taken as UTC.
+fn parse_csv_time(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    let value = value
+        .trim_end_matches(" UTC")
+        .trim_end_matches(" GPST")
+        .trim_end_matches('Z');
+    [
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y/%m/%d %H:%M:%S%.f",
+        "%d.%m.%Y %H:%M:%S%.f",
+        "%Y-%m-%d %H:%M",
+        "%m/%d/%Y %I:%M:%S %p",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    .map(|time| time.and_utc())
+}
+
+fn parse_csv(text: &str) -> Result<Parsed, GnssImportError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(sniff_delimiter(text))
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| GnssImportError::file(format!("Invalid CSV file: {e}")))?
+        .iter()
+        .map(normalise_header)
+        .collect();
+    let column = |aliases: &[&str]| {
+        aliases
+            .iter()
+            .find_map(|alias| headers.iter().position(|h| h == alias))
+    };
+    let (Some(latitude), Some(longitude)) = (column(LATITUDE_COLUMNS), column(LONGITUDE_COLUMNS))
+    else {
+        return Err(GnssImportError::file(format!(
+            "CSV file has no latitude and longitude columns (found: {})",
+            headers.join(", ")
+        )));
+    };
+    let name = column(NAME_COLUMNS);
+    let elevation = column(ELEVATION_COLUMNS);
+    let time = column(TIME_COLUMNS);
+    let date = column(DATE_COLUMNS).filter(|date| Some(*date) != time);
+    let comment = column(COMMENT_COLUMNS);
+    let fix = column(FIX_COLUMNS);
+    let horizontal_precision = column(HORIZONTAL_PRECISION_COLUMNS);
+    let vertical_precision = column(VERTICAL_PRECISION_COLUMNS);
+    let easting_rms = column(&["eastingrms"]);
+    let northing_rms = column(&["northingrms"]);
+
+    let mut parsed = Parsed::default();
+    for (index, record) in reader.records().enumerate() {
+        // The header is line 1
+        let location = format!("line {}", index + 2);
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                parsed.push(Err(e.to_string()), location, None);
+                continue;
+            }
+        };
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|column| record.get(column))
+                .filter(|value| !value.is_empty())
+        };
+        let number = |column: Option<usize>, label: &str| {
+            field(column)
+                .map(|value| {
+                    value
+                        .parse::<f64>()
+                        .map_err(|_| format!("Invalid {label} '{value}'"))
+                })
+                .transpose()
+        };
+        let point = (|| {
+            let latitude = number(Some(latitude), "latitude")?.ok_or("Missing latitude")?;
+            let longitude = number(Some(longitude), "longitude")?.ok_or("Missing longitude")?;
+            let time = match (field(date), field(time)) {
+                (Some(date), Some(time)) => Some(format!("{date} {time}")),
+                (date, time) => time.or(date).map(str::to_string),
+            }
+            .map(|value| parse_csv_time(&value).ok_or(format!("Invalid time '{value}'")))
+            .transpose()?;
+            let horizontal_precision = match number(horizontal_precision, "horizontal precision")? {
+                Some(precision) => Some(precision),
+                None => number(easting_rms, "easting RMS")?
+                    .zip(number(northing_rms, "northing RMS")?)
+                    .map(|(easting, northing)| easting.hypot(northing)),
+            };
+            Ok::<_, String>(GnssCreate {
+                time,
+                name: field(name).map(str::to_string),
+                comment: field(comment).map(str::to_string),
+                elevation_gps: number(elevation, "elevation")?,
+                fix_quality: field(fix).and_then(normalise_fix_quality),
+                horizontal_precision,
+                vertical_precision: number(vertical_precision, "vertical precision")?,
+                ..new_point(latitude, longitude)?
+            })
+        })();
+        parsed.push(point, location, field(name).map(str::to_string));
+    }
+    Ok(parsed)
+}
+
+/// Fields of an NMEA sentence without the leading `$`, after checking the
+/// checksum when there is one.
+fn nmea_fields(line: &str) -> Result<Vec<&str>, String> {
+    let body = line.strip_prefix('$').unwrap_or(line);
+    let body = match body.split_once('*') {
+        Some((body, checksum)) => {
+            let expected = u8::from_str_radix(checksum.trim(), 16)
+                .map_err(|_| format!("Invalid checksum '{}'", checksum.trim()))?;
+            let actual = body.bytes().fold(0, |sum, b| sum ^ b);
+            if actual != expected {
+                return Err(format!(
+                    "Checksum mismatch (expected {expected:02X}, computed {actual:02X})"
+                ));
+            }
+            body
+        }
+        None => body,
+    };
+    Ok(body.split(',').collect())
+}
+
+/// `4630.1234`, `N` to decimal degrees
+fn nmea_coordinate(value: &str, hemisphere: &str) -> Result<f64, String> {
+    let raw: f64 = value
+        .parse()
+        .map_err(|_| format!("Invalid coordinate '{value}'"))?;
+    let degrees = (raw / 100.0).trunc();
+    let minutes = raw - degrees * 100.0;
+    if minutes >= 60.0 {
+        return Err(format!("Invalid coordinate '{value}'"));
+    }
+    let coordinate = degrees + minutes / 60.0;
+    match hemisphere {
+        "N" | "E" => Ok(coordinate),
+        "S" | "W" => Ok(-coordinate),
+        other => Err(format!("Invalid hemisphere '{other}'")),
+    }
+}
+
+fn nmea_time(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value, "%H%M%S%.f").map_err(|_| format!("Invalid time '{value}'"))
+}
+
+/// Date and time of a valid RMC sentence
+fn rmc_date(fields: &[&str]) -> Option<(NaiveDate, NaiveTime)> {
+    let date = NaiveDate::parse_from_str(fields.get(9)?, "%d%m%y").ok()?;
+    let time = nmea_time(fields.get(1)?).ok()?;
+    Some((date, time))
+}
+
+/// Sentence type without the talker, e.g. `GGA` for `GNGGA`
+fn sentence_type<'a>(fields: &[&'a str]) -> &'a str {
+    let address = fields.first().copied().unwrap_or_default();
+    address
+        .get(address.len().saturating_sub(3)..)
+        .unwrap_or_default()
+}
+
+/// One point per GGA fix. GGA only has the time of day, so the date comes from
+/// the RMC sentences; fixes before the first RMC take its date, and a GGA time
+/// earlier than the previous one rolls over to the next day. GST precision is
+/// attached to the GGA fix of the same epoch. Sentences without a fix are
+/// skipped; other sentence types are ignored.
+fn parse_nmea(text: &str) -> Parsed {
+    let sentences: Vec<(usize, &str)> = text
+        .lines()
+        .map(str::trim)
+        .enumerate()
+        .filter(|(_, line)| line.starts_with('$'))
+        .collect();
+    let mut date = sentences.iter().find_map(|(_, line)| {
+        let fields = nmea_fields(line).ok()?;
+        (sentence_type(&fields) == "RMC")
+            .then(|| rmc_date(&fields).map(|(date, _)| date))
+            .flatten()
+    });
+    let mut previous_time: Option<NaiveTime> = None;
+    let mut last_epoch: Option<String> = None;
+
+    let mut parsed = Parsed::default();
+    for (index, line) in sentences {
+        let location = format!("line {}", index + 1);
+        let fields = match nmea_fields(line) {
+            Ok(fields) => fields,
+            Err(message) => {
+                parsed.push(Err(message), location, None);
+                continue;
+            }
+        };
+        let field = |i: usize| fields.get(i).copied().unwrap_or_default();
+        match sentence_type(&fields) {
+            "RMC" => {
+                if let Some((rmc_date, rmc_time)) = rmc_date(&fields) {
+                    date = Some(rmc_date);
+                    previous_time = Some(rmc_time);
+                }
+            }
+            "GGA" => {
+                if matches!(field(6), "" | "0") || field(2).is_empty() {
+                    continue;
+                }
+                let point = (|| {
+                    let time = nmea_time(field(1))?;
+                    if previous_time.is_some_and(|previous| time < previous) {
+                        date = date.and_then(|date| date.succ_opt());
+                    }
+                    previous_time = Some(time);
+                    let elevation = Some(field(9))
+                        .filter(|value| !value.is_empty())
+                        .map(|value| {
+                            value
+                                .parse::<f64>()
+                                .map_err(|_| format!("Invalid altitude '{value}'"))
+                        })
+                        .transpose()?;
+                    Ok::<_, String>(GnssCreate {
+                        time: date.map(|date| date.and_time(time).and_utc()),
+                        elevation_gps: elevation,
+                        fix_quality: normalise_fix_quality(field(6)),
+                        ..new_point(
+                            nmea_coordinate(field(2), field(3))?,
+                            nmea_coordinate(field(4), field(5))?,
+                        )?
+                    })
+                })();
+                last_epoch = point.is_ok().then(|| field(1).to_string());
+                parsed.push(point, location, None);
+            }
+            "GST" => {
+                if last_epoch.as_deref() != Some(field(1)) {
+                    continue;
+                }
+                let deviation = |i: usize| field(i).parse::<f64>().ok();
+                if let Some(point) = parsed.points.last_mut() {
+                    point.horizontal_precision = deviation(6)
+                        .zip(deviation(7))
+                        .map(|(latitude, longitude)| latitude.hypot(longitude));
+                    point.vertical_precision = deviation(8);
+                }
+            }
+            _ => {}
+        }
+    }
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn reads_gpx_waypoints_tracks_and_routes() {
+        let gpx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <wpt lat="46.5" lon="6.6"><ele>380.5</ele><time>2023-07-20T09:32:34Z</time><name>P1</name><fix>dgps</fix></wpt>
+  <trk><name>Transect</name><trkseg>
+    <trkpt lat="46.51" lon="6.61"><ele>381</ele></trkpt>
+    <trkpt lat="46.52" lon="6.62"></trkpt>
+  </trkseg></trk>
+  <rte><rtept lat="46.53" lon="6.63"><name>R1</name></rtept></rte>
+</gpx>"#;
+        let points = parse_gnss_file(gpx, "field.gpx").unwrap();
+        let names: Vec<_> = points.iter().map(|p| p.name.clone().unwrap()).collect();
+        assert_eq!(names, ["P1", "Transect 1", "Transect 2", "R1"]);
+        assert_eq!(
+            points[0].time,
+            Some(Utc.with_ymd_and_hms(2023, 7, 20, 9, 32, 34).unwrap())
+        );
+        assert_eq!(points[0].fix_quality.as_deref(), Some("dgps"));
+        assert_eq!(points[1].elevation_gps, Some(381.0));
+        assert_eq!(points[3].original_filename.as_deref(), Some("field.gpx"));
+    }
+
+    #[test]
+    fn reads_emlid_csv_and_reports_bad_rows() {
+        let csv = "Name,Code,Longitude,Latitude,Ellipsoidal height,Solution status,\
+Averaging start,Easting RMS,Northing RMS,Elevation RMS\n\
+P1,soil,6.6,46.5,420.1,FIX,2023-07-20 09:32:34.0 UTC,0.003,0.004,0.01\n\
+P2,soil,6.7,46.6,421.0,FLOAT,2023-07-20 09:40:00.0 UTC,0.05,0.05,0.1\n";
+        let points = parse_gnss_file(csv, "reach.csv").unwrap();
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].fix_quality.as_deref(), Some("rtk_fixed"));
+        assert_eq!(points[1].fix_quality.as_deref(), Some("rtk_float"));
+        assert!((points[0].horizontal_precision.unwrap() - 0.005).abs() < 1e-12);
+        assert_eq!(points[0].vertical_precision, Some(0.01));
+        assert_eq!(points[0].comment.as_deref(), Some("soil"));
+        assert_eq!(
+            points[0].time,
+            Some(Utc.with_ymd_and_hms(2023, 7, 20, 9, 32, 34).unwrap())
+        );
+
+        let trimble = "Point ID;Latitude;Longitude;Elevation;Fix Type;Horizontal Precision\n\
+1;46.5;6.6;400;RTK Fixed;0.01\n\
+2;146.5;6.6;400;RTK Fixed;0.01\n\
+3;abc;6.6;400;RTK Fixed;0.01\n";
+        let error = parse_gnss_file(trimble, "trimble.csv").err().unwrap();
+        assert_eq!(error.message, "2 of 3 points could not be read");
+        assert_eq!(error.points[0].location, "line 3");
+        assert_eq!(error.points[0].name.as_deref(), Some("2"));
+        assert_eq!(error.points[1].message, "Invalid latitude 'abc'");
+    }
+
+    #[test]
+    fn reads_nmea_fixes_with_dates_and_precision() {
+        let nmea = "$GPGGA,235959.00,4630.0000,N,00636.0000,E,0,00,,,M,,M,,*70\n\
+$GNRMC,235959.00,A,4630.0000,N,00636.0000,E,0.0,0.0,200723,,,A*47\n\
+$GNGGA,235959.50,4630.0000,N,00636.0000,E,4,12,0.8,380.5,M,48.0,M,1.0,0000*57\n\
+$GNGST,235959.50,0.010,0.008,0.006,45.0,0.003,0.004,0.010*75\n\
+$GNGGA,000000.50,4630.6000,S,00636.0000,W,5,12,0.8,381.0,M,48.0,M,1.0,0000*5A\n";
+        let points = parse_gnss_file(nmea, "log.txt").unwrap();
+        assert_eq!(points.len(), 2);
+        assert!((points[0].latitude.unwrap() - 46.5).abs() < 1e-12);
+        assert!((points[0].longitude.unwrap() - 6.6).abs() < 1e-12);
+        assert_eq!(points[0].fix_quality.as_deref(), Some("rtk_fixed"));
+        assert!((points[0].horizontal_precision.unwrap() - 0.005).abs() < 1e-12);
+        assert_eq!(points[0].vertical_precision, Some(0.01));
+        assert!((points[1].latitude.unwrap() + 46.51).abs() < 1e-12);
+        assert_eq!(points[1].horizontal_precision, None);
+        assert_eq!(
+            points[1].time,
+            Some(
+                Utc.with_ymd_and_hms(2023, 7, 21, 0, 0, 0).unwrap()
+                    + chrono::Duration::milliseconds(500)
+            )
+        );
+
+        let error = parse_gnss_file("$GNGGA,000000.50,4630.6000,S*00\n", "log.nmea")
+            .err()
+            .unwrap();
+        assert_eq!(error.points[0].location, "line 1");
+        assert!(error.points[0].message.starts_with("Checksum mismatch"));
+    }
+}
@@ -1,3 +1,4 @@
 pub mod db;
+pub mod import;
 pub mod models;
 pub mod views;
@@ -1,19 +1,18 @@
 use super::db::Model;
+use super::import::{GnssImportError, parse_gnss_file};
+use crate::common::files::decode_base64_text;
 use crate::config::Config;
 use async_trait::async_trait;
-use base64;
-use base64::Engine;
 use chrono::{DateTime, Utc};
 use crudcrate::{CRUDResource, ToCreateModel, ToUpdateModel, traits::MergeIntoActiveModel};
-use gpx::read;
 use sea_orm::{
     ActiveModelTrait,
     ActiveValue::{self, NotSet},
     ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, Order, QueryOrder, QuerySelect,
+    TransactionTrait,
     entity::prelude::*,
 };
 use serde::{Deserialize, Serialize};
-use std::io::Cursor;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
@@ -43,6 +42,12 @@ pub struct Gnss {
     pub coord_y: Option<f64>,
     #[crudcrate(update_model = false, create_model = false, on_create = Config::from_env().srid)]
     pub coord_srid: Option<i32>,
+    /// Solution quality, e.g. `rtk_fixed`, `rtk_float`, `dgps` or `single`
+    pub fix_quality: Option<String>,
+    /// Horizontal precision reported by the receiver, in metres
+    pub horizontal_precision: Option<f64>,
+    /// Vertical precision reported by the receiver, in metres
+    pub vertical_precision: Option<f64>,
     #[crudcrate(non_db_attr = true)]
     pub data_base64: Option<String>,
     #[crudcrate(non_db_attr = true)]
@@ -64,6 +69,9 @@ impl From<Model> for Gnss {
             coord_x: model.coord_x,
             coord_y: model.coord_y,
             coord_srid: model.coord_srid,
+            fix_quality: model.fix_quality,
+            horizontal_precision: model.horizontal_precision,
+            vertical_precision: model.vertical_precision,
             data_base64: None,
             filename: None,
         }
@@ -117,26 +125,14 @@ impl CRUDResource for Gnss {
         db: &DatabaseConnection,
         create_model: Self::CreateModel,
     ) -> Result<Self, DbErr> {
-        let gnss = GNSSCreateFromFile {
-            data_base64: create_model.data_base64.unwrap(),
-            filename: create_model.filename.unwrap(),
-        };
-        let creates = gnss.into_gnss_creates().unwrap();
-
-        let mut response_objs = Vec::new();
-        for create in creates {
-            let mut active_model: Self::ActiveModelType = create.into();
-
-            // Delete coord_x and coord_y from the active model
-            active_model.coord_x = NotSet;
-            active_model.coord_y = NotSet;
-            active_model.coord_srid = NotSet;
-
-            let response_obj = active_model.insert(db).await?;
-            response_objs.push(response_obj);
-        }
-        let obj = Self::get_one(db, response_objs[0].id).await?;
-        Ok(obj)
+        let creates = create_model
+            .into_gnss_creates()
+            .map_err(|e| DbErr::Custom(e.to_string()))?;
+        let ids = insert_gnss_points(db, creates).await?;
+        let first = ids
+            .first()
+            .ok_or(DbErr::Custom("No points to import".into()))?;
+        Self::get_one(db, *first).await
     }
 
     async fn update(
@@ -171,6 +167,12 @@ impl CRUDResource for Gnss {
             ("longitude", Self::ColumnType::Longitude),
             ("original_filename", Self::ColumnType::OriginalFilename),
             ("time", Self::ColumnType::Time),
+            ("fix_quality", Self::ColumnType::FixQuality),
+            (
+                "horizontal_precision",
+                Self::ColumnType::HorizontalPrecision,
+            ),
+            ("vertical_precision", Self::ColumnType::VerticalPrecision),
         ]
     }
 
@@ -179,6 +181,7 @@ impl CRUDResource for Gnss {
             ("name", Self::ColumnType::Name),
             ("original_filename", Self::ColumnType::OriginalFilename),
             ("comment", Self::ColumnType::Comment),
+            ("fix_quality", Self::ColumnType::FixQuality),
         ]
     }
 }
@@ -190,43 +193,45 @@ pub struct GNSSCreateFromFile {
 }
 
 impl GNSSCreateFromFile {
-    /// Converts the uploaded GPX file (in base64) into a list of `GNSSCreate` models.
-    /// The `srid` parameter defines the target spatial reference (e.g. from your config).
-    pub fn into_gnss_creates(self) -> Result<Vec<GnssCreate>, Box<dyn std::error::Error>> {
-        // Remove known base64 prefix if present
-        let base64_prefix = "data:application/gpx+xml;base64,";
-        let encoded = if self.data_base64.starts_with(base64_prefix) {
-            &self.data_base64[base64_prefix.len()..]
-        } else {
-            &self.data_base64
-        };
-        let decoded_bytes = base64::engine::general_purpose::STANDARD.decode(encoded)?;
-        let gpx_data = String::from_utf8(decoded_bytes)?;
-
-        // Parse the GPX file
-        let cursor = Cursor::new(gpx_data.as_bytes());
-        let gpx = read(cursor)?;
-
-        let mut creates = Vec::new();
-        for wpt in gpx.waypoints {
-            // Time is structured as: 2023-07-20T09:32:34.000000000Z convert to Date
-            let time: DateTime<Utc> =
-                DateTime::parse_from_rfc3339(&wpt.time.unwrap().format().unwrap())?.into();
-
-            creates.push(GnssCreate {
-                latitude: Some(wpt.point().y()),
-                longitude: Some(wpt.point().x()),
-                elevation_gps: wpt.elevation,
-                time: Some(time),
-                name: wpt.name,
-                comment: wpt.comment,
-                original_filename: Some(self.filename.clone()),
-                coord_x: None,
-                coord_y: None,
-                data_base64: None,
-                filename: None,
-            });
+    /// Converts the uploaded file (in base64) into a list of `GnssCreate`
+    /// models. GPX, CSV and NMEA files are accepted, see `super::import`.
+    pub fn into_gnss_creates(self) -> Result<Vec<GnssCreate>, GnssImportError> {
+        let text = decode_base64_text(&self.data_base64).map_err(GnssImportError::file)?;
+        parse_gnss_file(&text, &self.filename)
+    }
+}
+
+impl GnssCreate {
+    /// Unpacks the file of a create request into its points.
+    pub fn into_gnss_creates(self) -> Result<Vec<GnssCreate>, GnssImportError> {
+        let data_base64 = self
+            .data_base64
+            .ok_or_else(|| GnssImportError::file("data_base64 is required"))?;
+        GNSSCreateFromFile {
+            data_base64,
+            filename: self.filename.unwrap_or_default(),
         }
-        Ok(creates)
+        .into_gnss_creates()
+    }
+}
+
+/// Inserts the points of one file in a transaction and returns their ids.
+pub async fn insert_gnss_points(
+    db: &DatabaseConnection,
+    creates: Vec<GnssCreate>,
+) -> Result<Vec<Uuid>, DbErr> {
+    let txn = db.begin().await?;
+    let mut ids = Vec::new();
+    for create in creates {
+        let mut active_model: super::db::ActiveModel = create.into();
+
+        // coord_x, coord_y and coord_srid are generated by the database
+        active_model.coord_x = NotSet;
+        active_model.coord_y = NotSet;
+        active_model.coord_srid = NotSet;
+
+        ids.push(active_model.insert(&txn).await?.id);
     }
+    txn.commit().await?;
+    Ok(ids)
 }
@@ -1,4 +1,5 @@
-use super::models::{Gnss, GnssCreate, GnssUpdate};
+use super::import::GnssImportError;
+use super::models::{Gnss, GnssCreate, GnssUpdate, insert_gnss_points};
 use crate::common::auth::Role;
 use axum_keycloak_auth::{
     PassthroughMode, instance::KeycloakAuthInstance, layer::KeycloakAuthLayer,
@@ -20,7 +21,7 @@ where
     let mut mutating_router = OpenApiRouter::new()
         .routes(routes!(get_one_handler))
         .routes(routes!(get_all_handler))
-        .routes(routes!(create_one_handler))
+        .routes(routes!(create_gnss))
         .routes(routes!(update_one_handler))
         .routes(routes!(delete_one_handler))
         .routes(routes!(delete_many_handler))
@@ -45,3 +46,36 @@ where
 
     mutating_router
 }
+
+#[utoipa::path(
+    post,
+    path = "/",
+    request_body = GnssCreate,
+    responses(
+        (status = 201, description = "Points imported; the first one is returned", body = Gnss),
+        (status = 422, description = "Unreadable file or invalid points, listed per point", body = GnssImportError),
+        (status = 500, description = "Internal server error", body = GnssImportError)
+    ),
+    summary = "Import GNSS recordings from a file",
+    description = "Imports every point of the file in data_base64: GPX waypoints, track and route points, CSV exports of Emlid Reach and Trimble receivers (with solution status and precision columns), or raw NMEA logs (GGA fixes, dated by RMC and with GST precision). The format is taken from the file name extension, or from the content. If any point cannot be read nothing is imported and each failing point is listed."
+)]
+pub async fn create_gnss(
+    State(db): State<DatabaseConnection>,
+    Json(create): Json<GnssCreate>,
+) -> Result<(StatusCode, Json<Gnss>), (StatusCode, Json<GnssImportError>)> {
+    let internal_error = |_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(GnssImportError::file("Internal Server Error")),
+        )
+    };
+    let creates = create
+        .into_gnss_creates()
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, Json(e)))?;
+    let ids = insert_gnss_points(&db, creates)
+        .await
+        .map_err(internal_error)?;
+    let first = ids.first().copied().unwrap_or_default();
+    let gnss = Gnss::get_one(&db, first).await.map_err(internal_error)?;
+    Ok((StatusCode::CREATED, Json(gnss)))
+}
@@ -0,0 +1,504 @@
+//! Baseline estimation for instrument channels. Interpolating methods pass
+//! through the chosen baseline points, while asymmetric least squares and the
+//! rolling ball estimate the baseline from the whole signal.
+
+use super::db::BaselineMethodEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use utoipa::ToSchema;
+
+/// Highest polynomial degree accepted for polynomial baselines
+const MAX_POLYNOMIAL_DEGREE: usize = 10;
+
+/// Tuning parameters of the baseline algorithms, stored as `baseline_parameters`.
+/// Missing fields take their defaults; fields unused by a method are ignored.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BaselineParameters {
+    /// Degree of the polynomial fit
+    pub degree: usize,
+    /// Smoothness penalty of asymmetric least squares
+    pub lambda: f64,
+    /// Weight of points above the baseline in asymmetric least squares (0–1)
+    pub asymmetry: f64,
+    /// Reweighting passes of asymmetric least squares
+    pub iterations: usize,
+    /// Half-width of the rolling ball in x units
+    pub radius: f64,
+}
+
+impl Default for BaselineParameters {
+    fn default() -> Self {
+        Self {
+            degree: 3,
+            lambda: 1e5,
+            asymmetry: 0.01,
+            iterations: 10,
+            radius: 10.0,
+        }
+    }
+}
+
+impl BaselineMethodEnum {
+    /// Whether the method derives the baseline from the chosen baseline points.
+    pub fn uses_chosen_points(self) -> bool {
+        !matches!(self, Self::Als | Self::RollingBall)
+    }
+}
+
+impl BaselineParameters {
+    /// Checks the parameters used by `method`.
+    pub fn validate(&self, method: BaselineMethodEnum) -> Result<(), String> {
+        match method {
+            BaselineMethodEnum::Polynomial if self.degree > MAX_POLYNOMIAL_DEGREE => Err(format!(
+                "Polynomial degree must be at most {MAX_POLYNOMIAL_DEGREE}"
+            )),
+            BaselineMethodEnum::Als if !(self.lambda.is_finite() && self.lambda > 0.0) => {
+                Err("ALS lambda must be positive".into())
+            }
+            BaselineMethodEnum::Als if !(self.asymmetry > 0.0 && self.asymmetry < 1.0) => {
+                Err("ALS asymmetry must be between 0 and 1".into())
+            }
+            BaselineMethodEnum::Als if !(1..=100).contains(&self.iterations) => {
+                Err("ALS iterations must be between 1 and 100".into())
+            }
+            BaselineMethodEnum::RollingBall if !(self.radius.is_finite() && self.radius > 0.0) => {
+                Err("Rolling ball radius must be positive".into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Calculate the baseline of a signal with the given method.
+///
+/// # Arguments
+/// - `x`: Slice of x values (ascending).
+/// - `y`: Slice of y values.
+/// - `baseline_selected_points`: x-values chosen as baseline points; only used by
+///   the interpolating methods and the polynomial fit.
+/// - `method`: Baseline algorithm.
+/// - `parameters`: Tuning parameters of the algorithm.
+///
+/// # Returns
+/// A `Vec<f64>` containing the baseline value for each x.
+pub fn calculate_baseline(
+    x: &[f64],
+    y: &[f64],
+    baseline_selected_points: &[f64],
+    method: BaselineMethodEnum,
+    parameters: &BaselineParameters,
+) -> Result<Vec<f64>, String> {
+    parameters.validate(method)?;
+    let n = x.len().min(y.len());
+    let (x, y) = (&x[..n], &y[..n]);
+
+    match method {
+        BaselineMethodEnum::Als => return Ok(asymmetric_least_squares(y, parameters)),
+        BaselineMethodEnum::RollingBall => return Ok(rolling_ball(x, y, parameters.radius)),
+        _ => {}
+    }
+
+    let points = chosen_points(x, y, baseline_selected_points);
+    if points.is_empty() {
+        return Ok(vec![0.0; n]);
+    }
+    let baseline = match method {
+        BaselineMethodEnum::Polynomial => polynomial_fit(&points, parameters.degree)?.evaluate(x),
+        BaselineMethodEnum::CubicSpline if points.len() > 2 => {
+            let second = natural_spline_second_derivatives(&points);
+            interpolate(x, &points, |j, xi| cubic_spline_at(&points, &second, j, xi))
+        }
+        BaselineMethodEnum::Pchip if points.len() > 2 => {
+            let slopes = pchip_slopes(&points);
+            interpolate(x, &points, |j, xi| hermite_at(&points, &slopes, j, xi))
+        }
+        _ => interpolate(x, &points, |j, xi| {
+            let ((x0, y0), (x1, y1)) = (points[j], points[j + 1]);
+            y0 + (xi - x0) / (x1 - x0) * (y1 - y0)
+        }),
+    };
+    Ok(baseline)
+}
+
+/// Pairs each chosen x with the signal value at that x, sorted and deduplicated.
+fn chosen_points(x: &[f64], y: &[f64], baseline_selected_points: &[f64]) -> Vec<(f64, f64)> {
+    let mut points: Vec<(f64, f64)> = baseline_selected_points
+        .iter()
+        .filter_map(|&bp| {
+            x.iter()
+                .position(|&xi| (xi - bp).abs() < 1e-6)
+                .map(|i| (bp, y[i]))
+        })
+        .collect();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    points.dedup_by(|a, b| (a.0 - b.0).abs() < 1e-6);
+    points
+}
+
+/// Evaluates a piecewise interpolant at every x, holding the end values constant
+/// outside the chosen points. `segment` receives the index of the left point.
+fn interpolate(x: &[f64], points: &[(f64, f64)], segment: impl Fn(usize, f64) -> f64) -> Vec<f64> {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    x.iter()
+        .map(|&xi| {
+            if xi <= first.0 {
+                first.1
+            } else if xi >= last.0 {
+                last.1
+            } else {
+                let j = points.partition_point(|p| p.0 <= xi) - 1;
+                segment(j, xi)
+            }
+        })
+        .collect()
+}
+
+/// Second derivatives of the natural cubic spline through `points`.
+fn natural_spline_second_derivatives(points: &[(f64, f64)]) -> Vec<f64> {
+    let k = points.len();
+    let h: Vec<f64> = points.windows(2).map(|w| w[1].0 - w[0].0).collect();
+    let slope: Vec<f64> = points
+        .windows(2)
+        .zip(&h)
+        .map(|(w, h)| (w[1].1 - w[0].1) / h)
+        .collect();
+
+    // Thomas algorithm on the interior equations; the ends are zero.
+    let mut diag = vec![0.0; k];
+    let mut rhs = vec![0.0; k];
+    for i in 1..k - 1 {
+        diag[i] = 2.0 * (h[i - 1] + h[i]);
+        rhs[i] = 6.0 * (slope[i] - slope[i - 1]);
+        if i > 1 {
+            let factor = h[i - 1] / diag[i - 1];
+            diag[i] -= factor * h[i - 1];
+            rhs[i] -= factor * rhs[i - 1];
+        }
+    }
+    let mut second = vec![0.0; k];
+    for i in (1..k - 1).rev() {
+        second[i] = (rhs[i] - h[i] * second[i + 1]) / diag[i];
+    }
+    second
+}
+
+fn cubic_spline_at(points: &[(f64, f64)], second: &[f64], j: usize, xi: f64) -> f64 {
+    let ((x0, y0), (x1, y1)) = (points[j], points[j + 1]);
+    let h = x1 - x0;
+    let (a, b) = (x1 - xi, xi - x0);
+    second[j] * a.powi(3) / (6.0 * h)
+        + second[j + 1] * b.powi(3) / (6.0 * h)
+        + (y0 / h - second[j] * h / 6.0) * a
+        + (y1 / h - second[j + 1] * h / 6.0) * b
+}
+
+/// Fritsch–Carlson slopes, which keep the interpolant monotone between points.
+fn pchip_slopes(points: &[(f64, f64)]) -> Vec<f64> {
+    let k = points.len();
+    let h: Vec<f64> = points.windows(2).map(|w| w[1].0 - w[0].0).collect();
+    let delta: Vec<f64> = points
+        .windows(2)
+        .zip(&h)
+        .map(|(w, h)| (w[1].1 - w[0].1) / h)
+        .collect();
+
+    let mut slopes = vec![0.0; k];
+    for i in 1..k - 1 {
+        if delta[i - 1] * delta[i] > 0.0 {
+            let w1 = 2.0 * h[i] + h[i - 1];
+            let w2 = h[i] + 2.0 * h[i - 1];
+            slopes[i] = (w1 + w2) / (w1 / delta[i - 1] + w2 / delta[i]);
+        }
+    }
+    slopes[0] = pchip_end_slope(h[0], h[1], delta[0], delta[1]);
+    slopes[k - 1] = pchip_end_slope(h[k - 2], h[k - 3], delta[k - 2], delta[k - 3]);
+    slopes
+}
+
+fn pchip_end_slope(h0: f64, h1: f64, d0: f64, d1: f64) -> f64 {
+    let slope = ((2.0 * h0 + h1) * d0 - h0 * d1) / (h0 + h1);
+    if slope.signum() != d0.signum() {
+        0.0
+    } else if d0.signum() != d1.signum() && slope.abs() > 3.0 * d0.abs() {
+        3.0 * d0
+    } else {
+        slope
+    }
+}
+
+fn hermite_at(points: &[(f64, f64)], slopes: &[f64], j: usize, xi: f64) -> f64 {
+    let ((x0, y0), (x1, y1)) = (points[j], points[j + 1]);
+    let h = x1 - x0;
+    let t = (xi - x0) / h;
+    let (t2, t3) = (t * t, t * t * t);
+    (2.0 * t3 - 3.0 * t2 + 1.0) * y0
+        + (t3 - 2.0 * t2 + t) * h * slopes[j]
+        + (-2.0 * t3 + 3.0 * t2) * y1
+        + (t3 - t2) * h * slopes[j + 1]
+}
+
+/// Least-squares polynomial in a centred and scaled x for conditioning.
+struct Polynomial {
+    coefficients: Vec<f64>,
+    centre: f64,
+    scale: f64,
+}
+
+impl Polynomial {
+    fn evaluate(&self, x: &[f64]) -> Vec<f64> {
+        x.iter()
+            .map(|&xi| {
+                let u = (xi - self.centre) / self.scale;
+                self.coefficients
+                    .iter()
+                    .rev()
+                    .fold(0.0, |acc, c| acc * u + c)
+            })
+            .collect()
+    }
+}
+
+/// Fits a polynomial of `degree` through the points, lowering the degree when
+/// there are too few points to determine it.
+fn polynomial_fit(points: &[(f64, f64)], degree: usize) -> Result<Polynomial, String> {
+    let degree = degree.min(points.len() - 1);
+    let (lo, hi) = (points[0].0, points[points.len() - 1].0);
+    let centre = f64::midpoint(lo, hi);
+    let scale = if hi > lo { (hi - lo) / 2.0 } else { 1.0 };
+
+    // Normal equations of the Vandermonde system.
+    let terms = degree + 1;
+    let mut matrix = vec![vec![0.0; terms + 1]; terms];
+    for &(xi, yi) in points {
+        let u = (xi - centre) / scale;
+        let powers: Vec<f64> = std::iter::successors(Some(1.0), |p| Some(p * u))
+            .take(terms)
+            .collect();
+        for (row, &pr) in matrix.iter_mut().zip(&powers) {
+            for (cell, &pc) in row.iter_mut().zip(&powers) {
+                *cell += pr * pc;
+            }
+            row[terms] += pr * yi;
+        }
+    }
+    let coefficients = solve_augmented(matrix)
+        .ok_or_else(|| "Baseline points do not determine a polynomial".to_string())?;
+    Ok(Polynomial {
+        coefficients,
+        centre,
+        scale,
+    })
+}
+
+/// Gaussian elimination with partial pivoting on an augmented matrix.
+fn solve_augmented(mut matrix: Vec<Vec<f64>>) -> Option<Vec<f64>> {
+    let n = matrix.len();
+    for col in 0..n {
+        let pivot =
+            (col..n).max_by(|&a, &b| matrix[a][col].abs().total_cmp(&matrix[b][col].abs()))?;
+        if matrix[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        matrix.swap(col, pivot);
+        for row in col + 1..n {
+            let (upper, lower) = matrix.split_at_mut(row);
+            let (pivot_row, target) = (&upper[col], &mut lower[0]);
+            let factor = target[col] / pivot_row[col];
+            for (cell, pivot) in target[col..].iter_mut().zip(&pivot_row[col..]) {
+                *cell -= factor * pivot;
+            }
+        }
+    }
+    let mut solution = vec![0.0; n];
+    for row in (0..n).rev() {
+        let known: f64 = (row + 1..n).map(|k| matrix[row][k] * solution[k]).sum();
+        solution[row] = (matrix[row][n] - known) / matrix[row][row];
+    }
+    Some(solution)
+}
+
+/// Asymmetric least squares (Eilers and Boelens): a second-difference smoother
+/// whose weights favour points below the current baseline estimate. Assumes
+/// evenly spaced samples.
+fn asymmetric_least_squares(y: &[f64], parameters: &BaselineParameters) -> Vec<f64> {
+    let n = y.len();
+    if n < 3 {
+        return y.to_vec();
+    }
+
+    // Lower bands (diagonal, first and second subdiagonal) of lambda * D'D.
+    let mut penalty = vec![[0.0_f64; 3]; n];
+    for r in 0..n - 2 {
+        let coefficients = [1.0, -2.0, 1.0];
+        for (a, ca) in coefficients.iter().enumerate() {
+            for (b, cb) in coefficients.iter().enumerate().take(a + 1) {
+                penalty[r + a][a - b] += parameters.lambda * ca * cb;
+            }
+        }
+    }
+
+    let mut weights = vec![1.0; n];
+    let mut baseline = y.to_vec();
+    for _ in 0..parameters.iterations {
+        let mut bands = penalty.clone();
+        for (band, w) in bands.iter_mut().zip(&weights) {
+            band[0] += w;
+        }
+        let rhs: Vec<f64> = weights.iter().zip(y).map(|(w, yi)| w * yi).collect();
+        baseline = solve_pentadiagonal(&bands, &rhs);
+        for ((w, yi), zi) in weights.iter_mut().zip(y).zip(&baseline) {
+            *w = if yi > zi {
+                parameters.asymmetry
+            } else {
+                1.0 - parameters.asymmetry
+            };
+        }
+    }
+    baseline
+}
+
+/// Banded Cholesky solve of a symmetric positive definite pentadiagonal system
+/// given by its lower bands.
+fn solve_pentadiagonal(bands: &[[f64; 3]], rhs: &[f64]) -> Vec<f64> {
+    let n = bands.len();
+    let mut factor = vec![[0.0_f64; 3]; n];
+    for i in 0..n {
+        if i >= 2 {
+            factor[i][2] = bands[i][2] / factor[i - 2][0];
+        }
+        if i >= 1 {
+            factor[i][1] = (bands[i][1] - factor[i][2] * factor[i - 1][1]) / factor[i - 1][0];
+        }
+        factor[i][0] = (bands[i][0] - factor[i][1].powi(2) - factor[i][2].powi(2)).sqrt();
+    }
+
+    let mut forward = vec![0.0; n];
+    for i in 0..n {
+        let mut value = rhs[i];
+        if i >= 1 {
+            value -= factor[i][1] * forward[i - 1];
+        }
+        if i >= 2 {
+            value -= factor[i][2] * forward[i - 2];
+        }
+        forward[i] = value / factor[i][0];
+    }
+    let mut solution = vec![0.0; n];
+    for i in (0..n).rev() {
+        let mut value = forward[i];
+        if i + 1 < n {
+            value -= factor[i + 1][1] * solution[i + 1];
+        }
+        if i + 2 < n {
+            value -= factor[i + 2][2] * solution[i + 2];
+        }
+        solution[i] = value / factor[i][0];
+    }
+    solution
+}
+
+/// Rolling-ball baseline as a morphological opening (erosion then dilation)
+/// with a flat window of `radius` x units on either side.
+fn rolling_ball(x: &[f64], y: &[f64], radius: f64) -> Vec<f64> {
+    let eroded = sliding_extreme(x, y, radius, |a, b| a <= b);
+    sliding_extreme(x, &eroded, radius, |a, b| a >= b)
+}
+
+/// Sliding minimum or maximum over `|x_j - x_i| <= radius` using a monotone
+/// deque; `keeps(a, b)` is true when `a` should displace `b`.
+fn sliding_extreme(
+    x: &[f64],
+    y: &[f64],
+    radius: f64,
+    keeps: impl Fn(f64, f64) -> bool,
+) -> Vec<f64> {
+    let n = x.len();
+    let mut window: VecDeque<usize> = VecDeque::new();
+    let mut next = 0;
+    let mut result = Vec::with_capacity(n);
+    for i in 0..n {
+        while next < n && x[next] <= x[i] + radius {
+            while window.back().is_some_and(|&j| keeps(y[next], y[j])) {
+                window.pop_back();
+            }
+            window.push_back(next);
+            next += 1;
+        }
+        while window.front().is_some_and(|&j| x[j] < x[i] - radius) {
+            window.pop_front();
+        }
+        result.push(window.front().map_or(y[i], |&j| y[j]));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(n: usize) -> Vec<f64> {
+        std::iter::successors(Some(0.0), |v| Some(v + 1.0))
+            .take(n)
+            .collect()
+    }
+
+    #[test]
+    fn interpolating_methods_pass_through_chosen_points() {
+        let x = grid(21);
+        let y: Vec<f64> = x.iter().map(|v| 0.05 * v * v + 1.0).collect();
+        let chosen = [0.0, 5.0, 12.0, 20.0];
+        let parameters = BaselineParameters {
+            degree: 2,
+            ..BaselineParameters::default()
+        };
+
+        for method in [
+            BaselineMethodEnum::Linear,
+            BaselineMethodEnum::CubicSpline,
+            BaselineMethodEnum::Pchip,
+            BaselineMethodEnum::Polynomial,
+        ] {
+            let baseline = calculate_baseline(&x, &y, &chosen, method, &parameters).unwrap();
+            for &c in &chosen {
+                let i = x.iter().position(|&v| (v - c).abs() < 1e-9).unwrap();
+                assert!((baseline[i] - y[i]).abs() < 1e-9, "{method:?} at {c}");
+            }
+        }
+
+        // A quadratic signal is recovered exactly by a quadratic fit, and PCHIP
+        // stays monotone on monotone data.
+        let fit = calculate_baseline(&x, &y, &chosen, BaselineMethodEnum::Polynomial, &parameters)
+            .unwrap();
+        assert!(fit.iter().zip(&y).all(|(a, b)| (a - b).abs() < 1e-9));
+        let pchip =
+            calculate_baseline(&x, &y, &chosen, BaselineMethodEnum::Pchip, &parameters).unwrap();
+        assert!(pchip.windows(2).all(|w| w[1] >= w[0]));
+    }
+
+    #[test]
+    fn automatic_methods_ignore_narrow_peaks() {
+        let x = grid(200);
+        let y: Vec<f64> = x
+            .iter()
+            .map(|v| 2.0 + 0.01 * v + 10.0 * (-(v - 100.0).powi(2) / 8.0).exp())
+            .collect();
+        let parameters = BaselineParameters::default();
+
+        for method in [BaselineMethodEnum::Als, BaselineMethodEnum::RollingBall] {
+            let baseline = calculate_baseline(&x, &y, &[], method, &parameters).unwrap();
+            let expected = 2.0 + 0.01 * 100.0;
+            assert!((baseline[100] - expected).abs() < 0.5, "{method:?}");
+        }
+    }
+
+    #[test]
+    fn invalid_parameters_are_rejected() {
+        let parameters = BaselineParameters {
+            asymmetry: 1.5,
+            ..BaselineParameters::default()
+        };
+        assert!(parameters.validate(BaselineMethodEnum::Als).is_err());
+        assert!(parameters.validate(BaselineMethodEnum::Linear).is_ok());
+    }
+}
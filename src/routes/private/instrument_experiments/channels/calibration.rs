@@ -0,0 +1,263 @@
+//! Calibration of integrated peak areas against standards of known
+//! concentration, and inverse prediction of unknown samples.
+
+use super::db::CalibrationModelEnum;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Least-squares fit of area against concentration
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq)]
+pub struct CalibrationCurve {
+    pub model: CalibrationModelEnum,
+    /// Polynomial coefficients from the intercept upwards
+    pub coefficients: Vec<f64>,
+    /// Covariance matrix of the coefficients
+    pub covariance: Vec<Vec<f64>>,
+    pub r_squared: f64,
+    /// Standard deviation of the area residuals
+    pub residual_sd: f64,
+    pub standards: usize,
+    /// Lowest and highest standard concentration
+    pub concentration_range: (f64, f64),
+}
+
+/// Concentration predicted from an area
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prediction {
+    pub concentration: f64,
+    /// Standard deviation from the residual scatter and the coefficient
+    /// uncertainty (delta method)
+    pub sd: f64,
+}
+
+impl CalibrationModelEnum {
+    fn terms(self) -> usize {
+        match self {
+            Self::Linear => 2,
+            Self::Quadratic => 3,
+        }
+    }
+}
+
+/// Fits the calibration curve to `(concentration, area)` standards. One more
+/// standard than coefficients is needed to estimate the residual scatter.
+pub fn fit_calibration(
+    model: CalibrationModelEnum,
+    standards: &[(f64, f64)],
+) -> Result<CalibrationCurve, String> {
+    let terms = model.terms();
+    let distinct = {
+        let mut concentrations: Vec<f64> = standards.iter().map(|s| s.0).collect();
+        concentrations.sort_by(f64::total_cmp);
+        concentrations.dedup_by(|a, b| (*a - *b).abs() < f64::EPSILON);
+        concentrations.len()
+    };
+    if standards.len() <= terms || distinct < terms {
+        return Err(format!(
+            "A {} calibration needs at least {} standards at {terms} different concentrations",
+            match model {
+                CalibrationModelEnum::Linear => "linear",
+                CalibrationModelEnum::Quadratic => "quadratic",
+            },
+            terms + 1
+        ));
+    }
+
+    let powers = |c: f64| -> Vec<f64> {
+        std::iter::successors(Some(1.0), move |p| Some(p * c))
+            .take(terms)
+            .collect()
+    };
+    let mut normal = vec![vec![0.0; terms]; terms];
+    let mut moment = vec![0.0; terms];
+    for &(c, area) in standards {
+        let row = powers(c);
+        for (i, ri) in row.iter().enumerate() {
+            for (j, rj) in row.iter().enumerate() {
+                normal[i][j] += ri * rj;
+            }
+            moment[i] += ri * area;
+        }
+    }
+    let inverse = invert(normal).ok_or("Standards do not determine a calibration curve")?;
+    let coefficients: Vec<f64> = inverse
+        .iter()
+        .map(|row| row.iter().zip(&moment).map(|(a, b)| a * b).sum())
+        .collect();
+
+    let fitted = |c: f64| dot(&powers(c), &coefficients);
+    let n = f64::from(u32::try_from(standards.len()).unwrap_or(u32::MAX));
+    let dof = n - f64::from(u32::try_from(terms).unwrap_or(u32::MAX));
+    let mean_area = standards.iter().map(|s| s.1).sum::<f64>() / n;
+    let ss_res: f64 = standards
+        .iter()
+        .map(|&(c, a)| (a - fitted(c)).powi(2))
+        .sum();
+    let ss_tot: f64 = standards
+        .iter()
+        .map(|&(_, a)| (a - mean_area).powi(2))
+        .sum();
+    let variance = ss_res / dof;
+
+    let (lo, hi) = standards
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), s| {
+            (lo.min(s.0), hi.max(s.0))
+        });
+    Ok(CalibrationCurve {
+        model,
+        covariance: inverse
+            .iter()
+            .map(|row| row.iter().map(|v| v * variance).collect())
+            .collect(),
+        coefficients,
+        r_squared: if ss_tot > 0.0 {
+            1.0 - ss_res / ss_tot
+        } else {
+            1.0
+        },
+        residual_sd: variance.sqrt(),
+        standards: standards.len(),
+        concentration_range: (lo, hi),
+    })
+}
+
+impl CalibrationCurve {
+    /// Inverts the curve at `area`. For a quadratic curve the root on the same
+    /// branch as the standards and closest to their range is used; areas the
+    /// curve never reaches give `None`.
+    pub fn predict(&self, area: f64) -> Option<Prediction> {
+        let b = &self.coefficients;
+        let (lo, hi) = self.concentration_range;
+        let concentration = if b.len() == 3 && b[2].abs() > f64::EPSILON {
+            let discriminant = b[1].powi(2) - 4.0 * b[2] * (b[0] - area);
+            if discriminant < 0.0 {
+                return None;
+            }
+            let overall_slope = self.area_at(hi) - self.area_at(lo);
+            [1.0, -1.0]
+                .iter()
+                .map(|sign| (-b[1] + sign * discriminant.sqrt()) / (2.0 * b[2]))
+                .filter(|&c| self.slope_at(c) * overall_slope > 0.0)
+                .min_by(|a, b| distance(*a, lo, hi).total_cmp(&distance(*b, lo, hi)))?
+        } else {
+            if b[1].abs() < f64::EPSILON {
+                return None;
+            }
+            (area - b[0]) / b[1]
+        };
+
+        // Delta method: a new area measurement plus the uncertainty of the
+        // fitted curve at that concentration, scaled by the curve's slope.
+        let gradient: Vec<f64> = std::iter::successors(Some(1.0), |p| Some(p * concentration))
+            .take(b.len())
+            .collect();
+        let curve_variance: f64 = self
+            .covariance
+            .iter()
+            .zip(&gradient)
+            .map(|(row, gi)| gi * dot(row, &gradient))
+            .sum();
+        let slope = self.slope_at(concentration);
+        let sd = ((self.residual_sd.powi(2) + curve_variance).sqrt() / slope).abs();
+        Some(Prediction { concentration, sd })
+    }
+
+    fn area_at(&self, c: f64) -> f64 {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(0.0, |acc, b| acc * c + b)
+    }
+
+    fn slope_at(&self, c: f64) -> f64 {
+        let b = &self.coefficients;
+        b[1] + if b.len() == 3 { 2.0 * b[2] * c } else { 0.0 }
+    }
+}
+
+fn distance(c: f64, lo: f64, hi: f64) -> f64 {
+    if c < lo {
+        lo - c
+    } else if c > hi {
+        c - hi
+    } else {
+        0.0
+    }
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Gauss–Jordan inversion of a small square matrix.
+fn invert(mut matrix: Vec<Vec<f64>>) -> Option<Vec<Vec<f64>>> {
+    let n = matrix.len();
+    let mut inverse: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect();
+    for col in 0..n {
+        let pivot =
+            (col..n).max_by(|&a, &b| matrix[a][col].abs().total_cmp(&matrix[b][col].abs()))?;
+        if matrix[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        matrix.swap(col, pivot);
+        inverse.swap(col, pivot);
+        let scale = matrix[col][col];
+        for j in 0..n {
+            matrix[col][j] /= scale;
+            inverse[col][j] /= scale;
+        }
+        for row in 0..n {
+            if row == col {
+                continue;
+            }
+            let factor = matrix[row][col];
+            for j in 0..n {
+                matrix[row][j] -= factor * matrix[col][j];
+                inverse[row][j] -= factor * inverse[col][j];
+            }
+        }
+    }
+    Some(inverse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linear_curve_matches_classical_inverse_prediction() {
+        let standards = [(0.0, 0.1), (10.0, 20.3), (20.0, 39.8), (40.0, 80.4)];
+        let curve = fit_calibration(CalibrationModelEnum::Linear, &standards).unwrap();
+        assert!(curve.r_squared > 0.999);
+
+        let area = 50.0;
+        let prediction = curve.predict(area).unwrap();
+        let (b0, b1) = (curve.coefficients[0], curve.coefficients[1]);
+        assert!((prediction.concentration - (area - b0) / b1).abs() < 1e-9);
+
+        // s_x0 = s_y / b1 * sqrt(1 + 1/n + (y0 - mean_y)^2 / (b1^2 * Sxx))
+        let mean_c = 17.5;
+        let mean_area = standards.iter().map(|s| s.1).sum::<f64>() / 4.0;
+        let sxx: f64 = standards.iter().map(|s| (s.0 - mean_c).powi(2)).sum();
+        let expected = curve.residual_sd / b1
+            * (1.0 + 0.25 + (area - mean_area).powi(2) / (b1 * b1 * sxx)).sqrt();
+        assert!((prediction.sd - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn quadratic_curve_picks_root_on_the_standards_branch() {
+        // area = 1 + 2c + 0.05c^2, exact
+        let standards: Vec<(f64, f64)> = [0.0, 5.0, 10.0, 15.0, 20.0]
+            .iter()
+            .map(|&c| (c, 1.0 + 2.0 * c + 0.05 * c * c))
+            .collect();
+        let curve = fit_calibration(CalibrationModelEnum::Quadratic, &standards).unwrap();
+        let prediction = curve.predict(1.0 + 2.0 * 12.0 + 0.05 * 144.0).unwrap();
+        assert!((prediction.concentration - 12.0).abs() < 1e-6);
+
+        assert!(fit_calibration(CalibrationModelEnum::Quadratic, &standards[..3]).is_err());
+    }
+}
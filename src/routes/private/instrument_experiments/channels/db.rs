@@ -1,20 +1,131 @@
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 use serde_json::Value as Json;
+use utoipa::ToSchema;
 use uuid::Uuid;
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+
+/// Rule used to integrate the baseline-filtered signal between chosen pairs
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    EnumIter,
+    DeriveActiveEnum,
+    ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "integration_method_enum"
+)]
+pub enum IntegrationMethodEnum {
+    #[sea_orm(string_value = "trapezoid")]
+    Trapezoid,
+    /// Composite Simpson's rule, falling back to the non-uniform form on irregular spacing
+    #[default]
+    #[sea_orm(string_value = "simpson")]
+    Simpson,
+    /// Running trapezoid integral, also returned point by point
+    #[sea_orm(string_value = "cumulative")]
+    Cumulative,
+}
+
+/// Algorithm used to estimate the baseline of a channel
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    EnumIter,
+    DeriveActiveEnum,
+    ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "baseline_method_enum"
+)]
+pub enum BaselineMethodEnum {
+    /// Straight lines between the chosen baseline points
+    #[default]
+    #[sea_orm(string_value = "linear")]
+    Linear,
+    /// Natural cubic spline through the chosen baseline points
+    #[sea_orm(string_value = "cubic_spline")]
+    CubicSpline,
+    /// Shape-preserving piecewise cubic Hermite interpolation of the chosen points
+    #[sea_orm(string_value = "pchip")]
+    Pchip,
+    /// Least-squares polynomial through the chosen baseline points
+    #[sea_orm(string_value = "polynomial")]
+    Polynomial,
+    /// Asymmetric least squares smoothing of the whole signal
+    #[sea_orm(string_value = "als")]
+    Als,
+    /// Rolling-ball (morphological opening) of the whole signal
+    #[sea_orm(string_value = "rolling_ball")]
+    RollingBall,
+}
+
+/// Curve fitted to the standards of a channel, area against concentration
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    EnumIter,
+    DeriveActiveEnum,
+    ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "calibration_model_enum"
+)]
+pub enum CalibrationModelEnum {
+    #[default]
+    #[sea_orm(string_value = "linear")]
+    Linear,
+    #[sea_orm(string_value = "quadratic")]
+    Quadratic,
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
 #[sea_orm(table_name = "instrumentexperimentchannel")]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: Uuid,
     pub channel_name: String,
     pub experiment_id: Uuid,
-    pub baseline_spline: Option<Json>,
-    pub time_values: Option<Json>,
-    pub raw_values: Option<Json>,
-    pub baseline_values: Option<Json>,
+    pub baseline_spline: Option<Vec<f64>>,
+    pub time_values: Option<Vec<f64>>,
+    pub raw_values: Option<Vec<f64>>,
+    pub baseline_values: Option<Vec<f64>>,
     pub baseline_chosen_points: Option<Json>,
     pub integral_chosen_pairs: Option<Json>,
     pub integral_results: Option<Json>,
+    pub integration_method: IntegrationMethodEnum,
+    pub baseline_method: BaselineMethodEnum,
+    pub baseline_parameters: Option<Json>,
+    #[allow(clippy::struct_field_names)]
+    pub calibration_model: CalibrationModelEnum,
+    pub calibration: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -1,4 +1,7 @@
+mod baseline;
+pub mod calibration;
 pub mod db;
 pub mod models;
+pub mod peaks;
 mod tools;
 pub mod views;
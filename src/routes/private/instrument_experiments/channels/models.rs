@@ -3,15 +3,17 @@
 use async_trait::async_trait;
 use crudcrate::{CRUDResource, ToCreateModel, ToUpdateModel, traits::MergeIntoActiveModel};
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait,
-    Order, QueryOrder, QuerySelect, entity::prelude::*,
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection,
+    DbErr, EntityTrait, Order, QueryOrder, QuerySelect, TransactionTrait, entity::prelude::*,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value as Json;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-use super::db::Model;
+use super::baseline::{BaselineParameters, calculate_baseline};
+use super::db::{BaselineMethodEnum, CalibrationModelEnum, IntegrationMethodEnum, Model};
+use super::peaks::{PeakParameters, detect_peaks};
 
 /// The API model for an instrument experiment channel.
 #[derive(Clone, ToSchema, Serialize, Deserialize, ToCreateModel, ToUpdateModel)]
@@ -21,13 +23,135 @@ pub struct InstrumentExperimentChannel {
     pub id: Uuid,
     pub channel_name: String,
     pub experiment_id: Uuid,
-    pub baseline_spline: Option<Json>,
-    pub time_values: Option<Json>,
-    pub raw_values: Option<Json>,
-    pub baseline_values: Option<Json>,
+    pub baseline_spline: Option<Vec<f64>>,
+    pub time_values: Option<Vec<f64>>,
+    pub raw_values: Option<Vec<f64>>,
+    pub baseline_values: Option<Vec<f64>>,
     pub baseline_chosen_points: Option<Json>,
     pub integral_chosen_pairs: Option<Json>,
     pub integral_results: Option<Json>,
+    #[crudcrate(on_create = IntegrationMethodEnum::default())]
+    pub integration_method: IntegrationMethodEnum,
+    #[crudcrate(on_create = BaselineMethodEnum::default())]
+    pub baseline_method: BaselineMethodEnum,
+    /// Tuning parameters of the baseline method, see `BaselineParameters`
+    pub baseline_parameters: Option<Json>,
+    #[crudcrate(on_create = CalibrationModelEnum::default())]
+    pub calibration_model: CalibrationModelEnum,
+    /// Curve fitted to the standards in `integral_results`, see `CalibrationCurve`
+    pub calibration: Option<Json>,
+}
+
+/// The value held by an active model field, whether freshly set or loaded.
+fn stored_value<T: Clone + Into<sea_orm::Value>>(value: &ActiveValue<T>) -> Option<T> {
+    match value {
+        ActiveValue::Set(v) | ActiveValue::Unchanged(v) => Some(v.clone()),
+        ActiveValue::NotSet => None,
+    }
+}
+
+/// The values of the updated array field, falling back to the stored ones.
+fn stored_f64s(
+    active: &ActiveValue<Option<Vec<f64>>>,
+    original: &ActiveValue<Option<Vec<f64>>>,
+) -> Vec<f64> {
+    stored_value(active)
+        .flatten()
+        .or_else(|| stored_value(original).flatten())
+        .unwrap_or_default()
+}
+
+/// Recomputes `baseline_spline` and `baseline_values` with the channel's baseline method.
+fn apply_baseline(
+    active_model: &mut super::db::ActiveModel,
+    original_model: &super::db::ActiveModel,
+    baseline_chosen_points_json: Option<Json>,
+) -> Result<(), DbErr> {
+    let baseline_chosen_points: Vec<serde_json::Value> = baseline_chosen_points_json
+        .map_or_else(Vec::new, |json| {
+            serde_json::from_value(json).unwrap_or_default()
+        });
+    let method = stored_value(&active_model.baseline_method)
+        .or_else(|| stored_value(&original_model.baseline_method))
+        .unwrap_or_default();
+    let parameters: BaselineParameters = match stored_value(&active_model.baseline_parameters)
+        .or_else(|| stored_value(&original_model.baseline_parameters))
+        .flatten()
+    {
+        Some(json) => serde_json::from_value(json)
+            .map_err(|e| DbErr::Custom(format!("Invalid baseline parameters: {e}")))?,
+        None => BaselineParameters::default(),
+    };
+
+    let x = stored_f64s(&active_model.time_values, &original_model.time_values);
+    let y = stored_f64s(&active_model.raw_values, &original_model.raw_values);
+
+    if baseline_chosen_points.is_empty() && method.uses_chosen_points() {
+        active_model.baseline_spline = ActiveValue::Set(Some(Vec::new()));
+        active_model.baseline_values = ActiveValue::Set(Some(Vec::new()));
+        return Ok(());
+    }
+
+    // Extract chosen x-values.
+    let chosen_points: Vec<f64> = baseline_chosen_points
+        .into_iter()
+        .filter_map(|bp| bp.get("x").and_then(sea_orm::JsonValue::as_f64))
+        .collect();
+
+    let spline =
+        calculate_baseline(&x, &y, &chosen_points, method, &parameters).map_err(DbErr::Custom)?;
+    let filtered_baseline = super::tools::filter_baseline(&y, &spline);
+
+    active_model.baseline_spline = ActiveValue::Set(Some(spline));
+    active_model.baseline_values = ActiveValue::Set(Some(filtered_baseline));
+    Ok(())
+}
+
+/// Integrates the baseline-filtered signal between each chosen pair into `integral_results`.
+fn apply_integrals(
+    active_model: &mut super::db::ActiveModel,
+    original_model: &super::db::ActiveModel,
+    integral_chosen_pairs_json: Option<Json>,
+) -> Result<(), DbErr> {
+    let baseline_values = stored_f64s(
+        &active_model.baseline_values,
+        &original_model.baseline_values,
+    );
+    let time_values = stored_f64s(&active_model.time_values, &original_model.time_values);
+
+    let integral_chosen_pairs: Vec<serde_json::Value> = integral_chosen_pairs_json
+        .map_or_else(Vec::new, |json| {
+            serde_json::from_value(json).unwrap_or_default()
+        });
+
+    let integration_method = stored_value(&active_model.integration_method)
+        .or_else(|| stored_value(&original_model.integration_method))
+        .unwrap_or_default();
+
+    let mut integral_results = super::tools::calculate_integrals_for_pairs(
+        &integral_chosen_pairs,
+        &baseline_values,
+        &time_values,
+        integration_method,
+    );
+
+    let calibration_model = stored_value(&active_model.calibration_model)
+        .or_else(|| stored_value(&original_model.calibration_model))
+        .unwrap_or_default();
+    let calibration = super::tools::apply_calibration(&mut integral_results, calibration_model)
+        .map_err(DbErr::Custom)?;
+
+    let to_json = |e: serde_json::Error| DbErr::Custom(format!("Invalid integral results: {e}"));
+    active_model.integral_results = ActiveValue::Set(Some(
+        serde_json::to_value(&integral_results).map_err(to_json)?,
+    ));
+    active_model.calibration = ActiveValue::Set(
+        calibration
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(to_json)?,
+    );
+    Ok(())
 }
 
 impl From<Model> for InstrumentExperimentChannel {
@@ -43,7 +167,73 @@ impl From<Model> for InstrumentExperimentChannel {
             baseline_chosen_points: model.baseline_chosen_points,
             integral_chosen_pairs: model.integral_chosen_pairs,
             integral_results: model.integral_results,
+            integration_method: model.integration_method,
+            baseline_method: model.baseline_method,
+            baseline_parameters: model.baseline_parameters,
+            calibration_model: model.calibration_model,
+            calibration: model.calibration,
+        }
+    }
+}
+
+impl InstrumentExperimentChannel {
+    /// Merges `update_model` into the channel and recomputes its baseline and
+    /// integrals as needed.
+    async fn apply_update<C: ConnectionTrait>(
+        db: &C,
+        id: Uuid,
+        update_model: InstrumentExperimentChannelUpdate,
+    ) -> Result<Model, DbErr> {
+        // Fetch the original record as an ActiveModel.
+        let original_model: super::db::ActiveModel = super::db::Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound(
+                "Instrument experiment channel not found".into(),
+            ))?
+            .into();
+
+        // Merge the update payload into the original model.
+        let mut active_model = update_model
+            .clone()
+            .merge_into_activemodel(original_model.clone());
+
+        // --- Process baseline_chosen_points ---
+        // A new method or new parameters recompute the baseline from the stored points.
+        let baseline_changed = update_model.baseline_chosen_points.is_some()
+            || update_model.baseline_method.flatten().is_some()
+            || update_model.baseline_parameters.is_some();
+        if baseline_changed {
+            let baseline_chosen_points_json = match update_model.baseline_chosen_points {
+                Some(points) => points,
+                None => stored_value(&original_model.baseline_chosen_points).flatten(),
+            };
+            apply_baseline(
+                &mut active_model,
+                &original_model,
+                baseline_chosen_points_json,
+            )?;
+        }
+
+        // --- Process integral_chosen_pairs ---
+        // Changing the integration method, calibration model or baseline
+        // re-integrates the stored pairs.
+        let rerun = baseline_changed
+            || update_model.integration_method.flatten().is_some()
+            || update_model.calibration_model.flatten().is_some();
+        let integral_chosen_pairs_json = update_model
+            .integral_chosen_pairs
+            .or_else(|| stored_value(&original_model.integral_chosen_pairs).filter(|_| rerun));
+        if let Some(integral_chosen_pairs_json) = integral_chosen_pairs_json {
+            apply_integrals(
+                &mut active_model,
+                &original_model,
+                integral_chosen_pairs_json,
+            )?;
         }
+
+        // Execute the update in the database.
+        active_model.update(db).await
     }
 }
 
@@ -97,133 +287,278 @@ impl CRUDResource for InstrumentExperimentChannel {
         id: Uuid,
         update_model: Self::UpdateModel,
     ) -> Result<Self, DbErr> {
-        // Fetch the original record as an ActiveModel.
-        let original_model: super::db::ActiveModel = Self::EntityType::find_by_id(id)
-            .one(db)
-            .await?
-            .ok_or(DbErr::RecordNotFound(
-                "Instrument experiment channel not found".into(),
-            ))?
-            .into();
+        let response_obj = Self::apply_update(db, id, update_model).await?;
+        let obj = Self::get_one(db, response_obj.id).await?;
 
-        // Merge the update payload into the original model.
-        let mut active_model = update_model
-            .clone()
-            .merge_into_activemodel(original_model.clone());
+        Ok(obj)
+    }
 
-        // --- Process baseline_chosen_points ---
-        if let Some(baseline_chosen_points_json) = update_model.baseline_chosen_points {
-            let baseline_chosen_points: Vec<serde_json::Value> = baseline_chosen_points_json
-                .map_or_else(Vec::new, |json| {
-                    serde_json::from_value(json).unwrap_or_default()
-                });
-
-            // Extract time values (x) from active_model or fallback to the original.
-            let x: Vec<f64> = match &active_model.time_values {
-                ActiveValue::Set(Some(json)) | ActiveValue::Unchanged(Some(json)) => {
-                    serde_json::from_value(json.clone()).unwrap_or_default()
-                }
-                _ => match &original_model.time_values {
-                    ActiveValue::Set(Some(json)) | ActiveValue::Unchanged(Some(json)) => {
-                        serde_json::from_value(json.clone()).unwrap_or_default()
-                    }
-                    _ => Vec::new(),
-                },
-            };
+    fn sortable_columns() -> Vec<(&'static str, Self::ColumnType)> {
+        vec![
+            ("id", super::db::Column::Id),
+            ("channel_name", super::db::Column::ChannelName),
+        ]
+    }
 
-            // Extract raw values (y) similarly.
-            let y: Vec<f64> = match &active_model.raw_values {
-                ActiveValue::Set(Some(json)) | ActiveValue::Unchanged(Some(json)) => {
-                    serde_json::from_value(json.clone()).unwrap_or_default()
-                }
-                _ => match &original_model.raw_values {
-                    ActiveValue::Set(Some(json)) | ActiveValue::Unchanged(Some(json)) => {
-                        serde_json::from_value(json.clone()).unwrap_or_default()
-                    }
-                    _ => Vec::new(),
-                },
-            };
+    fn filterable_columns() -> Vec<(&'static str, Self::ColumnType)> {
+        vec![("channel_name", super::db::Column::ChannelName)]
+    }
+}
 
-            if baseline_chosen_points.is_empty() {
-                // Since the field expects a Json (not an Option), supply a JSON array.
-                active_model.baseline_spline = ActiveValue::Set(Some(serde_json::json!([])));
-                active_model.baseline_values = ActiveValue::Set(Some(serde_json::json!([])));
-            } else {
-                // Extract chosen x-values.
-                let chosen_points: Vec<f64> = baseline_chosen_points
-                    .into_iter()
-                    .filter_map(|bp| bp.get("x").and_then(sea_orm::JsonValue::as_f64))
-                    .collect();
-
-                let spline = super::tools::calculate_spline(&x, &y, &chosen_points, "linear");
-
-                let filtered_baseline = super::tools::filter_baseline(&y, &spline);
-
-                // Now set the computed values (no extra Some() wrapping).
-                active_model.baseline_spline =
-                    ActiveValue::Set(Some(serde_json::to_value(&spline).unwrap()));
-                active_model.baseline_values =
-                    ActiveValue::Set(Some(serde_json::to_value(&filtered_baseline).unwrap()));
-            }
-        }
+/// A point of a suggested integration pair
+#[derive(ToSchema, Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct PairPoint {
+    pub x: f64,
+    pub y: f64,
+}
 
-        // --- Process integral_chosen_pairs ---
-        if let Some(integral_chosen_pairs_json) = update_model.integral_chosen_pairs {
-            let baseline_values: Vec<f64> = match &active_model.baseline_values {
-                ActiveValue::Set(Some(json)) | ActiveValue::Unchanged(Some(json)) => {
-                    serde_json::from_value(json.clone()).unwrap_or_default()
-                }
-                _ => match &original_model.baseline_values {
-                    ActiveValue::Set(Some(json)) | ActiveValue::Unchanged(Some(json)) => {
-                        serde_json::from_value(json.clone()).unwrap_or_default()
-                    }
-                    _ => Vec::new(),
-                },
-            };
+/// Integration pair suggested by peak detection, in the shape of
+/// `integral_chosen_pairs` entries.
+#[derive(ToSchema, Serialize, Deserialize, Debug, Clone)]
+pub struct SuggestedPair {
+    pub start: PairPoint,
+    pub end: PairPoint,
+    pub apex: PairPoint,
+    pub prominence: f64,
+    /// Width at half prominence in x units
+    pub width: f64,
+    pub sample_name: String,
+}
 
-            let time_values: Vec<f64> = match &active_model.time_values {
-                ActiveValue::Set(Some(json)) | ActiveValue::Unchanged(Some(json)) => {
-                    serde_json::from_value(json.clone()).unwrap_or_default()
-                }
-                _ => match &original_model.time_values {
-                    ActiveValue::Set(Some(json)) | ActiveValue::Unchanged(Some(json)) => {
-                        serde_json::from_value(json.clone()).unwrap_or_default()
-                    }
-                    _ => Vec::new(),
-                },
-            };
+/// Detects peaks in the channel's `baseline_values` and suggests integration
+/// pairs, named in order from the experiment's `sample_names`.
+pub async fn suggest_integral_pairs(
+    db: &DatabaseConnection,
+    id: Uuid,
+    parameters: &PeakParameters,
+) -> Result<Vec<SuggestedPair>, DbErr> {
+    let (channel, experiment) = super::db::Entity::find_by_id(id)
+        .find_also_related(crate::routes::private::instrument_experiments::db::Entity)
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound(
+            "Instrument experiment channel not found".into(),
+        ))?;
 
-            let integral_chosen_pairs: Vec<serde_json::Value> = integral_chosen_pairs_json
-                .map_or_else(Vec::new, |json| {
-                    serde_json::from_value(json).unwrap_or_default()
-                });
+    let x = channel.time_values.unwrap_or_default();
+    let y = channel.baseline_values.unwrap_or_default();
+    if y.is_empty() {
+        return Err(DbErr::Custom(
+            "Channel has no baseline values; choose a baseline first".into(),
+        ));
+    }
+    let sample_names: Vec<String> = experiment
+        .and_then(|e| e.sample_names)
+        .and_then(|json| serde_json::from_value(json).ok())
+        .unwrap_or_default();
 
-            let integral_results = super::tools::calculate_integrals_for_pairs(
-                &integral_chosen_pairs,
-                &baseline_values,
-                &time_values,
-                "simpson",
-            );
+    let peaks = detect_peaks(&x, &y, parameters).map_err(DbErr::Custom)?;
+    let point = |i: usize| PairPoint { x: x[i], y: y[i] };
+    Ok(peaks
+        .iter()
+        .enumerate()
+        .map(|(k, peak)| SuggestedPair {
+            start: point(peak.start),
+            end: point(peak.end),
+            apex: point(peak.apex),
+            prominence: peak.prominence,
+            width: peak.width,
+            sample_name: sample_names
+                .get(k)
+                .cloned()
+                .unwrap_or_else(|| "undefined".to_string()),
+        })
+        .collect())
+}
 
-            active_model.integral_results =
-                ActiveValue::Set(Some(serde_json::to_value(&integral_results).unwrap()));
-        }
+/// Stores the suggested pairs as the channel's `integral_chosen_pairs` and
+/// integrates them into `integral_results`.
+pub async fn accept_suggested_pairs(
+    db: &DatabaseConnection,
+    id: Uuid,
+    parameters: &PeakParameters,
+) -> Result<InstrumentExperimentChannel, DbErr> {
+    let pairs = suggest_integral_pairs(db, id, parameters).await?;
+    let original_model: super::db::ActiveModel = super::db::Entity::find_by_id(id)
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound(
+            "Instrument experiment channel not found".into(),
+        ))?
+        .into();
 
-        // Execute the update in the database.
-        let response_obj = active_model.update(db).await?;
-        let obj = Self::get_one(db, response_obj.id).await?;
+    let pairs_json = serde_json::to_value(&pairs).map_err(|e| DbErr::Custom(e.to_string()))?;
+    let mut active_model = original_model.clone();
+    active_model.integral_chosen_pairs = ActiveValue::Set(Some(pairs_json.clone()));
+    apply_integrals(&mut active_model, &original_model, Some(pairs_json))?;
+    active_model.update(db).await?;
 
-        Ok(obj)
+    InstrumentExperimentChannel::get_one(db, id).await
+}
+
+/// Settings to copy from a source channel and where to copy them
+#[derive(ToSchema, Serialize, Deserialize, Debug, Clone)]
+pub struct CopySettingsRequest {
+    /// Channels to copy to
+    #[serde(default)]
+    pub target_channel_ids: Vec<Uuid>,
+    /// Experiments whose channels are all copied to
+    #[serde(default)]
+    pub target_experiment_ids: Vec<Uuid>,
+    /// Also copy the baseline method and parameters, integration method and
+    /// calibration model
+    #[serde(default)]
+    pub include_methods: bool,
+}
+
+/// Outcome of copying settings to one channel
+#[derive(ToSchema, Serialize, Deserialize, Debug, Clone)]
+pub struct CopySettingsResult {
+    pub channel_id: Uuid,
+    pub experiment_id: Uuid,
+    pub channel_name: String,
+    /// Whether the channel was updated and recomputed
+    pub updated: bool,
+    /// Why the channel could not be recomputed, e.g. an invalid baseline
+    pub error: Option<String>,
+    /// Copied pairs whose start or end is not one of the channel's
+    /// `time_values`, or that its baseline does not cover; they are not
+    /// integrated
+    #[schema(value_type = Vec<Object>)]
+    pub unmatched_pairs: Vec<Json>,
+}
+
+/// The channels selected by `target_channel_ids` and `target_experiment_ids`,
+/// excluding the source.
+async fn find_copy_targets(
+    db: &DatabaseConnection,
+    source_id: Uuid,
+    request: &CopySettingsRequest,
+) -> Result<Vec<Model>, DbErr> {
+    let targets = super::db::Entity::find()
+        .filter(
+            Condition::any()
+                .add(super::db::Column::Id.is_in(request.target_channel_ids.clone()))
+                .add(super::db::Column::ExperimentId.is_in(request.target_experiment_ids.clone())),
+        )
+        .filter(super::db::Column::Id.ne(source_id))
+        .order_by_asc(super::db::Column::ExperimentId)
+        .order_by_asc(super::db::Column::ChannelName)
+        .all(db)
+        .await?;
+    if let Some(missing) = request
+        .target_channel_ids
+        .iter()
+        .find(|id| **id != source_id && !targets.iter().any(|t| t.id == **id))
+    {
+        return Err(DbErr::RecordNotFound(format!(
+            "Channel {missing} not found"
+        )));
+    }
+    if let Some(missing) = request
+        .target_experiment_ids
+        .iter()
+        .find(|id| !targets.iter().any(|t| t.experiment_id == **id))
+    {
+        return Err(DbErr::RecordNotFound(format!(
+            "No channels found for experiment {missing}"
+        )));
+    }
+    if targets.is_empty() {
+        return Err(DbErr::Custom("No target channels to copy to".into()));
     }
+    Ok(targets)
+}
 
-    fn sortable_columns() -> Vec<(&'static str, Self::ColumnType)> {
-        vec![
-            ("id", super::db::Column::Id),
-            ("channel_name", super::db::Column::ChannelName),
-        ]
+/// Copies `baseline_chosen_points` and `integral_chosen_pairs` from the
+/// source channel to the targets, recomputing each target the same way as an
+/// update. A target that fails to recompute is reported and left unchanged;
+/// the other targets are written in one transaction.
+pub async fn copy_channel_settings(
+    db: &DatabaseConnection,
+    source_id: Uuid,
+    request: &CopySettingsRequest,
+) -> Result<Vec<CopySettingsResult>, DbErr> {
+    let source = super::db::Entity::find_by_id(source_id)
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound(
+            "Instrument experiment channel not found".into(),
+        ))?;
+    if source.baseline_chosen_points.is_none() && source.integral_chosen_pairs.is_none() {
+        return Err(DbErr::Custom(
+            "Source channel has no baseline points or integration pairs to copy".into(),
+        ));
     }
 
-    fn filterable_columns() -> Vec<(&'static str, Self::ColumnType)> {
-        vec![("channel_name", super::db::Column::ChannelName)]
+    let targets = find_copy_targets(db, source_id, request).await?;
+
+    let pairs: Vec<Json> = source
+        .integral_chosen_pairs
+        .clone()
+        .and_then(|json| serde_json::from_value(json).ok())
+        .unwrap_or_default();
+    let methods = request.include_methods;
+    // Without chosen points a point-based baseline is cleared, leaving
+    // nothing to integrate the copied pairs against
+    let clears_baseline = |target: &Model| {
+        let method = if methods {
+            source.baseline_method
+        } else {
+            target.baseline_method
+        };
+        source.baseline_chosen_points.is_none() && method.uses_chosen_points()
+    };
+    if !pairs.is_empty() && targets.iter().any(clears_baseline) {
+        return Err(DbErr::Custom(
+            "Source channel has integration pairs but no baseline points to copy".into(),
+        ));
+    }
+
+    let txn = db.begin().await?;
+    let mut results = Vec::new();
+    for target in targets {
+        let update = InstrumentExperimentChannelUpdate {
+            channel_name: None,
+            experiment_id: None,
+            baseline_spline: None,
+            time_values: None,
+            raw_values: None,
+            baseline_values: None,
+            baseline_chosen_points: Some(source.baseline_chosen_points.clone()),
+            integral_chosen_pairs: Some(source.integral_chosen_pairs.clone()),
+            integral_results: None,
+            integration_method: methods.then_some(Some(source.integration_method)),
+            baseline_method: methods.then_some(Some(source.baseline_method)),
+            baseline_parameters: methods.then(|| source.baseline_parameters.clone()),
+            calibration_model: methods.then_some(Some(source.calibration_model)),
+            calibration: None,
+        };
+        let (error, baseline_values, time_values) =
+            match InstrumentExperimentChannel::apply_update(&txn, target.id, update).await {
+                Ok(updated) => (None, updated.baseline_values, updated.time_values),
+                Err(DbErr::Custom(message)) => {
+                    (Some(message), target.baseline_values, target.time_values)
+                }
+                Err(e) => return Err(e),
+            };
+        let unmatched_pairs = super::tools::unmatched_pairs(
+            &pairs,
+            baseline_values.as_deref().unwrap_or(&[]),
+            time_values.as_deref().unwrap_or(&[]),
+        )
+        .into_iter()
+        .cloned()
+        .collect();
+        results.push(CopySettingsResult {
+            channel_id: target.id,
+            experiment_id: target.experiment_id,
+            channel_name: target.channel_name,
+            updated: error.is_none(),
+            error,
+            unmatched_pairs,
+        });
     }
+    txn.commit().await?;
+    Ok(results)
 }
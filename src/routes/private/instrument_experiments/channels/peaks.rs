@@ -0,0 +1,270 @@
+//! Peak detection on baseline-filtered channel signals, used to suggest
+//! integration pairs.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Share of the signal range used as minimum prominence when none is given
+const DEFAULT_PROMINENCE_FRACTION: f64 = 0.05;
+/// A peak ends where the signal comes within this share of its prominence of
+/// the valley floor
+const BOUND_FRACTION: f64 = 0.02;
+
+/// Parameters of the peak detection
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct PeakParameters {
+    /// Minimum prominence in signal units (default 5% of the signal range)
+    pub prominence: Option<f64>,
+    /// Minimum width at half prominence in x units
+    pub min_width: f64,
+    /// Points in the centred moving average applied before detection (1 disables it)
+    pub smoothing: usize,
+}
+
+impl Default for PeakParameters {
+    fn default() -> Self {
+        Self {
+            prominence: None,
+            min_width: 0.0,
+            smoothing: 5,
+        }
+    }
+}
+
+/// A detected peak, by index into the signal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DetectedPeak {
+    pub apex: usize,
+    pub start: usize,
+    pub end: usize,
+    pub prominence: f64,
+    pub width: f64,
+}
+
+/// Finds peaks in `y` and the bounds to integrate each one over. Neighbouring
+/// peaks never overlap; each is bounded by the valley towards its neighbour.
+pub fn detect_peaks(
+    x: &[f64],
+    y: &[f64],
+    parameters: &PeakParameters,
+) -> Result<Vec<DetectedPeak>, String> {
+    if parameters.smoothing == 0 {
+        return Err("Smoothing must be at least 1 point".into());
+    }
+    if !(parameters.min_width.is_finite() && parameters.min_width >= 0.0) {
+        return Err("Minimum width must not be negative".into());
+    }
+    let n = x.len().min(y.len());
+    if n < 3 {
+        return Ok(Vec::new());
+    }
+    let x = &x[..n];
+    let smoothed = moving_average(&y[..n], parameters.smoothing);
+
+    let range = smoothed.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+        - smoothed.iter().copied().fold(f64::INFINITY, f64::min);
+    let min_prominence = match parameters.prominence {
+        Some(p) if p.is_finite() && p >= 0.0 => p,
+        Some(_) => return Err("Prominence must not be negative".into()),
+        None => DEFAULT_PROMINENCE_FRACTION * range,
+    };
+
+    let mut peaks: Vec<DetectedPeak> = local_maxima(&smoothed)
+        .into_iter()
+        .filter_map(|apex| {
+            let (prominence, left_base, right_base) = prominence(&smoothed, apex);
+            let width =
+                half_prominence_width(x, &smoothed, apex, prominence, left_base, right_base);
+            (prominence > 0.0 && prominence >= min_prominence && width >= parameters.min_width)
+                .then_some(DetectedPeak {
+                    apex,
+                    start: left_base,
+                    end: right_base,
+                    prominence,
+                    width,
+                })
+        })
+        .collect();
+
+    // Narrow each peak to its valleys, stopping at the neighbouring apexes.
+    let apexes: Vec<usize> = peaks.iter().map(|p| p.apex).collect();
+    for (k, peak) in peaks.iter_mut().enumerate() {
+        let tolerance = BOUND_FRACTION * peak.prominence;
+        let left_limit = if k > 0 {
+            peak.start.max(apexes[k - 1])
+        } else {
+            peak.start
+        };
+        let right_limit = apexes
+            .get(k + 1)
+            .map_or(peak.end, |&next| peak.end.min(next));
+        peak.start = valley_bound(&smoothed, (left_limit..=peak.apex).rev(), tolerance);
+        peak.end = valley_bound(&smoothed, peak.apex..=right_limit, tolerance);
+    }
+    Ok(peaks)
+}
+
+/// Centred moving average over `window` points, shrinking at the edges.
+fn moving_average(y: &[f64], window: usize) -> Vec<f64> {
+    let half = window / 2;
+    (0..y.len())
+        .map(|i| {
+            let slice = &y[i.saturating_sub(half)..(i + half + 1).min(y.len())];
+            slice.iter().sum::<f64>() / f64::from(u32::try_from(slice.len()).unwrap_or(u32::MAX))
+        })
+        .collect()
+}
+
+/// Indices of local maxima; flat tops report their middle point.
+fn local_maxima(y: &[f64]) -> Vec<usize> {
+    let mut maxima = Vec::new();
+    let mut i = 1;
+    while i + 1 < y.len() {
+        if y[i - 1] < y[i] {
+            let mut ahead = i + 1;
+            while ahead + 1 < y.len() && (y[ahead] - y[i]).abs() < f64::EPSILON {
+                ahead += 1;
+            }
+            if y[ahead] < y[i] {
+                maxima.push((i + ahead - 1) / 2);
+                i = ahead;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    maxima
+}
+
+/// Topographic prominence of the peak at `apex` with its left and right bases.
+fn prominence(y: &[f64], apex: usize) -> (f64, usize, usize) {
+    let mut left_base = apex;
+    let mut i = apex;
+    while i > 0 && y[i - 1] <= y[apex] {
+        i -= 1;
+        if y[i] < y[left_base] {
+            left_base = i;
+        }
+    }
+    let mut right_base = apex;
+    let mut i = apex;
+    while i + 1 < y.len() && y[i + 1] <= y[apex] {
+        i += 1;
+        if y[i] < y[right_base] {
+            right_base = i;
+        }
+    }
+    (
+        y[apex] - y[left_base].max(y[right_base]),
+        left_base,
+        right_base,
+    )
+}
+
+/// Width of the peak where it crosses half its prominence, interpolated in x.
+fn half_prominence_width(
+    x: &[f64],
+    y: &[f64],
+    apex: usize,
+    prominence: f64,
+    left_base: usize,
+    right_base: usize,
+) -> f64 {
+    let height = y[apex] - prominence / 2.0;
+    let crossing = |inside: usize, outside: usize| {
+        let t = (y[inside] - height) / (y[inside] - y[outside]);
+        x[inside] + t * (x[outside] - x[inside])
+    };
+
+    let mut i = apex;
+    while i > left_base && y[i] > height {
+        i -= 1;
+    }
+    let left = if i < apex && y[i] <= height {
+        crossing(i + 1, i)
+    } else {
+        x[i]
+    };
+    let mut j = apex;
+    while j < right_base && y[j] > height {
+        j += 1;
+    }
+    let right = if j > apex && y[j] <= height {
+        crossing(j - 1, j)
+    } else {
+        x[j]
+    };
+    right - left
+}
+
+/// First index along `path` (walking away from the apex) where the signal is
+/// within `tolerance` of the lowest point on the path.
+fn valley_bound(y: &[f64], path: impl Iterator<Item = usize> + Clone, tolerance: f64) -> usize {
+    let floor = path.clone().map(|i| y[i]).fold(f64::INFINITY, f64::min);
+    let mut last = 0;
+    for i in path {
+        last = i;
+        if y[i] <= floor + tolerance {
+            break;
+        }
+    }
+    last
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gaussian(x: f64, centre: f64, height: f64) -> f64 {
+        height * (-(x - centre).powi(2) / 8.0).exp()
+    }
+
+    #[test]
+    fn finds_separated_peaks_with_non_overlapping_bounds() {
+        let x: Vec<f64> = std::iter::successors(Some(0.0), |v| Some(v + 0.5))
+            .take(400)
+            .collect();
+        let y: Vec<f64> = x
+            .iter()
+            .map(|&v| gaussian(v, 40.0, 10.0) + gaussian(v, 60.0, 4.0) + gaussian(v, 150.0, 0.1))
+            .collect();
+
+        let peaks = detect_peaks(&x, &y, &PeakParameters::default()).unwrap();
+        assert_eq!(peaks.len(), 2);
+        assert!((x[peaks[0].apex] - 40.0).abs() < 0.6);
+        assert!((x[peaks[1].apex] - 60.0).abs() < 0.6);
+        assert!(peaks[0].end <= peaks[1].start);
+        assert!(x[peaks[0].start] < 35.0 && x[peaks[1].end] > 65.0);
+        // Full width at half maximum of these gaussians is about 4.71.
+        let unsmoothed = PeakParameters {
+            smoothing: 1,
+            ..PeakParameters::default()
+        };
+        let peaks = detect_peaks(&x, &y, &unsmoothed).unwrap();
+        assert!((peaks[0].width - 4.71).abs() < 0.05);
+
+        let narrow_only = PeakParameters {
+            min_width: 10.0,
+            ..PeakParameters::default()
+        };
+        assert!(detect_peaks(&x, &y, &narrow_only).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_parameters() {
+        let x = [0.0, 1.0, 2.0];
+        let y = [0.0, 1.0, 0.0];
+        let zero_smoothing = PeakParameters {
+            smoothing: 0,
+            ..PeakParameters::default()
+        };
+        assert!(detect_peaks(&x, &y, &zero_smoothing).is_err());
+        let negative = PeakParameters {
+            prominence: Some(-1.0),
+            smoothing: 1,
+            ..PeakParameters::default()
+        };
+        assert!(detect_peaks(&x, &y, &negative).is_err());
+    }
+}
@@ -1,68 +1,8 @@
+use super::calibration::{CalibrationCurve, fit_calibration};
+use super::db::{CalibrationModelEnum, IntegrationMethodEnum};
 use serde_json::json;
 use std::cmp::Ordering;
 
-/// Calculate a spline for the given x and y data based on selected baseline points.
-/// This implementation uses linear interpolation between the baseline points.
-///
-/// # Arguments
-/// - `x`: Slice of x values.
-/// - `y`: Slice of y values.
-/// - `baseline_selected_points`: Slice of x-values chosen as baseline points.
-/// - `interpolation_method`: Interpolation method (currently only "linear" is supported).
-///
-/// # Returns
-/// A `Vec<f64>` containing the interpolated spline values for each x.
-pub fn calculate_spline(
-    x: &[f64],
-    y: &[f64],
-    baseline_selected_points: &[f64],
-    interpolation_method: &str,
-) -> Vec<f64> {
-    // Build pairs (baseline point, corresponding y value)
-    let pairs: Vec<(f64, f64)> = baseline_selected_points
-        .iter()
-        .filter_map(|&bp| {
-            x.iter()
-                .position(|&xi| (xi - bp).abs() < 1e-6)
-                .map(|i| (bp, y[i]))
-        })
-        .collect();
-
-    if pairs.is_empty() {
-        return vec![0.0; x.len()];
-    }
-
-    // For now we only support "linear" interpolation.
-    assert!(
-        (interpolation_method == "linear"),
-        "Interpolation method {interpolation_method} not supported, only 'linear' is available"
-    );
-
-    let mut spline = Vec::with_capacity(x.len());
-    for &xi in x {
-        let yi = if xi <= pairs.first().unwrap().0 {
-            pairs.first().unwrap().1
-        } else if xi >= pairs.last().unwrap().0 {
-            pairs.last().unwrap().1
-        } else {
-            // Find two consecutive pairs where xi fits.
-            let mut interp = pairs.first().unwrap().1;
-            for window in pairs.windows(2) {
-                if xi >= window[0].0 && xi <= window[1].0 {
-                    let (x0, y0) = window[0];
-                    let (x1, y1) = window[1];
-                    let t = (xi - x0) / (x1 - x0);
-                    interp = y0 + t * (y1 - y0);
-                    break;
-                }
-            }
-            interp
-        };
-        spline.push(yi);
-    }
-    spline
-}
-
 /// Compute the filtered baseline by subtracting the spline from the original y values.
 ///
 /// # Arguments
@@ -97,74 +37,230 @@ pub fn integrate_trapz(x: &[f64], y: &[f64]) -> f64 {
     area
 }
 
+/// Integrate the given data using composite Simpson's rule.
+///
+/// Evenly spaced samples use the classic 1/3 rule, with a 3/8 rule over the
+/// last three intervals when the interval count is odd. Irregularly spaced
+/// samples fall back to the non-uniform form of Simpson's rule, which fits a
+/// parabola through each pair of intervals, and correct the trailing odd
+/// interval with the matching end formula. Fewer than three points, or
+/// repeated x values, are integrated with the trapezoidal rule.
+///
+/// # Arguments
+/// - `x`: Slice of x values (ascending).
+/// - `y`: Slice of y values (must be the same length as `x`).
+///
+/// # Returns
+/// The computed integral as an `f64`.
+pub fn integrate_simpson(x: &[f64], y: &[f64]) -> f64 {
+    let n = x.len().min(y.len());
+    if n < 3 {
+        return integrate_trapz(&x[..n], &y[..n]);
+    }
+    let dx: Vec<f64> = x[..n].windows(2).map(|w| w[1] - w[0]).collect();
+    if dx.iter().any(|&h| h.abs() < f64::EPSILON) {
+        return integrate_trapz(&x[..n], &y[..n]);
+    }
+
+    let h = dx[0];
+    let uniform = dx
+        .iter()
+        .all(|&d| (d - h).abs() <= 1e-6 * h.abs().max(1e-12));
+    if uniform {
+        integrate_simpson_uniform(&y[..n], h)
+    } else {
+        integrate_simpson_irregular(&dx, &y[..n])
+    }
+}
+
+fn integrate_simpson_uniform(y: &[f64], h: f64) -> f64 {
+    let intervals = y.len() - 1;
+    if intervals == 1 {
+        return h * f64::midpoint(y[0], y[1]);
+    }
+    // Simpson's 1/3 rule needs an even number of intervals; the remaining
+    // three intervals of an odd count are covered by the 3/8 rule.
+    let even = if intervals.is_multiple_of(2) {
+        intervals
+    } else {
+        intervals - 3
+    };
+    let mut area = 0.0;
+    for i in (0..even).step_by(2) {
+        area += h / 3.0 * (y[i] + 4.0 * y[i + 1] + y[i + 2]);
+    }
+    if even < intervals {
+        let i = even;
+        area += 3.0 * h / 8.0 * (y[i] + 3.0 * y[i + 1] + 3.0 * y[i + 2] + y[i + 3]);
+    }
+    area
+}
+
+fn integrate_simpson_irregular(dx: &[f64], y: &[f64]) -> f64 {
+    let intervals = dx.len();
+    let mut area = 0.0;
+    let mut i = 0;
+    while i + 1 < intervals {
+        let (h0, h1) = (dx[i], dx[i + 1]);
+        let sum = h0 + h1;
+        area += sum / 6.0
+            * ((2.0 - h1 / h0) * y[i]
+                + sum * sum / (h0 * h1) * y[i + 1]
+                + (2.0 - h0 / h1) * y[i + 2]);
+        i += 2;
+    }
+    if !intervals.is_multiple_of(2) {
+        // Close the last interval with the parabola through the final three points.
+        let (h0, h1) = (dx[intervals - 2], dx[intervals - 1]);
+        let alpha = (2.0 * h1 * h1 + 3.0 * h0 * h1) / (6.0 * (h0 + h1));
+        let beta = (h1 * h1 + 3.0 * h0 * h1) / (6.0 * h0);
+        let eta = h1 * h1 * h1 / (6.0 * h0 * (h0 + h1));
+        area += alpha * y[intervals] + beta * y[intervals - 1] - eta * y[intervals - 2];
+    }
+    area
+}
+
+/// Cumulatively integrate the given data using the trapezoidal rule.
+///
+/// # Arguments
+/// - `x`: Slice of x values.
+/// - `y`: Slice of y values (must be the same length as `x`).
+///
+/// # Returns
+/// A `Vec<f64>` with the running integral at each x, starting at 0.
+pub fn integrate_cumulative(x: &[f64], y: &[f64]) -> Vec<f64> {
+    let n = x.len().min(y.len());
+    let mut running = Vec::with_capacity(n);
+    let mut area = 0.0;
+    for i in 0..n {
+        if i > 0 {
+            area += (x[i] - x[i - 1]) * f64::midpoint(y[i - 1], y[i]);
+        }
+        running.push(area);
+    }
+    running
+}
+
 /// Calculate the integral for a given range using the specified integration method.
-/// Currently, only "trapz" (trapezoidal) and "simpson" (which falls back to trapezoidal) are supported.
 ///
 /// # Arguments
 /// - `x`: Slice of x values.
 /// - `y`: Slice of y values.
-/// - `integration_method`: A string specifying the method ("trapz" or "simpson").
+/// - `integration_method`: The integration rule to apply.
 ///
 /// # Returns
 /// The computed integral as an `f64`.
-pub fn calculate_integral_for_range(x: &[f64], y: &[f64], integration_method: &str) -> f64 {
+pub fn calculate_integral_for_range(
+    x: &[f64],
+    y: &[f64],
+    integration_method: IntegrationMethodEnum,
+) -> f64 {
     match integration_method {
-        "trapz" => integrate_trapz(x, y),
-        // "simpson" => integrate_trapz(x, y), // Placeholder for Simpson's rule
-        _ => panic!("Integration method {integration_method} not supported"),
+        IntegrationMethodEnum::Trapezoid => integrate_trapz(x, y),
+        IntegrationMethodEnum::Simpson => integrate_simpson(x, y),
+        IntegrationMethodEnum::Cumulative => {
+            integrate_cumulative(x, y).last().copied().unwrap_or(0.0)
+        }
     }
 }
 
+/// The start and end x of an integration pair, 0 when missing.
+fn pair_bounds(pair: &serde_json::Value) -> (f64, f64) {
+    let x = |key: &str| {
+        pair.get(key)
+            .and_then(|v| v.get("x"))
+            .and_then(sea_orm::JsonValue::as_f64)
+            .unwrap_or(0.0)
+    };
+    (x("start"), x("end"))
+}
+
+/// Index of the time value matching `x`.
+fn time_index(time_values: &[f64], x: f64) -> Option<usize> {
+    time_values.iter().position(|&v| (v - x).abs() < 1e-6)
+}
+
+/// The time and baseline slices covered by a pair, in ascending time order.
+/// `None` when either bound is not one of the `time_values` or the baseline
+/// does not cover the range.
+fn pair_slices<'a>(
+    pair: &serde_json::Value,
+    baseline_values: &'a [f64],
+    time_values: &'a [f64],
+) -> Option<(&'a [f64], &'a [f64])> {
+    let (start, end) = pair_bounds(pair);
+    let si = time_index(time_values, start)?;
+    let ei = time_index(time_values, end)?;
+    let range = si.min(ei)..=si.max(ei);
+    Some((
+        time_values.get(range.clone())?,
+        baseline_values.get(range)?,
+    ))
+}
+
+/// Pairs that cannot be integrated because a bound is not one of the
+/// `time_values` or the baseline does not cover them; these are left out by
+/// `calculate_integrals_for_pairs`.
+pub fn unmatched_pairs<'a>(
+    pairs: &'a [serde_json::Value],
+    baseline_values: &[f64],
+    time_values: &[f64],
+) -> Vec<&'a serde_json::Value> {
+    pairs
+        .iter()
+        .filter(|pair| pair_slices(pair, baseline_values, time_values).is_none())
+        .collect()
+}
+
 /// Calculate the integral for each pair in the provided list.
 /// Each pair is expected to be a JSON object with the structure:
 /// { "start": {"x": value}, "end": {"x": value}, "`sample_name"`: "..." }
+/// Standards additionally carry their known "`standard_concentration`".
 ///
 /// # Arguments
 /// - `pairs`: A slice of JSON values representing the pairs.
 /// - `baseline_values`: Slice of baseline y values.
 /// - `time_values`: Slice of time x values.
-/// - `integration_method`: Integration method to use ("trapz" or "simpson").
+/// - `integration_method`: Integration rule to apply.
 ///
 /// # Returns
-/// A vector of JSON objects, each containing "start", "end", "area", and "`sample_name`".
+/// A vector of JSON objects, each containing "start", "end", "area", "method" and
+/// "`sample_name`". Cumulative integration also returns the running integral as
+/// "cumulative", and standards keep their "`standard_concentration`".
 pub fn calculate_integrals_for_pairs(
     pairs: &[serde_json::Value],
     baseline_values: &[f64],
     time_values: &[f64],
-    integration_method: &str,
+    integration_method: IntegrationMethodEnum,
 ) -> Vec<serde_json::Value> {
     let mut integration_results = Vec::new();
 
     for pair in pairs {
-        let start = pair
-            .get("start")
-            .and_then(|v| v.get("x"))
-            .and_then(sea_orm::JsonValue::as_f64)
-            .unwrap_or(0.0);
-        let end = pair
-            .get("end")
-            .and_then(|v| v.get("x"))
-            .and_then(sea_orm::JsonValue::as_f64)
-            .unwrap_or(0.0);
-
-        let start_index = time_values.iter().position(|&v| (v - start).abs() < 1e-6);
-        let end_index = time_values.iter().position(|&v| (v - end).abs() < 1e-6);
-
-        if let (Some(si), Some(ei)) = (start_index, end_index) {
-            let x_slice = &time_values[si..=ei];
-            let y_slice = &baseline_values[si..=ei];
+        if let Some((x_slice, y_slice)) = pair_slices(pair, baseline_values, time_values) {
+            let (start, end) = pair_bounds(pair);
+            let (start, end) = (start.min(end), start.max(end));
             let area = calculate_integral_for_range(x_slice, y_slice, integration_method);
             let sample_name = pair
                 .get("sample_name")
                 .and_then(|v| v.as_str())
                 .unwrap_or("undefined")
                 .to_string();
-            let result = json!({
+            let mut result = json!({
                 "start": start,
                 "end": end,
                 "area": area,
+                "method": integration_method,
                 "sample_name": sample_name,
             });
+            if integration_method == IntegrationMethodEnum::Cumulative {
+                result["cumulative"] = json!(integrate_cumulative(x_slice, y_slice));
+            }
+            if let Some(concentration) = pair
+                .get("standard_concentration")
+                .and_then(sea_orm::JsonValue::as_f64)
+            {
+                result["standard_concentration"] = json!(concentration);
+            }
             integration_results.push(result);
         }
     }
@@ -183,3 +279,140 @@ pub fn calculate_integrals_for_pairs(
 
     integration_results
 }
+
+/// Fit the calibration curve to the standards among the integral results and
+/// add the predicted "concentration" and "`concentration_sd`" to the other
+/// results. Returns `None` when no result is marked as a standard.
+///
+/// # Arguments
+/// - `results`: Integral results from `calculate_integrals_for_pairs`.
+/// - `model`: Calibration curve to fit.
+pub fn apply_calibration(
+    results: &mut [serde_json::Value],
+    model: CalibrationModelEnum,
+) -> Result<Option<CalibrationCurve>, String> {
+    let area = |r: &serde_json::Value| r.get("area").and_then(sea_orm::JsonValue::as_f64);
+    let standards: Vec<(f64, f64)> = results
+        .iter()
+        .filter_map(|r| {
+            let concentration = r
+                .get("standard_concentration")
+                .and_then(sea_orm::JsonValue::as_f64)?;
+            Some((concentration, area(r)?))
+        })
+        .collect();
+    if standards.is_empty() {
+        return Ok(None);
+    }
+
+    let curve = fit_calibration(model, &standards)?;
+    for result in results
+        .iter_mut()
+        .filter(|r| r.get("standard_concentration").is_none())
+    {
+        if let Some(prediction) = area(result).and_then(|a| curve.predict(a)) {
+            result["concentration"] = json!(prediction.concentration);
+            result["concentration_sd"] = json!(prediction.sd);
+        }
+    }
+    Ok(Some(curve))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simpson_is_exact_for_cubics_on_uniform_and_irregular_grids() {
+        let cubic = |x: f64| x * x * x - 2.0 * x + 1.0;
+        // Exact integral over [0, 2] is 4 - 4 + 2 = 2.
+        for x in [
+            vec![0.0, 0.5, 1.0, 1.5, 2.0],
+            vec![0.0, 0.4, 0.8, 1.2, 1.6, 2.0],
+        ] {
+            let y: Vec<f64> = x.iter().map(|&v| cubic(v)).collect();
+            assert!((integrate_simpson(&x, &y) - 2.0).abs() < 1e-9);
+        }
+
+        let quadratic = |x: f64| 3.0 * x * x + 1.0;
+        // Exact integral over [0, 2] is 8 + 2 = 10.
+        for x in [vec![0.0, 0.3, 1.0, 1.2, 2.0], vec![0.0, 0.1, 0.7, 2.0]] {
+            let y: Vec<f64> = x.iter().map(|&v| quadratic(v)).collect();
+            assert!((integrate_simpson(&x, &y) - 10.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn pairs_report_method_and_cumulative_series() {
+        let time = [0.0, 1.0, 2.0, 3.0];
+        let values = [0.0, 1.0, 2.0, 3.0];
+        let pairs = [json!({"start": {"x": 0.0}, "end": {"x": 2.0}, "sample_name": "a"})];
+
+        let results = calculate_integrals_for_pairs(
+            &pairs,
+            &values,
+            &time,
+            IntegrationMethodEnum::Cumulative,
+        );
+        assert_eq!(results[0]["method"], "cumulative");
+        assert_eq!(results[0]["cumulative"], json!([0.0, 0.5, 2.0]));
+        assert!((results[0]["area"].as_f64().unwrap() - 2.0).abs() < 1e-12);
+
+        let results =
+            calculate_integrals_for_pairs(&pairs, &values, &time, IntegrationMethodEnum::Simpson);
+        assert_eq!(results[0]["method"], "simpson");
+        assert!(results[0].get("cumulative").is_none());
+    }
+
+    #[test]
+    fn reports_pairs_outside_the_time_values() {
+        let time = [0.0, 0.5, 1.0, 1.5];
+        let pairs = [
+            json!({"start": {"x": 0.0}, "end": {"x": 1.0}, "sample_name": "a"}),
+            json!({"start": {"x": 0.5}, "end": {"x": 2.0}, "sample_name": "b"}),
+            json!({"start": {"x": 0.25}, "end": {"x": 1.5}, "sample_name": "c"}),
+        ];
+        let unmatched: Vec<&str> = unmatched_pairs(&pairs, &[1.0; 4], &time)
+            .iter()
+            .filter_map(|p| p["sample_name"].as_str())
+            .collect();
+        assert_eq!(unmatched, ["b", "c"]);
+        let integrated = calculate_integrals_for_pairs(
+            &pairs,
+            &[1.0; 4],
+            &time,
+            IntegrationMethodEnum::Trapezoid,
+        );
+        assert_eq!(integrated.len(), pairs.len() - unmatched.len());
+    }
+
+    #[test]
+    fn reversed_pairs_integrate_over_the_ascending_range() {
+        let time = [0.0, 1.0, 2.0, 3.0];
+        let values = [0.0, 1.0, 2.0, 3.0];
+        let pairs = [json!({"start": {"x": 2.0}, "end": {"x": 0.0}, "sample_name": "a"})];
+
+        assert!(unmatched_pairs(&pairs, &values, &time).is_empty());
+        let results = calculate_integrals_for_pairs(
+            &pairs,
+            &values,
+            &time,
+            IntegrationMethodEnum::Trapezoid,
+        );
+        assert_eq!(results[0]["start"], 0.0);
+        assert_eq!(results[0]["end"], 2.0);
+        assert!((results[0]["area"].as_f64().unwrap() - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn pairs_without_a_baseline_are_unmatched() {
+        let time = [0.0, 1.0, 2.0, 3.0];
+        let pairs = [json!({"start": {"x": 0.0}, "end": {"x": 2.0}, "sample_name": "a"})];
+
+        assert_eq!(unmatched_pairs(&pairs, &[], &time).len(), 1);
+        assert!(
+            calculate_integrals_for_pairs(&pairs, &[], &time, IntegrationMethodEnum::Simpson)
+                .is_empty()
+        );
+    }
+}
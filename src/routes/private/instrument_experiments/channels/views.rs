@@ -1,8 +1,11 @@
 use super::models::{
-    InstrumentExperimentChannel, InstrumentExperimentChannelCreate,
-    InstrumentExperimentChannelUpdate,
+    CopySettingsRequest, CopySettingsResult, InstrumentExperimentChannel,
+    InstrumentExperimentChannelCreate, InstrumentExperimentChannelUpdate, SuggestedPair,
+    accept_suggested_pairs, copy_channel_settings, suggest_integral_pairs,
 };
+use super::peaks::PeakParameters;
 use crate::common::auth::Role;
+use crate::common::errors::db_error_response;
 use axum_keycloak_auth::{
     PassthroughMode, instance::KeycloakAuthInstance, layer::KeycloakAuthLayer,
 };
@@ -31,6 +34,8 @@ where
         .routes(routes!(update_one_handler))
         .routes(routes!(delete_one_handler))
         .routes(routes!(delete_many_handler))
+        .routes(routes!(suggest_peaks, accept_peaks))
+        .routes(routes!(copy_settings))
         .with_state(db.clone());
 
     if let Some(instance) = keycloak_auth_instance {
@@ -52,3 +57,86 @@ where
 
     mutating_router
 }
+
+#[utoipa::path(
+    get,
+    path = "/{id}/peaks",
+    responses(
+        (status = 200, description = "Suggested integration pairs", body = Vec<SuggestedPair>),
+        (status = 404, description = "Channel not found"),
+        (status = 422, description = "No baseline values or invalid detection parameters"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("id" = Uuid, description = "Channel ID"),
+        ("prominence" = Option<f64>, Query, description = "Minimum peak prominence in signal units (default 5% of the signal range)"),
+        ("min_width" = Option<f64>, Query, description = "Minimum width at half prominence in x units (default 0)"),
+        ("smoothing" = Option<usize>, Query, description = "Points in the moving average applied before detection (default 5, 1 disables it)")
+    ),
+    summary = "Suggest integration pairs from detected peaks",
+    description = "Runs peak detection on the channel's baseline_values and returns start/end pairs for each peak, named in order from the experiment's sample_names. Nothing is stored."
+)]
+pub async fn suggest_peaks(
+    Path(id): Path<Uuid>,
+    State(db): State<DatabaseConnection>,
+    Query(parameters): Query<PeakParameters>,
+) -> Result<Json<Vec<SuggestedPair>>, (StatusCode, Json<String>)> {
+    suggest_integral_pairs(&db, id, &parameters)
+        .await
+        .map(Json)
+        .map_err(db_error_response)
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/peaks",
+    request_body = PeakParameters,
+    responses(
+        (status = 200, description = "Channel with the accepted pairs and their integrals", body = InstrumentExperimentChannel),
+        (status = 404, description = "Channel not found"),
+        (status = 422, description = "No baseline values or invalid detection parameters"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("id" = Uuid, description = "Channel ID")
+    ),
+    summary = "Accept detected peaks as integration pairs",
+    description = "Runs the same peak detection as the GET endpoint, replaces the channel's integral_chosen_pairs with the suggestions and fills integral_results using the channel's integration method."
+)]
+pub async fn accept_peaks(
+    Path(id): Path<Uuid>,
+    State(db): State<DatabaseConnection>,
+    Json(parameters): Json<PeakParameters>,
+) -> Result<Json<InstrumentExperimentChannel>, (StatusCode, Json<String>)> {
+    accept_suggested_pairs(&db, id, &parameters)
+        .await
+        .map(Json)
+        .map_err(db_error_response)
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/copy_settings",
+    request_body = CopySettingsRequest,
+    responses(
+        (status = 200, description = "Outcome for each target channel", body = Vec<CopySettingsResult>),
+        (status = 404, description = "Source channel, target channel or target experiment not found"),
+        (status = 422, description = "Nothing to copy or no target channels"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("id" = Uuid, description = "Source channel ID")
+    ),
+    summary = "Copy baseline and integration settings to other channels",
+    description = "Copies baseline_chosen_points and integral_chosen_pairs (and optionally the baseline, integration and calibration methods) to the listed channels and to every channel of the listed experiments, then recomputes each target as an update would. Targets that fail to recompute are reported with their error and left unchanged. Pairs that do not fall on a target's time_values are listed per target."
+)]
+pub async fn copy_settings(
+    Path(id): Path<Uuid>,
+    State(db): State<DatabaseConnection>,
+    Json(request): Json<CopySettingsRequest>,
+) -> Result<Json<Vec<CopySettingsResult>>, (StatusCode, Json<String>)> {
+    copy_channel_settings(&db, id, &request)
+        .await
+        .map(Json)
+        .map_err(db_error_response)
+}
@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use sea_orm::entity::prelude::*;
+use serde_json::Value as Json;
 use uuid::Uuid;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
@@ -23,6 +24,7 @@ pub struct Model {
     pub samples: Option<i32>,
     pub last_updated: DateTime<Utc>,
     pub project_id: Option<Uuid>,
+    pub sample_names: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
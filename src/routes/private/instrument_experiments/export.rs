@@ -0,0 +1,280 @@
+//! Content negotiation and file downloads of the experiment data views.
+
+use super::db;
+use crate::common::xlsx::{Cell, Sheet};
+use axum::{
+    body::Body,
+    http::{HeaderMap, HeaderValue, header},
+    response::{IntoResponse, Response},
+};
+use futures::{StreamExt, stream};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+
+pub const XLSX_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+/// Rows streamed per body chunk
+const STREAM_CHUNK_ROWS: usize = 1000;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Xlsx,
+}
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    pub format: Option<ExportFormat>,
+}
+
+impl ExportFormat {
+    /// The `format` query parameter wins over the `Accept` header, so that
+    /// plain download links work. JSON is the default.
+    pub fn negotiate(format: Option<Self>, headers: &HeaderMap) -> Self {
+        if let Some(format) = format {
+            return format;
+        }
+        let accept = headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        let mut ranges: Vec<(&str, f64)> = accept
+            .split(',')
+            .map(|range| {
+                let mut parts = range.split(';').map(str::trim);
+                let media_type = parts.next().unwrap_or_default();
+                let quality = parts
+                    .find_map(|p| p.strip_prefix("q="))
+                    .and_then(|q| q.parse().ok())
+                    .unwrap_or(1.0);
+                (media_type, quality)
+            })
+            .filter(|(_, quality)| *quality > 0.0)
+            .collect();
+        // Stable, so equal weights keep the client's order.
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranges
+            .into_iter()
+            .find_map(|(media_type, _)| match media_type {
+                "text/csv" => Some(Self::Csv),
+                XLSX_CONTENT_TYPE => Some(Self::Xlsx),
+                "application/json" => Some(Self::Json),
+                _ => None,
+            })
+            .unwrap_or(Self::Json)
+    }
+}
+
+/// File name of a download from the experiment name, e.g. `Run_12_raw.csv`.
+pub fn download_name(experiment: &db::Model, suffix: Option<&str>, extension: &str) -> String {
+    let name = experiment
+        .name
+        .as_deref()
+        .or(experiment.filename.as_deref())
+        .map(|name| name.rsplit_once('.').map_or(name, |(stem, _)| stem))
+        .filter(|name| !name.trim().is_empty())
+        .map_or_else(
+            || experiment.id.to_string(),
+            |name| {
+                name.trim()
+                    .chars()
+                    .map(|c| {
+                        if c.is_ascii_alphanumeric() || matches!(c, '-' | '_') {
+                            c
+                        } else {
+                            '_'
+                        }
+                    })
+                    .collect()
+            },
+        );
+    match suffix {
+        Some(suffix) => format!("{name}_{suffix}.{extension}"),
+        None => format!("{name}.{extension}"),
+    }
+}
+
+fn attachment(filename: &str) -> HeaderValue {
+    HeaderValue::from_str(&format!("attachment; filename=\"{filename}\""))
+        .unwrap_or_else(|_| HeaderValue::from_static("attachment"))
+}
+
+/// Streams `rows` in chunks, each chunk rendered by `render` with the index
+/// of its first row, between `open` and `close`.
+fn stream_rows<T, I>(
+    rows: I,
+    open: &'static str,
+    close: &'static str,
+    render: fn(Vec<(usize, T)>) -> String,
+) -> Body
+where
+    T: Send + 'static,
+    I: Iterator<Item = T> + Send + 'static,
+{
+    let chunks = stream::iter(rows.enumerate())
+        .ready_chunks(STREAM_CHUNK_ROWS)
+        .map(render);
+    Body::from_stream(
+        stream::once(async move { open.to_string() })
+            .chain(chunks)
+            .chain(stream::once(async move { close.to_string() }))
+            .map(Ok::<_, Infallible>),
+    )
+}
+
+/// Streams `rows` as a JSON array of rows, serialising a chunk at a time so
+/// large experiments are never built up as one document.
+pub fn stream_json_rows<T, I>(rows: I) -> Response
+where
+    T: Serialize + Send + 'static,
+    I: Iterator<Item = T> + Send + 'static,
+{
+    let body = stream_rows(rows, "[", "]", |rows| {
+        let mut chunk = String::new();
+        for (index, row) in rows {
+            if index > 0 {
+                chunk.push(',');
+            }
+            chunk.push_str(&serde_json::to_string(&row).unwrap_or_default());
+        }
+        chunk
+    });
+    ([(header::CONTENT_TYPE, "application/json")], body).into_response()
+}
+
+/// Streams `rows` as a CSV file download.
+pub fn stream_csv_rows<I>(rows: I, filename: &str) -> Response
+where
+    I: Iterator<Item = Vec<String>> + Send + 'static,
+{
+    let body = stream_rows(rows, "", "", |rows| {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for (_, row) in rows {
+            // Writing into memory cannot fail.
+            let _ = writer.write_record(&row);
+        }
+        String::from_utf8(writer.into_inner().unwrap_or_default()).unwrap_or_default()
+    });
+    (
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("text/csv; charset=utf-8"),
+            ),
+            (header::CONTENT_DISPOSITION, attachment(filename)),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// An XLSX workbook as a file download.
+pub fn xlsx_response(workbook: Vec<u8>, filename: &str) -> Response {
+    (
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(XLSX_CONTENT_TYPE),
+            ),
+            (header::CONTENT_DISPOSITION, attachment(filename)),
+        ],
+        workbook,
+    )
+        .into_response()
+}
+
+/// Metadata sheet of an experiment workbook, one field per row.
+pub fn metadata_sheet(experiment: &db::Model) -> Sheet {
+    let text = |value: Option<String>| value.map_or(Cell::Empty, Cell::Text);
+    let number = |value: Option<f64>| value.map_or(Cell::Empty, Cell::Number);
+    let sample_names = experiment
+        .sample_names
+        .as_ref()
+        .and_then(|names| names.as_array())
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(|n| n.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        });
+    let fields = [
+        ("id", Cell::Text(experiment.id.to_string())),
+        ("name", text(experiment.name.clone())),
+        ("date", text(experiment.date.map(|d| d.to_rfc3339()))),
+        ("description", text(experiment.description.clone())),
+        ("filename", text(experiment.filename.clone())),
+        ("device_filename", text(experiment.device_filename.clone())),
+        ("data_source", text(experiment.data_source.clone())),
+        (
+            "instrument_model",
+            text(experiment.instrument_model.clone()),
+        ),
+        ("init_e", number(experiment.init_e)),
+        ("sample_interval", number(experiment.sample_interval)),
+        ("run_time", number(experiment.run_time)),
+        ("quiet_time", number(experiment.quiet_time)),
+        ("sensitivity", number(experiment.sensitivity)),
+        ("samples", number(experiment.samples.map(f64::from))),
+        ("sample_names", text(sample_names)),
+        (
+            "project_id",
+            text(experiment.project_id.map(|id| id.to_string())),
+        ),
+        (
+            "last_updated",
+            Cell::Text(experiment.last_updated.to_rfc3339()),
+        ),
+    ];
+    Sheet {
+        name: "Metadata".into(),
+        rows: std::iter::once(vec![Cell::from("field"), Cell::from("value")])
+            .chain(
+                fields
+                    .into_iter()
+                    .map(|(field, value)| vec![Cell::Text(field.into()), value]),
+            )
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accept(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn negotiates_by_query_then_weighted_accept() {
+        assert_eq!(
+            ExportFormat::negotiate(None, &HeaderMap::new()),
+            ExportFormat::Json
+        );
+        assert_eq!(
+            ExportFormat::negotiate(None, &accept("text/csv")),
+            ExportFormat::Csv
+        );
+        assert_eq!(
+            ExportFormat::negotiate(
+                None,
+                &accept(&format!("text/csv;q=0.5, {XLSX_CONTENT_TYPE}"))
+            ),
+            ExportFormat::Xlsx
+        );
+        assert_eq!(
+            ExportFormat::negotiate(None, &accept("text/csv;q=0, */*")),
+            ExportFormat::Json
+        );
+        assert_eq!(
+            ExportFormat::negotiate(Some(ExportFormat::Csv), &accept("application/json")),
+            ExportFormat::Csv
+        );
+    }
+}
@@ -1,4 +1,7 @@
 pub mod channels;
 pub mod db;
 pub mod models;
+pub mod export;
+mod parsers;
+pub mod sample_mapping;
 pub mod views;
@@ -31,6 +31,9 @@ pub struct InstrumentExperiment {
     pub sensitivity: Option<f64>,
     pub samples: Option<i32>,
     pub project_id: Option<Uuid>,
+    /// Names of the injected samples in run order (JSON array of strings), used to
+    /// label detected peaks
+    pub sample_names: Option<serde_json::Value>,
     #[crudcrate(update_model = false, create_model = false)]
     pub channels: Vec<super::channels::models::InstrumentExperimentChannel>,
     #[crudcrate(non_db_attr = true, default = 0)]
@@ -56,6 +59,7 @@ impl From<Model> for InstrumentExperiment {
             id: model.id,
             last_updated: model.last_updated,
             project_id: model.project_id,
+            sample_names: model.sample_names,
             channels: vec![],
             channel_qty_filled: 0,
         }
@@ -102,6 +106,9 @@ impl CRUDResource for InstrumentExperiment {
                 .column(super::channels::db::Column::ExperimentId)
                 .column(super::channels::db::Column::IntegralResults)
                 .column(super::channels::db::Column::BaselineValues)
+                .column(super::channels::db::Column::IntegrationMethod)
+                .column(super::channels::db::Column::BaselineMethod)
+                .column(super::channels::db::Column::CalibrationModel)
                 .all(db)
                 .await?;
 
@@ -121,7 +128,7 @@ impl CRUDResource for InstrumentExperiment {
                 .count();
 
             for channel in &mut obj.channels {
-                channel.baseline_values = Some(serde_json::Value::Array(vec![]));
+                channel.baseline_values = Some(Vec::new());
             }
 
             experiments.push(obj);
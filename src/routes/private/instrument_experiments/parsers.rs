@@ -0,0 +1,238 @@
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+
+/// Header of a CH Instruments text export
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChiHeader {
+    pub date: Option<DateTime<Utc>>,
+    /// Technique line, e.g. `Amperometric i-t Curve`
+    pub technique: Option<String>,
+    /// Data file path on the workstation
+    pub file: Option<String>,
+    pub data_source: Option<String>,
+    pub instrument_model: Option<String>,
+    pub init_e: Option<f64>,
+    pub sample_interval: Option<f64>,
+    pub run_time: Option<f64>,
+    pub quiet_time: Option<f64>,
+    pub sensitivity: Option<f64>,
+}
+
+/// Current trace of one electrode
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChiChannel {
+    /// Column label without its unit, e.g. `i1`
+    pub name: String,
+    pub values: Vec<f64>,
+}
+
+/// Result of parsing a CHI text export
+#[derive(Debug, Clone, Default)]
+pub struct ParsedChiFile {
+    pub header: ChiHeader,
+    pub time_values: Vec<f64>,
+    pub channels: Vec<ChiChannel>,
+}
+
+/// Parse the date line of a CHI export, e.g. `Jan. 15, 2024   10:23:45` or
+/// `Sept. 3, 2024   08:00:12`.
+fn parse_chi_date(line: &str) -> Option<NaiveDateTime> {
+    let mut tokens = line.split_whitespace();
+    let month: String = tokens
+        .next()?
+        .trim_end_matches('.')
+        .chars()
+        .take(3)
+        .collect();
+    let rest: Vec<&str> = tokens.collect();
+    NaiveDateTime::parse_from_str(&format!("{month} {}", rest.join(" ")), "%b %d, %Y %H:%M:%S").ok()
+}
+
+fn split_fields(line: &str) -> Vec<&str> {
+    line.split([',', '\t']).map(str::trim).collect()
+}
+
+/// Parse a CH Instruments text export of an amperometric i-t run: a date line,
+/// the technique, `key: value` and `key = value` header lines, then a
+/// `Time/sec, i1/A, ...` table with one current column per electrode.
+pub fn parse_chi(text: &str, utc_offset_minutes: i32) -> Result<ParsedChiFile, String> {
+    let mut parsed = ParsedChiFile::default();
+    let mut lines = text.lines().enumerate();
+
+    let mut header_seen = false;
+    for (_, line) in lines.by_ref() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.to_ascii_lowercase().starts_with("time/") {
+            for (position, column) in split_fields(line).into_iter().enumerate().skip(1) {
+                let name = column.split('/').next().unwrap_or(column).trim();
+                if name.is_empty() {
+                    return Err(format!(
+                        "Data table header: column {} has no name",
+                        position + 1
+                    ));
+                }
+                parsed.channels.push(ChiChannel {
+                    name: name.to_string(),
+                    values: Vec::new(),
+                });
+            }
+            header_seen = true;
+            break;
+        }
+        read_header_line(&mut parsed.header, line, utc_offset_minutes);
+    }
+    if !header_seen {
+        return Err("No 'Time/sec' data table found in file".into());
+    }
+    if parsed.channels.is_empty() {
+        return Err("Data table has no current columns".into());
+    }
+
+    for (index, line) in lines {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = split_fields(line);
+        if fields.len() != parsed.channels.len() + 1 {
+            return Err(format!(
+                "Line {}: expected {} values, found {}",
+                index + 1,
+                parsed.channels.len() + 1,
+                fields.len()
+            ));
+        }
+        if let Some(position) = fields.iter().position(|f| f.is_empty()) {
+            let column = match position {
+                0 => "Time",
+                _ => &parsed.channels[position - 1].name,
+            };
+            return Err(format!(
+                "Line {}: column {} ({column}) is empty",
+                index + 1,
+                position + 1
+            ));
+        }
+        let values = fields
+            .iter()
+            .map(|f| f.parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|e| format!("Line {}: {e}", index + 1))?;
+        parsed.time_values.push(values[0]);
+        for (channel, value) in parsed.channels.iter_mut().zip(&values[1..]) {
+            channel.values.push(*value);
+        }
+    }
+    if parsed.time_values.is_empty() {
+        return Err("No data rows found in file".into());
+    }
+    Ok(parsed)
+}
+
+fn read_header_line(header: &mut ChiHeader, line: &str, utc_offset_minutes: i32) {
+    if header.date.is_none()
+        && header.technique.is_none()
+        && let Some(naive) = parse_chi_date(line)
+    {
+        header.date = Some(naive.and_utc() - Duration::minutes(i64::from(utc_offset_minutes)));
+        return;
+    }
+
+    if let Some((key, value)) = line.split_once('=') {
+        let key = key.trim().to_ascii_lowercase();
+        let value = value.trim().parse::<f64>().ok();
+        if key.starts_with("init e") {
+            header.init_e = value;
+        } else if key.starts_with("sample interval") {
+            header.sample_interval = value;
+        } else if key.starts_with("run time") {
+            header.run_time = value;
+        } else if key.starts_with("quiet time") {
+            header.quiet_time = value;
+        } else if key.starts_with("sensitivity") {
+            header.sensitivity = value;
+        }
+    } else if let Some((key, value)) = line.split_once(':') {
+        let value = Some(value.trim().to_string()).filter(|v| !v.is_empty());
+        match key.trim().to_ascii_lowercase().as_str() {
+            "file" => header.file = value,
+            "data source" => header.data_source = value,
+            "instrument model" => header.instrument_model = value,
+            _ => {}
+        }
+    } else if header.technique.is_none() {
+        header.technique = Some(line.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const SAMPLE: &str = "Sept. 3, 2024   08:00:12\r
+Amperometric i-t Curve\r
+File:  C:\\CHI\\run_042.bin\r
+Data Source:  Experiment\r
+Instrument Model:  CHI1030C\r
+Header:\r
+Note:\r
+\r
+Init E (V) = 0.6\r
+Sample Interval (s) = 0.1\r
+Run Time (sec) = 0.3\r
+Quiet Time (sec) = 2\r
+Scales during Run = 1\r
+Sensitivity (A/V) = 1.e-6\r
+\r
+Time/sec, i1/A, i2/A\r
+\r
+0.100, 1.250e-9, -3.0e-10\r
+0.200, 1.300e-9, -2.9e-10\r
+0.300, 1.350e-9, -2.8e-10\r
+";
+
+    #[test]
+    fn parses_header_and_channels() {
+        let parsed = parse_chi(SAMPLE, 120).unwrap();
+        let header = &parsed.header;
+        assert_eq!(
+            header.date,
+            Some(Utc.with_ymd_and_hms(2024, 9, 3, 6, 0, 12).unwrap())
+        );
+        assert_eq!(header.technique.as_deref(), Some("Amperometric i-t Curve"));
+        assert_eq!(header.file.as_deref(), Some("C:\\CHI\\run_042.bin"));
+        assert_eq!(header.instrument_model.as_deref(), Some("CHI1030C"));
+        assert_eq!(header.init_e, Some(0.6));
+        assert_eq!(header.sample_interval, Some(0.1));
+        assert_eq!(header.quiet_time, Some(2.0));
+        assert_eq!(header.sensitivity, Some(1e-6));
+
+        assert_eq!(parsed.time_values, vec![0.1, 0.2, 0.3]);
+        let names: Vec<&str> = parsed.channels.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["i1", "i2"]);
+        assert_eq!(
+            parsed.channels[1].values,
+            vec![-3.0e-10, -2.9e-10, -2.8e-10]
+        );
+    }
+
+    #[test]
+    fn rejects_files_without_table_or_with_ragged_rows() {
+        assert!(parse_chi("Amperometric i-t Curve\nInit E (V) = 0.6\n", 0).is_err());
+        let ragged = "Time/sec, i1/A\n0.1, 1e-9\n0.2\n";
+        let err = parse_chi(ragged, 0).unwrap_err();
+        assert!(err.starts_with("Line 3"));
+    }
+
+    #[test]
+    fn reports_empty_columns_instead_of_shifting_values() {
+        let missing = "Time/sec, i1/A, i2/A\n0.1, 1e-9, 2e-9\n0.2, , 2e-9\n";
+        let err = parse_chi(missing, 0).unwrap_err();
+        assert_eq!(err, "Line 3: column 2 (i1) is empty");
+        let unnamed = "Time/sec, , i2/A\n0.1, 1e-9, 2e-9\n";
+        let err = parse_chi(unnamed, 0).unwrap_err();
+        assert_eq!(err, "Data table header: column 2 has no name");
+    }
+}
@@ -0,0 +1,425 @@
+//! Mapping of integral results to plot samples, so instrument results can be
+//! previewed and written into a plot sample field with their provenance.
+
+use super::channels::db as channel_db;
+use super::db;
+use crate::routes::private::samples::{db as sample_db, sources::db as source_db};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, IdenStatic, ModelTrait, QueryFilter, TransactionTrait,
+    sea_query::{Expr, Func},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value as JsonValue, json};
+use std::collections::{HashMap, HashSet};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Numeric plot sample fields that instrument results can be written to
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SampleField {
+    Ph,
+    Rh,
+    Loi,
+    Mfc,
+    C,
+    N,
+    Cn,
+    ClayPercent,
+    SiltPercent,
+    SandPercent,
+    FeUgPerG,
+    NaUgPerG,
+    AlUgPerG,
+    KUgPerG,
+    CaUgPerG,
+    MgUgPerG,
+    MnUgPerG,
+    SUgPerG,
+    ClUgPerG,
+    PUgPerG,
+    SiUgPerG,
+    FungiPerG,
+    BacteriaPerG,
+    ArcheaPerG,
+    MethanogensPerG,
+    MethanotrophsPerG,
+}
+
+impl SampleField {
+    fn column(self) -> sample_db::Column {
+        use sample_db::Column;
+        match self {
+            Self::Ph => Column::Ph,
+            Self::Rh => Column::Rh,
+            Self::Loi => Column::Loi,
+            Self::Mfc => Column::Mfc,
+            Self::C => Column::C,
+            Self::N => Column::N,
+            Self::Cn => Column::Cn,
+            Self::ClayPercent => Column::ClayPercent,
+            Self::SiltPercent => Column::SiltPercent,
+            Self::SandPercent => Column::SandPercent,
+            Self::FeUgPerG => Column::FeUgPerG,
+            Self::NaUgPerG => Column::NaUgPerG,
+            Self::AlUgPerG => Column::AlUgPerG,
+            Self::KUgPerG => Column::KUgPerG,
+            Self::CaUgPerG => Column::CaUgPerG,
+            Self::MgUgPerG => Column::MgUgPerG,
+            Self::MnUgPerG => Column::MnUgPerG,
+            Self::SUgPerG => Column::SUgPerG,
+            Self::ClUgPerG => Column::ClUgPerG,
+            Self::PUgPerG => Column::PUgPerG,
+            Self::SiUgPerG => Column::SiUgPerG,
+            Self::FungiPerG => Column::FungiPerG,
+            Self::BacteriaPerG => Column::BacteriaPerG,
+            Self::ArcheaPerG => Column::ArcheaPerG,
+            Self::MethanogensPerG => Column::MethanogensPerG,
+            Self::MethanotrophsPerG => Column::MethanotrophsPerG,
+        }
+    }
+}
+
+/// Value of an integral result to write
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultValue {
+    /// Calibrated concentration
+    #[default]
+    Concentration,
+    /// Integrated peak area
+    Area,
+}
+
+impl ResultValue {
+    fn key(self) -> &'static str {
+        match self {
+            Self::Concentration => "concentration",
+            Self::Area => "area",
+        }
+    }
+}
+
+/// Explicit plot sample for a sample name of the integral results
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct SampleMatch {
+    pub sample_name: String,
+    pub plotsample_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct SampleMappingRequest {
+    /// Plot sample field to write
+    pub field: SampleField,
+    #[serde(default)]
+    pub value: ResultValue,
+    /// Channels whose results are averaged per sample; all channels of the
+    /// experiment when omitted
+    pub channel_ids: Option<Vec<Uuid>>,
+    /// Plot samples to use instead of matching by name
+    #[serde(default)]
+    pub matches: Vec<SampleMatch>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchStatus {
+    /// Matched to the only plot sample with the same name
+    Name,
+    /// Matched through an explicit plot sample id
+    Explicit,
+    /// No plot sample has this name
+    Unmatched,
+    /// Several plot samples have this name; give an explicit id
+    Ambiguous,
+}
+
+/// A sample of the integral results and the plot sample value it maps to.
+/// Only rows with a plot sample are written.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct SampleMappingRow {
+    pub sample_name: String,
+    pub status: MatchStatus,
+    pub plotsample_id: Option<Uuid>,
+    pub plotsample_name: Option<String>,
+    /// Value of the field before writing
+    pub current_value: Option<f64>,
+    /// Mean of the chosen result over the channels
+    pub new_value: f64,
+    pub channel_ids: Vec<Uuid>,
+}
+
+/// Mean result value of one sample across channels
+#[derive(Debug, Clone, PartialEq)]
+struct SampleValue {
+    sample_name: String,
+    value: f64,
+    channel_ids: Vec<Uuid>,
+}
+
+/// Sample names are matched ignoring case and surrounding whitespace.
+fn name_key(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Averages the chosen value of every named integral result that is not a
+/// standard, per sample name in order of first appearance.
+fn sample_values<'a>(
+    channels: impl IntoIterator<Item = (Uuid, &'a JsonValue)>,
+    value: ResultValue,
+) -> Vec<SampleValue> {
+    // Sample, sum of the values and their count
+    let mut samples: Vec<(SampleValue, f64, u32)> = Vec::new();
+    for (channel_id, results) in channels {
+        for result in results.as_array().into_iter().flatten() {
+            let (Some(name), Some(v)) = (
+                result.get("sample_name").and_then(JsonValue::as_str),
+                result.get(value.key()).and_then(JsonValue::as_f64),
+            ) else {
+                continue;
+            };
+            // Results without an assigned sample are named "undefined"
+            if name.trim().is_empty()
+                || name_key(name) == "undefined"
+                || result.get("standard_concentration").is_some()
+            {
+                continue;
+            }
+            let key = name_key(name);
+            if let Some((sample, sum, count)) = samples
+                .iter_mut()
+                .find(|(s, _, _)| name_key(&s.sample_name) == key)
+            {
+                *sum += v;
+                *count += 1;
+                if !sample.channel_ids.contains(&channel_id) {
+                    sample.channel_ids.push(channel_id);
+                }
+            } else {
+                let sample = SampleValue {
+                    sample_name: name.trim().to_string(),
+                    value: 0.0,
+                    channel_ids: vec![channel_id],
+                };
+                samples.push((sample, v, 1));
+            }
+        }
+    }
+    samples
+        .into_iter()
+        .map(|(sample, sum, count)| SampleValue {
+            value: sum / f64::from(count),
+            ..sample
+        })
+        .collect()
+}
+
+fn current_value(sample: &sample_db::Model, field: SampleField) -> Option<f64> {
+    match sample.get(field.column()) {
+        sea_orm::Value::Double(v) => v,
+        _ => None,
+    }
+}
+
+/// Matches the integral results of an experiment to plot samples, by name or
+/// through the explicit matches of the request, without writing anything.
+pub async fn preview_sample_values<C: ConnectionTrait>(
+    db: &C,
+    experiment_id: Uuid,
+    request: &SampleMappingRequest,
+) -> Result<Vec<SampleMappingRow>, DbErr> {
+    db::Entity::find_by_id(experiment_id)
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Experiment not found".into()))?;
+
+    let mut query =
+        channel_db::Entity::find().filter(channel_db::Column::ExperimentId.eq(experiment_id));
+    if let Some(ids) = &request.channel_ids {
+        query = query.filter(channel_db::Column::Id.is_in(ids.clone()));
+    }
+    let mut channels = query.all(db).await?;
+    if let Some(ids) = &request.channel_ids
+        && let Some(missing) = ids.iter().find(|id| !channels.iter().any(|c| c.id == **id))
+    {
+        return Err(DbErr::RecordNotFound(format!(
+            "Channel {missing} not found in this experiment"
+        )));
+    }
+    channels.sort_by(|a, b| a.channel_name.cmp(&b.channel_name));
+
+    let values = sample_values(
+        channels
+            .iter()
+            .filter_map(|c| c.integral_results.as_ref().map(|r| (c.id, r))),
+        request.value,
+    );
+    if values.is_empty() {
+        return Err(DbErr::Custom(format!(
+            "No named sample results with a {} to map",
+            request.value.key()
+        )));
+    }
+
+    let explicit = explicit_matches(db, request, &values).await?;
+    let keys: Vec<String> = values.iter().map(|v| name_key(&v.sample_name)).collect();
+    let mut by_name: HashMap<String, Vec<sample_db::Model>> = HashMap::new();
+    for sample in sample_db::Entity::find()
+        .filter(Expr::expr(Func::lower(Expr::col(sample_db::Column::Name))).is_in(keys))
+        .all(db)
+        .await?
+    {
+        by_name
+            .entry(name_key(&sample.name))
+            .or_default()
+            .push(sample);
+    }
+
+    let rows: Vec<SampleMappingRow> = values
+        .into_iter()
+        .map(|value| {
+            let key = name_key(&value.sample_name);
+            let (status, sample) = if let Some(sample) = explicit.get(&key) {
+                (MatchStatus::Explicit, Some(sample))
+            } else {
+                match by_name.get(&key).map(Vec::as_slice) {
+                    Some([sample]) => (MatchStatus::Name, Some(sample)),
+                    Some([_, _, ..]) => (MatchStatus::Ambiguous, None),
+                    _ => (MatchStatus::Unmatched, None),
+                }
+            };
+            SampleMappingRow {
+                sample_name: value.sample_name,
+                status,
+                plotsample_id: sample.map(|s| s.id),
+                plotsample_name: sample.map(|s| s.name.clone()),
+                current_value: sample.and_then(|s| current_value(s, request.field)),
+                new_value: value.value,
+                channel_ids: value.channel_ids,
+            }
+        })
+        .collect();
+
+    let mut targets = HashSet::new();
+    if let Some(row) = rows
+        .iter()
+        .find(|r| r.plotsample_id.is_some_and(|id| !targets.insert(id)))
+    {
+        return Err(DbErr::Custom(format!(
+            "Plot sample '{}' is matched by more than one sample name",
+            row.plotsample_name.as_deref().unwrap_or_default()
+        )));
+    }
+    Ok(rows)
+}
+
+/// Loads the plot samples of the explicit matches, keyed by sample name.
+async fn explicit_matches<C: ConnectionTrait>(
+    db: &C,
+    request: &SampleMappingRequest,
+    values: &[SampleValue],
+) -> Result<HashMap<String, sample_db::Model>, DbErr> {
+    if let Some(unknown) = request.matches.iter().find(|m| {
+        !values
+            .iter()
+            .any(|v| name_key(&v.sample_name) == name_key(&m.sample_name))
+    }) {
+        return Err(DbErr::Custom(format!(
+            "No sample results named '{}' to map",
+            unknown.sample_name
+        )));
+    }
+    let samples = sample_db::Entity::find()
+        .filter(sample_db::Column::Id.is_in(request.matches.iter().map(|m| m.plotsample_id)))
+        .all(db)
+        .await?;
+    request
+        .matches
+        .iter()
+        .map(|m| {
+            samples
+                .iter()
+                .find(|s| s.id == m.plotsample_id)
+                .map(|s| (name_key(&m.sample_name), s.clone()))
+                .ok_or_else(|| {
+                    DbErr::RecordNotFound(format!("Plot sample {} not found", m.plotsample_id))
+                })
+        })
+        .collect()
+}
+
+/// Writes the matched values into the plot sample field and records the
+/// source of each value. Unmatched and ambiguous samples are left out.
+pub async fn apply_sample_values(
+    db: &DatabaseConnection,
+    experiment_id: Uuid,
+    request: &SampleMappingRequest,
+) -> Result<Vec<SampleMappingRow>, DbErr> {
+    let txn = db.begin().await?;
+    let rows = preview_sample_values(&txn, experiment_id, request).await?;
+    let column = request.field.column();
+    let now = Utc::now();
+    for row in &rows {
+        let Some(plotsample_id) = row.plotsample_id else {
+            continue;
+        };
+        sample_db::Entity::update_many()
+            .col_expr(column, Expr::value(row.new_value))
+            .col_expr(sample_db::Column::LastUpdated, Expr::value(now))
+            .filter(sample_db::Column::Id.eq(plotsample_id))
+            .exec(&txn)
+            .await?;
+        source_db::ActiveModel {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            plotsample_id: ActiveValue::Set(plotsample_id),
+            field: ActiveValue::Set(column.as_str().to_string()),
+            value: ActiveValue::Set(row.new_value),
+            previous_value: ActiveValue::Set(row.current_value),
+            experiment_id: ActiveValue::Set(Some(experiment_id)),
+            channel_ids: ActiveValue::Set(json!(row.channel_ids)),
+            sample_name: ActiveValue::Set(row.sample_name.clone()),
+            result_value: ActiveValue::Set(request.value.key().to_string()),
+            written_on: ActiveValue::Set(now),
+        }
+        .insert(&txn)
+        .await?;
+    }
+    txn.commit().await?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn averages_named_results_across_channels() {
+        let (i1, i2) = (Uuid::new_v4(), Uuid::new_v4());
+        let first = json!([
+            {"sample_name": "Std 10", "area": 5.0, "standard_concentration": 10.0, "concentration": 10.0},
+            {"sample_name": "P1-A", "area": 2.0, "concentration": 4.0},
+            {"sample_name": "undefined-free", "area": 1.0},
+            {"area": 3.0, "concentration": 6.0},
+            {"sample_name": "undefined", "area": 3.0, "concentration": 6.0},
+        ]);
+        let second = json!([
+            {"sample_name": " p1-a ", "area": 2.2, "concentration": 5.0},
+        ]);
+
+        let values = sample_values([(i1, &first), (i2, &second)], ResultValue::Concentration);
+        assert_eq!(
+            values,
+            vec![SampleValue {
+                sample_name: "P1-A".into(),
+                value: 4.5,
+                channel_ids: vec![i1, i2],
+            }]
+        );
+
+        let areas = sample_values([(i1, &first)], ResultValue::Area);
+        let names: Vec<&str> = areas.iter().map(|v| v.sample_name.as_str()).collect();
+        assert_eq!(names, ["P1-A", "undefined-free"]);
+    }
+}
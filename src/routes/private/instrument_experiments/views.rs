@@ -1,16 +1,33 @@
 use crate::common::auth::Role;
+use crate::common::errors::db_error_response;
+use crate::common::files::decode_base64_text;
+use crate::common::xlsx::{Cell, Sheet, write_workbook};
 use crate::routes::private::instrument_experiments::channels::db as channel_db;
 use crate::routes::private::instrument_experiments::db;
+use crate::routes::private::instrument_experiments::export::{
+    ExportFormat, ExportQuery, download_name, metadata_sheet, stream_csv_rows, stream_json_rows,
+    xlsx_response,
+};
 use crate::routes::private::instrument_experiments::models::{
     InstrumentExperiment, InstrumentExperimentCreate, InstrumentExperimentUpdate,
 };
+use crate::routes::private::instrument_experiments::parsers::parse_chi;
+use crate::routes::private::instrument_experiments::sample_mapping::{
+    SampleMappingRequest, SampleMappingRow, apply_sample_values, preview_sample_values,
+};
+use axum::response::{IntoResponse, Response};
 use axum_keycloak_auth::{
     PassthroughMode, instance::KeycloakAuthInstance, layer::KeycloakAuthLayer,
 };
 use crudcrate::{CRUDResource, crud_handlers};
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QuerySelect, TransactionTrait,
+};
+use serde::Deserialize;
 use serde_json::{Value as JsonValue, json};
 use std::sync::Arc;
+use utoipa::ToSchema;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 crud_handlers!(
@@ -36,6 +53,9 @@ where
         .routes(routes!(get_raw_data))
         .routes(routes!(get_filtered_data))
         .routes(routes!(get_summary_data))
+        .routes(routes!(upload_chi_file))
+        .routes(routes!(preview_plot_sample_values))
+        .routes(routes!(apply_plot_sample_values))
         .with_state(db.clone());
 
     if let Some(instance) = keycloak_auth_instance {
@@ -58,36 +78,120 @@ where
     mutating_router
 }
 
+/// Name, time values and raw values of a channel
+type RawChannel = (String, Option<Vec<f64>>, Option<Vec<f64>>);
+/// Name, time values, baseline values and integral results of a channel
+type FilteredChannel = (
+    String,
+    Option<Vec<f64>>,
+    Option<Vec<f64>>,
+    Option<JsonValue>,
+);
+
+async fn find_experiment(
+    db: &DatabaseConnection,
+    id: Uuid,
+) -> Result<db::Model, (StatusCode, Json<String>)> {
+    db::Entity::find()
+        .filter(db::Column::Id.eq(id))
+        .one(db)
+        .await
+        .map_err(|_| (StatusCode::NOT_FOUND, Json("Not found".to_string())))?
+        .ok_or((StatusCode::NOT_FOUND, Json("Not found".to_string())))
+}
+
+/// Builds the workbook with the metadata, raw, filtered and summary sheets.
+/// Fails like the JSON and CSV formats when one of the views cannot be built,
+/// e.g. for an experiment without channels.
+async fn workbook_response(
+    db: &DatabaseConnection,
+    experiment: &db::Model,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    let text_rows = |rows: &mut dyn Iterator<Item = Vec<String>>| -> Vec<Vec<Cell>> {
+        rows.map(|row| row.iter().map(|v| Cell::from(v.as_str())).collect())
+            .collect()
+    };
+    let raw = text_rows(&mut raw_rows(db, experiment.id).await?);
+    let filtered = filtered_rows(db, experiment.id)
+        .await?
+        .map(|row| row.iter().map(Cell::from).collect())
+        .collect();
+    let summary = text_rows(&mut summary_rows(db, experiment.id).await?.into_iter());
+
+    let sheets = [
+        metadata_sheet(experiment),
+        Sheet {
+            name: "Raw".into(),
+            rows: raw,
+        },
+        Sheet {
+            name: "Filtered".into(),
+            rows: filtered,
+        },
+        Sheet {
+            name: "Summary".into(),
+            rows: summary,
+        },
+    ];
+    let workbook = write_workbook(&sheets)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.to_string())))?;
+    Ok(xlsx_response(
+        workbook,
+        &download_name(experiment, None, "xlsx"),
+    ))
+}
+
 #[utoipa::path(
     get,
     path = "/{id}/raw",
     responses(
-        (status = 200, description = "Raw data found", body = Vec<Vec<String>>),
+        (status = 200, description = "Raw data found", content(
+            (Vec<Vec<String>> = "application/json"),
+            (String = "text/csv"),
+            ("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")
+        )),
         (status = 404, description = "Experiment not found"),
         (status = 500, description = "Internal server error")
     ),
     params(
-        ("id" = Uuid, description = "Experiment ID")
+        ("id" = Uuid, description = "Experiment ID"),
+        ("format" = Option<String>, Query, description = "`json`, `csv` or `xlsx`; overrides the Accept header")
     ),
     summary = format!("Get raw {} data", InstrumentExperiment::RESOURCE_NAME_SINGULAR),
-    description = "Returns CSV data (as JSON) built from the raw time and raw_values of each channel."
+    description = "Returns the raw time and raw_values of each channel. The format is negotiated: JSON rows by default, a CSV file for `text/csv`, or for XLSX a workbook with the experiment metadata and the raw, filtered and summary data on separate sheets. JSON and CSV rows are streamed."
 )]
 pub async fn get_raw_data(
     Path(id): Path<Uuid>,
+    Query(query): Query<ExportQuery>,
+    headers: HeaderMap,
     State(db): State<DatabaseConnection>,
-) -> Result<Json<Vec<Vec<String>>>, (StatusCode, Json<String>)> {
-    // Fetch the experiment (using a direct query here for brevity)
-    let _experiment: db::Model = db::Entity::find()
-        .filter(db::Column::Id.eq(id))
-        .one(&db)
-        .await
-        .map_err(|_| (StatusCode::NOT_FOUND, Json("Not found".to_string())))?
-        .ok_or((StatusCode::NOT_FOUND, Json("Not found".to_string())))?;
+) -> Result<Response, (StatusCode, Json<String>)> {
+    let experiment = find_experiment(&db, id).await?;
+    match ExportFormat::negotiate(query.format, &headers) {
+        ExportFormat::Json => Ok(stream_json_rows(raw_rows(&db, id).await?)),
+        ExportFormat::Csv => Ok(stream_csv_rows(
+            raw_rows(&db, id).await?,
+            &download_name(&experiment, Some("raw"), "csv"),
+        )),
+        ExportFormat::Xlsx => workbook_response(&db, &experiment).await,
+    }
+}
 
-    // Fetch associated channels for this experiment.
-    let channels = channel_db::Entity::find()
+/// Rows of the raw data: a `Time/s` header with the channel names, then one
+/// row per time value.
+async fn raw_rows(
+    db: &DatabaseConnection,
+    id: Uuid,
+) -> Result<impl Iterator<Item = Vec<String>> + Send + 'static, (StatusCode, Json<String>)> {
+    // Fetch the signals of the experiment's channels.
+    let channels: Vec<RawChannel> = channel_db::Entity::find()
+        .select_only()
+        .column(channel_db::Column::ChannelName)
+        .column(channel_db::Column::TimeValues)
+        .column(channel_db::Column::RawValues)
         .filter(channel_db::Column::ExperimentId.eq(id))
-        .all(&db)
+        .into_tuple()
+        .all(db)
         .await
         .map_err(|_| {
             (
@@ -95,124 +199,117 @@ pub async fn get_raw_data(
                 Json("DB error".to_string()),
             )
         })?;
+    Ok(raw_table(channels))
+}
 
-    let mut channels = channels;
-    channels.sort_by(|a, b| a.channel_name.cmp(&b.channel_name));
+/// Assembles the raw rows, padding channels shorter than the reference time
+/// values with `N/A`.
+fn raw_table(mut channels: Vec<RawChannel>) -> impl Iterator<Item = Vec<String>> + Send + 'static {
+    channels.sort_by(|a, b| a.0.cmp(&b.0));
 
     // Build CSV header: "Time/s" plus each channel's name.
-    let mut header = vec!["Time/s".to_string()];
-    for channel in &channels {
-        header.push(channel.channel_name.clone());
-    }
-    let mut csv_data = vec![header];
+    let header: Vec<String> = std::iter::once("Time/s".to_string())
+        .chain(channels.iter().map(|c| c.0.clone()))
+        .collect();
 
-    // Use the first channel's time_values as the reference.
-    if let Some(first_channel) = channels.first() {
-        let time_values: Vec<f64> = if let Some(json) = &first_channel.time_values {
-            serde_json::from_value(json.clone()).unwrap_or_default()
-        } else {
-            Vec::new()
-        };
-
-        for time_value in time_values.clone() {
-            let mut row = vec![time_value.to_string()];
-            for channel in &channels {
-                let raw_values: Vec<f64> = if let Some(json) = &channel.raw_values {
-                    serde_json::from_value(json.clone()).unwrap_or_default()
-                } else {
-                    Vec::new()
-                };
-                let value = raw_values
-                    .get(
-                        time_values
-                            .iter()
-                            .position(|&v| (v - time_value).abs() < f64::EPSILON)
-                            .unwrap_or(0),
-                    )
-                    .map_or("N/A".to_string(), std::string::ToString::to_string);
-                row.push(value);
-            }
-            csv_data.push(row);
-        }
-    }
-    Ok(Json(csv_data))
+    // Use the first channel's time_values as the reference; all channels of
+    // an experiment share the same sampling.
+    let time_values = channels
+        .first()
+        .and_then(|c| c.1.clone())
+        .unwrap_or_default();
+    let raw_values: Vec<Vec<f64>> = channels
+        .into_iter()
+        .map(|c| c.2.unwrap_or_default())
+        .collect();
+    let rows = time_values.into_iter().enumerate().map(move |(i, time)| {
+        std::iter::once(time.to_string())
+            .chain(raw_values.iter().map(|values| {
+                values
+                    .get(i)
+                    .map_or("N/A".to_string(), std::string::ToString::to_string)
+            }))
+            .collect::<Vec<String>>()
+    });
+    std::iter::once(header).chain(rows)
+}
+
+/// An integrated sample of a channel with its slice of the filtered signal
+struct FilteredSample {
+    column: String,
+    end: f64,
+    baseline_values: Vec<f64>,
 }
 
 #[utoipa::path(
     get,
     path = "/{id}/filtered",
     responses(
-        (status = 200, description = "Filtered data found", body = Vec<Vec<JsonValue>>),
+        (status = 200, description = "Filtered data found", content(
+            (Vec<Vec<JsonValue>> = "application/json"),
+            (String = "text/csv"),
+            ("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")
+        )),
         (status = 404, description = "Experiment not found"),
         (status = 500, description = "Internal server error")
     ),
     params(
-        ("id" = Uuid, description = "Experiment ID")
+        ("id" = Uuid, description = "Experiment ID"),
+        ("format" = Option<String>, Query, description = "`json`, `csv` or `xlsx`; overrides the Accept header")
     ),
     summary = format!("Get filtered {} data", InstrumentExperiment::RESOURCE_NAME_SINGULAR),
-    description = "Returns baseline-filtered CSV data built by slicing each channel’s baseline_values according to the 'start' and 'end' markers in each channel’s integral_results."
+    description = "Returns baseline-filtered data built by slicing each channel’s baseline_values according to the 'start' and 'end' markers in each channel’s integral_results. Formats are negotiated as for the raw data."
 )]
 pub async fn get_filtered_data(
     Path(id): Path<Uuid>,
+    Query(query): Query<ExportQuery>,
+    headers: HeaderMap,
     State(db): State<DatabaseConnection>,
-) -> Result<Json<Vec<Vec<JsonValue>>>, (StatusCode, Json<String>)> {
-    // Query channels for the given experiment ID.
-    let channels = channel_db::Entity::find()
+) -> Result<Response, (StatusCode, Json<String>)> {
+    let experiment = find_experiment(&db, id).await?;
+    match ExportFormat::negotiate(query.format, &headers) {
+        ExportFormat::Json => Ok(stream_json_rows(filtered_rows(&db, id).await?)),
+        ExportFormat::Csv => Ok(stream_csv_rows(
+            filtered_rows(&db, id).await?.map(|row| {
+                row.into_iter()
+                    .map(|value| match value {
+                        JsonValue::Null => String::new(),
+                        JsonValue::String(s) => s,
+                        other => other.to_string(),
+                    })
+                    .collect()
+            }),
+            &download_name(&experiment, Some("filtered"), "csv"),
+        )),
+        ExportFormat::Xlsx => workbook_response(&db, &experiment).await,
+    }
+}
+
+/// Rows of the filtered data: a `time/s` header with one column per
+/// integrated sample, then one row per time step until every sample has ended.
+async fn filtered_rows(
+    db: &DatabaseConnection,
+    id: Uuid,
+) -> Result<impl Iterator<Item = Vec<JsonValue>> + Send + 'static, (StatusCode, Json<String>)> {
+    // Query the signals and results of the channels for the given experiment ID.
+    let mut channels: Vec<FilteredChannel> = channel_db::Entity::find()
+        .select_only()
+        .column(channel_db::Column::ChannelName)
+        .column(channel_db::Column::TimeValues)
+        .column(channel_db::Column::BaselineValues)
+        .column(channel_db::Column::IntegralResults)
         .filter(channel_db::Column::ExperimentId.eq(id))
-        .all(&db)
+        .into_tuple()
+        .all(db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.to_string())))?;
     if channels.is_empty() {
         return Err((StatusCode::NOT_FOUND, Json("No channels found".to_string())));
     }
-    let mut channels = channels;
-    channels.sort_by(|a, b| a.channel_name.cmp(&b.channel_name));
-
-    let mut samples = build_vector_of_samples(&channels);
-
-    // Ensure unique column names.
-    ensure_unique_column_name(&mut samples);
-
-    // Sort samples by their column name.
-    samples.sort_by(|a, b| {
-        let a_col = a.get("column").and_then(|v| v.as_str()).unwrap_or("");
-        let b_col = b.get("column").and_then(|v| v.as_str()).unwrap_or("");
-        a_col.cmp(b_col)
-    });
-
-    // Adjust each sample: set adjusted_start = 0 and adjusted_end = end - start.
-    for sample in &mut samples {
-        let start = sample
-            .get("start")
-            .and_then(sea_orm::JsonValue::as_f64)
-            .unwrap_or(0.0);
-        let end = sample
-            .get("end")
-            .and_then(sea_orm::JsonValue::as_f64)
-            .unwrap_or(0.0);
-        sample.insert("adjusted_start".to_string(), json!(0.0));
-        sample.insert("adjusted_end".to_string(), json!(end - start));
-    }
-
-    // Build CSV header: "time/s" plus each sample's column name.
-    let mut header: Vec<JsonValue> = vec![JsonValue::String("time/s".to_string())];
-    for sample in &samples {
-        if let Some(col) = sample.get("column").and_then(|v| v.as_str()) {
-            header.push(JsonValue::String(col.to_string()));
-        }
-    }
-    let mut csv_data: Vec<Vec<JsonValue>> = vec![header];
+    channels.sort_by(|a, b| a.0.cmp(&b.0));
 
     // Determine the time_step from the first channel.
-    let first_channel = channels.first().ok_or((
-        StatusCode::INTERNAL_SERVER_ERROR,
-        Json("No channel found".to_string()),
-    ))?;
-    let time_values: Vec<f64> = if let Some(ref json) = first_channel.time_values {
-        serde_json::from_value(json.clone()).unwrap_or_default()
-    } else {
-        Vec::new()
-    };
+    let time_values = channels[0].1.clone().unwrap_or_default();
     if time_values.len() < 2 {
         return Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -221,114 +318,93 @@ pub async fn get_filtered_data(
     }
     #[allow(clippy::cast_possible_truncation)]
     let time_step = (time_values[1] - time_values[0]).round() as i64;
-    #[allow(clippy::cast_possible_truncation)]
-    let max_time = samples
-        .iter()
-        .filter_map(|sample| sample.get("end").and_then(sea_orm::JsonValue::as_f64))
-        .fold(0.0, f64::max)
-        .round() as i64;
-
-    // Build CSV rows: for each time value (stepping by time_step), add baseline data from each sample.
-    let mut t = 0;
-    while t <= max_time {
-        let mut row: Vec<JsonValue> = vec![JsonValue::Number(serde_json::Number::from(t))];
-        let mut empty_count = 0;
-        for sample in &samples {
-            let baseline: Vec<JsonValue> = sample
-                .get("baseline_values")
-                .and_then(|v| serde_json::from_value(v.clone()).ok())
-                .unwrap_or_default();
-            let value = baseline
-                .get(usize::try_from(t / time_step).unwrap())
-                .cloned()
-                .unwrap_or(JsonValue::Null);
-            if value.is_null() {
-                empty_count += 1;
-            }
-            row.push(value);
-        }
-        if empty_count == samples.len() {
-            break;
-        }
-        csv_data.push(row);
-        t += time_step;
+    if time_step < 1 {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json("Filtered data needs a time step of at least one second".to_string()),
+        ));
     }
-    Ok(Json(csv_data))
+
+    Ok(filtered_table(build_vector_of_samples(channels), time_step))
+}
+
+/// Assembles the filtered rows, leaving samples that have ended empty and
+/// stopping once every sample has ended.
+fn filtered_table(
+    mut samples: Vec<FilteredSample>,
+    time_step: i64,
+) -> impl Iterator<Item = Vec<JsonValue>> + Send + 'static {
+    // Ensure unique column names.
+    ensure_unique_column_name(&mut samples);
+
+    // Sort samples by their column name.
+    samples.sort_by(|a, b| a.column.cmp(&b.column));
+
+    // Build CSV header: "time/s" plus each sample's column name.
+    let header: Vec<JsonValue> = std::iter::once(json!("time/s"))
+        .chain(samples.iter().map(|s| json!(s.column)))
+        .collect();
+
+    #[allow(clippy::cast_possible_truncation)]
+    let max_time = samples.iter().map(|s| s.end).fold(0.0, f64::max).round() as i64;
+
+    // Build CSV rows: for each time value (stepping by time_step), add the
+    // baseline data of each sample, stopping once every sample has ended.
+    let rows = (0..)
+        .map(move |step| step * time_step)
+        .take_while(move |t| *t <= max_time)
+        .map(move |t| {
+            let index = usize::try_from(t / time_step).unwrap_or(usize::MAX);
+            std::iter::once(json!(t))
+                .chain(samples.iter().map(|s| {
+                    s.baseline_values
+                        .get(index)
+                        .map_or(JsonValue::Null, |v| json!(v))
+                }))
+                .collect::<Vec<JsonValue>>()
+        })
+        .take_while(|row| row.iter().skip(1).any(|v| !v.is_null()));
+    std::iter::once(header).chain(rows)
 }
 
-fn ensure_unique_column_name(
-    samples: &mut Vec<serde_json::Map<String, JsonValue>>,
-) -> &mut Vec<serde_json::Map<String, JsonValue>> {
+fn ensure_unique_column_name(samples: &mut [FilteredSample]) {
     let mut updates = Vec::new();
-    for i in 0..samples.len() {
-        let col_i = samples[i]
-            .get("column")
-            .and_then(|v| v.as_str())
-            .unwrap_or("");
-        for (j, sample_j) in samples.iter().enumerate() {
-            if i != j {
-                let col_j = sample_j
-                    .get("column")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("");
-                if col_i == col_j {
-                    updates.push((i, format!("{col_i}_{i}")));
-                }
-            }
+    for (i, sample_i) in samples.iter().enumerate() {
+        let duplicated = samples
+            .iter()
+            .enumerate()
+            .any(|(j, sample_j)| i != j && sample_i.column == sample_j.column);
+        if duplicated {
+            updates.push((i, format!("{}_{i}", sample_i.column)));
         }
     }
 
     for (i, new_col) in updates {
-        samples[i].insert("column".to_string(), JsonValue::String(new_col));
+        samples[i].column = new_col;
     }
-
-    samples
 }
 
-fn build_vector_of_samples(
-    channels: &Vec<channel_db::Model>,
-) -> Vec<serde_json::Map<String, JsonValue>> {
-    // Build a vector of sample results.
-    // Each sample result is a JSON object (represented as a serde_json::Map)
-    let mut samples: Vec<serde_json::Map<String, JsonValue>> = Vec::new();
-
-    for channel in channels {
-        // Parse time_values and baseline_values as Vec<f64>.
-        let time_values: Vec<f64> = if let Some(ref json) = channel.time_values {
-            serde_json::from_value(json.clone()).unwrap_or_default()
-        } else {
-            Vec::new()
-        };
-        let baseline_values: Vec<f64> = if let Some(ref json) = channel.baseline_values {
-            serde_json::from_value(json.clone()).unwrap_or_default()
-        } else {
-            Vec::new()
-        };
+fn build_vector_of_samples(channels: Vec<FilteredChannel>) -> Vec<FilteredSample> {
+    let mut samples = Vec::new();
+
+    for (channel_name, time_values, baseline_values, integral_results) in channels {
+        let time_values = time_values.unwrap_or_default();
+        let baseline_values = baseline_values.unwrap_or_default();
 
         // Parse integral_results as an array of JSON objects.
-        let integral_results: Vec<JsonValue> = if let Some(ref json) = channel.integral_results {
-            serde_json::from_value(json.clone()).unwrap_or_default()
-        } else {
-            Vec::new()
-        };
-
-        for mut result in integral_results {
-            // Insert channel_name into the result.
-            if let Some(obj) = result.as_object_mut() {
-                obj.insert(
-                    "channel_name".to_string(),
-                    JsonValue::String(channel.channel_name.clone()),
-                );
-                // Compute a column name using channel_name and sample_name (or "undefined")
-                let sample_name = obj
-                    .get("sample_name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("undefined");
-                let column = format!("{}_{}", channel.channel_name, sample_name)
-                    .to_lowercase()
-                    .replace(' ', "_");
-                obj.insert("column".to_string(), JsonValue::String(column));
-            }
+        let integral_results: Vec<JsonValue> = integral_results
+            .and_then(|json| serde_json::from_value(json).ok())
+            .unwrap_or_default();
+
+        for result in integral_results {
+            // Compute a column name using channel_name and sample_name (or "undefined")
+            let sample_name = result
+                .get("sample_name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("undefined");
+            let column = format!("{channel_name}_{sample_name}")
+                .to_lowercase()
+                .replace(' ', "_");
 
             // Get start and end markers.
             let start = result
@@ -342,108 +418,334 @@ fn build_vector_of_samples(
             // Find indices in time_values.
             let start_index = time_values.iter().position(|&v| (v - start).abs() < 1e-6);
             let end_index = time_values.iter().position(|&v| (v - end).abs() < 1e-6);
-            if let (Some(si), Some(ei)) = (start_index, end_index) {
-                let slice: Vec<f64> = baseline_values.get(si..ei).unwrap_or(&[]).to_vec();
-                if let Some(obj) = result.as_object_mut() {
-                    obj.insert(
-                        "baseline_values".to_string(),
-                        serde_json::to_value(slice).unwrap_or(JsonValue::Null),
-                    );
-                }
-            }
-            samples.push(result.as_object().cloned().unwrap());
+            let slice = match (start_index, end_index) {
+                (Some(si), Some(ei)) => baseline_values.get(si..ei).unwrap_or(&[]).to_vec(),
+                _ => Vec::new(),
+            };
+            samples.push(FilteredSample {
+                column,
+                end,
+                baseline_values: slice,
+            });
         }
     }
     samples
 }
 
+/// Columns of each sample in the summary export
+const SUMMARY_COLUMNS_PER_SAMPLE: usize = 6;
+
 #[utoipa::path(
     get,
     path = "/{id}/summary",
     responses(
-        (status = 200, description = "Summary data found", body = Vec<Vec<String>>),
+        (status = 200, description = "Summary data found", content(
+            (Vec<Vec<String>> = "application/json"),
+            (String = "text/csv"),
+            ("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")
+        )),
         (status = 404, description = "Experiment not found"),
         (status = 500, description = "Internal server error")
     ),
     params(
-        ("id" = Uuid, description = "Experiment ID")
+        ("id" = Uuid, description = "Experiment ID"),
+        ("format" = Option<String>, Query, description = "`json`, `csv` or `xlsx`; overrides the Accept header")
     ),
     summary = format!("Get summary data for {}", InstrumentExperiment::RESOURCE_NAME_SINGULAR),
-    description = "Returns CSV data (as JSON) built from each channel’s integral_results."
+    description = "Returns a row per channel built from its integral_results, with the calibrated concentration and its standard deviation per sample. Standards show their known concentration. Formats are negotiated as for the raw data."
 )]
 pub async fn get_summary_data(
     Path(id): Path<Uuid>,
+    Query(query): Query<ExportQuery>,
+    headers: HeaderMap,
     State(db): State<DatabaseConnection>,
-) -> Result<Json<Vec<Vec<String>>>, (StatusCode, Json<String>)> {
-    let channels = channel_db::Entity::find()
+) -> Result<Response, (StatusCode, Json<String>)> {
+    let experiment = find_experiment(&db, id).await?;
+    match ExportFormat::negotiate(query.format, &headers) {
+        ExportFormat::Json => Ok(Json(summary_rows(&db, id).await?).into_response()),
+        ExportFormat::Csv => Ok(stream_csv_rows(
+            summary_rows(&db, id).await?.into_iter(),
+            &download_name(&experiment, Some("summary"), "csv"),
+        )),
+        ExportFormat::Xlsx => workbook_response(&db, &experiment).await,
+    }
+}
+
+/// Rows of the summary: one row per channel with six columns per sample.
+async fn summary_rows(
+    db: &DatabaseConnection,
+    id: Uuid,
+) -> Result<Vec<Vec<String>>, (StatusCode, Json<String>)> {
+    // Only the results are needed, not the channel signals.
+    let mut channels: Vec<(String, Option<JsonValue>)> = channel_db::Entity::find()
+        .select_only()
+        .column(channel_db::Column::ChannelName)
+        .column(channel_db::Column::IntegralResults)
         .filter(channel_db::Column::ExperimentId.eq(id))
-        .all(&db)
+        .into_tuple()
+        .all(db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.to_string())))?;
     if channels.is_empty() {
         return Err((StatusCode::NOT_FOUND, Json("No channels found".to_string())));
     }
-    let mut channels = channels;
-    channels.sort_by(|a, b| a.channel_name.cmp(&b.channel_name));
+    channels.sort_by(|a, b| a.0.cmp(&b.0));
 
     // Determine the maximum number of samples (i.e. length of integral_results) among all channels.
     let mut max_samples = 0;
     let mut channel_results: Vec<(String, Vec<JsonValue>)> = Vec::new();
-    for channel in &channels {
-        let integral_results: Vec<JsonValue> = if let Some(ref json) = channel.integral_results {
-            serde_json::from_value(json.clone()).unwrap_or_default()
-        } else {
-            Vec::new()
-        };
+    for (channel_name, integral_results) in channels {
+        let integral_results: Vec<JsonValue> = integral_results
+            .and_then(|json| serde_json::from_value(json).ok())
+            .unwrap_or_default();
         max_samples = max_samples.max(integral_results.len());
-        channel_results.push((channel.channel_name.clone(), integral_results));
+        channel_results.push((channel_name, integral_results));
     }
 
-    // Build CSV header: "measurement" plus four columns per sample.
+    // Build CSV header: "measurement" plus six columns per sample.
     let mut header = vec!["measurement".to_string()];
     for i in 1..=max_samples {
         header.push(format!("sample{i}_start"));
         header.push(format!("sample{i}_end"));
         header.push(format!("sample{i}_electrons_transferred_mol"));
         header.push(format!("sample{i}_sample_name"));
+        header.push(format!("sample{i}_concentration"));
+        header.push(format!("sample{i}_concentration_sd"));
     }
     let mut csv_data = vec![header];
 
+    let number = |sample: &JsonValue, key: &str| {
+        sample
+            .get(key)
+            .and_then(sea_orm::JsonValue::as_f64)
+            .map_or("nan".to_string(), |v| v.to_string())
+    };
+
     // For each channel, build a row with its name and then each sample's integral data.
     for (channel_name, integral_results) in channel_results {
         let mut row = vec![channel_name];
         for sample in &integral_results {
-            let start = sample
-                .get("start")
-                .and_then(sea_orm::JsonValue::as_f64)
-                .map_or("nan".to_string(), |v| v.to_string());
-            let end = sample
-                .get("end")
-                .and_then(sea_orm::JsonValue::as_f64)
-                .map_or("nan".to_string(), |v| v.to_string());
-            let area = sample
-                .get("area")
-                .and_then(sea_orm::JsonValue::as_f64)
-                .map_or("nan".to_string(), |v| v.to_string());
             let sample_name = sample
                 .get("sample_name")
                 .and_then(|v| v.as_str())
                 .unwrap_or("nan")
                 .to_string();
-            row.push(start);
-            row.push(end);
-            row.push(area);
+            // Standards report their known concentration without uncertainty.
+            let concentration = if sample.get("standard_concentration").is_some() {
+                number(sample, "standard_concentration")
+            } else {
+                number(sample, "concentration")
+            };
+            row.push(number(sample, "start"));
+            row.push(number(sample, "end"));
+            row.push(number(sample, "area"));
             row.push(sample_name);
+            row.push(concentration);
+            row.push(number(sample, "concentration_sd"));
         }
         // If there are fewer samples than max_samples, fill the remaining columns with "nan".
-        let remaining = max_samples - row.len() / 4;
-        for _ in 0..remaining {
-            row.push("nan".to_string());
-            row.push("nan".to_string());
-            row.push("nan".to_string());
-            row.push("nan".to_string());
-        }
+        let remaining = max_samples - integral_results.len();
+        row.extend(std::iter::repeat_n(
+            "nan".to_string(),
+            remaining * SUMMARY_COLUMNS_PER_SAMPLE,
+        ));
         csv_data.push(row);
     }
-    Ok(Json(csv_data))
+    Ok(csv_data)
+}
+
+/// Request body for the CH Instruments file upload endpoint.
+#[derive(Deserialize, ToSchema)]
+pub struct ChiUploadRequest {
+    /// File content as a base64 data URL (or plain base64)
+    pub data_base64: String,
+    /// Name of the uploaded file
+    pub filename: Option<String>,
+    /// Experiment name; defaults to the file name
+    pub name: Option<String>,
+    /// Defaults to the technique line of the file
+    pub description: Option<String>,
+    pub project_id: Option<Uuid>,
+    /// Offset of the workstation clock from UTC in minutes
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+    /// Names of the injected samples in run order
+    pub sample_names: Option<Vec<String>>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/upload",
+    request_body = ChiUploadRequest,
+    responses(
+        (status = 201, description = "Experiment created from the file", body = InstrumentExperiment),
+        (status = 422, description = "Invalid or unreadable file"),
+        (status = 500, description = "Internal server error")
+    ),
+    summary = "Upload a CH Instruments amperometry file",
+    description = "Parses a CH Instruments text export of an amperometric i-t run. The experiment metadata (date, instrument model, init E, sample interval, run and quiet time, sensitivity) is taken from the file header, and one channel is created per current column with its time_values and raw_values."
+)]
+pub async fn upload_chi_file(
+    State(db): State<DatabaseConnection>,
+    Json(req): Json<ChiUploadRequest>,
+) -> Result<(StatusCode, Json<InstrumentExperiment>), (StatusCode, Json<String>)> {
+    let text = decode_base64_text(&req.data_base64)
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, Json(e)))?;
+    let parsed = parse_chi(&text, req.utc_offset_minutes)
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, Json(e)))?;
+
+    let header = parsed.header;
+    let experiment_id = Uuid::new_v4();
+    let experiment = db::ActiveModel {
+        id: ActiveValue::Set(experiment_id),
+        name: ActiveValue::Set(req.name.or_else(|| req.filename.clone())),
+        date: ActiveValue::Set(header.date),
+        description: ActiveValue::Set(req.description.or(header.technique)),
+        filename: ActiveValue::Set(req.filename),
+        device_filename: ActiveValue::Set(header.file),
+        data_source: ActiveValue::Set(header.data_source),
+        instrument_model: ActiveValue::Set(header.instrument_model),
+        init_e: ActiveValue::Set(header.init_e),
+        sample_interval: ActiveValue::Set(header.sample_interval),
+        run_time: ActiveValue::Set(header.run_time),
+        quiet_time: ActiveValue::Set(header.quiet_time),
+        sensitivity: ActiveValue::Set(header.sensitivity),
+        samples: ActiveValue::Set(
+            req.sample_names
+                .as_ref()
+                .and_then(|names| i32::try_from(names.len()).ok()),
+        ),
+        last_updated: ActiveValue::Set(chrono::Utc::now()),
+        project_id: ActiveValue::Set(req.project_id),
+        sample_names: ActiveValue::Set(req.sample_names.map(|names| json!(names))),
+    };
+
+    let txn = db.begin().await.map_err(db_error_response)?;
+    experiment.insert(&txn).await.map_err(db_error_response)?;
+    for channel in parsed.channels {
+        channel_db::ActiveModel {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            channel_name: ActiveValue::Set(channel.name),
+            experiment_id: ActiveValue::Set(experiment_id),
+            time_values: ActiveValue::Set(Some(parsed.time_values.clone())),
+            raw_values: ActiveValue::Set(Some(channel.values)),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await
+        .map_err(db_error_response)?;
+    }
+    txn.commit().await.map_err(db_error_response)?;
+
+    let experiment = InstrumentExperiment::get_one(&db, experiment_id)
+        .await
+        .map_err(db_error_response)?;
+    Ok((StatusCode::CREATED, Json(experiment)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/plot_samples/preview",
+    request_body = SampleMappingRequest,
+    responses(
+        (status = 200, description = "Values that would be written", body = Vec<SampleMappingRow>),
+        (status = 404, description = "Experiment, channel or plot sample not found"),
+        (status = 422, description = "No results to map or conflicting matches"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("id" = Uuid, description = "Experiment ID")
+    ),
+    summary = "Preview pushing results into plot samples",
+    description = "Matches the sample names of the integral results to plot samples by name, or through explicit plot sample ids, and returns the current and new value of the chosen field. Results are averaged over the selected channels; standards are left out. Nothing is written."
+)]
+pub async fn preview_plot_sample_values(
+    Path(id): Path<Uuid>,
+    State(db): State<DatabaseConnection>,
+    Json(request): Json<SampleMappingRequest>,
+) -> Result<Json<Vec<SampleMappingRow>>, (StatusCode, Json<String>)> {
+    preview_sample_values(&db, id, &request)
+        .await
+        .map(Json)
+        .map_err(db_error_response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(column: &str, end: f64, baseline_values: Vec<f64>) -> FilteredSample {
+        FilteredSample {
+            column: column.to_string(),
+            end,
+            baseline_values,
+        }
+    }
+
+    #[test]
+    fn raw_rows_pad_shorter_channels() {
+        let channels = vec![
+            ("i2".to_string(), None, Some(vec![5.0])),
+            (
+                "i1".to_string(),
+                Some(vec![0.0, 1.0, 2.0]),
+                Some(vec![1.0, 2.0, 3.0]),
+            ),
+        ];
+        let rows: Vec<Vec<String>> = raw_table(channels).collect();
+        assert_eq!(
+            rows,
+            [
+                vec!["Time/s", "i1", "i2"],
+                vec!["0", "1", "5"],
+                vec!["1", "2", "N/A"],
+                vec!["2", "3", "N/A"],
+            ]
+        );
+    }
+
+    #[test]
+    fn filtered_rows_stop_once_every_sample_has_ended() {
+        let samples = vec![
+            sample("i1_b", 20.0, vec![1.0, 2.0, 3.0]),
+            sample("i1_a", 10.0, vec![4.0]),
+        ];
+        let rows: Vec<Vec<JsonValue>> = filtered_table(samples, 5).collect();
+        assert_eq!(
+            rows,
+            [
+                vec![json!("time/s"), json!("i1_a"), json!("i1_b")],
+                vec![json!(0), json!(4.0), json!(1.0)],
+                vec![json!(5), JsonValue::Null, json!(2.0)],
+                vec![json!(10), JsonValue::Null, json!(3.0)],
+            ]
+        );
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/plot_samples",
+    request_body = SampleMappingRequest,
+    responses(
+        (status = 200, description = "Values written; unmatched and ambiguous rows were skipped", body = Vec<SampleMappingRow>),
+        (status = 404, description = "Experiment, channel or plot sample not found"),
+        (status = 422, description = "No results to map or conflicting matches"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("id" = Uuid, description = "Experiment ID")
+    ),
+    summary = "Push results into plot samples",
+    description = "Writes the values shown by the preview into the chosen plot sample field. Each written value is recorded with its experiment, channels, sample name and previous value, and can be listed on the plot sample."
+)]
+pub async fn apply_plot_sample_values(
+    Path(id): Path<Uuid>,
+    State(db): State<DatabaseConnection>,
+    Json(request): Json<SampleMappingRequest>,
+) -> Result<Json<Vec<SampleMappingRow>>, (StatusCode, Json<String>)> {
+    apply_sample_values(&db, id, &request)
+        .await
+        .map(Json)
+        .map_err(db_error_response)
 }
@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Where a plot sample value written from instrument results came from
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, ToSchema)]
+#[sea_orm(table_name = "plotsample_value_source")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub plotsample_id: Uuid,
+    /// Name of the plot sample field that was written
+    pub field: String,
+    #[sea_orm(column_type = "Double")]
+    pub value: f64,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub previous_value: Option<f64>,
+    pub experiment_id: Option<Uuid>,
+    /// Channels whose results were averaged into the value
+    #[schema(value_type = Vec<Uuid>)]
+    pub channel_ids: Json,
+    pub sample_name: String,
+    /// Integral result the value was taken from, `concentration` or `area`
+    pub result_value: String,
+    pub written_on: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::routes::private::samples::db::Entity",
+        from = "Column::PlotsampleId",
+        to = "crate::routes::private::samples::db::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Plotsample,
+}
+
+impl Related<crate::routes::private::samples::db::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Plotsample.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
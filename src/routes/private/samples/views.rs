@@ -1,10 +1,12 @@
 use super::models::{PlotSample, PlotSampleCreate, PlotSampleUpdate};
+use super::sources::db as source_db;
 use crate::common::auth::Role;
+use crate::common::errors::db_error_response;
 use axum_keycloak_auth::{
     PassthroughMode, instance::KeycloakAuthInstance, layer::KeycloakAuthLayer,
 };
 use crudcrate::{CRUDResource, crud_handlers};
-use sea_orm::DatabaseConnection;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
 use std::sync::Arc;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
@@ -24,6 +26,7 @@ where
         .routes(routes!(update_one_handler))
         .routes(routes!(delete_one_handler))
         .routes(routes!(delete_many_handler))
+        .routes(routes!(get_value_sources))
         .with_state(db.clone());
 
     if let Some(instance) = keycloak_auth_instance {
@@ -45,3 +48,29 @@ where
 
     mutating_router
 }
+
+#[utoipa::path(
+    get,
+    path = "/{id}/sources",
+    responses(
+        (status = 200, description = "Sources of the written values", body = Vec<source_db::Model>),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("id" = Uuid, description = "Plot sample ID")
+    ),
+    summary = format!("Get value sources of a {}", PlotSample::RESOURCE_NAME_SINGULAR),
+    description = "Lists the values written into this plot sample from instrument experiment results, newest first, with the experiment, channels and sample name each came from."
+)]
+pub async fn get_value_sources(
+    Path(id): Path<Uuid>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<Vec<source_db::Model>>, (StatusCode, Json<String>)> {
+    source_db::Entity::find()
+        .filter(source_db::Column::PlotsampleId.eq(id))
+        .order_by_desc(source_db::Column::WrittenOn)
+        .all(&db)
+        .await
+        .map(Json)
+        .map_err(db_error_response)
+}
@@ -14,6 +14,7 @@ pub struct Model {
     pub last_updated: DateTime<Utc>,
     pub serial_number: Option<String>,
     pub manufacturer: Option<String>,
+    pub sensortype_id: Option<Uuid>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -22,6 +23,16 @@ pub enum Relation {
     SensorProfileAssignments,
     #[sea_orm(has_many = "crate::routes::private::sensors::data::db::Entity")]
     Sensordata,
+    #[sea_orm(has_many = "crate::routes::private::sensors::measurements::db::Entity")]
+    Measurements,
+    #[sea_orm(
+        belongs_to = "crate::routes::private::sensors::types::db::Entity",
+        from = "Column::SensortypeId",
+        to = "crate::routes::private::sensors::types::db::Column::Id",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    SensorType,
 }
 
 impl Related<crate::routes::private::sensors::profile::assignment::db::Entity> for Entity {
@@ -36,4 +47,16 @@ impl Related<crate::routes::private::sensors::data::db::Entity> for Entity {
     }
 }
 
+impl Related<crate::routes::private::sensors::measurements::db::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Measurements.def()
+    }
+}
+
+impl Related<crate::routes::private::sensors::types::db::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SensorType.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
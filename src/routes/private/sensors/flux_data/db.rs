@@ -1,9 +1,24 @@
 use chrono::{DateTime, Utc};
 use sea_orm::entity::prelude::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
+/// Concentration model a chamber flux was derived from
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "flux_model_enum")]
+pub enum FluxModelEnum {
+    #[sea_orm(string_value = "linear")]
+    Linear,
+    #[sea_orm(string_value = "exponential")]
+    Exponential,
+    #[sea_orm(string_value = "hmr")]
+    Hmr,
+}
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, ToSchema)]
 #[sea_orm(table_name = "flux_data")]
 pub struct Model {
@@ -21,6 +36,23 @@ pub struct Model {
     pub r2_h2o: Option<f64>,
     pub swc: Option<f64>,
     pub n_measurements: Option<i32>,
+    pub fit_start_s: Option<f64>,
+    pub fit_end_s: Option<f64>,
+    pub collar_offset_cm: Option<f64>,
+    pub volume_ml: Option<f64>,
+    pub effective_volume_ml: Option<f64>,
+    pub flux_model_co2: Option<FluxModelEnum>,
+    pub flux_model_ch4: Option<FluxModelEnum>,
+    pub flux_model_h2o: Option<FluxModelEnum>,
+    #[sea_orm(column_type = "Json")]
+    pub flux_fits: Option<serde_json::Value>,
+    pub qc_flagged_co2: Option<bool>,
+    pub qc_flagged_ch4: Option<bool>,
+    pub qc_flagged_h2o: Option<bool>,
+    #[sea_orm(column_type = "Json")]
+    pub qc_reasons: Option<serde_json::Value>,
+    pub qc_override: bool,
+    pub has_ch4: bool,
     #[sea_orm(column_type = "Json")]
     pub raw_readings: Option<serde_json::Value>,
 }
@@ -0,0 +1,582 @@
+use super::db as FluxDB;
+use super::qc::db::FluxGasEnum;
+use super::sensitivity::{nearest_reading, nearest_tms_profile};
+use super::summary::{load_chamber_fluxes, unflagged_flux};
+use super::views::RawReading;
+use crate::routes::private::sensors::profile::db as ProfileDB;
+use crate::routes::private::sensors::profile::models::{DepthAverageData, SensorProfile};
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use sea_orm::{DatabaseConnection, DbErr};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Missing-value code of both networks
+const MISSING: &str = "-9999";
+/// Largest time between a closure and the soil reading paired with it
+const MAX_SOIL_OFFSET: TimeDelta = TimeDelta::minutes(30);
+/// Averaging period of the FLUXNET half-hourly table
+const HALF_HOUR: TimeDelta = TimeDelta::minutes(30);
+
+#[derive(ToSchema, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FluxExportFormat {
+    /// ICOS ETC chamber fluxes: one row per closure
+    Icos,
+    /// FLUXNET half-hourly table with one qualified column per chamber
+    Fluxnet,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FluxExportQuery {
+    pub format: FluxExportFormat,
+    /// Network site code, e.g. `CH-Dav`
+    pub site_id: String,
+    pub area_id: Option<Uuid>,
+    pub sensorprofile_id: Option<Uuid>,
+    pub setting: Option<String>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    /// TMS temperature depth; defaults to the moisture sensor depth
+    pub ts_depth_cm: Option<i32>,
+}
+
+/// One file of an export
+#[derive(ToSchema, Serialize, Debug, Clone, PartialEq)]
+pub struct ExportFile {
+    pub filename: String,
+    pub media_type: String,
+    pub content: String,
+}
+
+/// Data file followed by the variable and chamber metadata files
+#[derive(ToSchema, Serialize, Debug, Clone, PartialEq)]
+pub struct FluxExport {
+    pub format: FluxExportFormat,
+    pub site_id: String,
+    pub files: Vec<ExportFile>,
+}
+
+/// A chamber profile with its export qualifier and co-located soil series
+struct ExportChamber {
+    profile: ProfileDB::Model,
+    /// `chamber_id_external`, otherwise the profile name
+    chamber_id: String,
+    /// Horizontal index of the FLUXNET `_H_V_R` qualifier, from 1
+    index: usize,
+    tms: Option<ProfileDB::Model>,
+    ts_depth_cm: Option<i32>,
+    swc_depth_cm: Option<i32>,
+    ts: Vec<DepthAverageData>,
+    swc: Vec<DepthAverageData>,
+}
+
+/// One closure in network units
+struct ExportRow {
+    chamber: usize,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    replicate: String,
+    setting: Option<String>,
+    /// µmol CO2 m⁻² s⁻¹
+    fc: Option<f64>,
+    fc_r2: Option<f64>,
+    /// nmol CH4 m⁻² s⁻¹
+    fch4: Option<f64>,
+    fch4_r2: Option<f64>,
+    /// mmol H2O m⁻² s⁻¹
+    fh2o: Option<f64>,
+    fh2o_r2: Option<f64>,
+    /// °C
+    ts: Option<f64>,
+    /// %
+    swc: Option<f64>,
+    /// Chamber air temperature, °C
+    ta: Option<f64>,
+    /// Chamber air pressure, kPa
+    pa: Option<f64>,
+}
+
+fn value(v: Option<f64>) -> String {
+    v.filter(|v| v.is_finite())
+        .map_or_else(|| MISSING.to_string(), |v| format!("{v:.4}"))
+}
+
+/// Rows as CSV text; names with commas, quotes or line breaks are quoted.
+fn csv_text(rows: impl IntoIterator<Item = Vec<String>>) -> String {
+    let mut writer = csv::WriterBuilder::new()
+        .terminator(csv::Terminator::Any(b'\n'))
+        .from_writer(Vec::new());
+    for row in rows {
+        // Writing into memory cannot fail.
+        let _ = writer.write_record(&row);
+    }
+    String::from_utf8(writer.into_inner().unwrap_or_default()).unwrap_or_default()
+}
+
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%d%H%M").to_string()
+}
+
+fn average(values: impl Iterator<Item = Option<f64>>) -> Option<f64> {
+    let values: Vec<f64> = values.flatten().collect();
+    (!values.is_empty()).then(|| super::fitting::mean(&values))
+}
+
+/// Build the network row of a closure, taking its end and chamber air conditions
+/// from the stored raw readings.
+fn export_row(record: &FluxDB::Model, chamber: &ExportChamber) -> ExportRow {
+    let readings: Vec<RawReading> = record
+        .raw_readings
+        .clone()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+    let duration = readings
+        .last()
+        .and_then(|r| std::time::Duration::try_from_secs_f64(r.t).ok())
+        .and_then(|d| TimeDelta::from_std(d).ok())
+        .unwrap_or_default();
+    let soil =
+        |series: &[DepthAverageData]| nearest_reading(series, record.measured_on, MAX_SOIL_OFFSET);
+    let r2 = |gas, r2: Option<f64>| unflagged_flux(record, gas).and(r2);
+
+    ExportRow {
+        chamber: chamber.index,
+        start: record.measured_on,
+        end: record.measured_on + duration,
+        replicate: record.replicate.clone(),
+        setting: record.setting.clone(),
+        fc: unflagged_flux(record, FluxGasEnum::Co2),
+        fc_r2: r2(FluxGasEnum::Co2, record.r2_co2),
+        fch4: unflagged_flux(record, FluxGasEnum::Ch4),
+        fch4_r2: r2(FluxGasEnum::Ch4, record.r2_ch4),
+        fh2o: unflagged_flux(record, FluxGasEnum::H2o).map(|v| v / 1e3),
+        fh2o_r2: r2(FluxGasEnum::H2o, record.r2_h2o),
+        ts: soil(&chamber.ts),
+        swc: soil(&chamber.swc).map(|v| v * 100.0),
+        ta: average(readings.iter().map(|r| Some(r.temp))),
+        pa: average(readings.iter().map(|r| Some(r.press))),
+    }
+}
+
+/// ICOS ETC chamber layout: one row per closure, identified by chamber
+fn icos_data(rows: &[ExportRow], chambers: &[ExportChamber]) -> String {
+    let header = [
+        "TIMESTAMP_START",
+        "TIMESTAMP_END",
+        "CHAMBER_ID",
+        "REPLICATE",
+        "SETTING",
+        "FC",
+        "FC_R2",
+        "FCH4",
+        "FCH4_R2",
+        "FH2O",
+        "FH2O_R2",
+        "TS",
+        "SWC",
+        "TA",
+        "PA",
+    ];
+    let mut lines = vec![header.map(String::from).to_vec()];
+    for row in rows {
+        lines.push(vec![
+            timestamp(row.start),
+            timestamp(row.end),
+            chambers[row.chamber - 1].chamber_id.clone(),
+            row.replicate.clone(),
+            row.setting.clone().unwrap_or_else(|| MISSING.to_string()),
+            value(row.fc),
+            value(row.fc_r2),
+            value(row.fch4),
+            value(row.fch4_r2),
+            value(row.fh2o),
+            value(row.fh2o_r2),
+            value(row.ts),
+            value(row.swc),
+            value(row.ta),
+            value(row.pa),
+        ]);
+    }
+    csv_text(lines)
+}
+
+/// Variables of each chamber in the FLUXNET table
+const FLUXNET_VARIABLES: [&str; 5] = ["FC", "FCH4", "FH2O", "TS", "SWC"];
+
+/// FLUXNET half-hourly layout: a continuous half-hour grid over the closures,
+/// one `VAR_H_1_1` column per variable and chamber, averaging the closures that
+/// start within each half hour.
+fn fluxnet_data(rows: &[ExportRow], chambers: &[ExportChamber]) -> String {
+    let mut header = vec!["TIMESTAMP_START".to_string(), "TIMESTAMP_END".to_string()];
+    for chamber in chambers {
+        for variable in FLUXNET_VARIABLES {
+            header.push(format!("{variable}_{}_1_1", chamber.index));
+        }
+    }
+    let mut lines = vec![header];
+
+    let bucket_of = |time: DateTime<Utc>| time.duration_trunc(HALF_HOUR).unwrap_or(time);
+    let mut buckets: HashMap<(DateTime<Utc>, usize), Vec<&ExportRow>> = HashMap::new();
+    for row in rows {
+        buckets
+            .entry((bucket_of(row.start), row.chamber))
+            .or_default()
+            .push(row);
+    }
+    let (Some(first), Some(last)) = (
+        rows.iter().map(|r| bucket_of(r.start)).min(),
+        rows.iter().map(|r| bucket_of(r.start)).max(),
+    ) else {
+        return csv_text(lines);
+    };
+
+    let mut bucket = first;
+    while bucket <= last {
+        let mut line = vec![timestamp(bucket), timestamp(bucket + HALF_HOUR)];
+        for chamber in chambers {
+            let closures = buckets
+                .get(&(bucket, chamber.index))
+                .map_or(&[][..], Vec::as_slice);
+            line.extend([
+                value(average(closures.iter().map(|r| r.fc))),
+                value(average(closures.iter().map(|r| r.fch4))),
+                value(average(closures.iter().map(|r| r.fh2o))),
+                value(average(closures.iter().map(|r| r.ts))),
+                value(average(closures.iter().map(|r| r.swc))),
+            ]);
+        }
+        lines.push(line);
+        bucket += HALF_HOUR;
+    }
+    csv_text(lines)
+}
+
+/// Units and description of each exported variable
+fn variable_info(format: FluxExportFormat, chambers: &[ExportChamber]) -> String {
+    let units = |variable: &str| match variable {
+        "FC" => ("umolCO2 m-2 s-1", "CO2 flux"),
+        "FCH4" => ("nmolCH4 m-2 s-1", "CH4 flux"),
+        "FH2O" => ("mmolH2O m-2 s-1", "H2O flux"),
+        "FC_R2" | "FCH4_R2" | "FH2O_R2" => ("adimensional", "R2 of the selected flux fit"),
+        "TS" => ("deg C", "Soil temperature of the nearest TMS logger"),
+        "SWC" => ("%", "Soil water content of the nearest TMS logger"),
+        "TA" => ("deg C", "Chamber air temperature, closure mean"),
+        "PA" => ("kPa", "Chamber air pressure, closure mean"),
+        _ => ("YYYYMMDDHHMM", "UTC"),
+    };
+
+    let mut lines = vec![
+        ["VARIABLE", "UNITS", "DESCRIPTION", "CHAMBER_ID"]
+            .map(String::from)
+            .to_vec(),
+    ];
+    let mut push = |variable: String, base: &str, chamber_id: &str| {
+        let (unit, description) = units(base);
+        lines.push(vec![
+            variable,
+            unit.to_string(),
+            description.to_string(),
+            chamber_id.to_string(),
+        ]);
+    };
+    push("TIMESTAMP_START".into(), "TIMESTAMP_START", MISSING);
+    push("TIMESTAMP_END".into(), "TIMESTAMP_END", MISSING);
+    match format {
+        FluxExportFormat::Icos => {
+            for variable in [
+                "FC", "FC_R2", "FCH4", "FCH4_R2", "FH2O", "FH2O_R2", "TS", "SWC", "TA", "PA",
+            ] {
+                push(variable.into(), variable, MISSING);
+            }
+        }
+        FluxExportFormat::Fluxnet => {
+            for chamber in chambers {
+                for variable in FLUXNET_VARIABLES {
+                    push(
+                        format!("{variable}_{}_1_1", chamber.index),
+                        variable,
+                        &chamber.chamber_id,
+                    );
+                }
+            }
+        }
+    }
+    csv_text(lines)
+}
+
+/// Chamber metadata from the sensor profiles
+fn chamber_metadata(chambers: &[ExportChamber]) -> String {
+    let header = [
+        "CHAMBER_ID",
+        "H_INDEX",
+        "NAME",
+        "INSTRUMENT_MODEL",
+        "AREA_M2",
+        "VOLUME_L",
+        "COORD_X",
+        "COORD_Y",
+        "COORD_Z",
+        "COORD_SRID",
+        "TMS_PROFILE",
+        "TS_DEPTH_CM",
+        "SWC_DEPTH_CM",
+    ];
+    let mut lines = vec![header.map(String::from).to_vec()];
+    for chamber in chambers {
+        let profile = &chamber.profile;
+        let text = |v: Option<String>| v.unwrap_or_else(|| MISSING.to_string());
+        lines.push(vec![
+            chamber.chamber_id.clone(),
+            chamber.index.to_string(),
+            profile.name.clone(),
+            text(profile.instrument_model.clone()),
+            value(profile.area_cm2.map(|v| v / 1e4)),
+            value(profile.volume_ml.map(|v| v / 1e3)),
+            value(profile.coord_x),
+            value(profile.coord_y),
+            value(profile.coord_z),
+            text(profile.coord_srid.map(|v| v.to_string())),
+            text(chamber.tms.as_ref().map(|t| t.name.clone())),
+            text(chamber.ts_depth_cm.map(|v| v.to_string())),
+            text(chamber.swc_depth_cm.map(|v| v.to_string())),
+        ]);
+    }
+    csv_text(lines)
+}
+
+/// Nearest TMS profile of a chamber with its soil temperature and moisture
+/// over the closures.
+async fn load_chamber(
+    db: &DatabaseConnection,
+    profile: ProfileDB::Model,
+    index: usize,
+    period: (DateTime<Utc>, DateTime<Utc>),
+    ts_depth_cm: Option<i32>,
+) -> Result<ExportChamber, DbErr> {
+    let mut chamber = ExportChamber {
+        chamber_id: profile
+            .chamber_id_external
+            .clone()
+            .unwrap_or_else(|| profile.name.clone()),
+        index,
+        tms: nearest_tms_profile(db, profile.id).await?,
+        profile,
+        ts_depth_cm,
+        swc_depth_cm: None,
+        ts: Vec::new(),
+        swc: Vec::new(),
+    };
+    let Some(tms) = chamber.tms.clone() else {
+        return Ok(chamber);
+    };
+    let tms = SensorProfile::from(tms);
+    let (from, to) = (
+        Some(period.0 - MAX_SOIL_OFFSET),
+        Some(period.1 + MAX_SOIL_OFFSET),
+    );
+    let (mut vwc, _) = tms
+        .load_moisture_data_by_depth_cm(db, None, from, to)
+        .await?;
+    chamber.swc_depth_cm = vwc.keys().min().copied();
+    if let Some(depth) = chamber.swc_depth_cm {
+        chamber.swc = vwc.remove(&depth).unwrap_or_default();
+    }
+    chamber.ts_depth_cm = ts_depth_cm.or(chamber.swc_depth_cm);
+    if let Some(depth) = chamber.ts_depth_cm {
+        chamber.ts = tms
+            .load_average_temperature_series_by_depth_cm(db, None, from, to)
+            .await?
+            .remove(&depth)
+            .unwrap_or_default();
+    }
+    Ok(chamber)
+}
+
+/// Export the chamber fluxes matching the query with co-located soil data in a
+/// network layout.
+pub async fn export_fluxes(
+    db: &DatabaseConnection,
+    query: &FluxExportQuery,
+) -> Result<FluxExport, DbErr> {
+    let site_id = query.site_id.trim();
+    if site_id.is_empty() || site_id.contains([',', '/', '\\']) {
+        return Err(DbErr::Custom("site_id must be a network site code".into()));
+    }
+
+    let (profiles, records) = load_chamber_fluxes(
+        db,
+        query.area_id,
+        query.sensorprofile_id,
+        query.setting.as_deref(),
+        query.start,
+        query.end,
+    )
+    .await?;
+    let (Some(first), Some(last)) = (records.first(), records.last()) else {
+        return Err(DbErr::RecordNotFound("No flux records to export".into()));
+    };
+    let period = (first.measured_on, last.measured_on);
+
+    let mut with_records: Vec<ProfileDB::Model> = profiles
+        .into_values()
+        .filter(|p| records.iter().any(|r| r.sensorprofile_id == p.id))
+        .collect();
+    with_records.sort_by(|a, b| (a.position, &a.name).cmp(&(b.position, &b.name)));
+
+    let mut chambers = Vec::with_capacity(with_records.len());
+    let mut index_of: BTreeMap<Uuid, usize> = BTreeMap::new();
+    for (i, profile) in with_records.into_iter().enumerate() {
+        index_of.insert(profile.id, i);
+        chambers.push(load_chamber(db, profile, i + 1, period, query.ts_depth_cm).await?);
+    }
+    let rows: Vec<ExportRow> = records
+        .iter()
+        .map(|r| export_row(r, &chambers[index_of[&r.sensorprofile_id]]))
+        .collect();
+
+    let years = format!("{}-{}", period.0.format("%Y"), period.1.format("%Y"));
+    let (data_name, data, prefix) = match query.format {
+        FluxExportFormat::Icos => (
+            format!(
+                "ICOSETC_{site_id}_FLUXES_CHAMBER_{}_{}.csv",
+                period.0.format("%Y%m%d"),
+                period.1.format("%Y%m%d")
+            ),
+            icos_data(&rows, &chambers),
+            format!("ICOSETC_{site_id}"),
+        ),
+        FluxExportFormat::Fluxnet => (
+            format!("FLX_{site_id}_FLUXNET_CHAMBER_HH_{years}.csv"),
+            fluxnet_data(&rows, &chambers),
+            format!("FLX_{site_id}"),
+        ),
+    };
+    let csv = |filename: String, content: String| ExportFile {
+        filename,
+        media_type: "text/csv".to_string(),
+        content,
+    };
+
+    Ok(FluxExport {
+        format: query.format,
+        site_id: site_id.to_string(),
+        files: vec![
+            csv(data_name, data),
+            csv(
+                format!("{prefix}_VARINFO_CHAMBER.csv"),
+                variable_info(query.format, &chambers),
+            ),
+            csv(
+                format!("{prefix}_BADM_CHAMBER.csv"),
+                chamber_metadata(&chambers),
+            ),
+        ],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chamber(index: usize) -> ExportChamber {
+        ExportChamber {
+            profile: ProfileDB::Model {
+                id: Uuid::new_v4(),
+                name: format!("Chamber {index}"),
+                description: None,
+                area_id: Uuid::new_v4(),
+                profile_type: ProfileDB::ProfileTypeEnum::Chamber,
+                soil_type_vwc: None,
+                coord_x: None,
+                coord_y: None,
+                coord_z: None,
+                coord_srid: None,
+                volume_ml: None,
+                area_cm2: None,
+                instrument_model: None,
+                chamber_id_external: None,
+                position: None,
+                logger_depth_m: None,
+                baro_profile_id: None,
+                reference_electrode: None,
+                last_updated: Utc::now(),
+            },
+            chamber_id: format!("C{index}"),
+            index,
+            tms: None,
+            ts_depth_cm: None,
+            swc_depth_cm: None,
+            ts: Vec::new(),
+            swc: Vec::new(),
+        }
+    }
+
+    fn row(chamber: usize, start: &str, fc: Option<f64>) -> ExportRow {
+        let start = DateTime::parse_from_rfc3339(start).unwrap().to_utc();
+        ExportRow {
+            chamber,
+            start,
+            end: start + TimeDelta::minutes(2),
+            replicate: "1".to_string(),
+            setting: None,
+            fc,
+            fc_r2: fc.map(|_| 0.99),
+            fch4: None,
+            fch4_r2: None,
+            fh2o: None,
+            fh2o_r2: None,
+            ts: Some(12.5),
+            swc: None,
+            ta: None,
+            pa: None,
+        }
+    }
+
+    #[test]
+    fn writes_icos_rows_with_missing_values() {
+        let chambers = [chamber(1)];
+        let csv = icos_data(&[row(1, "2026-06-01T10:05:00Z", Some(2.5))], &chambers);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[1],
+            "202606011005,202606011007,C1,1,-9999,2.5000,0.9900,-9999,-9999,-9999,-9999,12.5000,-9999,-9999,-9999"
+        );
+    }
+
+    #[test]
+    fn quotes_names_with_delimiters() {
+        let mut chambers = [chamber(1)];
+        chambers[0].chamber_id = "North, \"wet\"".to_string();
+        chambers[0].profile.name = "Plot A,\nrow 2".to_string();
+        let csv = icos_data(&[row(1, "2026-06-01T10:05:00Z", Some(2.5))], &chambers);
+        assert!(csv.contains(",\"North, \"\"wet\"\"\",1,"));
+        let metadata = chamber_metadata(&chambers);
+        assert!(metadata.contains(",\"Plot A,\nrow 2\","));
+        let mut reader = csv::Reader::from_reader(metadata.as_bytes());
+        let record = reader.records().next().unwrap().unwrap();
+        assert_eq!(record.len(), 13);
+        assert_eq!(&record[0], "North, \"wet\"");
+    }
+
+    #[test]
+    fn fills_fluxnet_half_hour_grid() {
+        let chambers = [chamber(1), chamber(2)];
+        let rows = [
+            row(1, "2026-06-01T10:05:00Z", Some(2.0)),
+            row(1, "2026-06-01T10:20:00Z", Some(4.0)),
+            row(2, "2026-06-01T11:10:00Z", None),
+        ];
+        let csv = fluxnet_data(&rows, &chambers);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].starts_with("TIMESTAMP_START,TIMESTAMP_END,FC_1_1_1,FCH4_1_1_1"));
+        assert!(lines[0].ends_with("TS_2_1_1,SWC_2_1_1"));
+        // 10:00, 10:30 (empty) and 11:00
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].starts_with("202606011000,202606011030,3.0000,-9999"));
+        assert!(lines[2].split(',').skip(2).all(|v| v == MISSING));
+        assert!(lines[3].ends_with(",-9999,-9999,-9999,12.5000,-9999"));
+    }
+}
@@ -0,0 +1,227 @@
+use super::db::FluxModelEnum;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Fewest readings a three-parameter curve is fitted to
+const MIN_NONLINEAR_READINGS: usize = 5;
+/// AIC improvement a non-linear model needs over the linear fit to be selected
+const MIN_AIC_IMPROVEMENT: f64 = 2.0;
+/// Rates scanned per model before refining the best one
+const RATE_GRID_SIZE: u32 = 120;
+
+/// Flux, goodness of fit and Akaike information criterion of one model
+#[derive(ToSchema, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ModelFit {
+    pub flux: f64,
+    pub r2: f64,
+    pub aic: f64,
+}
+
+/// All models fitted to the concentration series of one gas
+#[derive(ToSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GasFits {
+    pub linear: ModelFit,
+    pub exponential: Option<ModelFit>,
+    pub hmr: Option<ModelFit>,
+    pub selected: FluxModelEnum,
+}
+
+impl GasFits {
+    pub fn selected_fit(&self) -> ModelFit {
+        match self.selected {
+            FluxModelEnum::Exponential => self.exponential.unwrap_or(self.linear),
+            FluxModelEnum::Hmr => self.hmr.unwrap_or(self.linear),
+            FluxModelEnum::Linear => self.linear,
+        }
+    }
+}
+
+/// Model fits of every gas, stored in `flux_data.flux_fits`
+#[derive(ToSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FluxFits {
+    pub co2: GasFits,
+    pub ch4: GasFits,
+    pub h2o: GasFits,
+}
+
+pub fn count(len: usize) -> f64 {
+    f64::from(u32::try_from(len).unwrap_or(u32::MAX))
+}
+
+pub fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / count(values.len())
+}
+
+/// AIC of a least-squares fit with `params` curve parameters (plus the variance)
+fn aic(len: usize, rss: f64, params: u32) -> f64 {
+    let len = count(len);
+    len * (rss / len).max(f64::MIN_POSITIVE).ln() + 2.0 * f64::from(params + 1)
+}
+
+fn r_squared(rss: f64, c: &[f64]) -> f64 {
+    let c_mean = mean(c);
+    let tss: f64 = c.iter().map(|v| (v - c_mean).powi(2)).sum();
+    if tss < f64::EPSILON {
+        0.0
+    } else {
+        1.0 - rss / tss
+    }
+}
+
+/// Least-squares `c = a + b·x`; returns `(b, rss)`
+fn regress(x: &[f64], c: &[f64]) -> Option<(f64, f64)> {
+    let (x_mean, c_mean) = (mean(x), mean(c));
+    let variance: f64 = x.iter().map(|v| (v - x_mean).powi(2)).sum();
+    if variance < f64::EPSILON {
+        return None;
+    }
+    let covariance: f64 = x
+        .iter()
+        .zip(c)
+        .map(|(xi, ci)| (xi - x_mean) * (ci - c_mean))
+        .sum();
+    let slope = covariance / variance;
+    let intercept = c_mean - slope * x_mean;
+    let rss = x
+        .iter()
+        .zip(c)
+        .map(|(xi, ci)| (ci - intercept - slope * xi).powi(2))
+        .sum();
+    Some((slope, rss))
+}
+
+/// Fit `c = a + b·exp(rate·(t − t_ref))` for a fixed rate; returns `(b, rss)`
+fn exponential_at_rate(t: &[f64], c: &[f64], t_ref: f64, rate: f64) -> Option<(f64, f64)> {
+    let x: Vec<f64> = t.iter().map(|t| (rate * (t - t_ref)).exp()).collect();
+    if x.iter().any(|v| !v.is_finite()) {
+        return None;
+    }
+    regress(&x, c)
+}
+
+/// Best rate of `c = a + b·exp(rate·(t − t_ref))` for rates of the given sign with
+/// magnitudes between 0.01 and 20 inverse series lengths: a log-spaced scan
+/// refined by golden-section search. Returns `(rate, b, rss)`.
+fn best_exponential(t: &[f64], c: &[f64], t_ref: f64, sign: f64) -> Option<(f64, f64, f64)> {
+    let span = t.last()? - t.first()?;
+    if span <= 0.0 {
+        return None;
+    }
+    let (log_min, log_max) = ((0.01 / span).ln(), (20.0 / span).ln());
+    let rate_at = |log_rate: f64| sign * log_rate.exp();
+    let rss_at = |log_rate: f64| {
+        exponential_at_rate(t, c, t_ref, rate_at(log_rate)).map_or(f64::INFINITY, |(_, rss)| rss)
+    };
+
+    let step = (log_max - log_min) / f64::from(RATE_GRID_SIZE - 1);
+    let grid: Vec<f64> = (0..RATE_GRID_SIZE)
+        .map(|i| log_min + step * f64::from(i))
+        .collect();
+    let best = (0..grid.len()).min_by(|&a, &b| rss_at(grid[a]).total_cmp(&rss_at(grid[b])))?;
+
+    let inv_phi = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut lo, mut hi) = (
+        grid[best.saturating_sub(1)],
+        grid[(best + 1).min(grid.len() - 1)],
+    );
+    for _ in 0..40 {
+        let m1 = hi - inv_phi * (hi - lo);
+        let m2 = lo + inv_phi * (hi - lo);
+        if rss_at(m1) < rss_at(m2) {
+            hi = m2;
+        } else {
+            lo = m1;
+        }
+    }
+    let rate = rate_at(lo.midpoint(hi));
+    let (b, rss) = exponential_at_rate(t, c, t_ref, rate)?;
+    rss.is_finite().then_some((rate, b, rss))
+}
+
+/// Fit the linear, exponential and HMR models to one gas and select the best.
+///
+/// - Linear: ordinary least squares over the fit window.
+/// - Exponential: `c = a + b·exp(λ·(t − t₁))` with λ of either sign (curving up or
+///   down); the flux is the slope `b·λ` at the first fitted reading `t₁`.
+/// - HMR (Hutchinson–Mosier regression): `c = φ + b·exp(−κ·t)` with κ > 0 (chamber
+///   saturation); the flux is the slope `−b·κ` extrapolated to chamber closure
+///   (`t = 0`), i.e. through a skipped dead band.
+///
+/// Fluxes are slopes times `flux_per_slope`. A non-linear model is selected only if
+/// its AIC is at least 2 below the linear one.
+pub fn fit_gas(t: &[f64], c: &[f64], flux_per_slope: f64, linear: (f64, f64)) -> GasFits {
+    let n = t.len();
+    let linear_rss = regress(t, c).map_or(0.0, |(_, rss)| rss);
+    let linear = ModelFit {
+        flux: linear.0,
+        r2: linear.1,
+        aic: aic(n, linear_rss, 2),
+    };
+
+    let curve_fit = |sign: f64, t_ref: f64| {
+        if n < MIN_NONLINEAR_READINGS {
+            return None;
+        }
+        let (rate, b, rss) = best_exponential(t, c, t_ref, sign)?;
+        let flux = b * rate * flux_per_slope;
+        flux.is_finite().then(|| ModelFit {
+            flux,
+            r2: r_squared(rss, c),
+            aic: aic(n, rss, 3),
+        })
+    };
+    let mut fits = GasFits {
+        linear,
+        exponential: [1.0, -1.0]
+            .into_iter()
+            .filter_map(|sign| curve_fit(sign, t[0]))
+            .min_by(|a, b| a.aic.total_cmp(&b.aic)),
+        hmr: curve_fit(-1.0, 0.0),
+        selected: FluxModelEnum::Linear,
+    };
+
+    let mut best_aic = linear.aic - MIN_AIC_IMPROVEMENT;
+    for (model, fit) in [
+        (FluxModelEnum::Exponential, fits.exponential),
+        (FluxModelEnum::Hmr, fits.hmr),
+    ] {
+        if let Some(fit) = fit.filter(|f| f.aic <= best_aic) {
+            best_aic = fit.aic;
+            fits.selected = model;
+        }
+    }
+    fits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(f: impl Fn(f64) -> f64) -> (Vec<f64>, Vec<f64>) {
+        let t: Vec<f64> = (10..=120).step_by(2).map(f64::from).collect();
+        let c = t.iter().map(|&t| f(t)).collect();
+        (t, c)
+    }
+
+    #[test]
+    fn keeps_linear_model_for_linear_series() {
+        let (t, c) = series(|t| 420.0 + 0.5 * t + if t % 4.0 == 0.0 { 0.05 } else { -0.05 });
+        let fits = fit_gas(&t, &c, 1.0, (0.5, 0.999));
+        assert_eq!(fits.selected, FluxModelEnum::Linear);
+        assert!((fits.selected_fit().flux - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn selects_hmr_for_saturating_series() {
+        // Saturating chamber with an initial slope of 2 ppm/s at closure
+        let (t, c) = series(|t| 420.0 + 2.0 / 0.02 * (1.0 - (-0.02 * t).exp()));
+        let fits = fit_gas(&t, &c, 1.0, (0.6, 0.9));
+        assert_ne!(fits.selected, FluxModelEnum::Linear);
+
+        let hmr = fits.hmr.unwrap();
+        assert!((hmr.flux - 2.0).abs() < 1e-3, "HMR flux {}", hmr.flux);
+        // The exponential slope is taken at the first fitted reading (t = 10 s)
+        let exponential = fits.exponential.unwrap();
+        assert!((exponential.flux - 2.0 * (-0.2f64).exp()).abs() < 1e-3);
+    }
+}
@@ -1,3 +1,9 @@
 pub mod db;
+pub mod export;
+pub mod fitting;
 pub mod models;
+pub mod parsers;
+pub mod qc;
+pub mod sensitivity;
+pub mod summary;
 pub mod views;
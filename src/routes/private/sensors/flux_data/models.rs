@@ -1,12 +1,18 @@
-use super::db::Model;
+use super::db::{FluxModelEnum, Model};
+use super::fitting::{FluxFits, fit_gas, mean};
+use super::qc::db::{self as FluxQcDB, FluxGasEnum};
+use super::qc::models::{FluxQcResult, evaluate, load_thresholds};
+use super::views::RawReading;
+use crate::routes::private::sensors::profile::db as ProfileDB;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use crudcrate::{CRUDResource, ToCreateModel, ToUpdateModel, traits::MergeIntoActiveModel};
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait,
-    Order, QueryOrder, QuerySelect, entity::prelude::*,
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection,
+    DbErr, EntityTrait, Order, QueryOrder, QuerySelect, TransactionTrait, entity::prelude::*,
 };
 use serde::{Deserialize, Serialize};
+use soil_sensor_toolbox::compute_gas_flux;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
@@ -27,6 +33,40 @@ pub struct FluxData {
     pub r2_h2o: Option<f64>,
     pub swc: Option<f64>,
     pub n_measurements: Option<i32>,
+    // Fit window in seconds since the start of the closure (excludes the mixing
+    // dead band); changing it on update refits the stored raw readings
+    pub fit_start_s: Option<f64>,
+    pub fit_end_s: Option<f64>,
+    // Collar height above the soil surface, or a measured headspace volume that
+    // replaces the profile geometry; changing either on update refits the record
+    pub collar_offset_cm: Option<f64>,
+    pub volume_ml: Option<f64>,
+    /// Headspace volume the fluxes were computed with
+    #[crudcrate(update_model = false)]
+    pub effective_volume_ml: Option<f64>,
+    // Model selected per gas for flux_* and r2_*, and every model's flux, R² and AIC
+    #[crudcrate(update_model = false)]
+    pub flux_model_co2: Option<FluxModelEnum>,
+    #[crudcrate(update_model = false)]
+    pub flux_model_ch4: Option<FluxModelEnum>,
+    #[crudcrate(update_model = false)]
+    pub flux_model_h2o: Option<FluxModelEnum>,
+    #[crudcrate(update_model = false)]
+    #[schema(value_type = Option<FluxFits>)]
+    pub flux_fits: Option<serde_json::Value>,
+    // QC flags per gas (true = flagged) and the failed checks. Setting a flag by
+    // hand sets qc_override, which keeps recomputes from re-evaluating them;
+    // resetting qc_override to false re-evaluates.
+    pub qc_flagged_co2: Option<bool>,
+    pub qc_flagged_ch4: Option<bool>,
+    pub qc_flagged_h2o: Option<bool>,
+    #[crudcrate(update_model = false)]
+    pub qc_reasons: Option<serde_json::Value>,
+    #[crudcrate(create_model = false, on_create = false)]
+    pub qc_override: bool,
+    /// False for analysers without a CH4 channel, whose CH4 flux stays empty
+    #[crudcrate(create_model = false, update_model = false, on_create = true)]
+    pub has_ch4: bool,
     #[crudcrate(update_model = false)]
     pub raw_readings: Option<serde_json::Value>,
 }
@@ -47,6 +87,21 @@ impl From<Model> for FluxData {
             r2_h2o: model.r2_h2o,
             swc: model.swc,
             n_measurements: model.n_measurements,
+            fit_start_s: model.fit_start_s,
+            fit_end_s: model.fit_end_s,
+            collar_offset_cm: model.collar_offset_cm,
+            volume_ml: model.volume_ml,
+            effective_volume_ml: model.effective_volume_ml,
+            flux_model_co2: model.flux_model_co2,
+            flux_model_ch4: model.flux_model_ch4,
+            flux_model_h2o: model.flux_model_h2o,
+            flux_fits: model.flux_fits,
+            qc_flagged_co2: model.qc_flagged_co2,
+            qc_flagged_ch4: model.qc_flagged_ch4,
+            qc_flagged_h2o: model.qc_flagged_h2o,
+            qc_reasons: model.qc_reasons,
+            qc_override: model.qc_override,
+            has_ch4: model.has_ch4,
             raw_readings: model.raw_readings,
         }
     }
@@ -101,16 +156,36 @@ impl CRUDResource for FluxData {
         id: Uuid,
         update_model: Self::UpdateModel,
     ) -> Result<Self, DbErr> {
-        let db_obj: super::db::ActiveModel = super::db::Entity::find_by_id(id)
+        let existing = super::db::Entity::find_by_id(id)
             .one(db)
             .await?
             .ok_or(DbErr::RecordNotFound(format!(
                 "{} not found",
                 Self::RESOURCE_NAME_SINGULAR
-            )))?
-            .into();
-        let updated_obj: super::db::ActiveModel = update_model.merge_into_activemodel(db_obj);
-        let response_obj = updated_obj.update(db).await?;
+            )))?;
+        let db_obj: super::db::ActiveModel = existing.clone().into();
+        let flagged_by_hand = [
+            update_model.qc_flagged_co2,
+            update_model.qc_flagged_ch4,
+            update_model.qc_flagged_h2o,
+        ]
+        .iter()
+        .any(|flag| matches!(flag, Some(Some(_))));
+        let qc_reset = !flagged_by_hand && matches!(update_model.qc_override, Some(Some(false)));
+
+        let mut updated_obj: super::db::ActiveModel = update_model.merge_into_activemodel(db_obj);
+        let refit = changes_fit(&updated_obj, &existing);
+        if flagged_by_hand {
+            updated_obj.qc_override = ActiveValue::Set(true);
+        }
+        // A failed refit must not leave the new window next to the old fluxes
+        let txn = db.begin().await?;
+        let response_obj = updated_obj.update(&txn).await?;
+        if (refit || qc_reset) && response_obj.raw_readings.is_some() {
+            Self::recompute(&txn, response_obj.id).await?;
+        }
+        txn.commit().await?;
+
         let obj = Self::get_one(db, response_obj.id).await?;
         Ok(obj)
     }
@@ -132,3 +207,403 @@ impl CRUDResource for FluxData {
         ]
     }
 }
+
+/// Chamber volume used when a profile has no `volume_ml`
+const DEFAULT_VOLUME_ML: f64 = 16852.1;
+/// Collar area used when a profile has no `area_cm2`
+const DEFAULT_AREA_CM2: f64 = 318.0;
+/// Fewest readings a flux is fitted to
+pub const MIN_FIT_READINGS: usize = 3;
+
+/// Headspace volume and collar area a flux is computed with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChamberGeometry {
+    pub volume_ml: f64,
+    pub area_cm2: f64,
+}
+
+impl ChamberGeometry {
+    /// Geometry of one measurement: the measured headspace volume if given,
+    /// otherwise the chamber volume of the profile plus the collar offset height
+    /// times the collar area.
+    pub fn for_measurement(
+        profile: &ProfileDB::Model,
+        collar_offset_cm: Option<f64>,
+        volume_ml: Option<f64>,
+    ) -> Result<Self, String> {
+        let area_cm2 = profile.area_cm2.unwrap_or(DEFAULT_AREA_CM2);
+        if collar_offset_cm.is_some_and(|offset| offset < 0.0) {
+            return Err("collar_offset_cm must not be negative".to_string());
+        }
+        let volume_ml = volume_ml.unwrap_or_else(|| {
+            // 1 cm × 1 cm² = 1 mL
+            profile.volume_ml.unwrap_or(DEFAULT_VOLUME_ML)
+                + collar_offset_cm.unwrap_or(0.0) * area_cm2
+        });
+        if volume_ml <= 0.0 || area_cm2 <= 0.0 {
+            return Err("Chamber volume and collar area must be positive".to_string());
+        }
+        Ok(Self {
+            volume_ml,
+            area_cm2,
+        })
+    }
+}
+
+/// Readings whose elapsed time `t` lies within the fit window (inclusive).
+pub fn readings_in_window(
+    readings: &[RawReading],
+    fit_start_s: Option<f64>,
+    fit_end_s: Option<f64>,
+) -> Vec<RawReading> {
+    readings
+        .iter()
+        .filter(|r| fit_start_s.is_none_or(|s| r.t >= s) && fit_end_s.is_none_or(|e| r.t <= e))
+        .cloned()
+        .collect()
+}
+
+/// Universal gas constant [J/(mol·K)], as used by `compute_gas_flux`
+const R_GAS: f64 = 8.314;
+
+/// Fit fluxes to the readings inside the window with the given chamber
+/// geometry. The linear fluxes come from `compute_gas_flux`; exponential and
+/// HMR fits are added and the best model is selected per gas.
+pub fn fit_fluxes(
+    geometry: ChamberGeometry,
+    readings: &[RawReading],
+    fit_start_s: Option<f64>,
+    fit_end_s: Option<f64>,
+) -> Result<FluxFits, String> {
+    let window = readings_in_window(readings, fit_start_s, fit_end_s);
+    if window.len() < MIN_FIT_READINGS {
+        return Err(format!(
+            "Fit window contains {} readings, at least {MIN_FIT_READINGS} are required",
+            window.len()
+        ));
+    }
+
+    let volume_m3 = geometry.volume_ml * 1e-6;
+    let area_m2 = geometry.area_cm2 * 1e-4;
+
+    let timestamps: Vec<f64> = window.iter().map(|r| r.t).collect();
+    let co2_ppm: Vec<f64> = window.iter().map(|r| r.co2).collect();
+    let ch4_ppb: Vec<f64> = window.iter().map(|r| r.ch4).collect();
+    let h2o_mmol: Vec<f64> = window.iter().map(|r| r.h2o).collect();
+    let temp_c: Vec<f64> = window.iter().map(|r| r.temp).collect();
+    let press_kpa: Vec<f64> = window.iter().map(|r| r.press).collect();
+
+    let linear = compute_gas_flux(
+        &timestamps,
+        &co2_ppm,
+        &ch4_ppb,
+        &h2o_mmol,
+        &temp_c,
+        &press_kpa,
+        volume_m3,
+        area_m2,
+    );
+
+    // Moles of air per unit area (mol/m²), converting a mixing ratio slope to a flux
+    let pv_art =
+        mean(&press_kpa) * 1000.0 / (R_GAS * (mean(&temp_c) + 273.15)) * (volume_m3 / area_m2);
+
+    Ok(FluxFits {
+        co2: fit_gas(
+            &timestamps,
+            &co2_ppm,
+            pv_art,
+            (linear.flux_co2_umol_m2_s, linear.r2_co2),
+        ),
+        ch4: fit_gas(
+            &timestamps,
+            &ch4_ppb,
+            pv_art,
+            (linear.flux_ch4_nmol_m2_s, linear.r2_ch4),
+        ),
+        h2o: fit_gas(
+            &timestamps,
+            &h2o_mmol,
+            pv_art * 1e3,
+            (linear.flux_h2o_umol_m2_s, linear.r2_h2o),
+        ),
+    })
+}
+
+/// Store the selected flux, R² and model of each gas and all model fits on a
+/// record. CH4 is left empty for analysers without a CH4 channel.
+pub fn apply_fits(
+    active_model: &mut super::db::ActiveModel,
+    fits: &FluxFits,
+    has_ch4: bool,
+) -> Result<(), String> {
+    let co2 = fits.co2.selected_fit();
+    let h2o = fits.h2o.selected_fit();
+    active_model.flux_co2_umol_m2_s = ActiveValue::Set(Some(co2.flux));
+    active_model.r2_co2 = ActiveValue::Set(Some(co2.r2));
+    active_model.flux_model_co2 = ActiveValue::Set(Some(fits.co2.selected));
+    active_model.flux_h2o_umol_m2_s = ActiveValue::Set(Some(h2o.flux));
+    active_model.r2_h2o = ActiveValue::Set(Some(h2o.r2));
+    active_model.flux_model_h2o = ActiveValue::Set(Some(fits.h2o.selected));
+
+    let mut fits_json =
+        serde_json::to_value(fits).map_err(|e| format!("Failed to serialize flux fits: {e}"))?;
+    if has_ch4 {
+        let ch4 = fits.ch4.selected_fit();
+        active_model.flux_ch4_nmol_m2_s = ActiveValue::Set(Some(ch4.flux));
+        active_model.r2_ch4 = ActiveValue::Set(Some(ch4.r2));
+        active_model.flux_model_ch4 = ActiveValue::Set(Some(fits.ch4.selected));
+    } else {
+        active_model.flux_ch4_nmol_m2_s = ActiveValue::Set(None);
+        active_model.r2_ch4 = ActiveValue::Set(None);
+        active_model.flux_model_ch4 = ActiveValue::Set(None);
+        if let Some(object) = fits_json.as_object_mut() {
+            object.remove("ch4");
+        }
+    }
+    active_model.flux_fits = ActiveValue::Set(Some(fits_json));
+    Ok(())
+}
+
+/// Check the selected fits and the readings of the fit window against the QC
+/// thresholds. CH4 is not checked for analysers without a CH4 channel.
+pub fn evaluate_qc(
+    thresholds: &[FluxQcDB::Model],
+    fits: &FluxFits,
+    window: &[RawReading],
+    has_ch4: bool,
+) -> FluxQcResult {
+    evaluate(
+        thresholds,
+        [
+            (FluxGasEnum::Co2, Some(fits.co2.selected_fit().r2)),
+            (
+                FluxGasEnum::Ch4,
+                has_ch4.then(|| fits.ch4.selected_fit().r2),
+            ),
+            (FluxGasEnum::H2o, Some(fits.h2o.selected_fit().r2)),
+        ],
+        window,
+    )
+}
+
+/// Whether an update changes the fit window, collar offset or volume of a
+/// record. Compared after merging so that clearing a value also refits.
+fn changes_fit(updated: &super::db::ActiveModel, existing: &Model) -> bool {
+    updated.fit_start_s.as_ref() != &existing.fit_start_s
+        || updated.fit_end_s.as_ref() != &existing.fit_end_s
+        || updated.collar_offset_cm.as_ref() != &existing.collar_offset_cm
+        || updated.volume_ml.as_ref() != &existing.volume_ml
+}
+
+/// Refit a record from its stored raw readings with its fit window, collar
+/// offset or volume, and the chamber geometry of `profile`. CH4 stays empty for
+/// records imported without a CH4 channel. QC flags are re-evaluated against
+/// `thresholds` unless they were set by hand.
+fn refit(
+    model: Model,
+    profile: &ProfileDB::Model,
+    thresholds: &[FluxQcDB::Model],
+) -> Result<super::db::ActiveModel, String> {
+    let raw_readings: Vec<RawReading> = model
+        .raw_readings
+        .clone()
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| format!("Invalid raw_readings: {e}"))?
+        .ok_or("Record has no raw readings to recompute from")?;
+    let geometry =
+        ChamberGeometry::for_measurement(profile, model.collar_offset_cm, model.volume_ml)?;
+    let fits = fit_fluxes(geometry, &raw_readings, model.fit_start_s, model.fit_end_s)?;
+
+    let has_ch4 = model.has_ch4;
+    let window = readings_in_window(&raw_readings, model.fit_start_s, model.fit_end_s);
+    let qc = (!model.qc_override).then(|| evaluate_qc(thresholds, &fits, &window, has_ch4));
+    let mut active_model: super::db::ActiveModel = model.into();
+    apply_fits(&mut active_model, &fits, has_ch4)?;
+    active_model.n_measurements = ActiveValue::Set(i32::try_from(window.len()).ok());
+    active_model.effective_volume_ml = ActiveValue::Set(Some(geometry.volume_ml));
+    if let Some(qc) = qc {
+        qc.apply(&mut active_model);
+    }
+    Ok(active_model)
+}
+
+impl FluxData {
+    /// Refit a record with its current settings and the current chamber
+    /// geometry of its profile, see `refit`.
+    pub async fn recompute<C: ConnectionTrait>(db: &C, id: Uuid) -> Result<Self, DbErr> {
+        let model = super::db::Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound(format!(
+                "{} not found",
+                Self::RESOURCE_NAME_SINGULAR
+            )))?;
+        let profile = ProfileDB::Entity::find_by_id(model.sensorprofile_id)
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Sensor profile not found".into()))?;
+        let thresholds = if model.qc_override {
+            Vec::new()
+        } else {
+            load_thresholds(db).await?
+        };
+
+        let updated = refit(model, &profile, &thresholds)
+            .map_err(DbErr::Custom)?
+            .update(db)
+            .await?;
+        Ok(FluxData::from(updated))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::ActiveValue::Set;
+
+    fn profile() -> ProfileDB::Model {
+        ProfileDB::Model {
+            id: Uuid::new_v4(),
+            name: "Chamber 1".to_string(),
+            description: None,
+            area_id: Uuid::new_v4(),
+            profile_type: ProfileDB::ProfileTypeEnum::Chamber,
+            soil_type_vwc: None,
+            coord_x: None,
+            coord_y: None,
+            coord_z: None,
+            coord_srid: None,
+            volume_ml: Some(10_000.0),
+            area_cm2: Some(300.0),
+            instrument_model: None,
+            chamber_id_external: None,
+            position: None,
+            logger_depth_m: None,
+            baro_profile_id: None,
+            reference_electrode: None,
+            last_updated: Utc::now(),
+        }
+    }
+
+    /// A record with readings every 5 s over two minutes and rising CO2 and CH4
+    fn record(has_ch4: bool) -> Model {
+        let readings: Vec<RawReading> = (0..=24)
+            .map(|i| {
+                let t = f64::from(i) * 5.0;
+                RawReading {
+                    t,
+                    co2: 420.0 + 0.5 * t + if i % 2 == 0 { 0.05 } else { -0.05 },
+                    ch4: if has_ch4 { 1900.0 + 0.2 * t } else { 0.0 },
+                    h2o: 12.0 + 0.001 * t,
+                    temp: 20.0,
+                    press: 97.0,
+                    soilp: None,
+                }
+            })
+            .collect();
+        Model {
+            id: Uuid::new_v4(),
+            sensorprofile_id: Uuid::new_v4(),
+            measured_on: Utc::now(),
+            replicate: "1".to_string(),
+            setting: None,
+            flux_co2_umol_m2_s: None,
+            flux_ch4_nmol_m2_s: None,
+            flux_h2o_umol_m2_s: None,
+            r2_co2: None,
+            r2_ch4: None,
+            r2_h2o: None,
+            swc: None,
+            n_measurements: None,
+            fit_start_s: None,
+            fit_end_s: None,
+            collar_offset_cm: None,
+            volume_ml: None,
+            effective_volume_ml: None,
+            flux_model_co2: None,
+            flux_model_ch4: None,
+            flux_model_h2o: None,
+            flux_fits: None,
+            qc_flagged_co2: None,
+            qc_flagged_ch4: None,
+            qc_flagged_h2o: None,
+            qc_reasons: None,
+            qc_override: false,
+            has_ch4,
+            raw_readings: Some(serde_json::to_value(readings).unwrap()),
+        }
+    }
+
+    fn set<T: Clone + Into<sea_orm::Value>>(value: &ActiveValue<T>) -> T {
+        match value {
+            ActiveValue::Set(v) => v.clone(),
+            _ => panic!("value was not set"),
+        }
+    }
+
+    #[test]
+    fn refit_counts_and_fits_only_the_window() {
+        let full = refit(record(true), &profile(), &[]).unwrap();
+        assert_eq!(set(&full.n_measurements), Some(25));
+
+        let mut windowed = record(true);
+        windowed.fit_start_s = Some(30.0);
+        windowed.fit_end_s = Some(90.0);
+        let windowed = refit(windowed, &profile(), &[]).unwrap();
+        assert_eq!(set(&windowed.n_measurements), Some(13));
+        assert!(set(&windowed.flux_co2_umol_m2_s).is_some());
+
+        let mut too_short = record(true);
+        too_short.fit_start_s = Some(100.0);
+        too_short.fit_end_s = Some(105.0);
+        assert!(refit(too_short, &profile(), &[]).is_err());
+    }
+
+    #[test]
+    fn refit_keeps_ch4_empty_without_a_ch4_channel() {
+        let with_ch4 = refit(record(true), &profile(), &[]).unwrap();
+        assert!(set(&with_ch4.flux_ch4_nmol_m2_s).is_some());
+        assert!(set(&with_ch4.flux_fits).unwrap().get("ch4").is_some());
+
+        let without_ch4 = refit(record(false), &profile(), &[]).unwrap();
+        assert_eq!(set(&without_ch4.flux_ch4_nmol_m2_s), None);
+        assert_eq!(set(&without_ch4.r2_ch4), None);
+        assert_eq!(set(&without_ch4.flux_model_ch4), None);
+        assert!(set(&without_ch4.flux_fits).unwrap().get("ch4").is_none());
+    }
+
+    #[test]
+    fn setting_or_clearing_collar_offset_and_volume_refits() {
+        let mut existing = record(true);
+        existing.collar_offset_cm = Some(2.0);
+        let update = |offset: Option<f64>, volume: Option<f64>| {
+            let mut updated: super::super::db::ActiveModel = existing.clone().into();
+            updated.collar_offset_cm = Set(offset);
+            updated.volume_ml = Set(volume);
+            updated
+        };
+        assert!(!changes_fit(&existing.clone().into(), &existing));
+        assert!(!changes_fit(&update(Some(2.0), None), &existing));
+        assert!(changes_fit(&update(Some(3.0), None), &existing));
+        assert!(changes_fit(&update(None, None), &existing));
+        assert!(changes_fit(&update(Some(2.0), Some(12_000.0)), &existing));
+
+        // Profile volume of 10 L plus 2 cm over the 300 cm² collar
+        let offset = refit(existing.clone(), &profile(), &[]).unwrap();
+        assert_eq!(set(&offset.effective_volume_ml), Some(10_600.0));
+        let mut measured = existing.clone();
+        measured.volume_ml = Some(12_000.0);
+        let measured = refit(measured, &profile(), &[]).unwrap();
+        assert_eq!(set(&measured.effective_volume_ml), Some(12_000.0));
+        let mut cleared = existing;
+        cleared.collar_offset_cm = None;
+        let cleared = refit(cleared, &profile(), &[]).unwrap();
+        assert_eq!(set(&cleared.effective_volume_ml), Some(10_000.0));
+        assert_ne!(
+            set(&offset.flux_co2_umol_m2_s),
+            set(&cleared.flux_co2_umol_m2_s)
+        );
+    }
+}
@@ -0,0 +1,359 @@
+use super::views::RawReading;
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+
+/// One row of a LI-COR trace gas analyser `.data` export
+#[derive(Debug, Clone, PartialEq)]
+pub struct LicorRow {
+    pub time_utc: DateTime<Utc>,
+    pub remark: String,
+    pub co2_ppm: f64,
+    pub ch4_ppb: Option<f64>,
+    pub h2o_mmol_mol: f64,
+    pub temp_c: f64,
+    pub press_kpa: f64,
+}
+
+/// Start (and optionally end) of one chamber closure and the chamber label it
+/// belongs to, matched against `chamber_id_external`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosureMarker {
+    pub label: String,
+    pub start: DateTime<Utc>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+/// Readings of a single chamber closure, with time relative to its start.
+#[derive(Clone)]
+pub struct Closure {
+    pub label: String,
+    pub start: DateTime<Utc>,
+    pub readings: Vec<RawReading>,
+    /// False for analysers without a CH4 channel (e.g. the N2O LI-7820)
+    pub has_ch4: bool,
+}
+
+fn column(names: &[&str], candidates: &[&str]) -> Option<usize> {
+    candidates
+        .iter()
+        .find_map(|c| names.iter().position(|n| n.eq_ignore_ascii_case(c)))
+}
+
+/// Parse a LI-7810/LI-7820 `.data` file (`DATAH`/`DATAU` header rows followed by
+/// tab separated `DATA` rows). Times come from the `SECONDS`/`NANOSECONDS` epoch
+/// columns and are therefore UTC. Chamber temperature and pressure are taken from
+/// `CHAMBER_T`/`CHAMBER_P` when an auxiliary sensor was logged, otherwise from the
+/// cavity.
+pub fn parse_licor_data(text: &str) -> Result<Vec<LicorRow>, String> {
+    let mut lines = text.lines();
+    let header: Vec<&str> = lines
+        .by_ref()
+        .find(|line| line.starts_with("DATAH"))
+        .ok_or("No DATAH header found in LI-COR .data file")?
+        .split('\t')
+        .map(str::trim)
+        .collect();
+
+    let required = |candidates: &[&str]| {
+        column(&header, candidates)
+            .ok_or_else(|| format!("No {} column in LI-COR .data file", candidates[0]))
+    };
+    let seconds_col = required(&["SECONDS"])?;
+    let co2_col = required(&["CO2"])?;
+    let h2o_col = required(&["H2O"])?;
+    let temp_col = required(&["CHAMBER_T", "CAVITY_T"])?;
+    let press_col = required(&["CHAMBER_P", "CAVITY_P"])?;
+    let nanos_col = column(&header, &["NANOSECONDS"]);
+    let remark_col = column(&header, &["REMARK"]);
+    let methane_col = column(&header, &["CH4"]);
+
+    let mut h2o_factor = 1.0;
+    let mut rows = Vec::new();
+    for line in lines {
+        let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+        match fields.first().copied() {
+            // The LI-7810 logs H2O in ppm, `RawReading` expects mmol/mol
+            Some("DATAU")
+                if fields
+                    .get(h2o_col)
+                    .is_some_and(|u| u.eq_ignore_ascii_case("ppm")) =>
+            {
+                h2o_factor = 1e-3;
+            }
+            Some("DATA") => {
+                let number = |col: usize| {
+                    fields
+                        .get(col)
+                        .and_then(|v| v.parse::<f64>().ok())
+                        .filter(|v| v.is_finite())
+                };
+                let seconds: i64 = fields
+                    .get(seconds_col)
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(|| format!("Invalid SECONDS value in line '{line}'"))?;
+                let nanos: u32 = nanos_col
+                    .and_then(|col| fields.get(col))
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0);
+                let time_utc = DateTime::from_timestamp(seconds, nanos)
+                    .ok_or_else(|| format!("Invalid timestamp {seconds}"))?;
+
+                // Rows with a missing gas or climate value (e.g. during warm-up) are skipped
+                let (Some(co2_ppm), Some(h2o), Some(temp_c), Some(press_kpa)) = (
+                    number(co2_col),
+                    number(h2o_col),
+                    number(temp_col),
+                    number(press_col),
+                ) else {
+                    continue;
+                };
+
+                rows.push(LicorRow {
+                    time_utc,
+                    remark: remark_col
+                        .and_then(|col| fields.get(col))
+                        .map(|r| r.trim_matches('"').to_string())
+                        .unwrap_or_default(),
+                    co2_ppm,
+                    ch4_ppb: methane_col.and_then(number),
+                    h2o_mmol_mol: h2o * h2o_factor,
+                    temp_c,
+                    press_kpa,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Closure markers from the `REMARK` column: a closure starts at every row whose
+/// remark is set and differs from the previous remark.
+pub fn remark_markers(rows: &[LicorRow]) -> Vec<ClosureMarker> {
+    let mut markers = Vec::new();
+    let mut previous = "";
+    for row in rows {
+        if !row.remark.is_empty() && row.remark != previous {
+            markers.push(ClosureMarker {
+                label: row.remark.clone(),
+                start: row.time_utc,
+                end: None,
+            });
+        }
+        if !row.remark.is_empty() {
+            previous = &row.remark;
+        }
+    }
+    markers
+}
+
+/// Lower-case a column name and drop everything but letters and digits, so that
+/// `IV Date`, `IV_Date` and `ivdate` compare equal.
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Duration from a (non-negative) number of seconds
+fn seconds(value: f64) -> Duration {
+    Duration::from_std(std::time::Duration::from_secs_f64(value.max(0.0))).unwrap_or_default()
+}
+
+const SUMMARY_DATE_FORMATS: [&str; 4] = ["%Y-%m-%d", "%Y/%m/%d", "%m/%d/%Y", "%d.%m.%Y"];
+
+/// Parse the observation summary exported by `SoilFluxPro`. Each observation gives
+/// the chamber label (or port number), the start date and time in the local time
+/// of the instrument, and the observation length in seconds.
+pub fn parse_soilfluxpro_summary(
+    text: &str,
+    utc_offset_minutes: i32,
+) -> Result<Vec<ClosureMarker>, String> {
+    let delimiter = if text.contains('\t') { '\t' } else { ',' };
+    let mut lines = text.lines();
+
+    let (header, label_col, date_col, time_col) = loop {
+        let line = lines
+            .next()
+            .ok_or("No observation header found in SoilFluxPro summary")?;
+        let header: Vec<String> = line.split(delimiter).map(normalize).collect();
+        let find = |names: &[&str]| header.iter().position(|h| names.contains(&h.as_str()));
+        if let (Some(label), Some(date), Some(time)) = (
+            find(&["label", "chamberlabel", "port", "portno"]),
+            find(&["ivdate", "dateiv", "obsdate", "date"]),
+            find(&["ivtime", "timeiv", "obstime", "time"]),
+        ) {
+            break (header, label, date, time);
+        }
+    };
+    let length_col = header
+        .iter()
+        .position(|h| ["obslength", "observationlength", "duration"].contains(&h.as_str()));
+
+    let mut markers = Vec::new();
+    for line in lines.filter(|l| !l.trim().is_empty()) {
+        let fields: Vec<&str> = line
+            .split(delimiter)
+            .map(|f| f.trim().trim_matches('"'))
+            .collect();
+        let (Some(label), Some(date), Some(time)) = (
+            fields.get(label_col),
+            fields.get(date_col),
+            fields.get(time_col),
+        ) else {
+            continue;
+        };
+        let date = SUMMARY_DATE_FORMATS
+            .iter()
+            .find_map(|f| NaiveDate::parse_from_str(date, f).ok())
+            .ok_or_else(|| format!("Invalid date '{date}' in SoilFluxPro summary"))?;
+        let time = NaiveTime::parse_from_str(time, "%H:%M:%S")
+            .map_err(|e| format!("Invalid time '{time}' in SoilFluxPro summary: {e}"))?;
+        let start = NaiveDateTime::new(date, time).and_utc()
+            - Duration::minutes(i64::from(utc_offset_minutes));
+        let end = length_col
+            .and_then(|col| fields.get(col))
+            .and_then(|v| v.parse::<f64>().ok())
+            .map(|s| start + seconds(s));
+
+        markers.push(ClosureMarker {
+            label: (*label).to_string(),
+            start,
+            end,
+        });
+    }
+    Ok(markers)
+}
+
+/// Closure length used for markers without an end (e.g. `REMARK` splits) when no
+/// `max_duration_s` is given, so that ambient readings logged until the next
+/// remark are not fitted.
+pub const DEFAULT_MAX_CLOSURE_S: f64 = 300.0;
+
+/// Split analyser rows into closures. Each closure runs from its marker to the
+/// marker's end, the next marker or `max_duration_s` (`DEFAULT_MAX_CLOSURE_S`
+/// for markers without an end), whichever comes first.
+pub fn split_closures(
+    rows: &[LicorRow],
+    mut markers: Vec<ClosureMarker>,
+    max_duration_s: Option<f64>,
+) -> Vec<Closure> {
+    markers.sort_by_key(|m| m.start);
+    let next_starts: Vec<Option<DateTime<Utc>>> = markers
+        .iter()
+        .skip(1)
+        .map(|m| Some(m.start))
+        .chain(std::iter::once(None))
+        .collect();
+
+    markers
+        .into_iter()
+        .zip(next_starts)
+        .filter_map(|(marker, next_start)| {
+            let max_end = max_duration_s
+                .or(marker.end.is_none().then_some(DEFAULT_MAX_CLOSURE_S))
+                .map(|s| marker.start + seconds(s));
+            let end = [marker.end, next_start, max_end]
+                .into_iter()
+                .flatten()
+                .min();
+            let closure_rows: Vec<&LicorRow> = rows
+                .iter()
+                .filter(|r| r.time_utc >= marker.start && end.is_none_or(|e| r.time_utc < e))
+                .collect();
+            let first = closure_rows.first()?.time_utc;
+
+            Some(Closure {
+                has_ch4: closure_rows.iter().all(|r| r.ch4_ppb.is_some()),
+                readings: closure_rows
+                    .iter()
+                    .map(|r| RawReading {
+                        t: (r.time_utc - first).as_seconds_f64(),
+                        co2: r.co2_ppm,
+                        ch4: r.ch4_ppb.unwrap_or(0.0),
+                        h2o: r.h2o_mmol_mol,
+                        temp: r.temp_c,
+                        press: r.press_kpa,
+                        soilp: None,
+                    })
+                    .collect(),
+                label: marker.label,
+                start: first,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LICOR_DATA: &str = "Model:\tLI-7810 CH4/CO2/H2O Trace Gas Analyzer\n\
+        SN:\tTG10-01000\n\
+        Timezone:\tEurope/Zurich\n\
+        DATAH\tSECONDS\tNANOSECONDS\tNDX\tDIAG\tREMARK\tDATE\tTIME\tH2O\tCO2\tCH4\tCAVITY_P\tCAVITY_T\n\
+        DATAU\ts\tns\tDN\tDN\t\tdate\ttime\tppm\tppm\tppb\tkPa\tC\n\
+        DATA\t1717200000\t0\t1\t0\tC1\t2024-06-01\t02:00:00\t12000\t420.0\t2000.0\t100.1\t55.0\n\
+        DATA\t1717200001\t0\t2\t0\t\t2024-06-01\t02:00:01\t12010\t420.5\t2000.5\t100.1\t55.0\n\
+        DATA\t1717200002\t0\t3\t0\t\t2024-06-01\t02:00:02\t12020\tnan\t2001.0\t100.1\t55.0\n\
+        DATA\t1717200010\t0\t4\t0\tC2\t2024-06-01\t02:00:10\t11000\t415.0\t1990.0\t100.1\t55.0\n\
+        DATA\t1717200011\t0\t5\t0\t\t2024-06-01\t02:00:11\t11010\t415.4\t1990.2\t100.1\t55.0\n";
+
+    #[test]
+    fn splits_licor_data_by_remark() {
+        let rows = parse_licor_data(LICOR_DATA).unwrap();
+        assert_eq!(rows.len(), 4);
+        assert!((rows[0].h2o_mmol_mol - 12.0).abs() < 1e-9);
+
+        let closures = split_closures(&rows, remark_markers(&rows), None);
+        assert_eq!(closures.len(), 2);
+        assert_eq!(closures[0].label, "C1");
+        assert_eq!(closures[0].readings.len(), 2);
+        assert_eq!(closures[1].label, "C2");
+        assert!((closures[1].readings[1].t - 1.0).abs() < 1e-9);
+        assert!(closures[1].has_ch4);
+    }
+
+    #[test]
+    fn remark_closures_stop_at_default_length() {
+        let row = |s: i64, remark: &str| LicorRow {
+            time_utc: DateTime::from_timestamp(1_717_200_000 + s, 0).unwrap(),
+            remark: remark.to_string(),
+            co2_ppm: 420.0,
+            ch4_ppb: Some(2000.0),
+            h2o_mmol_mol: 12.0,
+            temp_c: 20.0,
+            press_kpa: 100.0,
+        };
+        // Ambient readings follow the closure until the next remark an hour later
+        let rows: Vec<LicorRow> = (0..3600)
+            .step_by(60)
+            .map(|s| row(s, if s == 0 { "C1" } else { "" }))
+            .chain([row(3600, "C2")])
+            .collect();
+
+        let closures = split_closures(&rows, remark_markers(&rows), None);
+        assert_eq!(closures[0].readings.len(), 5);
+        let closures = split_closures(&rows, remark_markers(&rows), Some(120.0));
+        assert_eq!(closures[0].readings.len(), 2);
+    }
+
+    #[test]
+    fn splits_licor_data_by_soilfluxpro_summary() {
+        let summary = "Obs#\tLabel\tIV Date\tIV Time\tObs Length\tLin_Flux\n\
+                       1\tC7\t2024-06-01\t02:00:00\t1.5\t2.1\n";
+        let markers = parse_soilfluxpro_summary(summary, 120).unwrap();
+        assert_eq!(markers.len(), 1);
+        assert_eq!(
+            markers[0].start,
+            DateTime::from_timestamp(1_717_200_000, 0).unwrap()
+        );
+
+        let rows = parse_licor_data(LICOR_DATA).unwrap();
+        let closures = split_closures(&rows, markers, None);
+        assert_eq!(closures.len(), 1);
+        assert_eq!(closures[0].label, "C7");
+        assert_eq!(closures[0].readings.len(), 2);
+    }
+}
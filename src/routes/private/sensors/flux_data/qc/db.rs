@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Gas measured by a flux chamber
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "flux_gas_enum")]
+pub enum FluxGasEnum {
+    #[sea_orm(string_value = "co2")]
+    Co2,
+    #[sea_orm(string_value = "ch4")]
+    Ch4,
+    #[sea_orm(string_value = "h2o")]
+    H2o,
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, ToSchema)]
+#[sea_orm(table_name = "flux_qc_threshold")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub gas: FluxGasEnum,
+    pub min_r2: Option<f64>,
+    pub min_readings: Option<i32>,
+    pub max_pressure_drift_kpa: Option<f64>,
+    pub max_temperature_drift_c: Option<f64>,
+    pub last_updated: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,266 @@
+use super::db::{FluxGasEnum, Model};
+use crate::routes::private::sensors::flux_data::views::RawReading;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use crudcrate::{CRUDResource, ToCreateModel, ToUpdateModel, traits::MergeIntoActiveModel};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection,
+    DbErr, EntityTrait, Order, QueryOrder, QuerySelect, entity::prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// QC thresholds of one gas. A flux failing any of them is flagged; an empty
+/// threshold disables the check.
+#[derive(ToSchema, Serialize, Deserialize, ToCreateModel, ToUpdateModel)]
+#[active_model = "super::db::ActiveModel"]
+pub struct FluxQcThreshold {
+    #[crudcrate(update_model = false, create_model = false, on_create = Uuid::new_v4())]
+    pub id: Uuid,
+    #[crudcrate(update_model = false, create_model = false, on_update = Utc::now(), on_create = Utc::now())]
+    pub last_updated: DateTime<Utc>,
+    pub gas: FluxGasEnum,
+    pub min_r2: Option<f64>,
+    /// Fewest readings in the fit window
+    pub min_readings: Option<i32>,
+    /// Largest pressure range during the closure
+    pub max_pressure_drift_kpa: Option<f64>,
+    /// Largest temperature range during the closure
+    pub max_temperature_drift_c: Option<f64>,
+}
+
+impl From<Model> for FluxQcThreshold {
+    fn from(model: Model) -> Self {
+        Self {
+            id: model.id,
+            last_updated: model.last_updated,
+            gas: model.gas,
+            min_r2: model.min_r2,
+            min_readings: model.min_readings,
+            max_pressure_drift_kpa: model.max_pressure_drift_kpa,
+            max_temperature_drift_c: model.max_temperature_drift_c,
+        }
+    }
+}
+
+#[async_trait]
+impl CRUDResource for FluxQcThreshold {
+    type EntityType = super::db::Entity;
+    type ColumnType = super::db::Column;
+    type ActiveModelType = super::db::ActiveModel;
+    type CreateModel = FluxQcThresholdCreate;
+    type UpdateModel = FluxQcThresholdUpdate;
+
+    const ID_COLUMN: Self::ColumnType = super::db::Column::Id;
+    const RESOURCE_NAME_SINGULAR: &'static str = "flux QC threshold";
+    const RESOURCE_NAME_PLURAL: &'static str = "flux QC thresholds";
+    const RESOURCE_DESCRIPTION: &'static str = "Quality control thresholds per gas that flux records are flagged against. Changes apply to new records and to records that are recomputed.";
+
+    async fn get_all(
+        db: &DatabaseConnection,
+        condition: Condition,
+        order_column: Self::ColumnType,
+        order_direction: Order,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<Self>, DbErr> {
+        let models = Self::EntityType::find()
+            .filter(condition)
+            .order_by(order_column, order_direction)
+            .offset(offset)
+            .limit(limit)
+            .all(db)
+            .await?;
+        Ok(models.into_iter().map(FluxQcThreshold::from).collect())
+    }
+
+    async fn get_one(db: &DatabaseConnection, id: Uuid) -> Result<Self, DbErr> {
+        let model = Self::EntityType::find()
+            .filter(Self::ColumnType::Id.eq(id))
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound(format!(
+                "{} not found",
+                Self::RESOURCE_NAME_SINGULAR
+            )))?;
+        Ok(FluxQcThreshold::from(model))
+    }
+
+    async fn update(
+        db: &DatabaseConnection,
+        id: Uuid,
+        update_model: Self::UpdateModel,
+    ) -> Result<Self, DbErr> {
+        let db_obj: super::db::ActiveModel = super::db::Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound(format!(
+                "{} not found",
+                Self::RESOURCE_NAME_SINGULAR
+            )))?
+            .into();
+
+        let updated_obj: super::db::ActiveModel = update_model.merge_into_activemodel(db_obj);
+        let response_obj = updated_obj.update(db).await?;
+        let obj = Self::get_one(db, response_obj.id).await?;
+        Ok(obj)
+    }
+
+    fn sortable_columns() -> Vec<(&'static str, Self::ColumnType)> {
+        vec![
+            ("gas", Self::ColumnType::Gas),
+            ("last_updated", Self::ColumnType::LastUpdated),
+        ]
+    }
+
+    fn filterable_columns() -> Vec<(&'static str, Self::ColumnType)> {
+        vec![("gas", Self::ColumnType::Gas)]
+    }
+}
+
+/// Load the thresholds of all gases.
+pub async fn load_thresholds<C: ConnectionTrait>(db: &C) -> Result<Vec<Model>, DbErr> {
+    super::db::Entity::find().all(db).await
+}
+
+/// Outcome of the QC checks of one flux record. Flags are `None` for gases
+/// without a flux.
+#[derive(Debug, Default, PartialEq)]
+pub struct FluxQcResult {
+    pub flagged_co2: Option<bool>,
+    pub flagged_ch4: Option<bool>,
+    pub flagged_h2o: Option<bool>,
+    /// Failed checks per gas
+    pub reasons: BTreeMap<String, Vec<String>>,
+}
+
+impl FluxQcResult {
+    /// Store the flags and reasons on a flux record.
+    pub fn apply(
+        self,
+        active_model: &mut crate::routes::private::sensors::flux_data::db::ActiveModel,
+    ) {
+        active_model.qc_flagged_co2 = ActiveValue::Set(self.flagged_co2);
+        active_model.qc_flagged_ch4 = ActiveValue::Set(self.flagged_ch4);
+        active_model.qc_flagged_h2o = ActiveValue::Set(self.flagged_h2o);
+        active_model.qc_reasons = ActiveValue::Set(serde_json::to_value(self.reasons).ok());
+    }
+}
+
+/// Range of a quantity over the readings
+fn drift(values: impl Iterator<Item = f64>) -> f64 {
+    let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+        (min.min(v), max.max(v))
+    });
+    if min.is_finite() { max - min } else { 0.0 }
+}
+
+/// Check the R² of each gas's selected fit (`None` for gases without a flux) and
+/// the readings of the fit window against the thresholds.
+pub fn evaluate(
+    thresholds: &[Model],
+    r2: [(FluxGasEnum, Option<f64>); 3],
+    window: &[RawReading],
+) -> FluxQcResult {
+    let pressure_drift = drift(window.iter().map(|r| r.press));
+    let temperature_drift = drift(window.iter().map(|r| r.temp));
+
+    let mut result = FluxQcResult::default();
+    for (gas, r2) in r2 {
+        let Some(r2) = r2 else { continue };
+        let mut failed = Vec::new();
+        if let Some(threshold) = thresholds.iter().find(|t| t.gas == gas) {
+            if let Some(min_r2) = threshold.min_r2
+                && r2 < min_r2
+            {
+                failed.push(format!("R² {r2:.3} below {min_r2}"));
+            }
+            if let Some(min_readings) = threshold.min_readings
+                && usize::try_from(min_readings).is_ok_and(|min| window.len() < min)
+            {
+                failed.push(format!(
+                    "{} readings in fit window, at least {min_readings} required",
+                    window.len()
+                ));
+            }
+            if let Some(max_drift) = threshold.max_pressure_drift_kpa
+                && pressure_drift > max_drift
+            {
+                failed.push(format!(
+                    "Pressure drift {pressure_drift:.2} kPa exceeds {max_drift} kPa"
+                ));
+            }
+            if let Some(max_drift) = threshold.max_temperature_drift_c
+                && temperature_drift > max_drift
+            {
+                failed.push(format!(
+                    "Temperature drift {temperature_drift:.2} °C exceeds {max_drift} °C"
+                ));
+            }
+        }
+
+        let flagged = Some(!failed.is_empty());
+        match gas {
+            FluxGasEnum::Co2 => result.flagged_co2 = flagged,
+            FluxGasEnum::Ch4 => result.flagged_ch4 = flagged,
+            FluxGasEnum::H2o => result.flagged_h2o = flagged,
+        }
+        if !failed.is_empty() {
+            result.reasons.insert(gas.to_value(), failed);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn threshold(gas: FluxGasEnum, min_r2: f64) -> Model {
+        Model {
+            id: Uuid::new_v4(),
+            gas,
+            min_r2: Some(min_r2),
+            min_readings: Some(10),
+            max_pressure_drift_kpa: Some(0.5),
+            max_temperature_drift_c: None,
+            last_updated: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn flags_gases_failing_thresholds() {
+        let thresholds = [
+            threshold(FluxGasEnum::Co2, 0.9),
+            threshold(FluxGasEnum::H2o, 0.7),
+        ];
+        let window: Vec<RawReading> = (0..12)
+            .map(|i| RawReading {
+                t: f64::from(i),
+                co2: 420.0,
+                ch4: 2000.0,
+                h2o: 10.0,
+                temp: 20.0 + f64::from(i),
+                press: 95.0,
+                soilp: None,
+            })
+            .collect();
+
+        let result = evaluate(
+            &thresholds,
+            [
+                (FluxGasEnum::Co2, Some(0.95)),
+                (FluxGasEnum::Ch4, None),
+                (FluxGasEnum::H2o, Some(0.5)),
+            ],
+            &window,
+        );
+        assert_eq!(result.flagged_co2, Some(false));
+        assert_eq!(result.flagged_ch4, None);
+        assert_eq!(result.flagged_h2o, Some(true));
+        assert_eq!(result.reasons.len(), 1);
+        assert_eq!(result.reasons["h2o"], vec!["R² 0.500 below 0.7"]);
+    }
+}
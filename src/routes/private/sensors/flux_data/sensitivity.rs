@@ -0,0 +1,429 @@
+use super::db as FluxDB;
+use super::fitting::{count, mean};
+use super::qc::db::FluxGasEnum;
+use super::summary::{FluxGrouping, SeriesKey, load_chamber_fluxes, unflagged_flux};
+use crate::routes::private::sensors::profile::db as ProfileDB;
+use crate::routes::private::sensors::profile::models::{DepthAverageData, SensorProfile};
+use chrono::{DateTime, TimeDelta, Utc};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, Statement};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Gas constant, J mol⁻¹ K⁻¹
+const GAS_CONSTANT: f64 = 8.314;
+const KELVIN: f64 = 273.15;
+/// Temperature the basal rates are reported at, °C
+pub const REFERENCE_TEMPERATURE_C: f64 = 10.0;
+/// Default largest time between a flux and the soil temperature paired with it
+const DEFAULT_MAX_OFFSET_MINUTES: i64 = 60;
+/// Largest accepted `max_offset_minutes`, one week
+const MAX_OFFSET_MINUTES: i64 = 7 * 24 * 60;
+/// Fewest pairs a model is fitted to
+const MIN_PAIRS: usize = 3;
+
+#[derive(Deserialize, Debug)]
+pub struct TemperatureSensitivityQuery {
+    /// Installation depth of the TMS temperature sensor
+    pub depth_cm: i32,
+    pub area_id: Option<Uuid>,
+    pub sensorprofile_id: Option<Uuid>,
+    pub setting: Option<String>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub gas: Option<FluxGasEnum>,
+    #[serde(default)]
+    pub group_by: FluxGrouping,
+    pub max_offset_minutes: Option<i64>,
+}
+
+/// A flux paired with the soil temperature recorded closest to it
+#[derive(ToSchema, Serialize, Debug, Clone, PartialEq)]
+pub struct FluxTemperaturePair {
+    pub flux_data_id: Uuid,
+    pub measured_on: DateTime<Utc>,
+    pub flux: f64,
+    pub soil_temperature_c: f64,
+    /// TMS profile the temperature comes from
+    pub tms_sensorprofile_id: Uuid,
+}
+
+/// `F = R10 · Q10^((T − 10)/10)`, fitted as a linear regression of `ln F` on `T`
+#[derive(ToSchema, Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct Q10Fit {
+    pub q10: f64,
+    pub q10_se: f64,
+    /// Flux at the reference temperature
+    pub r10: f64,
+    pub r10_se: f64,
+    /// R² of the log-transformed fit
+    pub r2: f64,
+    pub n: usize,
+}
+
+/// `F = A · exp(−Ea / (R · T))`, fitted as a linear regression of `ln F` on `1/T`
+#[derive(ToSchema, Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct ArrheniusFit {
+    pub activation_energy_kj_mol: f64,
+    pub activation_energy_se: f64,
+    /// Flux at the reference temperature
+    pub r10: f64,
+    pub r10_se: f64,
+    /// R² of the log-transformed fit
+    pub r2: f64,
+    pub n: usize,
+}
+
+#[derive(ToSchema, Serialize, Debug, Clone, PartialEq)]
+pub struct TemperatureSensitivitySeries {
+    pub area_id: Uuid,
+    /// Chamber profile, empty when grouped by area
+    pub sensorprofile_id: Option<Uuid>,
+    pub setting: Option<String>,
+    pub pairs: Vec<FluxTemperaturePair>,
+    /// Fluxes without a soil temperature within the allowed offset
+    pub unpaired: usize,
+    /// Paired fluxes left out of the fits for not being positive
+    pub non_positive: usize,
+    pub q10: Option<Q10Fit>,
+    pub arrhenius: Option<ArrheniusFit>,
+}
+
+#[derive(ToSchema, Serialize, Debug, Clone, PartialEq)]
+pub struct TemperatureSensitivity {
+    pub gas: FluxGasEnum,
+    pub depth_cm: i32,
+    pub reference_temperature_c: f64,
+    pub series: Vec<TemperatureSensitivitySeries>,
+}
+
+/// Least-squares line with the statistics needed for standard errors
+struct LineFit {
+    intercept: f64,
+    slope: f64,
+    /// Residual standard error
+    sigma: f64,
+    x_mean: f64,
+    sxx: f64,
+    n: usize,
+    r2: f64,
+}
+
+impl LineFit {
+    fn new(x: &[f64], y: &[f64]) -> Option<Self> {
+        let n = x.len();
+        if n < MIN_PAIRS {
+            return None;
+        }
+        let (x_mean, y_mean) = (mean(x), mean(y));
+        let sxx: f64 = x.iter().map(|v| (v - x_mean).powi(2)).sum();
+        if sxx < f64::EPSILON {
+            return None;
+        }
+        let sxy: f64 = x
+            .iter()
+            .zip(y)
+            .map(|(xi, yi)| (xi - x_mean) * (yi - y_mean))
+            .sum();
+        let slope = sxy / sxx;
+        let intercept = y_mean - slope * x_mean;
+        let rss: f64 = x
+            .iter()
+            .zip(y)
+            .map(|(xi, yi)| (yi - intercept - slope * xi).powi(2))
+            .sum();
+        let tss: f64 = y.iter().map(|v| (v - y_mean).powi(2)).sum();
+        Some(Self {
+            intercept,
+            slope,
+            sigma: (rss / count(n - 2)).sqrt(),
+            x_mean,
+            sxx,
+            n,
+            r2: if tss < f64::EPSILON {
+                0.0
+            } else {
+                1.0 - rss / tss
+            },
+        })
+    }
+
+    fn slope_se(&self) -> f64 {
+        self.sigma / self.sxx.sqrt()
+    }
+
+    /// Fitted value at `x` and its standard error
+    fn predict(&self, x: f64) -> (f64, f64) {
+        let se = self.sigma * (1.0 / count(self.n) + (x - self.x_mean).powi(2) / self.sxx).sqrt();
+        (self.intercept + self.slope * x, se)
+    }
+}
+
+/// Fit the Q10 model to positive fluxes; standard errors of the back-transformed
+/// parameters are propagated with the delta method.
+fn fit_q10(temperatures: &[f64], log_fluxes: &[f64]) -> Option<Q10Fit> {
+    let line = LineFit::new(temperatures, log_fluxes)?;
+    let q10 = (10.0 * line.slope).exp();
+    let (log_r10, log_r10_se) = line.predict(REFERENCE_TEMPERATURE_C);
+    let r10 = log_r10.exp();
+    Some(Q10Fit {
+        q10,
+        q10_se: q10 * 10.0 * line.slope_se(),
+        r10,
+        r10_se: r10 * log_r10_se,
+        r2: line.r2,
+        n: line.n,
+    })
+}
+
+/// Fit the Arrhenius model to positive fluxes, standard errors as for [`fit_q10`].
+fn fit_arrhenius(temperatures: &[f64], log_fluxes: &[f64]) -> Option<ArrheniusFit> {
+    let inverse_kelvin: Vec<f64> = temperatures.iter().map(|t| 1.0 / (t + KELVIN)).collect();
+    let line = LineFit::new(&inverse_kelvin, log_fluxes)?;
+    let (log_r10, log_r10_se) = line.predict(1.0 / (REFERENCE_TEMPERATURE_C + KELVIN));
+    let r10 = log_r10.exp();
+    Some(ArrheniusFit {
+        activation_energy_kj_mol: -line.slope * GAS_CONSTANT / 1e3,
+        activation_energy_se: line.slope_se() * GAS_CONSTANT / 1e3,
+        r10,
+        r10_se: r10 * log_r10_se,
+        r2: line.r2,
+        n: line.n,
+    })
+}
+
+/// Reading recorded closest to `time`, if within `max_offset`
+pub fn nearest_reading(
+    series: &[DepthAverageData],
+    time: DateTime<Utc>,
+    max_offset: TimeDelta,
+) -> Option<f64> {
+    let i = series.partition_point(|d| d.time_utc < time);
+    [i.checked_sub(1), Some(i)]
+        .into_iter()
+        .flatten()
+        .filter_map(|j| series.get(j))
+        .map(|d| ((d.time_utc - time).abs(), d.y))
+        .filter(|(offset, _)| *offset <= max_offset)
+        .min_by_key(|(offset, _)| *offset)
+        .map(|(_, y)| y)
+}
+
+/// Nearest TMS profile in the same area as a chamber profile
+pub async fn nearest_tms_profile(
+    db: &DatabaseConnection,
+    profile_id: Uuid,
+) -> Result<Option<ProfileDB::Model>, DbErr> {
+    let sql = r"
+        SELECT t.id
+        FROM sensorprofile AS t
+        JOIN sensorprofile AS c ON c.id = $1
+        WHERE t.profile_type = 'tms'
+        AND t.area_id = c.area_id
+        ORDER BY st_distance(t.geom, c.geom) NULLS LAST, t.name
+        LIMIT 1
+    ";
+    let stmt =
+        Statement::from_sql_and_values(db.get_database_backend(), sql, vec![profile_id.into()]);
+    let Some(row) = db.query_one(stmt).await? else {
+        return Ok(None);
+    };
+    let tms_id: Uuid = row.try_get("", "id")?;
+    ProfileDB::Entity::find_by_id(tms_id).one(db).await
+}
+
+/// Soil temperatures at `depth_cm` of the TMS profile nearest each chamber with
+/// records, keyed by chamber profile, with the id of the TMS profile.
+async fn load_soil_temperatures(
+    db: &DatabaseConnection,
+    records: &[FluxDB::Model],
+    depth_cm: i32,
+    max_offset: TimeDelta,
+) -> Result<HashMap<Uuid, (Uuid, Vec<DepthAverageData>)>, DbErr> {
+    let profile_ids: BTreeSet<Uuid> = records.iter().map(|r| r.sensorprofile_id).collect();
+    let mut temperatures: HashMap<Uuid, (Uuid, Vec<DepthAverageData>)> = HashMap::new();
+    for profile_id in profile_ids {
+        let measured: Vec<DateTime<Utc>> = records
+            .iter()
+            .filter(|r| r.sensorprofile_id == profile_id)
+            .map(|r| r.measured_on)
+            .collect();
+        let (Some(first), Some(last)) = (measured.first(), measured.last()) else {
+            continue;
+        };
+        let Some(tms) = nearest_tms_profile(db, profile_id).await? else {
+            continue;
+        };
+        let tms_id = tms.id;
+        let mut by_depth = SensorProfile::from(tms)
+            .load_average_temperature_series_by_depth_cm(
+                db,
+                None,
+                Some(*first - max_offset),
+                Some(*last + max_offset),
+            )
+            .await?;
+        if let Some(series) = by_depth.remove(&depth_cm) {
+            temperatures.insert(profile_id, (tms_id, series));
+        }
+    }
+    Ok(temperatures)
+}
+
+/// Pair each chamber flux matching the query with the soil temperature of the
+/// nearest TMS profile in its area, then fit the Q10 and Arrhenius models per
+/// chamber or area and setting.
+pub async fn temperature_sensitivity(
+    db: &DatabaseConnection,
+    query: &TemperatureSensitivityQuery,
+) -> Result<TemperatureSensitivity, DbErr> {
+    let gas = query.gas.unwrap_or(FluxGasEnum::Co2);
+    let max_offset_minutes = query
+        .max_offset_minutes
+        .unwrap_or(DEFAULT_MAX_OFFSET_MINUTES);
+    let max_offset = TimeDelta::try_minutes(max_offset_minutes)
+        .filter(|_| (0..=MAX_OFFSET_MINUTES).contains(&max_offset_minutes))
+        .ok_or(DbErr::Custom(format!(
+            "max_offset_minutes must be between 0 and {MAX_OFFSET_MINUTES}"
+        )))?;
+
+    let (profiles, records) = load_chamber_fluxes(
+        db,
+        query.area_id,
+        query.sensorprofile_id,
+        query.setting.as_deref(),
+        query.start,
+        query.end,
+    )
+    .await?;
+
+    let temperatures = load_soil_temperatures(db, &records, query.depth_cm, max_offset).await?;
+
+    let mut groups: BTreeMap<SeriesKey, TemperatureSensitivitySeries> = BTreeMap::new();
+    for record in &records {
+        let Some(flux) = unflagged_flux(record, gas) else {
+            continue;
+        };
+        let area_id = profiles[&record.sensorprofile_id].area_id;
+        let sensorprofile_id = match query.group_by {
+            FluxGrouping::Profile => Some(record.sensorprofile_id),
+            FluxGrouping::Area => None,
+        };
+        let series = groups
+            .entry((area_id, sensorprofile_id, record.setting.clone()))
+            .or_insert_with(|| TemperatureSensitivitySeries {
+                area_id,
+                sensorprofile_id,
+                setting: record.setting.clone(),
+                pairs: Vec::new(),
+                unpaired: 0,
+                non_positive: 0,
+                q10: None,
+                arrhenius: None,
+            });
+        let paired = temperatures
+            .get(&record.sensorprofile_id)
+            .and_then(|(tms_id, series)| {
+                Some((
+                    *tms_id,
+                    nearest_reading(series, record.measured_on, max_offset)?,
+                ))
+            });
+        match paired {
+            Some((tms_sensorprofile_id, soil_temperature_c)) => {
+                series.pairs.push(FluxTemperaturePair {
+                    flux_data_id: record.id,
+                    measured_on: record.measured_on,
+                    flux,
+                    soil_temperature_c,
+                    tms_sensorprofile_id,
+                });
+            }
+            None => series.unpaired += 1,
+        }
+    }
+
+    let series = groups
+        .into_values()
+        .map(|mut series| {
+            let (soil_temperatures, log_fluxes): (Vec<f64>, Vec<f64>) = series
+                .pairs
+                .iter()
+                .filter(|p| p.flux > 0.0)
+                .map(|p| (p.soil_temperature_c, p.flux.ln()))
+                .unzip();
+            series.non_positive = series.pairs.len() - log_fluxes.len();
+            series.q10 = fit_q10(&soil_temperatures, &log_fluxes);
+            series.arrhenius = fit_arrhenius(&soil_temperatures, &log_fluxes);
+            series
+        })
+        .collect();
+
+    Ok(TemperatureSensitivity {
+        gas,
+        depth_cm: query.depth_cm,
+        reference_temperature_c: REFERENCE_TEMPERATURE_C,
+        series,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recovers_q10_and_activation_energy() {
+        let temperatures: Vec<f64> = (0..=20).map(|t| f64::from(t) * 1.5).collect();
+        // Exact Q10 = 2.5 with R10 = 3 and a small alternating deviation
+        let log_fluxes: Vec<f64> = temperatures
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                let noise = if i % 2 == 0 { 0.01 } else { -0.01 };
+                (3.0 * 2.5f64.powf((t - 10.0) / 10.0)).ln() + noise
+            })
+            .collect();
+
+        let q10 = fit_q10(&temperatures, &log_fluxes).unwrap();
+        assert!((q10.q10 - 2.5).abs() < 0.01, "Q10 {}", q10.q10);
+        assert!((q10.r10 - 3.0).abs() < 0.01, "R10 {}", q10.r10);
+        assert!(q10.q10_se > 0.0 && q10.q10_se < 0.01);
+        assert_eq!(q10.n, 21);
+
+        // Exact Arrhenius series with Ea = 60 kJ/mol
+        let log_fluxes: Vec<f64> = temperatures
+            .iter()
+            .map(|t| 20.0 - 60e3 / (GAS_CONSTANT * (t + KELVIN)))
+            .collect();
+        let arrhenius = fit_arrhenius(&temperatures, &log_fluxes).unwrap();
+        assert!((arrhenius.activation_energy_kj_mol - 60.0).abs() < 1e-6);
+        assert!(arrhenius.activation_energy_se < 1e-6);
+    }
+
+    #[test]
+    fn pairs_nearest_temperature_within_offset() {
+        let start = DateTime::parse_from_rfc3339("2026-06-01T12:00:00Z")
+            .unwrap()
+            .to_utc();
+        let series: Vec<DepthAverageData> = (0..4)
+            .map(|i| DepthAverageData {
+                time_utc: start + TimeDelta::minutes(15 * i),
+                y: f64::from(u8::try_from(i).unwrap()),
+            })
+            .collect();
+        let max_offset = TimeDelta::minutes(10);
+        assert_eq!(
+            nearest_reading(&series, start + TimeDelta::minutes(20), max_offset),
+            Some(1.0)
+        );
+        assert_eq!(
+            nearest_reading(&series, start + TimeDelta::minutes(40), max_offset),
+            Some(3.0)
+        );
+        assert_eq!(
+            nearest_reading(&series, start + TimeDelta::minutes(60), max_offset),
+            None
+        );
+    }
+}
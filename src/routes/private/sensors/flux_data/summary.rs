@@ -0,0 +1,532 @@
+use super::db as FluxDB;
+use super::fitting::{count, mean};
+use super::qc::db::FluxGasEnum;
+use crate::routes::private::sensors::profile::db::{self as ProfileDB, ProfileTypeEnum};
+use crate::routes::private::sensors::weather_data::models::{load_points, resolve_station};
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Molar mass of carbon, g/mol
+const C_MOLAR_MASS: f64 = 12.011;
+/// Molar mass of CO2, g/mol
+const CO2_MOLAR_MASS: f64 = 44.009;
+/// Molar mass of CH4, g/mol
+const CH4_MOLAR_MASS: f64 = 16.043;
+/// 100-year global warming potential of CH4 (IPCC AR6, non-fossil)
+pub const DEFAULT_GWP_CH4: f64 = 27.0;
+pub const DEFAULT_Q10: f64 = 2.0;
+/// Longest gap in the station temperature that is interpolated across
+const MAX_TEMPERATURE_GAP: TimeDelta = TimeDelta::hours(3);
+
+/// Unit of the summarised fluxes. Cumulative emissions are the same unit
+/// integrated over time (per second or per hour).
+#[derive(ToSchema, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FluxUnit {
+    /// µmol m⁻² s⁻¹ (CH4 in nmol m⁻² s⁻¹), as stored
+    #[default]
+    UmolM2S,
+    /// mg C m⁻² h⁻¹ (CO2 and CH4 only)
+    MgCM2H,
+    /// mg CO2-equivalent m⁻² h⁻¹ (CO2 and CH4 only)
+    Co2eqMgM2H,
+}
+
+impl FluxUnit {
+    /// Factor converting a stored flux of `gas` to this unit; `None` where the
+    /// unit does not apply to the gas.
+    pub fn factor(self, gas: FluxGasEnum, gwp_ch4: f64) -> Option<f64> {
+        match (self, gas) {
+            (Self::UmolM2S, _) => Some(1.0),
+            (_, FluxGasEnum::H2o) => None,
+            (Self::MgCM2H, FluxGasEnum::Co2) => Some(C_MOLAR_MASS * 1e-3 * 3600.0),
+            (Self::MgCM2H, FluxGasEnum::Ch4) => Some(C_MOLAR_MASS * 1e-6 * 3600.0),
+            (Self::Co2eqMgM2H, FluxGasEnum::Co2) => Some(CO2_MOLAR_MASS * 1e-3 * 3600.0),
+            (Self::Co2eqMgM2H, FluxGasEnum::Ch4) => Some(CH4_MOLAR_MASS * 1e-6 * 3600.0 * gwp_ch4),
+        }
+    }
+
+    /// Seconds in the time unit of the flux
+    fn seconds(self) -> f64 {
+        match self {
+            Self::UmolM2S => 1.0,
+            Self::MgCM2H | Self::Co2eqMgM2H => 3600.0,
+        }
+    }
+
+    fn labels(self) -> (&'static str, &'static str) {
+        match self {
+            Self::UmolM2S => (
+                "µmol m⁻² s⁻¹ (CH4: nmol m⁻² s⁻¹)",
+                "µmol m⁻² (CH4: nmol m⁻²)",
+            ),
+            Self::MgCM2H => ("mg C m⁻² h⁻¹", "mg C m⁻²"),
+            Self::Co2eqMgM2H => ("mg CO2-eq m⁻² h⁻¹", "mg CO2-eq m⁻²"),
+        }
+    }
+}
+
+/// What a summary series is made of
+#[derive(ToSchema, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FluxGrouping {
+    /// One series per chamber profile and setting
+    #[default]
+    Profile,
+    /// One series per area and setting, pooling its chambers
+    Area,
+}
+
+/// How fluxes are interpolated between campaigns for cumulative emissions
+#[derive(ToSchema, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FluxInterpolation {
+    /// Linear between campaign means
+    #[default]
+    Linear,
+    /// Scaled by the hourly air temperature of the nearest weather station with
+    /// a Q10 response, the basal rate being linear between campaigns
+    Temperature,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct FluxSummaryQuery {
+    pub area_id: Option<Uuid>,
+    pub sensorprofile_id: Option<Uuid>,
+    pub setting: Option<String>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub group_by: FluxGrouping,
+    #[serde(default)]
+    pub unit: FluxUnit,
+    #[serde(default)]
+    pub interpolation: FluxInterpolation,
+    /// Global warming potential of CH4 for CO2-equivalents (default 27)
+    pub gwp_ch4: Option<f64>,
+    /// Q10 of the temperature interpolation (default 2)
+    pub q10: Option<f64>,
+}
+
+/// Mean, sample standard deviation and count of the fluxes of one gas
+#[derive(ToSchema, Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct GasStats {
+    pub mean: f64,
+    pub sd: Option<f64>,
+    pub n: usize,
+}
+
+/// One measurement day of a series
+#[derive(ToSchema, Serialize, Debug, Clone, PartialEq)]
+pub struct FluxCampaignSummary {
+    pub campaign_date: NaiveDate,
+    /// Mean measurement time, used as the campaign time for cumulative emissions
+    pub measured_on: DateTime<Utc>,
+    pub co2: Option<GasStats>,
+    pub ch4: Option<GasStats>,
+    pub h2o: Option<GasStats>,
+    /// Emission since the first campaign of the series with a flux of the gas
+    pub cumulative_co2: Option<f64>,
+    pub cumulative_ch4: Option<f64>,
+    pub cumulative_h2o: Option<f64>,
+}
+
+#[derive(ToSchema, Serialize, Debug, Clone, PartialEq)]
+pub struct FluxSummarySeries {
+    pub area_id: Uuid,
+    /// Chamber profile, empty when grouped by area
+    pub sensorprofile_id: Option<Uuid>,
+    pub setting: Option<String>,
+    pub campaigns: Vec<FluxCampaignSummary>,
+}
+
+#[derive(ToSchema, Serialize, Debug, Clone, PartialEq)]
+pub struct FluxSummary {
+    pub unit: FluxUnit,
+    pub flux_unit: String,
+    pub cumulative_unit: String,
+    pub interpolation: FluxInterpolation,
+    pub series: Vec<FluxSummarySeries>,
+}
+
+/// Hourly station temperatures and the Q10 that scale fluxes between campaigns
+#[derive(Clone, Copy)]
+struct TemperatureResponse<'a> {
+    temperatures: &'a [(DateTime<Utc>, f64)],
+    q10: f64,
+}
+
+/// Series key: area, chamber profile (empty when pooled by area) and setting
+pub type SeriesKey = (Uuid, Option<Uuid>, Option<String>);
+
+fn stats(values: &[f64]) -> Option<GasStats> {
+    if values.is_empty() {
+        return None;
+    }
+    let m = mean(values);
+    let sd = (values.len() > 1).then(|| {
+        let ss: f64 = values.iter().map(|v| (v - m).powi(2)).sum();
+        (ss / count(values.len() - 1)).sqrt()
+    });
+    Some(GasStats {
+        mean: m,
+        sd,
+        n: values.len(),
+    })
+}
+
+/// Stored flux of a gas, unless it is QC-flagged
+pub fn unflagged_flux(record: &FluxDB::Model, gas: FluxGasEnum) -> Option<f64> {
+    match gas {
+        FluxGasEnum::Co2 => record
+            .flux_co2_umol_m2_s
+            .filter(|_| record.qc_flagged_co2 != Some(true)),
+        FluxGasEnum::Ch4 => record
+            .flux_ch4_nmol_m2_s
+            .filter(|_| record.qc_flagged_ch4 != Some(true)),
+        FluxGasEnum::H2o => record
+            .flux_h2o_umol_m2_s
+            .filter(|_| record.qc_flagged_h2o != Some(true)),
+    }
+}
+
+/// Mean of the times, at millisecond precision
+fn mean_time(times: &[DateTime<Utc>]) -> DateTime<Utc> {
+    let first = times[0];
+    let total: i64 = times.iter().map(|t| (*t - first).num_milliseconds()).sum();
+    first + TimeDelta::milliseconds(total / i64::try_from(times.len()).unwrap_or(i64::MAX))
+}
+
+/// Temperature at `time`, linearly interpolated between the neighbouring
+/// station records if they are close enough.
+fn temperature_at(temperatures: &[(DateTime<Utc>, f64)], time: DateTime<Utc>) -> Option<f64> {
+    let i = temperatures.partition_point(|(t, _)| *t < time);
+    if let Some(&(t, value)) = temperatures.get(i)
+        && t == time
+    {
+        return Some(value);
+    }
+    let (t0, v0) = *temperatures.get(i.checked_sub(1)?)?;
+    let (t1, v1) = *temperatures.get(i)?;
+    if t1 - t0 > MAX_TEMPERATURE_GAP {
+        return None;
+    }
+    let fraction = (time - t0).as_seconds_f64() / (t1 - t0).as_seconds_f64();
+    Some(v0 + fraction * (v1 - v0))
+}
+
+/// Emission between two campaigns of fluxes `f0` and `f1`, in flux units times
+/// `seconds_per_unit` seconds.
+///
+/// Without temperatures the flux is linear in between (trapezoid rule). With
+/// them, the basal rate `R = F / Q10^(T/10)` is linear in between and the flux
+/// `R·Q10^(T/10)` follows the hourly temperature; hours without a temperature
+/// fall back to the linear flux.
+fn interval_emission(
+    (t0, f0): (DateTime<Utc>, f64),
+    (t1, f1): (DateTime<Utc>, f64),
+    temperature: Option<TemperatureResponse>,
+    seconds_per_unit: f64,
+) -> f64 {
+    let span = (t1 - t0).as_seconds_f64();
+    let Some(TemperatureResponse { temperatures, q10 }) = temperature else {
+        return f0.midpoint(f1) * span / seconds_per_unit;
+    };
+    let response = |t: f64| q10.powf(t / 10.0);
+    let basal = temperature_at(temperatures, t0)
+        .zip(temperature_at(temperatures, t1))
+        .map(|(temp0, temp1)| (f0 / response(temp0), f1 / response(temp1)));
+
+    let mut grid = vec![t0];
+    let mut next = t0 + TimeDelta::hours(1);
+    while next < t1 {
+        grid.push(next);
+        next += TimeDelta::hours(1);
+    }
+    grid.push(t1);
+
+    let flux_at = |time: DateTime<Utc>| {
+        let fraction = (time - t0).as_seconds_f64() / span;
+        let linear = f0 + fraction * (f1 - f0);
+        basal
+            .zip(temperature_at(temperatures, time))
+            .map_or(linear, |((r0, r1), temp)| {
+                (r0 + fraction * (r1 - r0)) * response(temp)
+            })
+    };
+    grid.windows(2)
+        .map(|w| {
+            flux_at(w[0]).midpoint(flux_at(w[1])) * (w[1] - w[0]).as_seconds_f64()
+                / seconds_per_unit
+        })
+        .sum()
+}
+
+/// Cumulative emission at each point since the first one.
+fn cumulate(
+    points: &[(DateTime<Utc>, f64)],
+    temperature: Option<TemperatureResponse>,
+    seconds_per_unit: f64,
+) -> Vec<f64> {
+    let mut total = 0.0;
+    let mut cumulative = Vec::with_capacity(points.len());
+    for (i, point) in points.iter().enumerate() {
+        if i > 0 {
+            total += interval_emission(points[i - 1], *point, temperature, seconds_per_unit);
+        }
+        cumulative.push(total);
+    }
+    cumulative
+}
+
+/// Campaign summaries of the records of one series, in the requested unit.
+/// QC-flagged fluxes are left out.
+fn summarise_campaigns(
+    records: &[&FluxDB::Model],
+    unit: FluxUnit,
+    gwp_ch4: f64,
+    temperature: Option<TemperatureResponse>,
+) -> Vec<FluxCampaignSummary> {
+    let mut campaigns: BTreeMap<NaiveDate, Vec<&FluxDB::Model>> = BTreeMap::new();
+    for record in records {
+        campaigns
+            .entry(record.measured_on.date_naive())
+            .or_default()
+            .push(record);
+    }
+
+    let gas_values = |records: &[&FluxDB::Model], gas: FluxGasEnum| -> Vec<f64> {
+        let Some(factor) = unit.factor(gas, gwp_ch4) else {
+            return Vec::new();
+        };
+        records
+            .iter()
+            .filter_map(|r| unflagged_flux(r, gas))
+            .map(|v| v * factor)
+            .collect()
+    };
+
+    let mut summaries: Vec<FluxCampaignSummary> = campaigns
+        .into_iter()
+        .map(|(campaign_date, records)| {
+            let times: Vec<DateTime<Utc>> = records.iter().map(|r| r.measured_on).collect();
+            FluxCampaignSummary {
+                campaign_date,
+                measured_on: mean_time(&times),
+                co2: stats(&gas_values(&records, FluxGasEnum::Co2)),
+                ch4: stats(&gas_values(&records, FluxGasEnum::Ch4)),
+                h2o: stats(&gas_values(&records, FluxGasEnum::H2o)),
+                cumulative_co2: None,
+                cumulative_ch4: None,
+                cumulative_h2o: None,
+            }
+        })
+        .collect();
+
+    for gas in [FluxGasEnum::Co2, FluxGasEnum::Ch4, FluxGasEnum::H2o] {
+        let stats_of = |c: &FluxCampaignSummary| match gas {
+            FluxGasEnum::Co2 => c.co2,
+            FluxGasEnum::Ch4 => c.ch4,
+            FluxGasEnum::H2o => c.h2o,
+        };
+        let indices: Vec<usize> = (0..summaries.len())
+            .filter(|&i| stats_of(&summaries[i]).is_some())
+            .collect();
+        let points: Vec<(DateTime<Utc>, f64)> = indices
+            .iter()
+            .filter_map(|&i| Some((summaries[i].measured_on, stats_of(&summaries[i])?.mean)))
+            .collect();
+        for (i, total) in indices
+            .into_iter()
+            .zip(cumulate(&points, temperature, unit.seconds()))
+        {
+            let cumulative = match gas {
+                FluxGasEnum::Co2 => &mut summaries[i].cumulative_co2,
+                FluxGasEnum::Ch4 => &mut summaries[i].cumulative_ch4,
+                FluxGasEnum::H2o => &mut summaries[i].cumulative_h2o,
+            };
+            *cumulative = Some(total);
+        }
+    }
+    summaries
+}
+
+/// Chamber profiles matching the filters, keyed by id, and their flux records in
+/// time order.
+pub async fn load_chamber_fluxes(
+    db: &DatabaseConnection,
+    area_id: Option<Uuid>,
+    sensorprofile_id: Option<Uuid>,
+    setting: Option<&str>,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Result<(BTreeMap<Uuid, ProfileDB::Model>, Vec<FluxDB::Model>), DbErr> {
+    let mut profile_condition =
+        Condition::all().add(ProfileDB::Column::ProfileType.eq(ProfileTypeEnum::Chamber));
+    if let Some(area_id) = area_id {
+        profile_condition = profile_condition.add(ProfileDB::Column::AreaId.eq(area_id));
+    }
+    if let Some(sensorprofile_id) = sensorprofile_id {
+        profile_condition = profile_condition.add(ProfileDB::Column::Id.eq(sensorprofile_id));
+    }
+    let profiles: BTreeMap<Uuid, ProfileDB::Model> = ProfileDB::Entity::find()
+        .filter(profile_condition)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|p| (p.id, p))
+        .collect();
+
+    let mut condition = Condition::all()
+        .add(FluxDB::Column::SensorprofileId.is_in(profiles.keys().copied().collect::<Vec<_>>()));
+    if let Some(setting) = setting {
+        condition = condition.add(FluxDB::Column::Setting.eq(setting));
+    }
+    if let Some(start) = start {
+        condition = condition.add(FluxDB::Column::MeasuredOn.gte(start));
+    }
+    if let Some(end) = end {
+        condition = condition.add(FluxDB::Column::MeasuredOn.lte(end));
+    }
+    let records = FluxDB::Entity::find()
+        .filter(condition)
+        .order_by_asc(FluxDB::Column::MeasuredOn)
+        .all(db)
+        .await?;
+
+    Ok((profiles, records))
+}
+
+/// Group the chamber fluxes matching the query into series and summarise their
+/// campaigns.
+pub async fn flux_summary(
+    db: &DatabaseConnection,
+    query: &FluxSummaryQuery,
+) -> Result<FluxSummary, DbErr> {
+    let gwp_ch4 = query.gwp_ch4.unwrap_or(DEFAULT_GWP_CH4);
+    let q10 = query.q10.unwrap_or(DEFAULT_Q10);
+    if q10 <= 0.0 {
+        return Err(DbErr::Custom("q10 must be positive".into()));
+    }
+
+    let (profiles, records) = load_chamber_fluxes(
+        db,
+        query.area_id,
+        query.sensorprofile_id,
+        query.setting.as_deref(),
+        query.start,
+        query.end,
+    )
+    .await?;
+
+    let mut groups: BTreeMap<SeriesKey, Vec<&FluxDB::Model>> = BTreeMap::new();
+    for record in &records {
+        let Some(profile) = profiles.get(&record.sensorprofile_id) else {
+            continue;
+        };
+        let profile_id = match query.group_by {
+            FluxGrouping::Profile => Some(profile.id),
+            FluxGrouping::Area => None,
+        };
+        groups
+            .entry((profile.area_id, profile_id, record.setting.clone()))
+            .or_default()
+            .push(record);
+    }
+
+    let mut series = Vec::with_capacity(groups.len());
+    for ((area_id, sensorprofile_id, setting), records) in groups {
+        let temperatures: Option<Vec<(DateTime<Utc>, f64)>> = match query.interpolation {
+            FluxInterpolation::Linear => None,
+            FluxInterpolation::Temperature => {
+                let station = resolve_station(db, records[0].sensorprofile_id, None).await?;
+                let start = records.first().map(|r| r.measured_on - TimeDelta::days(1));
+                let end = records.last().map(|r| r.measured_on + TimeDelta::days(1));
+                let points = load_points(db, station.id, "hourly", start, end).await?;
+                Some(
+                    points
+                        .into_iter()
+                        .filter_map(|p| Some((p.time_utc, p.air_temperature_c?)))
+                        .collect(),
+                )
+            }
+        };
+        series.push(FluxSummarySeries {
+            area_id,
+            sensorprofile_id,
+            setting,
+            campaigns: summarise_campaigns(
+                &records,
+                query.unit,
+                gwp_ch4,
+                temperatures
+                    .as_deref()
+                    .map(|temperatures| TemperatureResponse { temperatures, q10 }),
+            ),
+        });
+    }
+
+    let (flux_unit, cumulative_unit) = query.unit.labels();
+    Ok(FluxSummary {
+        unit: query.unit,
+        flux_unit: flux_unit.to_string(),
+        cumulative_unit: cumulative_unit.to_string(),
+        interpolation: query.interpolation,
+        series,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 6, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn linear_cumulative_is_trapezoid_between_campaigns() {
+        let points = [(at(1, 12), 2.0), (at(2, 12), 4.0), (at(4, 12), 0.0)];
+        let cumulative = cumulate(&points, None, 3600.0);
+        assert_eq!(cumulative, vec![0.0, 72.0, 168.0]);
+    }
+
+    #[test]
+    fn temperature_interpolation_follows_q10_response() {
+        // Same flux at both campaigns at 10 °C, 20 °C for the day in between
+        let points = [(at(1, 0), 1.0), (at(3, 0), 1.0)];
+        let temperatures: Vec<(DateTime<Utc>, f64)> = (0..=48)
+            .map(|h| {
+                let t = at(1, 0) + TimeDelta::hours(h);
+                (t, if (12..=36).contains(&h) { 20.0 } else { 10.0 })
+            })
+            .collect();
+        let linear = cumulate(&points, None, 3600.0)[1];
+        let response = TemperatureResponse {
+            temperatures: &temperatures,
+            q10: 2.0,
+        };
+        let scaled = cumulate(&points, Some(response), 3600.0)[1];
+        assert!((linear - 48.0).abs() < 1e-9);
+        // 24 h doubled, plus the two ramp hours at 1.5
+        assert!((scaled - 73.0).abs() < 1e-9, "scaled {scaled}");
+    }
+
+    #[test]
+    fn converts_units_per_gas() {
+        let mg_c = FluxUnit::MgCM2H
+            .factor(FluxGasEnum::Co2, DEFAULT_GWP_CH4)
+            .unwrap();
+        assert!((mg_c - 43.2396).abs() < 1e-9);
+        assert_eq!(FluxUnit::Co2eqMgM2H.factor(FluxGasEnum::H2o, 27.0), None);
+        let ch4_eq = FluxUnit::Co2eqMgM2H.factor(FluxGasEnum::Ch4, 27.0).unwrap();
+        assert!((ch4_eq - 16.043e-6 * 3600.0 * 27.0).abs() < 1e-12);
+    }
+}
@@ -1,5 +1,24 @@
-use super::models::{FluxData, FluxDataCreate, FluxDataUpdate};
+use super::export::{FluxExport, FluxExportFormat, FluxExportQuery, export_fluxes};
+use super::models::{
+    ChamberGeometry, FluxData, FluxDataCreate, FluxDataUpdate, MIN_FIT_READINGS, apply_fits,
+    evaluate_qc, fit_fluxes, readings_in_window,
+};
+use super::parsers::{parse_licor_data, parse_soilfluxpro_summary, remark_markers, split_closures};
+use super::qc::{
+    db::{self as FluxQcDB, FluxGasEnum},
+    models::load_thresholds,
+};
+use super::sensitivity::{
+    TemperatureSensitivity, TemperatureSensitivityQuery, temperature_sensitivity,
+};
+use super::summary::{
+    FluxGrouping, FluxInterpolation, FluxSummary, FluxSummaryQuery, FluxUnit, flux_summary,
+};
 use crate::common::auth::Role;
+use crate::common::errors::db_error_response;
+use crate::common::files::decode_base64_text;
+use crate::common::idempotency::{self, Claim};
+use crate::common::models::BatchQuery;
 use crate::routes::private::sensors::profile::db as ProfileDB;
 use axum::response::IntoResponse;
 use axum_keycloak_auth::{
@@ -7,9 +26,12 @@ use axum_keycloak_auth::{
 };
 use chrono::{DateTime, Utc};
 use crudcrate::{CRUDResource, crud_handlers};
-use sea_orm::{ActiveModelTrait, ActiveValue, DatabaseConnection, EntityTrait};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection,
+    EntityTrait, Iterable, QueryFilter, TransactionTrait, sea_query::OnConflict,
+};
 use serde::{Deserialize, Serialize};
-use soil_sensor_toolbox::compute_gas_flux;
+use std::collections::HashMap;
 use std::sync::Arc;
 use utoipa::ToSchema;
 use utoipa_axum::{router::OpenApiRouter, routes};
@@ -17,27 +39,37 @@ use utoipa_axum::{router::OpenApiRouter, routes};
 crud_handlers!(FluxData, FluxDataUpdate, FluxDataCreate);
 
 /// A single error from a batch ingest operation.
-#[derive(Serialize, ToSchema)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct BatchIngestError {
     pub index: usize,
     pub message: String,
 }
 
 /// Result of a batch ingest operation.
-#[derive(Serialize, ToSchema)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct BatchIngestResult {
+    /// Records inserted or replaced
     pub inserted: usize,
     pub errors: Vec<BatchIngestError>,
 }
 
 /// Request body for the ingest endpoint: raw chamber time series data.
-#[derive(Deserialize, ToSchema)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct IngestFluxRequest {
     pub sensorprofile_id: uuid::Uuid,
     pub measured_on: DateTime<Utc>,
     pub replicate: String,
     pub setting: Option<String>,
     pub raw_readings: Vec<RawReading>,
+    /// Fit only readings with `t` at or after this many seconds (mixing dead band)
+    pub fit_start_s: Option<f64>,
+    /// Fit only readings with `t` up to this many seconds
+    pub fit_end_s: Option<f64>,
+    /// Collar height above the soil surface at this measurement; its volume is
+    /// added to the chamber volume of the profile
+    pub collar_offset_cm: Option<f64>,
+    /// Measured headspace volume, replacing the profile volume and collar offset
+    pub volume_ml: Option<f64>,
 }
 
 /// A single raw reading from the chamber time series.
@@ -63,7 +95,7 @@ pub struct RawReading {
         (status = 500, description = "Internal server error")
     ),
     summary = "Ingest raw chamber data and compute fluxes server-side",
-    description = "Accepts raw chamber time series readings, computes gas fluxes using the soil-sensor-toolbox, and stores both raw and processed data.",
+    description = "Accepts raw chamber time series readings, fits linear, exponential and HMR models per gas, selects the model with the lowest AIC (a non-linear model must improve it by at least 2) and stores both raw and processed data. `flux_model_*` reports the selected model and `flux_fits` every model's flux, R² and AIC. The headspace volume is the measured `volume_ml` if given, otherwise the profile's chamber volume plus `collar_offset_cm` times its collar area; it is stored as `effective_volume_ml`.",
     operation_id = "ingest_flux_data",
 )]
 pub async fn ingest_flux_data(
@@ -100,79 +132,23 @@ pub async fn ingest_flux_data(
         }
     };
 
-    let volume_ml = profile.volume_ml.unwrap_or(16852.1);
-    let area_cm2 = profile.area_cm2.unwrap_or(318.0);
-    let volume_m3 = volume_ml * 1e-6;
-    let area_m2 = area_cm2 * 1e-4;
-
-    // Extract arrays from raw readings
-    let timestamps: Vec<f64> = req.raw_readings.iter().map(|r| r.t).collect();
-    let co2_ppm: Vec<f64> = req.raw_readings.iter().map(|r| r.co2).collect();
-    let ch4_ppb: Vec<f64> = req.raw_readings.iter().map(|r| r.ch4).collect();
-    let h2o_mmol: Vec<f64> = req.raw_readings.iter().map(|r| r.h2o).collect();
-    let temp_c: Vec<f64> = req.raw_readings.iter().map(|r| r.temp).collect();
-    let press_kpa: Vec<f64> = req.raw_readings.iter().map(|r| r.press).collect();
-
-    // Compute gas fluxes server-side
-    let result = compute_gas_flux(
-        &timestamps,
-        &co2_ppm,
-        &ch4_ppb,
-        &h2o_mmol,
-        &temp_c,
-        &press_kpa,
-        volume_m3,
-        area_m2,
-    );
-
-    // Compute SWC: average of soilp values where present
-    let valid_soilp: Vec<f64> = req
-        .raw_readings
-        .iter()
-        .filter_map(|r| r.soilp)
-        .collect();
-    let swc = if valid_soilp.is_empty() {
-        None
-    } else {
-        Some(valid_soilp.iter().sum::<f64>() / valid_soilp.len() as f64)
-    };
-
-    let n_measurements = i32::try_from(req.raw_readings.len()).ok();
+    let thresholds = load_thresholds(&db).await.map_err(|e| {
+        (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            axum::Json(format!("Database error: {e}")),
+        )
+    })?;
 
-    // Serialize raw_readings to JSON
-    let raw_json = match serde_json::to_value(&req.raw_readings) {
-        Ok(v) => Some(v),
-        Err(e) => {
-            return Err((
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                axum::Json(format!("Failed to serialize raw_readings: {e}")),
-            ));
-        }
-    };
+    // Compute gas fluxes server-side within the fit window
+    let active_model = build_flux_record(&profile, req, true, &thresholds)
+        .map_err(|e| (axum::http::StatusCode::UNPROCESSABLE_ENTITY, axum::Json(e)))?;
 
-    // Create the flux_data record
-    let active_model = super::db::ActiveModel {
-        id: ActiveValue::Set(uuid::Uuid::new_v4()),
-        sensorprofile_id: ActiveValue::Set(req.sensorprofile_id),
-        measured_on: ActiveValue::Set(req.measured_on),
-        replicate: ActiveValue::Set(req.replicate),
-        setting: ActiveValue::Set(req.setting),
-        flux_co2_umol_m2_s: ActiveValue::Set(Some(result.flux_co2_umol_m2_s)),
-        flux_ch4_nmol_m2_s: ActiveValue::Set(Some(result.flux_ch4_nmol_m2_s)),
-        flux_h2o_umol_m2_s: ActiveValue::Set(Some(result.flux_h2o_umol_m2_s)),
-        r2_co2: ActiveValue::Set(Some(result.r2_co2)),
-        r2_ch4: ActiveValue::Set(Some(result.r2_ch4)),
-        r2_h2o: ActiveValue::Set(Some(result.r2_h2o)),
-        swc: ActiveValue::Set(swc),
-        n_measurements: ActiveValue::Set(n_measurements),
-        raw_readings: ActiveValue::Set(raw_json),
+    let stored = match upsert_flux_record(&db, active_model).await {
+        Ok(id) => FluxData::get_one(&db, id).await,
+        Err(e) => Err(e),
     };
-
-    match active_model.insert(&db).await {
-        Ok(model) => {
-            let flux_data: FluxData = model.into();
-            Ok((axum::http::StatusCode::CREATED, axum::Json(flux_data)))
-        }
+    match stored {
+        Ok(flux_data) => Ok((axum::http::StatusCode::CREATED, axum::Json(flux_data))),
         Err(e) => Err((
             axum::http::StatusCode::INTERNAL_SERVER_ERROR,
             axum::Json(format!("Failed to insert flux data: {e}")),
@@ -180,43 +156,51 @@ pub async fn ingest_flux_data(
     }
 }
 
-/// Process a single ingest request, returning Ok(()) on success or an error message.
-async fn process_single_ingest(
-    db: &DatabaseConnection,
+/// Insert a flux record, replacing the values of an existing record with the same
+/// profile, time and replicate (hand-set QC flags included). Returns the id of
+/// the stored record.
+async fn upsert_flux_record<C: ConnectionTrait>(
+    db: &C,
+    record: super::db::ActiveModel,
+) -> Result<uuid::Uuid, DbErr> {
+    use super::db::Column;
+
+    let result = super::db::Entity::insert(record)
+        .on_conflict(
+            OnConflict::columns([
+                Column::SensorprofileId,
+                Column::MeasuredOn,
+                Column::Replicate,
+            ])
+            .update_columns(Column::iter().filter(|c| {
+                !matches!(
+                    c,
+                    Column::Id | Column::SensorprofileId | Column::MeasuredOn | Column::Replicate
+                )
+            }))
+            .to_owned(),
+        )
+        .exec(db)
+        .await?;
+    Ok(result.last_insert_id)
+}
+
+/// Compute fluxes for a single ingest request and build the `flux_data` record.
+/// `has_ch4` is false for analysers without a CH4 channel.
+fn build_flux_record(
+    profile: &ProfileDB::Model,
     req: IngestFluxRequest,
-) -> Result<(), String> {
+    has_ch4: bool,
+    thresholds: &[FluxQcDB::Model],
+) -> Result<super::db::ActiveModel, String> {
     if req.raw_readings.is_empty() {
         return Err("raw_readings must not be empty".to_string());
     }
 
-    let profile = ProfileDB::Entity::find_by_id(req.sensorprofile_id)
-        .one(db)
-        .await
-        .map_err(|e| format!("Database error: {e}"))?
-        .ok_or_else(|| format!("Sensor profile {} not found", req.sensorprofile_id))?;
-
-    let volume_ml = profile.volume_ml.unwrap_or(16852.1);
-    let area_cm2 = profile.area_cm2.unwrap_or(318.0);
-    let volume_m3 = volume_ml * 1e-6;
-    let area_m2 = area_cm2 * 1e-4;
-
-    let timestamps: Vec<f64> = req.raw_readings.iter().map(|r| r.t).collect();
-    let co2_ppm: Vec<f64> = req.raw_readings.iter().map(|r| r.co2).collect();
-    let ch4_ppb: Vec<f64> = req.raw_readings.iter().map(|r| r.ch4).collect();
-    let h2o_mmol: Vec<f64> = req.raw_readings.iter().map(|r| r.h2o).collect();
-    let temp_c: Vec<f64> = req.raw_readings.iter().map(|r| r.temp).collect();
-    let press_kpa: Vec<f64> = req.raw_readings.iter().map(|r| r.press).collect();
-
-    let result = compute_gas_flux(
-        &timestamps,
-        &co2_ppm,
-        &ch4_ppb,
-        &h2o_mmol,
-        &temp_c,
-        &press_kpa,
-        volume_m3,
-        area_m2,
-    );
+    let geometry = ChamberGeometry::for_measurement(profile, req.collar_offset_cm, req.volume_ml)?;
+    let fits = fit_fluxes(geometry, &req.raw_readings, req.fit_start_s, req.fit_end_s)?;
+    let window = readings_in_window(&req.raw_readings, req.fit_start_s, req.fit_end_s);
+    let qc = evaluate_qc(thresholds, &fits, &window, has_ch4);
 
     let valid_soilp: Vec<f64> = req.raw_readings.iter().filter_map(|r| r.soilp).collect();
     let swc = if valid_soilp.is_empty() {
@@ -225,30 +209,47 @@ async fn process_single_ingest(
         Some(valid_soilp.iter().sum::<f64>() / valid_soilp.len() as f64)
     };
 
-    let n_measurements = i32::try_from(req.raw_readings.len()).ok();
+    // Readings the fluxes were fitted on, excluding the dead band
+    let n_measurements = i32::try_from(window.len()).ok();
 
     let raw_json = serde_json::to_value(&req.raw_readings)
         .map_err(|e| format!("Failed to serialize raw_readings: {e}"))?;
 
-    let active_model = super::db::ActiveModel {
+    let mut active_model = super::db::ActiveModel {
         id: ActiveValue::Set(uuid::Uuid::new_v4()),
         sensorprofile_id: ActiveValue::Set(req.sensorprofile_id),
         measured_on: ActiveValue::Set(req.measured_on),
         replicate: ActiveValue::Set(req.replicate),
         setting: ActiveValue::Set(req.setting),
-        flux_co2_umol_m2_s: ActiveValue::Set(Some(result.flux_co2_umol_m2_s)),
-        flux_ch4_nmol_m2_s: ActiveValue::Set(Some(result.flux_ch4_nmol_m2_s)),
-        flux_h2o_umol_m2_s: ActiveValue::Set(Some(result.flux_h2o_umol_m2_s)),
-        r2_co2: ActiveValue::Set(Some(result.r2_co2)),
-        r2_ch4: ActiveValue::Set(Some(result.r2_ch4)),
-        r2_h2o: ActiveValue::Set(Some(result.r2_h2o)),
         swc: ActiveValue::Set(swc),
         n_measurements: ActiveValue::Set(n_measurements),
+        fit_start_s: ActiveValue::Set(req.fit_start_s),
+        fit_end_s: ActiveValue::Set(req.fit_end_s),
+        collar_offset_cm: ActiveValue::Set(req.collar_offset_cm),
+        volume_ml: ActiveValue::Set(req.volume_ml),
+        effective_volume_ml: ActiveValue::Set(Some(geometry.volume_ml)),
         raw_readings: ActiveValue::Set(Some(raw_json)),
+        has_ch4: ActiveValue::Set(has_ch4),
+        ..Default::default()
     };
+    apply_fits(&mut active_model, &fits, has_ch4)?;
+    qc.apply(&mut active_model);
+    Ok(active_model)
+}
+
+/// Process a single ingest request, returning Ok(()) on success or an error message.
+async fn process_single_ingest<C: ConnectionTrait>(
+    db: &C,
+    req: IngestFluxRequest,
+    thresholds: &[FluxQcDB::Model],
+) -> Result<(), String> {
+    let profile = ProfileDB::Entity::find_by_id(req.sensorprofile_id)
+        .one(db)
+        .await
+        .map_err(|e| format!("Database error: {e}"))?
+        .ok_or_else(|| format!("Sensor profile {} not found", req.sensorprofile_id))?;
 
-    active_model
-        .insert(db)
+    upsert_flux_record(db, build_flux_record(&profile, req, true, thresholds)?)
         .await
         .map_err(|e| format!("Failed to insert flux data: {e}"))?;
 
@@ -259,28 +260,496 @@ async fn process_single_ingest(
     post,
     path = "/ingest_batch",
     request_body = Vec<IngestFluxRequest>,
+    params(
+        ("atomic" = Option<bool>, Query, description = "Ingest all entries in one transaction, rolling back on the first error"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when the same request was already processed with this key in the last 24 hours; reusing the key for a different request is rejected")
+    ),
     responses(
         (status = 200, description = "Batch ingest results.", body = BatchIngestResult),
+        (status = 400, description = "Invalid Idempotency-Key header"),
+        (status = 422, description = "Atomic batch rolled back; the failing entry is reported, or the Idempotency-Key was used with a different request", body = BatchIngestResult),
+        (status = 500, description = "Internal server error")
     ),
     summary = "Batch ingest raw chamber data and compute fluxes server-side",
-    description = "Accepts an array of raw chamber time series readings. Each entry is processed independently; errors are recorded per-entry without aborting the batch.",
+    description = "Accepts an array of raw chamber time series readings. Entries replace existing records with the same `sensorprofile_id`, `measured_on` and `replicate`, so re-sending a batch does not create duplicates. By default each entry is processed independently and errors are recorded per entry without aborting the batch; with `atomic=true` the batch is all or nothing.",
     operation_id = "ingest_flux_data_batch",
 )]
 pub async fn ingest_flux_data_batch(
     axum::extract::State(db): axum::extract::State<DatabaseConnection>,
+    axum::extract::Query(query): axum::extract::Query<BatchQuery>,
+    headers: HeaderMap,
     axum::Json(requests): axum::Json<Vec<IngestFluxRequest>>,
-) -> axum::Json<BatchIngestResult> {
+) -> Result<
+    (axum::http::StatusCode, axum::Json<BatchIngestResult>),
+    (axum::http::StatusCode, axum::Json<String>),
+> {
+    const SCOPE: &str = "flux_data/ingest_batch";
+
+    let key = idempotency::key_from_headers(&headers)
+        .map_err(|e| (axum::http::StatusCode::BAD_REQUEST, axum::Json(e)))?;
+    // Holds the idempotency key until the response is stored; atomic batches
+    // also insert their entries in it
+    let txn = db.begin().await.map_err(db_error_response)?;
+    if let Some(key) = &key {
+        match idempotency::claim(&txn, SCOPE, key, &(query.atomic, &requests))
+            .await
+            .map_err(db_error_response)?
+        {
+            Claim::New => {}
+            Claim::Replay(stored) => return Ok((axum::http::StatusCode::OK, axum::Json(stored))),
+            Claim::Mismatch => {
+                return Err((
+                    axum::http::StatusCode::UNPROCESSABLE_ENTITY,
+                    axum::Json(idempotency::MISMATCH_MESSAGE.to_string()),
+                ));
+            }
+        }
+    }
+
+    let thresholds = load_thresholds(&db).await.map_err(db_error_response)?;
     let mut inserted = 0usize;
     let mut errors = Vec::new();
 
-    for (index, req) in requests.into_iter().enumerate() {
-        match process_single_ingest(&db, req).await {
-            Ok(()) => inserted += 1,
+    if query.atomic.unwrap_or(false) {
+        for (index, req) in requests.into_iter().enumerate() {
+            if let Err(message) = process_single_ingest(&txn, req, &thresholds).await {
+                txn.rollback().await.map_err(db_error_response)?;
+                let errors = vec![BatchIngestError { index, message }];
+                return Ok((
+                    axum::http::StatusCode::UNPROCESSABLE_ENTITY,
+                    axum::Json(BatchIngestResult {
+                        inserted: 0,
+                        errors,
+                    }),
+                ));
+            }
+            inserted += 1;
+        }
+    } else {
+        for (index, req) in requests.into_iter().enumerate() {
+            match process_single_ingest(&db, req, &thresholds).await {
+                Ok(()) => inserted += 1,
+                Err(message) => errors.push(BatchIngestError { index, message }),
+            }
+        }
+    }
+
+    let result = BatchIngestResult { inserted, errors };
+    if let Some(key) = &key {
+        idempotency::store(&txn, SCOPE, key, &result)
+            .await
+            .map_err(db_error_response)?;
+    }
+    txn.commit().await.map_err(db_error_response)?;
+    Ok((axum::http::StatusCode::OK, axum::Json(result)))
+}
+
+/// Request body for the analyser file import endpoint.
+#[derive(Deserialize, ToSchema)]
+pub struct LicorImportRequest {
+    /// LI-7810/LI-7820 `.data` file as a base64 data URL (or plain base64)
+    pub data_base64: String,
+    /// Optional `SoilFluxPro` observation summary defining the closures. Without it,
+    /// closures are split at the `REMARK` entries of the `.data` file.
+    pub summary_base64: Option<String>,
+    /// Only match chambers (`chamber_id_external`) of this area
+    pub area_id: Option<uuid::Uuid>,
+    pub setting: Option<String>,
+    /// Upper limit of a closure length in seconds. Closures without an end
+    /// (split at `REMARK` entries) default to 300 s.
+    pub max_closure_s: Option<f64>,
+    /// Fit window applied to every closure, in seconds since its start
+    pub fit_start_s: Option<f64>,
+    pub fit_end_s: Option<f64>,
+    /// Collar height above the soil surface per chamber (`chamber_id_external`)
+    #[serde(default)]
+    pub collar_offsets_cm: HashMap<String, f64>,
+    /// Offset of the `SoilFluxPro` summary times from UTC in minutes (e.g. 60 for CET)
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+}
+
+/// Chamber profile with the given `chamber_id_external`, optionally within an area.
+async fn find_chamber_profile(
+    db: &DatabaseConnection,
+    label: &str,
+    area_id: Option<uuid::Uuid>,
+) -> Result<ProfileDB::Model, String> {
+    let mut query =
+        ProfileDB::Entity::find().filter(ProfileDB::Column::ChamberIdExternal.eq(label));
+    if let Some(area_id) = area_id {
+        query = query.filter(ProfileDB::Column::AreaId.eq(area_id));
+    }
+    let mut profiles = query
+        .all(db)
+        .await
+        .map_err(|e| format!("Database error: {e}"))?;
+    match profiles.len() {
+        0 => Err(format!("No sensor profile with chamber_id_external '{label}'")),
+        1 => Ok(profiles.remove(0)),
+        _ => Err(format!(
+            "Several sensor profiles use chamber_id_external '{label}'; select an area_id"
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/import",
+    request_body = LicorImportRequest,
+    responses(
+        (status = 200, description = "Import results per closure.", body = BatchIngestResult),
+        (status = 422, description = "Invalid file")
+    ),
+    summary = "Import a LI-COR analyser file and compute fluxes server-side",
+    description = "Parses a LI-7810/LI-7820 `.data` export, splits it into chamber closures using the `REMARK` entries or a SoilFluxPro summary, maps each closure to a sensor profile by `chamber_id_external` and computes its fluxes like the ingest endpoint. Closures are numbered per chamber as replicates; errors are recorded per closure without aborting the import.",
+    operation_id = "import_flux_data",
+)]
+pub async fn import_flux_data(
+    axum::extract::State(db): axum::extract::State<DatabaseConnection>,
+    axum::Json(req): axum::Json<LicorImportRequest>,
+) -> Result<axum::Json<BatchIngestResult>, (axum::http::StatusCode, axum::Json<String>)> {
+    let unprocessable = |e: String| (axum::http::StatusCode::UNPROCESSABLE_ENTITY, axum::Json(e));
+
+    let rows = parse_licor_data(&decode_base64_text(&req.data_base64).map_err(unprocessable)?)
+        .map_err(unprocessable)?;
+    let markers = match &req.summary_base64 {
+        Some(summary) => parse_soilfluxpro_summary(
+            &decode_base64_text(summary).map_err(unprocessable)?,
+            req.utc_offset_minutes,
+        )
+        .map_err(unprocessable)?,
+        None => remark_markers(&rows),
+    };
+    if markers.is_empty() {
+        return Err(unprocessable(
+            "No closures found; add remarks in the analyser or provide a SoilFluxPro summary"
+                .to_string(),
+        ));
+    }
+
+    let thresholds = load_thresholds(&db).await.map_err(|e| {
+        (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            axum::Json(format!("Database error: {e}")),
+        )
+    })?;
+    let mut inserted = 0usize;
+    let mut errors = Vec::new();
+    let mut replicates: HashMap<String, usize> = HashMap::new();
+
+    for (index, closure) in split_closures(&rows, markers, req.max_closure_s)
+        .into_iter()
+        .enumerate()
+    {
+        let replicate = replicates.entry(closure.label.clone()).or_default();
+        *replicate += 1;
+        let replicate = replicate.to_string();
+
+        let result = async {
+            if closure.readings.len() < MIN_FIT_READINGS {
+                return Err(format!(
+                    "Closure '{}' at {} has fewer than {MIN_FIT_READINGS} readings",
+                    closure.label, closure.start
+                ));
+            }
+            let profile = find_chamber_profile(&db, &closure.label, req.area_id).await?;
+            let record = build_flux_record(
+                &profile,
+                IngestFluxRequest {
+                    sensorprofile_id: profile.id,
+                    measured_on: closure.start,
+                    replicate,
+                    setting: req.setting.clone(),
+                    raw_readings: closure.readings,
+                    fit_start_s: req.fit_start_s,
+                    fit_end_s: req.fit_end_s,
+                    collar_offset_cm: req.collar_offsets_cm.get(&closure.label).copied(),
+                    volume_ml: None,
+                },
+                closure.has_ch4,
+                &thresholds,
+            )?;
+            upsert_flux_record(&db, record)
+                .await
+                .map_err(|e| format!("Failed to insert flux data: {e}"))
+        }
+        .await;
+
+        match result {
+            Ok(_) => inserted += 1,
             Err(message) => errors.push(BatchIngestError { index, message }),
         }
     }
 
-    axum::Json(BatchIngestResult { inserted, errors })
+    Ok(axum::Json(BatchIngestResult { inserted, errors }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/recompute",
+    responses(
+        (status = 200, description = "Flux record refitted.", body = FluxData),
+        (status = 404, description = "Flux record or sensor profile not found"),
+        (status = 422, description = "No raw readings or too few readings in the fit window"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("id" = Uuid, description = "Flux data ID")
+    ),
+    summary = "Recompute the fluxes of a record",
+    description = "Refits the stored `raw_readings` within the record's `fit_start_s`/`fit_end_s` window, using the record's `volume_ml` or `collar_offset_cm` and the current `volume_ml` and `area_cm2` of its sensor profile.",
+    operation_id = "recompute_flux_data",
+)]
+pub async fn recompute_flux_data(
+    axum::extract::State(db): axum::extract::State<DatabaseConnection>,
+    axum::extract::Path(id): axum::extract::Path<uuid::Uuid>,
+) -> Result<axum::Json<FluxData>, (axum::http::StatusCode, axum::Json<String>)> {
+    FluxData::recompute(&db, id)
+        .await
+        .map(axum::Json)
+        .map_err(db_error_response)
+}
+
+/// Selection of flux records to recompute. All given filters must match.
+#[derive(Deserialize, ToSchema)]
+#[allow(clippy::option_option)]
+pub struct FluxRecomputeRequest {
+    pub sensorprofile_id: Option<uuid::Uuid>,
+    pub area_id: Option<uuid::Uuid>,
+    pub setting: Option<String>,
+    /// Only records measured on or after this time
+    pub start: Option<DateTime<Utc>>,
+    /// Only records measured on or before this time
+    pub end: Option<DateTime<Utc>>,
+    /// New fit window bounds stored on every selected record before refitting;
+    /// records keep their own value of a bound that is omitted and `null` clears
+    /// it. A record whose refit fails keeps its old window.
+    #[serde(default, with = "crudcrate::serde_with::rust::double_option")]
+    #[schema(value_type = Option<f64>)]
+    pub fit_start_s: Option<Option<f64>>,
+    #[serde(default, with = "crudcrate::serde_with::rust::double_option")]
+    #[schema(value_type = Option<f64>)]
+    pub fit_end_s: Option<Option<f64>>,
+}
+
+/// A record that could not be recomputed.
+#[derive(Serialize, ToSchema)]
+pub struct FluxRecomputeError {
+    pub id: uuid::Uuid,
+    pub message: String,
+}
+
+/// Result of a bulk recompute.
+#[derive(Serialize, ToSchema)]
+pub struct FluxRecomputeResult {
+    pub updated: usize,
+    pub errors: Vec<FluxRecomputeError>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/recompute",
+    request_body = FluxRecomputeRequest,
+    responses(
+        (status = 200, description = "Bulk recompute results.", body = FluxRecomputeResult),
+        (status = 422, description = "No filter given"),
+        (status = 500, description = "Internal server error")
+    ),
+    summary = "Recompute the fluxes of many records",
+    description = "Refits every flux record matching the filter from its stored raw readings, optionally setting a new fit window first. Use it after correcting the chamber volume or collar area of a profile. Errors are recorded per record without aborting.",
+    operation_id = "recompute_flux_data_bulk",
+)]
+pub async fn recompute_flux_data_bulk(
+    axum::extract::State(db): axum::extract::State<DatabaseConnection>,
+    axum::Json(req): axum::Json<FluxRecomputeRequest>,
+) -> Result<axum::Json<FluxRecomputeResult>, (axum::http::StatusCode, axum::Json<String>)> {
+    let internal = |e: DbErr| {
+        (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            axum::Json(format!("Database error: {e}")),
+        )
+    };
+
+    if req.sensorprofile_id.is_none()
+        && req.area_id.is_none()
+        && req.setting.is_none()
+        && req.start.is_none()
+        && req.end.is_none()
+    {
+        return Err((
+            axum::http::StatusCode::UNPROCESSABLE_ENTITY,
+            axum::Json("At least one filter is required".to_string()),
+        ));
+    }
+
+    let mut condition = Condition::all();
+    if let Some(sensorprofile_id) = req.sensorprofile_id {
+        condition = condition.add(super::db::Column::SensorprofileId.eq(sensorprofile_id));
+    }
+    if let Some(area_id) = req.area_id {
+        let profile_ids: Vec<uuid::Uuid> = ProfileDB::Entity::find()
+            .filter(ProfileDB::Column::AreaId.eq(area_id))
+            .all(&db)
+            .await
+            .map_err(internal)?
+            .into_iter()
+            .map(|p| p.id)
+            .collect();
+        condition = condition.add(super::db::Column::SensorprofileId.is_in(profile_ids));
+    }
+    if let Some(setting) = &req.setting {
+        condition = condition.add(super::db::Column::Setting.eq(setting.clone()));
+    }
+    if let Some(start) = req.start {
+        condition = condition.add(super::db::Column::MeasuredOn.gte(start));
+    }
+    if let Some(end) = req.end {
+        condition = condition.add(super::db::Column::MeasuredOn.lte(end));
+    }
+
+    let records = super::db::Entity::find()
+        .filter(condition)
+        .all(&db)
+        .await
+        .map_err(internal)?;
+
+    let mut updated = 0usize;
+    let mut errors = Vec::new();
+    for record in records {
+        let id = record.id;
+        let result = async {
+            let txn = db.begin().await?;
+            if req.fit_start_s.is_some() || req.fit_end_s.is_some() {
+                let mut active_model: super::db::ActiveModel = record.into();
+                if let Some(fit_start_s) = req.fit_start_s {
+                    active_model.fit_start_s = ActiveValue::Set(fit_start_s);
+                }
+                if let Some(fit_end_s) = req.fit_end_s {
+                    active_model.fit_end_s = ActiveValue::Set(fit_end_s);
+                }
+                active_model.update(&txn).await?;
+            }
+            let flux_data = FluxData::recompute(&txn, id).await?;
+            txn.commit().await?;
+            Ok::<_, DbErr>(flux_data)
+        }
+        .await;
+
+        match result {
+            Ok(_) => updated += 1,
+            Err(DbErr::Custom(message) | DbErr::RecordNotFound(message)) => {
+                errors.push(FluxRecomputeError { id, message });
+            }
+            Err(e) => errors.push(FluxRecomputeError {
+                id,
+                message: format!("Database error: {e}"),
+            }),
+        }
+    }
+
+    Ok(axum::Json(FluxRecomputeResult { updated, errors }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/summary",
+    responses(
+        (status = 200, description = "Campaign summaries per series.", body = FluxSummary),
+        (status = 404, description = "Weather station not found"),
+        (status = 422, description = "Invalid Q10 or no weather station for temperature interpolation"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("area_id" = Option<Uuid>, Query, description = "Only chambers in this area"),
+        ("sensorprofile_id" = Option<Uuid>, Query, description = "Only this chamber profile"),
+        ("setting" = Option<String>, Query, description = "Only records of this setting"),
+        ("start" = Option<String>, Query, description = "Start of date range (ISO 8601)"),
+        ("end" = Option<String>, Query, description = "End of date range (ISO 8601)"),
+        ("group_by" = Option<FluxGrouping>, Query, description = "One series per chamber profile (`profile`, default) or per area (`area`), each split by setting"),
+        ("unit" = Option<FluxUnit>, Query, description = "`umol_m2_s` (default, CH4 in nmol), `mg_c_m2_h` or `co2eq_mg_m2_h`"),
+        ("interpolation" = Option<FluxInterpolation>, Query, description = "`linear` (default) or `temperature` between campaigns"),
+        ("gwp_ch4" = Option<f64>, Query, description = "Global warming potential of CH4 for CO2-equivalents (default 27)"),
+        ("q10" = Option<f64>, Query, description = "Q10 of the temperature interpolation (default 2)")
+    ),
+    summary = "Summarise flux campaigns",
+    description = "Groups chamber fluxes into series by profile or area and setting, and each series into campaigns by measurement day. Returns the mean, standard deviation and count per gas and campaign, leaving out QC-flagged fluxes, and the cumulative emission since the first campaign. Cumulative emissions interpolate linearly between campaign means, or with `interpolation=temperature` scale a linearly interpolated basal rate by the hourly air temperature of the nearest weather station using `q10`. Water vapour has no carbon or CO2-equivalent value.",
+    operation_id = "summarise_flux_data",
+)]
+pub async fn summarise_flux_data(
+    axum::extract::State(db): axum::extract::State<DatabaseConnection>,
+    axum::extract::Query(query): axum::extract::Query<FluxSummaryQuery>,
+) -> Result<axum::Json<FluxSummary>, (axum::http::StatusCode, axum::Json<String>)> {
+    flux_summary(&db, &query)
+        .await
+        .map(axum::Json)
+        .map_err(db_error_response)
+}
+
+#[utoipa::path(
+    get,
+    path = "/temperature_sensitivity",
+    responses(
+        (status = 200, description = "Temperature sensitivity per series.", body = TemperatureSensitivity),
+        (status = 422, description = "Invalid query"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("depth_cm" = i32, Query, description = "Installation depth of the TMS temperature sensor"),
+        ("gas" = Option<FluxGasEnum>, Query, description = "Gas whose fluxes are fitted (default `co2`)"),
+        ("area_id" = Option<Uuid>, Query, description = "Only chambers in this area"),
+        ("sensorprofile_id" = Option<Uuid>, Query, description = "Only this chamber profile"),
+        ("setting" = Option<String>, Query, description = "Only records of this setting"),
+        ("start" = Option<String>, Query, description = "Start of date range (ISO 8601)"),
+        ("end" = Option<String>, Query, description = "End of date range (ISO 8601)"),
+        ("group_by" = Option<FluxGrouping>, Query, description = "Fit per chamber profile (`profile`, default) or per area (`area`), each split by setting"),
+        ("max_offset_minutes" = Option<i64>, Query, description = "Largest time between a flux and its soil temperature, 0 to 10080 (default 60)")
+    ),
+    summary = "Fit flux temperature sensitivity",
+    description = "Pairs each chamber flux with the soil temperature at `depth_cm` of the nearest TMS profile in the same area, recorded closest to the measurement. Fits `F = R10·Q10^((T − 10)/10)` and the Arrhenius model `F = A·exp(−Ea/(R·T))` by log-linear regression on positive, non-flagged fluxes, returning the parameters with standard errors and the pairs used.",
+    operation_id = "flux_temperature_sensitivity",
+)]
+pub async fn flux_temperature_sensitivity(
+    axum::extract::State(db): axum::extract::State<DatabaseConnection>,
+    axum::extract::Query(query): axum::extract::Query<TemperatureSensitivityQuery>,
+) -> Result<axum::Json<TemperatureSensitivity>, (axum::http::StatusCode, axum::Json<String>)> {
+    temperature_sensitivity(&db, &query)
+        .await
+        .map(axum::Json)
+        .map_err(db_error_response)
+}
+
+#[utoipa::path(
+    get,
+    path = "/export",
+    responses(
+        (status = 200, description = "Data and metadata files.", body = FluxExport),
+        (status = 404, description = "No flux records match the filters"),
+        (status = 422, description = "Invalid site code"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("format" = FluxExportFormat, Query, description = "`icos` (one row per closure) or `fluxnet` (half-hourly table)"),
+        ("site_id" = String, Query, description = "Network site code used in the file names"),
+        ("area_id" = Option<Uuid>, Query, description = "Only chambers in this area"),
+        ("sensorprofile_id" = Option<Uuid>, Query, description = "Only this chamber profile"),
+        ("setting" = Option<String>, Query, description = "Only records of this setting"),
+        ("start" = Option<String>, Query, description = "Start of date range (ISO 8601)"),
+        ("end" = Option<String>, Query, description = "End of date range (ISO 8601)"),
+        ("ts_depth_cm" = Option<i32>, Query, description = "TMS temperature depth (default: the moisture sensor depth)")
+    ),
+    summary = "Export fluxes for network submission",
+    description = "Writes chamber fluxes with the soil temperature and moisture of the nearest TMS profile, recorded within 30 minutes of each closure, as CSV in the ICOS ETC chamber or FLUXNET half-hourly layout. Timestamps are UTC `YYYYMMDDHHMM`, missing and QC-flagged values are `-9999`, and FLUXNET columns carry the `_H_V_R` qualifier with one horizontal index per chamber. Chambers are identified by `chamber_id_external`, otherwise by profile name. The response holds the data file, a variable file with units and a metadata file describing each chamber.",
+    operation_id = "export_flux_data",
+)]
+pub async fn export_flux_data(
+    axum::extract::State(db): axum::extract::State<DatabaseConnection>,
+    axum::extract::Query(query): axum::extract::Query<FluxExportQuery>,
+) -> Result<axum::Json<FluxExport>, (axum::http::StatusCode, axum::Json<String>)> {
+    export_fluxes(&db, &query)
+        .await
+        .map(axum::Json)
+        .map_err(db_error_response)
 }
 
 pub fn router(
@@ -299,6 +768,12 @@ where
         .routes(routes!(delete_many_handler))
         .routes(routes!(ingest_flux_data))
         .routes(routes!(ingest_flux_data_batch))
+        .routes(routes!(import_flux_data))
+        .routes(routes!(recompute_flux_data))
+        .routes(routes!(recompute_flux_data_bulk))
+        .routes(routes!(summarise_flux_data))
+        .routes(routes!(flux_temperature_sensitivity))
+        .routes(routes!(export_flux_data))
         .with_state(db.clone());
 
     if let Some(instance) = keycloak_auth_instance {
@@ -0,0 +1,338 @@
+use super::db::Model;
+use crate::routes::private::sensors::profile::db::ProfileTypeEnum;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use crudcrate::{CRUDResource, ToCreateModel, ToUpdateModel, traits::MergeIntoActiveModel};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection,
+    DbErr, EntityTrait, Order, QueryOrder, QuerySelect, Statement, entity::prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Hydrostatic pressure of one metre of fresh water (ρ = 1000 kg/m³, g = 9.80665 m/s²)
+pub const KPA_PER_M_WATER: f64 = 9.806_65;
+
+#[derive(ToSchema, Serialize, Deserialize, ToCreateModel, ToUpdateModel, Debug, Clone)]
+#[active_model = "super::db::ActiveModel"]
+pub struct GroundwaterData {
+    #[crudcrate(update_model = false, create_model = false, on_create = Uuid::new_v4())]
+    pub id: Uuid,
+    pub sensorprofile_id: Uuid,
+    pub measured_on: DateTime<Utc>,
+    // Absolute pressure recorded by the logger
+    pub pressure_kpa: f64,
+    pub temp_c: Option<f64>,
+    // Barometric compensation results (piezometers only, set by `compensate_profile`;
+    // values supplied on create are discarded)
+    #[crudcrate(update_model = false)]
+    pub baro_pressure_kpa: Option<f64>,
+    #[crudcrate(update_model = false)]
+    pub water_column_m: Option<f64>,
+    #[crudcrate(update_model = false)]
+    pub depth_below_surface_m: Option<f64>,
+    #[crudcrate(update_model = false)]
+    pub water_level_masl: Option<f64>,
+}
+
+impl From<Model> for GroundwaterData {
+    fn from(model: Model) -> Self {
+        Self {
+            id: model.id,
+            sensorprofile_id: model.sensorprofile_id,
+            measured_on: model.measured_on,
+            pressure_kpa: model.pressure_kpa,
+            temp_c: model.temp_c,
+            baro_pressure_kpa: model.baro_pressure_kpa,
+            water_column_m: model.water_column_m,
+            depth_below_surface_m: model.depth_below_surface_m,
+            water_level_masl: model.water_level_masl,
+        }
+    }
+}
+
+#[async_trait]
+impl CRUDResource for GroundwaterData {
+    type EntityType = super::db::Entity;
+    type ColumnType = super::db::Column;
+    type ActiveModelType = super::db::ActiveModel;
+    type CreateModel = GroundwaterDataCreate;
+    type UpdateModel = GroundwaterDataUpdate;
+
+    const ID_COLUMN: Self::ColumnType = super::db::Column::Id;
+    const RESOURCE_NAME_SINGULAR: &'static str = "groundwater data";
+    const RESOURCE_NAME_PLURAL: &'static str = "groundwater data records";
+    const RESOURCE_DESCRIPTION: &'static str = "Pressure readings of piezometer and barologger profiles. Piezometer readings are compensated against a barologger in the same area and converted to water column, depth below surface and water level elevation.";
+
+    async fn get_all(
+        db: &DatabaseConnection,
+        condition: Condition,
+        order_column: Self::ColumnType,
+        order_direction: Order,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<Self>, DbErr> {
+        let models = Self::EntityType::find()
+            .filter(condition)
+            .order_by(order_column, order_direction)
+            .offset(offset)
+            .limit(limit)
+            .all(db)
+            .await?;
+        Ok(models.into_iter().map(GroundwaterData::from).collect())
+    }
+
+    async fn get_one(db: &DatabaseConnection, id: Uuid) -> Result<Self, DbErr> {
+        let model = Self::EntityType::find()
+            .filter(Self::ColumnType::Id.eq(id))
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound(format!(
+                "{} not found",
+                Self::RESOURCE_NAME_SINGULAR
+            )))?;
+        Ok(GroundwaterData::from(model))
+    }
+
+    async fn create(
+        db: &DatabaseConnection,
+        create_model: Self::CreateModel,
+    ) -> Result<Self, DbErr> {
+        let sensorprofile_id = create_model.sensorprofile_id;
+        let mut active_model: Self::ActiveModelType = create_model.into();
+        active_model.baro_pressure_kpa = ActiveValue::Set(None);
+        active_model.water_column_m = ActiveValue::Set(None);
+        active_model.depth_below_surface_m = ActiveValue::Set(None);
+        active_model.water_level_masl = ActiveValue::Set(None);
+        let result = Self::EntityType::insert(active_model).exec(db).await?;
+
+        Self::recompensate_affected(db, sensorprofile_id).await?;
+        Self::get_one(db, result.last_insert_id).await
+    }
+
+    async fn update(
+        db: &DatabaseConnection,
+        id: Uuid,
+        update_model: Self::UpdateModel,
+    ) -> Result<Self, DbErr> {
+        let db_obj: super::db::ActiveModel = super::db::Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound(format!(
+                "{} not found",
+                Self::RESOURCE_NAME_SINGULAR
+            )))?
+            .into();
+        let updated_obj: super::db::ActiveModel = update_model.merge_into_activemodel(db_obj);
+        let response_obj = updated_obj.update(db).await?;
+
+        Self::recompensate_affected(db, response_obj.sensorprofile_id).await?;
+        Self::get_one(db, response_obj.id).await
+    }
+
+    fn sortable_columns() -> Vec<(&'static str, Self::ColumnType)> {
+        vec![
+            ("id", Self::ColumnType::Id),
+            ("measured_on", Self::ColumnType::MeasuredOn),
+            ("sensorprofile_id", Self::ColumnType::SensorprofileId),
+        ]
+    }
+
+    fn filterable_columns() -> Vec<(&'static str, Self::ColumnType)> {
+        vec![("sensorprofile_id", Self::ColumnType::SensorprofileId)]
+    }
+}
+
+impl GroundwaterData {
+    /// Reference barologger of a piezometer: the explicitly configured one, otherwise
+    /// the nearest barologger profile in the same area.
+    pub async fn resolve_baro_profile(
+        db: &DatabaseConnection,
+        profile: &crate::routes::private::sensors::profile::db::Model,
+    ) -> Result<Option<Uuid>, DbErr> {
+        if profile.baro_profile_id.is_some() {
+            return Ok(profile.baro_profile_id);
+        }
+
+        let sql = r"
+            SELECT b.id
+            FROM sensorprofile AS b
+            JOIN sensorprofile AS p ON p.id = $1
+            WHERE b.area_id = p.area_id
+              AND b.profile_type = 'barologger'
+            ORDER BY st_distance(b.geom, p.geom) NULLS LAST, b.name
+            LIMIT 1
+        ";
+        let stmt =
+            Statement::from_sql_and_values(db.get_database_backend(), sql, vec![profile.id.into()]);
+        match db.query_one(stmt).await? {
+            Some(row) => Ok(Some(row.try_get("", "id")?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store parsed logger readings of a profile, skipping timestamps that already
+    /// exist. Returns the number of inserted readings.
+    pub async fn insert_readings(
+        db: &DatabaseConnection,
+        profile_id: Uuid,
+        readings: Vec<super::parsers::LoggerReading>,
+    ) -> Result<u64, DbErr> {
+        const CHUNK_SIZE: usize = 1000;
+
+        let active_models: Vec<super::db::ActiveModel> = readings
+            .into_iter()
+            .map(|r| super::db::ActiveModel {
+                id: ActiveValue::Set(Uuid::new_v4()),
+                sensorprofile_id: ActiveValue::Set(profile_id),
+                measured_on: ActiveValue::Set(r.measured_on),
+                pressure_kpa: ActiveValue::Set(r.pressure_kpa),
+                temp_c: ActiveValue::Set(r.temp_c),
+                baro_pressure_kpa: ActiveValue::Set(None),
+                water_column_m: ActiveValue::Set(None),
+                depth_below_surface_m: ActiveValue::Set(None),
+                water_level_masl: ActiveValue::Set(None),
+            })
+            .collect();
+
+        let mut inserted = 0u64;
+        for chunk in active_models.chunks(CHUNK_SIZE) {
+            inserted += super::db::Entity::insert_many(chunk.to_vec())
+                .on_conflict(
+                    sea_orm::sea_query::OnConflict::columns([
+                        super::db::Column::SensorprofileId,
+                        super::db::Column::MeasuredOn,
+                    ])
+                    .do_nothing()
+                    .to_owned(),
+                )
+                .exec_without_returning(db)
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Recompute the compensated levels of every reading of a piezometer profile.
+    ///
+    /// The barometric pressure is linearly interpolated between the two closest
+    /// barologger readings; readings without barologger data within three hours
+    /// on both sides are left uncompensated. Returns the number of updated readings.
+    pub async fn compensate_profile(
+        db: &DatabaseConnection,
+        profile_id: Uuid,
+    ) -> Result<u64, DbErr> {
+        let profile = crate::routes::private::sensors::profile::db::Entity::find_by_id(profile_id)
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Sensor profile not found".into()))?;
+
+        if profile.profile_type != ProfileTypeEnum::Groundwater {
+            return Err(DbErr::Custom(
+                "Only groundwater profiles can be compensated".into(),
+            ));
+        }
+
+        let baro_profile_id =
+            Self::resolve_baro_profile(db, &profile)
+                .await?
+                .ok_or(DbErr::Custom(
+                    "No barologger profile found in the area of this groundwater profile".into(),
+                ))?;
+
+        let sql = r"
+            WITH comp AS (
+                SELECT
+                    g.id,
+                    CASE
+                        WHEN prev.measured_on = g.measured_on THEN prev.pressure_kpa
+                        WHEN prev.measured_on IS NOT NULL
+                         AND next.measured_on IS NOT NULL
+                         AND g.measured_on - prev.measured_on <= INTERVAL '3 hours'
+                         AND next.measured_on - g.measured_on <= INTERVAL '3 hours'
+                        THEN prev.pressure_kpa
+                            + (next.pressure_kpa - prev.pressure_kpa)
+                            * EXTRACT(EPOCH FROM g.measured_on - prev.measured_on)
+                            / EXTRACT(EPOCH FROM next.measured_on - prev.measured_on)
+                    END AS baro
+                FROM groundwater_data AS g
+                LEFT JOIN LATERAL (
+                    SELECT measured_on, pressure_kpa
+                    FROM groundwater_data
+                    WHERE sensorprofile_id = $2 AND measured_on <= g.measured_on
+                    ORDER BY measured_on DESC
+                    LIMIT 1
+                ) AS prev ON true
+                LEFT JOIN LATERAL (
+                    SELECT measured_on, pressure_kpa
+                    FROM groundwater_data
+                    WHERE sensorprofile_id = $2 AND measured_on > g.measured_on
+                    ORDER BY measured_on ASC
+                    LIMIT 1
+                ) AS next ON true
+                WHERE g.sensorprofile_id = $1
+            )
+            UPDATE groundwater_data AS g
+            SET baro_pressure_kpa = c.baro,
+                water_column_m = (g.pressure_kpa - c.baro) / $3,
+                depth_below_surface_m = $4::float8 - (g.pressure_kpa - c.baro) / $3,
+                water_level_masl = $5::float8 - ($4::float8 - (g.pressure_kpa - c.baro) / $3)
+            FROM comp AS c
+            WHERE c.id = g.id
+        ";
+        let stmt = Statement::from_sql_and_values(
+            db.get_database_backend(),
+            sql,
+            vec![
+                profile_id.into(),
+                baro_profile_id.into(),
+                KPA_PER_M_WATER.into(),
+                profile.logger_depth_m.into(),
+                profile.coord_z.into(),
+            ],
+        );
+        let result = db.execute(stmt).await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Recompensate after readings of `profile_id` changed: the profile itself if it
+    /// is a piezometer, or every piezometer referencing it if it is a barologger.
+    pub async fn recompensate_affected(
+        db: &DatabaseConnection,
+        profile_id: Uuid,
+    ) -> Result<(), DbErr> {
+        let profile = crate::routes::private::sensors::profile::db::Entity::find_by_id(profile_id)
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Sensor profile not found".into()))?;
+
+        match profile.profile_type {
+            // Missing barologger data is not an error; levels stay empty until it arrives
+            ProfileTypeEnum::Groundwater
+                if Self::resolve_baro_profile(db, &profile).await?.is_some() =>
+            {
+                Self::compensate_profile(db, profile_id).await?;
+            }
+            ProfileTypeEnum::Barologger => {
+                let piezometers = crate::routes::private::sensors::profile::db::Entity::find()
+                    .filter(
+                        crate::routes::private::sensors::profile::db::Column::AreaId
+                            .eq(profile.area_id),
+                    )
+                    .filter(
+                        crate::routes::private::sensors::profile::db::Column::ProfileType
+                            .eq(ProfileTypeEnum::Groundwater),
+                    )
+                    .all(db)
+                    .await?;
+                for piezometer in piezometers {
+                    if Self::resolve_baro_profile(db, &piezometer).await? == Some(profile_id) {
+                        Self::compensate_profile(db, piezometer.id).await?;
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
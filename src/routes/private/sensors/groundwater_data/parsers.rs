@@ -0,0 +1,244 @@
+use super::models::KPA_PER_M_WATER;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+/// Supported water level logger exports
+#[derive(ToSchema, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GroundwaterFileFormat {
+    /// Solinst Levelogger/Barologger `.xle` (XML)
+    Xle,
+    /// van Essen Diver-Office CSV export
+    DiverCsv,
+}
+
+impl GroundwaterFileFormat {
+    /// Guess the format from the file content
+    pub fn detect(text: &str) -> Self {
+        if text.contains("<Body_xle>") || text.trim_start().starts_with("<?xml") {
+            Self::Xle
+        } else {
+            Self::DiverCsv
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoggerReading {
+    pub measured_on: DateTime<Utc>,
+    pub pressure_kpa: f64,
+    pub temp_c: Option<f64>,
+}
+
+/// Convert a pressure (or equivalent water column) to kPa
+pub fn pressure_to_kpa(value: f64, unit: &str) -> Result<f64, String> {
+    let factor = match unit.trim().to_lowercase().replace(' ', "").as_str() {
+        "kpa" => 1.0,
+        "hpa" | "mbar" => 0.1,
+        "bar" => 100.0,
+        "pa" => 0.001,
+        "psi" => 6.894_757,
+        "m" | "mh2o" => KPA_PER_M_WATER,
+        "cm" | "cmh2o" => KPA_PER_M_WATER / 100.0,
+        "mm" | "mmh2o" => KPA_PER_M_WATER / 1000.0,
+        "ft" | "fth2o" => KPA_PER_M_WATER * 0.3048,
+        other => return Err(format!("Unsupported pressure unit '{other}'")),
+    };
+    Ok(value * factor)
+}
+
+fn to_utc(naive: NaiveDateTime, utc_offset_minutes: i32) -> DateTime<Utc> {
+    naive.and_utc() - Duration::minutes(i64::from(utc_offset_minutes))
+}
+
+/// Text content of the first `<tag>...</tag>` in `xml`
+fn tag_text<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+    let start = xml.find(&open)? + open.len();
+    let end = xml[start..].find(&close)? + start;
+    Some(xml[start..end].trim())
+}
+
+/// Parse a Solinst `.xle` file. The level channel is read as absolute pressure
+/// (Leveloggers report it as metres of water including the atmosphere).
+pub fn parse_xle(text: &str, utc_offset_minutes: i32) -> Result<Vec<LoggerReading>, String> {
+    let mut pressure_channel: Option<(String, String)> = None;
+    let mut temperature_channel: Option<String> = None;
+
+    for n in 1..=4 {
+        let Some(header) = tag_text(text, &format!("Ch{n}_data_header")) else {
+            continue;
+        };
+        let identification = tag_text(header, "Identification")
+            .unwrap_or_default()
+            .to_uppercase();
+        let unit = tag_text(header, "Unit").unwrap_or_default().to_string();
+        if identification.contains("TEMP") {
+            temperature_channel.get_or_insert(format!("ch{n}"));
+        } else if identification.contains("LEVEL") || identification.contains("PRESSURE") {
+            pressure_channel.get_or_insert((format!("ch{n}"), unit));
+        }
+    }
+
+    let (pressure_tag, pressure_unit) =
+        pressure_channel.ok_or("No level or pressure channel found in .xle file")?;
+
+    let mut readings = Vec::new();
+    for log in text.split("<Log").skip(1) {
+        let (Some(date), Some(time)) = (tag_text(log, "Date"), tag_text(log, "Time")) else {
+            continue;
+        };
+        let naive = NaiveDateTime::parse_from_str(&format!("{date} {time}"), "%Y/%m/%d %H:%M:%S")
+            .map_err(|e| format!("Invalid timestamp '{date} {time}': {e}"))?;
+        let pressure = tag_text(log, &pressure_tag)
+            .ok_or_else(|| format!("Missing {pressure_tag} value at {date} {time}"))?
+            .parse::<f64>()
+            .map_err(|e| format!("Invalid {pressure_tag} value at {date} {time}: {e}"))?;
+        let temp_c = temperature_channel
+            .as_deref()
+            .and_then(|tag| tag_text(log, tag))
+            .and_then(|value| value.parse::<f64>().ok());
+
+        readings.push(LoggerReading {
+            measured_on: to_utc(naive, utc_offset_minutes),
+            pressure_kpa: pressure_to_kpa(pressure, &pressure_unit)?,
+            temp_c,
+        });
+    }
+
+    Ok(readings)
+}
+
+/// Unit written in brackets after a column name, e.g. `Pressure[cmH2O]`
+fn header_unit(column: &str) -> Option<String> {
+    let start = column.find(['[', '('])? + 1;
+    let end = column[start..].find([']', ')'])? + start;
+    Some(column[start..end].to_string())
+}
+
+const DIVER_TIMESTAMP_FORMATS: [&str; 5] = [
+    "%Y/%m/%d %H:%M:%S",
+    "%Y-%m-%d %H:%M:%S",
+    "%d.%m.%Y %H:%M:%S",
+    "%d/%m/%Y %H:%M:%S",
+    "%d/%m/%Y %H:%M",
+];
+
+/// Parse a van Essen Diver-Office CSV export. Metadata lines before the
+/// `Date/time` header are skipped; pressure defaults to cmH2O if no unit is given.
+pub fn parse_diver_csv(text: &str, utc_offset_minutes: i32) -> Result<Vec<LoggerReading>, String> {
+    let mut lines = text.lines();
+    let header = lines
+        .by_ref()
+        .find(|line| line.trim_start().to_lowercase().starts_with("date"))
+        .ok_or("No 'Date/time' header found in Diver CSV file")?;
+
+    // European exports use ';' with decimal commas
+    let delimiter = if header.contains(';') { ';' } else { ',' };
+    let columns: Vec<&str> = header.split(delimiter).collect();
+    let pressure_col = columns
+        .iter()
+        .position(|c| c.to_lowercase().contains("pressure"))
+        .ok_or("No pressure column found in Diver CSV file")?;
+    let pressure_unit = header_unit(columns[pressure_col]).unwrap_or_else(|| "cmH2O".to_string());
+    let temperature_col = columns
+        .iter()
+        .position(|c| c.to_lowercase().contains("temp"));
+
+    let parse_number = |value: &str| -> Option<f64> {
+        let value = value.trim();
+        if delimiter == ';' {
+            value.replace(',', ".").parse::<f64>().ok()
+        } else {
+            value.parse::<f64>().ok()
+        }
+    };
+
+    let mut readings = Vec::new();
+    for line in lines {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.to_uppercase().starts_with("END OF") {
+            break;
+        }
+        let fields: Vec<&str> = line.split(delimiter).collect();
+        let timestamp = fields[0].trim();
+        let naive = DIVER_TIMESTAMP_FORMATS
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(timestamp, format).ok())
+            .ok_or_else(|| format!("Invalid timestamp '{timestamp}'"))?;
+        let Some(pressure) = fields.get(pressure_col).and_then(|v| parse_number(v)) else {
+            continue;
+        };
+
+        readings.push(LoggerReading {
+            measured_on: to_utc(naive, utc_offset_minutes),
+            pressure_kpa: pressure_to_kpa(pressure, &pressure_unit)?,
+            temp_c: temperature_col
+                .and_then(|col| fields.get(col))
+                .and_then(|v| parse_number(v)),
+        });
+    }
+
+    Ok(readings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_solinst_xle() {
+        let text = r#"<?xml version="1.0" encoding="ISO-8859-1"?>
+<Body_xle>
+<Ch1_data_header><Identification>LEVEL</Identification><Unit>m</Unit></Ch1_data_header>
+<Ch2_data_header><Identification>TEMPERATURE</Identification><Unit>°C</Unit></Ch2_data_header>
+<Data>
+<Log id="1"><Date>2024/06/01</Date><Time>02:00:00</Time><ms>0</ms><ch1>10.5</ch1><ch2>9.8</ch2></Log>
+<Log id="2"><Date>2024/06/01</Date><Time>02:15:00</Time><ms>0</ms><ch1>10.6</ch1><ch2>9.7</ch2></Log>
+</Data>
+</Body_xle>"#;
+        assert_eq!(
+            GroundwaterFileFormat::detect(text),
+            GroundwaterFileFormat::Xle
+        );
+
+        let readings = parse_xle(text, 120).unwrap();
+        assert_eq!(readings.len(), 2);
+        assert_eq!(
+            readings[0].measured_on,
+            DateTime::parse_from_rfc3339("2024-06-01T00:00:00Z").unwrap()
+        );
+        assert!((readings[0].pressure_kpa - 10.5 * KPA_PER_M_WATER).abs() < 1e-9);
+        assert_eq!(readings[1].temp_c, Some(9.7));
+    }
+
+    #[test]
+    fn parses_diver_csv_with_decimal_comma() {
+        let text = "Serial number:;AB123\n\
+                    Location:;P1\n\
+                    Date/time;Pressure[cmH2O];Temperature[°C]\n\
+                    2024/06/01 00:00:00;1034,5;12,3\n\
+                    2024/06/01 01:00:00;1035,0;12,1\n\
+                    END OF DATA FILE OF DATALOGGER FOR WINDOWS\n";
+        assert_eq!(
+            GroundwaterFileFormat::detect(text),
+            GroundwaterFileFormat::DiverCsv
+        );
+
+        let readings = parse_diver_csv(text, 0).unwrap();
+        assert_eq!(readings.len(), 2);
+        assert!((readings[0].pressure_kpa - 10.345 * KPA_PER_M_WATER).abs() < 1e-9);
+        assert_eq!(readings[0].temp_c, Some(12.3));
+    }
+
+    #[test]
+    fn rejects_unknown_pressure_unit() {
+        assert!(pressure_to_kpa(1.0, "inHg").is_err());
+        assert!((pressure_to_kpa(1013.25, "hPa").unwrap() - 101.325).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,185 @@
+use super::models::{GroundwaterData, GroundwaterDataCreate, GroundwaterDataUpdate};
+use super::parsers::{GroundwaterFileFormat, parse_diver_csv, parse_xle};
+use crate::common::auth::Role;
+use crate::common::errors::db_error_response;
+use crate::common::files::decode_base64_text;
+use crate::routes::private::sensors::profile::db::{self as ProfileDB, ProfileTypeEnum};
+use axum::response::IntoResponse;
+use axum_keycloak_auth::{
+    PassthroughMode, instance::KeycloakAuthInstance, layer::KeycloakAuthLayer,
+};
+use crudcrate::{CRUDResource, crud_handlers};
+use sea_orm::{DatabaseConnection, EntityTrait};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+crud_handlers!(
+    GroundwaterData,
+    GroundwaterDataUpdate,
+    GroundwaterDataCreate
+);
+
+/// Request body for the logger file import endpoint.
+#[derive(Deserialize, ToSchema)]
+pub struct GroundwaterImportRequest {
+    pub sensorprofile_id: uuid::Uuid,
+    /// File content as a base64 data URL (or plain base64)
+    pub data_base64: String,
+    /// Detected from the content when omitted
+    pub format: Option<GroundwaterFileFormat>,
+    /// Offset of the logger clock from UTC in minutes (e.g. 60 for CET)
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+}
+
+/// Result of a logger file import.
+#[derive(Serialize, ToSchema)]
+pub struct GroundwaterImportResult {
+    pub parsed: usize,
+    pub inserted: u64,
+    pub skipped_duplicates: u64,
+}
+
+#[utoipa::path(
+    post,
+    path = "/import",
+    request_body = GroundwaterImportRequest,
+    responses(
+        (status = 201, description = "Logger file imported.", body = GroundwaterImportResult),
+        (status = 404, description = "Sensor profile not found"),
+        (status = 422, description = "Invalid file or profile type"),
+        (status = 500, description = "Internal server error")
+    ),
+    summary = "Import a water level logger file",
+    description = "Imports a Solinst `.xle` or van Essen Diver CSV file into a groundwater or barologger profile. Readings already stored for the same timestamp are skipped. Affected piezometers are then compensated against their barologger.",
+    operation_id = "import_groundwater_data",
+)]
+pub async fn import_groundwater_data(
+    axum::extract::State(db): axum::extract::State<DatabaseConnection>,
+    axum::Json(req): axum::Json<GroundwaterImportRequest>,
+) -> impl IntoResponse {
+    let Some(profile) = ProfileDB::Entity::find_by_id(req.sensorprofile_id)
+        .one(&db)
+        .await
+        .map_err(db_error_response)?
+    else {
+        return Err((
+            axum::http::StatusCode::NOT_FOUND,
+            axum::Json(format!("Sensor profile {} not found", req.sensorprofile_id)),
+        ));
+    };
+
+    if !matches!(
+        profile.profile_type,
+        ProfileTypeEnum::Groundwater | ProfileTypeEnum::Barologger
+    ) {
+        return Err((
+            axum::http::StatusCode::UNPROCESSABLE_ENTITY,
+            axum::Json("Profile must be of type 'groundwater' or 'barologger'".to_string()),
+        ));
+    }
+
+    // .xle files are usually ISO-8859-1 encoded (e.g. the degree sign)
+    let text = decode_base64_text(&req.data_base64)
+        .map_err(|e| (axum::http::StatusCode::UNPROCESSABLE_ENTITY, axum::Json(e)))?;
+
+    let format = req
+        .format
+        .unwrap_or_else(|| GroundwaterFileFormat::detect(&text));
+    let readings = match format {
+        GroundwaterFileFormat::Xle => parse_xle(&text, req.utc_offset_minutes),
+        GroundwaterFileFormat::DiverCsv => parse_diver_csv(&text, req.utc_offset_minutes),
+    }
+    .map_err(|e| (axum::http::StatusCode::UNPROCESSABLE_ENTITY, axum::Json(e)))?;
+
+    if readings.is_empty() {
+        return Err((
+            axum::http::StatusCode::UNPROCESSABLE_ENTITY,
+            axum::Json("No readings found in file".to_string()),
+        ));
+    }
+
+    let parsed = readings.len();
+    let inserted = GroundwaterData::insert_readings(&db, profile.id, readings)
+        .await
+        .map_err(db_error_response)?;
+
+    GroundwaterData::recompensate_affected(&db, profile.id)
+        .await
+        .map_err(db_error_response)?;
+
+    Ok((
+        axum::http::StatusCode::CREATED,
+        axum::Json(GroundwaterImportResult {
+            parsed,
+            inserted,
+            skipped_duplicates: parsed as u64 - inserted,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/compensate/{sensorprofile_id}",
+    responses(
+        (status = 200, description = "Number of compensated readings.", body = u64),
+        (status = 404, description = "Sensor profile not found"),
+        (status = 422, description = "Not a groundwater profile or no barologger available"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("sensorprofile_id" = Uuid, description = "Groundwater sensor profile ID")
+    ),
+    summary = "Recompute barometric compensation",
+    description = "Recomputes water column, depth below surface and water level elevation of all readings of a groundwater profile, e.g. after changing its barologger, logger depth or `coord_z`.",
+    operation_id = "compensate_groundwater_profile",
+)]
+pub async fn compensate_groundwater_profile(
+    axum::extract::State(db): axum::extract::State<DatabaseConnection>,
+    axum::extract::Path(sensorprofile_id): axum::extract::Path<uuid::Uuid>,
+) -> Result<Json<u64>, (axum::http::StatusCode, axum::Json<String>)> {
+    GroundwaterData::compensate_profile(&db, sensorprofile_id)
+        .await
+        .map(Json)
+        .map_err(db_error_response)
+}
+
+pub fn router(
+    db: &DatabaseConnection,
+    keycloak_auth_instance: Option<Arc<KeycloakAuthInstance>>,
+) -> OpenApiRouter
+where
+    GroundwaterData: CRUDResource,
+{
+    let mut mutating_router = OpenApiRouter::new()
+        .routes(routes!(get_one_handler))
+        .routes(routes!(get_all_handler))
+        .routes(routes!(create_one_handler))
+        .routes(routes!(update_one_handler))
+        .routes(routes!(delete_one_handler))
+        .routes(routes!(delete_many_handler))
+        .routes(routes!(import_groundwater_data))
+        .routes(routes!(compensate_groundwater_profile))
+        .with_state(db.clone());
+
+    if let Some(instance) = keycloak_auth_instance {
+        mutating_router = mutating_router.layer(
+            KeycloakAuthLayer::<Role>::builder()
+                .instance(instance)
+                .passthrough_mode(PassthroughMode::Block)
+                .persist_raw_claims(false)
+                .expected_audiences(vec![String::from("account")])
+                .required_roles(vec![Role::Administrator])
+                .build(),
+        );
+    } else {
+        println!(
+            "Warning: Mutating routes of {} router are not protected",
+            GroundwaterData::RESOURCE_NAME_PLURAL
+        );
+    }
+
+    mutating_router
+}
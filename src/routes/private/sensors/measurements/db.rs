@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use uuid::Uuid;
+
+/// One value of one registered channel, stored in the `sensor_measurement` hypertable
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "sensor_measurement")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub sensor_id: Uuid,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub channel_id: Uuid,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub time_utc: DateTime<Utc>,
+    pub value: f64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::routes::private::sensors::db::Entity",
+        from = "Column::SensorId",
+        to = "crate::routes::private::sensors::db::Column::Id",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    Sensor,
+    #[sea_orm(
+        belongs_to = "crate::routes::private::sensors::types::channels::db::Entity",
+        from = "Column::ChannelId",
+        to = "crate::routes::private::sensors::types::channels::db::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Channel,
+}
+
+impl Related<crate::routes::private::sensors::db::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Sensor.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,8 +1,12 @@
 pub mod data;
 pub mod db;
 pub mod flux_data;
+pub mod groundwater_data;
+pub mod measurements;
 pub mod models;
 pub mod profile;
 pub mod redox_data;
 pub mod services;
+pub mod types;
 pub mod views;
+pub mod weather_data;
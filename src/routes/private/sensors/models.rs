@@ -24,6 +24,8 @@ pub struct Sensor {
     pub manufacturer: Option<String>,
     pub description: Option<String>,
     pub comment: Option<String>,
+    // Registered logger type; unset for TMS loggers stored in `sensordata`
+    pub sensortype_id: Option<Uuid>,
     #[crudcrate(update_model = false, create_model = false, on_update = chrono::Utc::now(), on_create = chrono::Utc::now())]
     pub last_updated: chrono::DateTime<Utc>,
     #[crudcrate(non_db_attr = true, default = None)]
@@ -54,6 +56,7 @@ impl From<Model> for Sensor {
             manufacturer: model.manufacturer,
             description: model.description,
             comment: model.comment,
+            sensortype_id: model.sensortype_id,
             last_updated: model.last_updated,
             data: vec![],
             data_base64: None,
@@ -178,8 +181,20 @@ impl CRUDResource for Sensor {
         let active_model: Self::ActiveModelType = create_model.clone().into();
         let result = Self::EntityType::insert(active_model).exec(db).await?;
 
-        // Process sensor data from base64 if provided
-        if let Some(ref data_base64) = create_model.data_base64 {
+        // Loggers of a registered type go to the generic measurement table
+        if let (Some(data_base64), Some((sensor_type, channels))) = (
+            &create_model.data_base64,
+            generic_sensor_type(db, create_model.sensortype_id).await?,
+        ) {
+            insert_measurements(
+                db,
+                result.last_insert_id,
+                data_base64,
+                &sensor_type,
+                &channels,
+            )
+            .await?;
+        } else if let Some(ref data_base64) = create_model.data_base64 {
             // Process the base64 string into SensorData objects
             let new_data_result =
                 crate::routes::private::sensors::services::process_sensor_data_base64(
@@ -240,16 +255,27 @@ impl CRUDResource for Sensor {
         id: Uuid,
         update_model: Self::UpdateModel,
     ) -> Result<Self, DbErr> {
-        let db_obj: super::db::ActiveModel = super::db::Entity::find_by_id(id)
+        let existing = super::db::Entity::find_by_id(id)
             .one(db)
             .await?
             .ok_or(DbErr::RecordNotFound(format!(
                 "{} not found",
                 Self::RESOURCE_NAME_SINGULAR
-            )))?
-            .into();
-        // Process sensor data from base64 if provided
-        if let Some(ref data_base64) = update_model.data_base64 {
+            )))?;
+        let sensortype_id = match update_model.sensortype_id {
+            Some(sensortype_id) => sensortype_id,
+            None => existing.sensortype_id,
+        };
+        let db_obj: super::db::ActiveModel = existing.into();
+
+        // Loggers of a registered type go to the generic measurement table.
+        // Overlapping uploads are fine there, duplicates are skipped on insert.
+        if let (Some(data_base64), Some((sensor_type, channels))) = (
+            &update_model.data_base64,
+            generic_sensor_type(db, sensortype_id).await?,
+        ) {
+            insert_measurements(db, id, data_base64, &sensor_type, &channels).await?;
+        } else if let Some(ref data_base64) = update_model.data_base64 {
             // Process the base64 string into SensorData objects
             let new_data_result =
                 crate::routes::private::sensors::services::process_sensor_data_base64(
@@ -353,6 +379,10 @@ impl CRUDResource for Sensor {
             .filter(crate::routes::private::sensors::data::db::Column::SensorId.eq(id))
             .exec(db)
             .await?;
+        crate::routes::private::sensors::measurements::db::Entity::delete_many()
+            .filter(crate::routes::private::sensors::measurements::db::Column::SensorId.eq(id))
+            .exec(db)
+            .await?;
 
         let res = <Self::EntityType as EntityTrait>::delete_by_id(id)
             .exec(db)
@@ -388,6 +418,12 @@ impl CRUDResource for Sensor {
                 .filter(crate::routes::private::sensors::data::db::Column::SensorId.eq(*id))
                 .exec(db)
                 .await?;
+            crate::routes::private::sensors::measurements::db::Entity::delete_many()
+                .filter(
+                    crate::routes::private::sensors::measurements::db::Column::SensorId.eq(*id),
+                )
+                .exec(db)
+                .await?;
         }
 
         Self::EntityType::delete_many()
@@ -561,8 +597,104 @@ async fn get_data_range(
         .await?;
 
     if let (Some(first), Some(last)) = (first_data, last_data) {
-        Ok((Some(first.time_utc), Some(last.time_utc)))
-    } else {
-        Ok((None, None))
+        return Ok((Some(first.time_utc), Some(last.time_utc)));
+    }
+
+    // Sensors of a registered type keep their data in the generic measurement table
+    let row = db
+        .query_one(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "SELECT MIN(time_utc) AS data_from, MAX(time_utc) AS data_to \
+             FROM sensor_measurement WHERE sensor_id = $1",
+            vec![sensor_id.into()],
+        ))
+        .await?;
+
+    match row {
+        Some(row) => Ok((row.try_get("", "data_from")?, row.try_get("", "data_to")?)),
+        None => Ok((None, None)),
+    }
+}
+
+type SensorTypeWithChannels = (
+    crate::routes::private::sensors::types::db::Model,
+    Vec<crate::routes::private::sensors::types::channels::db::Model>,
+);
+
+/// Returns the sensor type and its channels when the sensor uses a registered
+/// non-TMS type, or `None` when the data belongs in `sensordata`.
+async fn generic_sensor_type(
+    db: &DatabaseConnection,
+    sensortype_id: Option<Uuid>,
+) -> Result<Option<SensorTypeWithChannels>, DbErr> {
+    let Some(sensortype_id) = sensortype_id else {
+        return Ok(None);
+    };
+    let sensor_type = crate::routes::private::sensors::types::db::Entity::find_by_id(sensortype_id)
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Sensor type not found".into()))?;
+
+    if sensor_type.parser == crate::routes::private::sensors::types::db::SensorParserEnum::Tms {
+        return Ok(None);
     }
+
+    let channels = crate::routes::private::sensors::types::channels::db::Entity::find()
+        .filter(
+            crate::routes::private::sensors::types::channels::db::Column::SensortypeId
+                .eq(sensortype_id),
+        )
+        .all(db)
+        .await?;
+
+    Ok(Some((sensor_type, channels)))
+}
+
+/// Parse a logger export with the sensor type's parser and store it in the
+/// `sensor_measurement` hypertable, skipping rows that already exist.
+async fn insert_measurements(
+    db: &DatabaseConnection,
+    sensor_id: Uuid,
+    data_base64: &str,
+    sensor_type: &crate::routes::private::sensors::types::db::Model,
+    channels: &[crate::routes::private::sensors::types::channels::db::Model],
+) -> Result<(), DbErr> {
+    const CHUNK_SIZE: usize = 1000;
+
+    let parsed = crate::routes::private::sensors::services::process_sensor_measurements_base64(
+        data_base64,
+        sensor_type,
+        channels,
+    )
+    .map_err(DbErr::Custom)?;
+
+    let active_models: Vec<crate::routes::private::sensors::measurements::db::ActiveModel> =
+        parsed
+            .into_iter()
+            .map(
+                |obj| crate::routes::private::sensors::measurements::db::ActiveModel {
+                    sensor_id: Set(sensor_id),
+                    channel_id: Set(obj.channel_id),
+                    time_utc: Set(obj.time_utc),
+                    value: Set(obj.value),
+                },
+            )
+            .collect();
+
+    for chunk in active_models.chunks(CHUNK_SIZE) {
+        crate::routes::private::sensors::measurements::db::Entity::insert_many(chunk.to_vec())
+            .on_conflict(
+                sea_orm::sea_query::OnConflict::columns([
+                    crate::routes::private::sensors::measurements::db::Column::SensorId,
+                    crate::routes::private::sensors::measurements::db::Column::ChannelId,
+                    crate::routes::private::sensors::measurements::db::Column::TimeUtc,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await?;
+    }
+
+    Ok(())
 }
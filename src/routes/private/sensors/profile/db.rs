@@ -91,6 +91,37 @@ pub enum ProfileTypeEnum {
     Chamber,
     #[sea_orm(string_value = "redox")]
     Redox,
+    #[sea_orm(string_value = "groundwater")]
+    Groundwater,
+    #[sea_orm(string_value = "barologger")]
+    Barologger,
+    #[sea_orm(string_value = "weather")]
+    Weather,
+}
+
+/// Reference electrode the potentials of a redox profile are measured against
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "reference_electrode_enum"
+)]
+pub enum ReferenceElectrodeEnum {
+    /// Silver/silver chloride in saturated potassium chloride
+    #[sea_orm(string_value = "ag_agcl_sat_kcl")]
+    AgAgclSatKcl,
+    /// Silver/silver chloride in 3 M potassium chloride
+    #[sea_orm(string_value = "ag_agcl_3m_kcl")]
+    AgAgcl3mKcl,
+    /// Saturated calomel electrode
+    #[sea_orm(string_value = "calomel_sat_kcl")]
+    CalomelSatKcl,
+    /// Potentials are already reported against the standard hydrogen electrode
+    #[sea_orm(string_value = "she")]
+    She,
 }
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
@@ -112,6 +143,9 @@ pub struct Model {
     pub instrument_model: Option<String>,
     pub chamber_id_external: Option<String>,
     pub position: Option<i32>,
+    pub logger_depth_m: Option<f64>,
+    pub baro_profile_id: Option<Uuid>,
+    pub reference_electrode: Option<ReferenceElectrodeEnum>,
     pub last_updated: DateTime<Utc>,
 }
 
@@ -131,6 +165,10 @@ pub enum Relation {
     FluxData,
     #[sea_orm(has_many = "crate::routes::private::sensors::redox_data::db::Entity")]
     RedoxData,
+    #[sea_orm(has_many = "crate::routes::private::sensors::groundwater_data::db::Entity")]
+    GroundwaterData,
+    #[sea_orm(has_many = "crate::routes::private::sensors::weather_data::db::Entity")]
+    WeatherData,
 }
 
 impl Related<crate::routes::private::areas::db::Entity> for Entity {
@@ -157,4 +195,16 @@ impl Related<crate::routes::private::sensors::redox_data::db::Entity> for Entity
     }
 }
 
+impl Related<crate::routes::private::sensors::groundwater_data::db::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::GroundwaterData.def()
+    }
+}
+
+impl Related<crate::routes::private::sensors::weather_data::db::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WeatherData.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
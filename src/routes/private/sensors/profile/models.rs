@@ -3,7 +3,8 @@ use std::collections::HashMap;
 use super::db::Model;
 use crate::{
     config::Config,
-    routes::private::sensors::profile::db::{ProfileTypeEnum, SoilTypeEnum},
+    routes::private::sensors::redox_data::{eh::RedoxScale, models::RedoxResolution},
+    routes::private::sensors::profile::db::{ProfileTypeEnum, ReferenceElectrodeEnum, SoilTypeEnum},
 };
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
@@ -23,6 +24,16 @@ pub struct DepthAverageData {
     pub y: f64,
 }
 
+/// Time series of one registered sensor-type channel, grouped by installation depth
+#[derive(ToSchema, Serialize, Deserialize, Debug, Clone)]
+pub struct ChannelSeries {
+    pub sensorprofile_id: Uuid,
+    pub channel: String,
+    pub units: String,
+    pub resolution: String,
+    pub data_by_depth_cm: HashMap<i32, Vec<DepthAverageData>>,
+}
+
 #[derive(ToSchema, Serialize, Deserialize, ToCreateModel, ToUpdateModel, Clone)]
 #[active_model = "super::db::ActiveModel"]
 pub struct SensorProfile {
@@ -52,6 +63,11 @@ pub struct SensorProfile {
     pub chamber_id_external: Option<String>,
     // Redox/chamber position number
     pub position: Option<i32>,
+    // Groundwater-specific fields
+    pub logger_depth_m: Option<f64>,
+    pub baro_profile_id: Option<Uuid>,
+    // Redox-specific fields
+    pub reference_electrode: Option<ReferenceElectrodeEnum>,
     #[crudcrate(update_model = false, create_model = false)]
     #[schema(no_recursion)]
     pub assignments:
@@ -65,6 +81,9 @@ pub struct SensorProfile {
     // Raw moisture counts grouped by depth in cm (for reference)
     #[crudcrate(non_db_attr = true, default = HashMap::new())]
     pub moisture_raw_by_depth_cm: HashMap<i32, Vec<DepthAverageData>>,
+    // Redox potentials in mV grouped by electrode depth in cm (redox profiles)
+    #[crudcrate(non_db_attr = true, default = HashMap::new())]
+    pub redox_mv_by_depth_cm: HashMap<i32, Vec<DepthAverageData>>,
     // Temperature data used by the public API response
     #[crudcrate(non_db_attr = true, default = HashMap::new())]
     pub data_by_depth_cm: HashMap<i32, Vec<DepthAverageData>>,
@@ -103,10 +122,14 @@ impl SensorProfile {
             instrument_model: model.instrument_model,
             chamber_id_external: model.chamber_id_external,
             position: model.position,
+            logger_depth_m: model.logger_depth_m,
+            baro_profile_id: model.baro_profile_id,
+            reference_electrode: model.reference_electrode,
             assignments,
             temperature_by_depth_cm: HashMap::new(),
             moisture_vwc_by_depth_cm: HashMap::new(),
             moisture_raw_by_depth_cm: HashMap::new(),
+            redox_mv_by_depth_cm: HashMap::new(),
             data_by_depth_cm: HashMap::new(),
             resolution: None,
         }
@@ -293,6 +316,24 @@ impl SensorProfile {
         sensor_profile.temperature_by_depth_cm = temperature_data;
         sensor_profile.moisture_vwc_by_depth_cm = moisture_vwc_data;
         sensor_profile.moisture_raw_by_depth_cm = moisture_raw_data;
+        if sensor_profile.profile_type == ProfileTypeEnum::Redox {
+            sensor_profile.redox_mv_by_depth_cm =
+                crate::routes::private::sensors::redox_data::models::load_redox_series(
+                    db,
+                    id,
+                    start,
+                    end,
+                    match resolution {
+                        "raw" => RedoxResolution::Raw,
+                        "hourly" => RedoxResolution::Hourly,
+                        _ => RedoxResolution::Daily,
+                    },
+                    RedoxScale::Reference,
+                    None,
+                )
+                .await?
+                .redox_mv_by_depth_cm;
+        }
 
         sensor_profile.data_by_depth_cm = sensor_profile.temperature_by_depth_cm.clone();
         sensor_profile.resolution = Some(resolution.to_string());
@@ -793,4 +834,148 @@ impl SensorProfile {
             .await?;
         Ok(vwc_data)
     }
+
+    /// Load one registered channel with automatic resolution based on start/end date range.
+    pub async fn get_channel_with_date_range(
+        db: &DatabaseConnection,
+        id: Uuid,
+        channel: &str,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<ChannelSeries, DbErr> {
+        let sensor_profile = Self::get_one(db, id).await?;
+        let units = Self::channel_units(db, id, channel)
+            .await?
+            .ok_or(DbErr::RecordNotFound(format!(
+                "Channel '{channel}' not found for sensor profile"
+            )))?;
+
+        let span_days = Self::compute_span_days(db, id, start, end).await;
+        let resolution = if span_days <= 7 {
+            "raw"
+        } else if span_days <= 90 {
+            "hourly"
+        } else {
+            "6h"
+        };
+
+        let data_by_depth_cm = sensor_profile
+            .load_channel_series_by_depth_cm(
+                db,
+                channel,
+                Self::channel_aggregate_table(resolution),
+                start,
+                end,
+            )
+            .await?;
+
+        Ok(ChannelSeries {
+            sensorprofile_id: id,
+            channel: channel.to_string(),
+            units,
+            resolution: resolution.to_string(),
+            data_by_depth_cm,
+        })
+    }
+
+    /// Continuous aggregate of `sensor_measurement` matching a resolution label.
+    pub fn channel_aggregate_table(resolution: &str) -> Option<&'static str> {
+        match resolution {
+            "hourly" => Some("sensor_measurement_hourly"),
+            "6h" => Some("sensor_measurement_6h"),
+            _ => None,
+        }
+    }
+
+    /// Units of a channel recorded by any sensor assigned to the profile, or `None`
+    /// if none of the assigned sensor types defines the channel.
+    pub async fn channel_units(
+        db: &DatabaseConnection,
+        profile_id: Uuid,
+        channel: &str,
+    ) -> Result<Option<String>, DbErr> {
+        let sql = r"
+            SELECT c.units
+            FROM sensorprofile_assignment AS spa
+            JOIN sensor AS s ON s.id = spa.sensor_id
+            JOIN sensortype_channel AS c
+              ON c.sensortype_id = s.sensortype_id
+             AND c.name = $2
+            WHERE spa.sensorprofile_id = $1
+            LIMIT 1
+        ";
+        let stmt = Statement::from_sql_and_values(
+            db.get_database_backend(),
+            sql,
+            vec![profile_id.into(), channel.into()],
+        );
+        match db.query_one(stmt).await? {
+            Some(row) => Ok(Some(row.try_get("", "units")?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Load a registered channel from `sensor_measurement` (or one of its continuous
+    /// aggregates), keyed by the logger installation depth (`depth_cm_sensor1`).
+    pub async fn load_channel_series_by_depth_cm(
+        &self,
+        db: &DatabaseConnection,
+        channel: &str,
+        aggregate_table: Option<&str>,
+        date_from: Option<DateTime<Utc>>,
+        date_to: Option<DateTime<Utc>>,
+    ) -> Result<HashMap<i32, Vec<DepthAverageData>>, DbErr> {
+        let (source, time_col, value_col) = match aggregate_table {
+            Some(table) => (table, "bucket", "avg_value"),
+            None => ("sensor_measurement", "time_utc", "value"),
+        };
+
+        let mut conditions = Vec::new();
+        let mut params: Vec<sea_orm::Value> = vec![self.id.into(), channel.into()];
+
+        if let Some(df) = date_from {
+            params.push(df.into());
+            conditions.push(format!(" AND m.{time_col} >= ${}", params.len()));
+        }
+        if let Some(dt) = date_to {
+            params.push(dt.into());
+            conditions.push(format!(" AND m.{time_col} <= ${}", params.len()));
+        }
+        let conditions = conditions.concat();
+
+        let sql = format!(
+            r"
+            SELECT
+                spa.depth_cm_sensor1 AS depth_cm,
+                m.{time_col} AS time_utc,
+                m.{value_col} AS y
+            FROM sensorprofile_assignment AS spa
+            JOIN sensor AS s ON s.id = spa.sensor_id
+            JOIN sensortype_channel AS c
+              ON c.sensortype_id = s.sensortype_id
+             AND c.name = $2
+            JOIN {source} AS m
+              ON m.sensor_id = spa.sensor_id
+             AND m.channel_id = c.id
+             AND m.{time_col} BETWEEN spa.date_from AND spa.date_to
+             {conditions}
+            WHERE spa.sensorprofile_id = $1
+            ORDER BY depth_cm, m.{time_col}
+            "
+        );
+
+        let stmt = Statement::from_sql_and_values(db.get_database_backend(), &sql, params);
+        let rows = db.query_all(stmt).await?;
+
+        let mut map: HashMap<i32, Vec<DepthAverageData>> = HashMap::new();
+        for row in rows {
+            let depth_cm: i32 = row.try_get("", "depth_cm")?;
+            let time_utc: DateTime<Utc> = row.try_get("", "time_utc")?;
+            let y: f64 = row.try_get("", "y")?;
+            map.entry(depth_cm)
+                .or_default()
+                .push(DepthAverageData { time_utc, y });
+        }
+        Ok(map)
+    }
 }
@@ -1,11 +1,16 @@
-use super::models::{SensorProfile, SensorProfileCreate, SensorProfileUpdate};
+use super::models::{ChannelSeries, SensorProfile, SensorProfileCreate, SensorProfileUpdate};
 use crate::common::auth::Role;
+use crate::common::errors::db_error_response;
 use crate::common::models::DateRangeQuery;
+use crate::routes::private::sensors::weather_data::models::{
+    WeatherAlignedSeries, get_aligned_to_profile,
+};
 use axum_keycloak_auth::{
     PassthroughMode, instance::KeycloakAuthInstance, layer::KeycloakAuthLayer,
 };
 use crudcrate::{CRUDResource, crud_handlers};
 use sea_orm::DatabaseConnection;
+use serde::Deserialize;
 use std::sync::Arc;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
@@ -45,6 +50,79 @@ pub async fn get_one(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/{id}/channels/{channel}",
+    responses(
+        (status = 200, description = "Channel series found", body = ChannelSeries),
+        (status = 404, description = "SensorProfile or channel not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("id" = Uuid, description = "SensorProfile ID"),
+        ("channel" = String, description = "Channel name as registered on the sensor type"),
+        ("start" = Option<String>, Query, description = "Start of date range (ISO 8601)"),
+        ("end" = Option<String>, Query, description = "End of date range (ISO 8601)")
+    ),
+    summary = "Get sensor profile channel data",
+    description = "Returns the time series of a registered sensor-type channel for the sensors assigned to the profile, grouped by installation depth. The resolution (raw, hourly or 6h) is chosen from the requested date range."
+)]
+pub async fn get_channel(
+    State(db): State<sea_orm::DatabaseConnection>,
+    Path((id, channel)): Path<(uuid::Uuid, String)>,
+    Query(query): Query<DateRangeQuery>,
+) -> Result<Json<ChannelSeries>, (axum::http::StatusCode, axum::Json<String>)> {
+    match SensorProfile::get_channel_with_date_range(&db, id, &channel, query.start, query.end)
+        .await
+    {
+        Ok(item) => Ok(Json(item)),
+        Err(DbErr::RecordNotFound(_)) => Err((
+            axum::http::StatusCode::NOT_FOUND,
+            Json("Not Found".to_string()),
+        )),
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json("Internal Server Error".to_string()),
+        )),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct WeatherQuery {
+    pub start: Option<chrono::DateTime<chrono::Utc>>,
+    pub end: Option<chrono::DateTime<chrono::Utc>>,
+    pub station_id: Option<uuid::Uuid>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/weather",
+    responses(
+        (status = 200, description = "Station data aligned to the profile", body = WeatherAlignedSeries),
+        (status = 404, description = "SensorProfile or station not found"),
+        (status = 422, description = "No weather station available"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("id" = Uuid, description = "SensorProfile ID"),
+        ("start" = Option<String>, Query, description = "Start of date range (ISO 8601)"),
+        ("end" = Option<String>, Query, description = "End of date range (ISO 8601)"),
+        ("station_id" = Option<Uuid>, Query, description = "Weather station profile; defaults to the nearest one")
+    ),
+    summary = "Get weather data aligned to a sensor profile",
+    description = "Returns air temperature, precipitation, snow depth and relative humidity of a weather station on the timestamps of the profile's temperature series for the same range and resolution. Precipitation is summed over each interval, the other variables are averaged."
+)]
+pub async fn get_weather(
+    State(db): State<sea_orm::DatabaseConnection>,
+    Path(id): Path<uuid::Uuid>,
+    Query(query): Query<WeatherQuery>,
+) -> Result<Json<WeatherAlignedSeries>, (axum::http::StatusCode, axum::Json<String>)> {
+    get_aligned_to_profile(&db, id, query.station_id, query.start, query.end)
+        .await
+        .map(Json)
+        .map_err(db_error_response)
+}
+
 pub fn router(
     db: &DatabaseConnection,
     keycloak_auth_instance: Option<Arc<KeycloakAuthInstance>>,
@@ -54,6 +132,8 @@ where
 {
     let mut mutating_router = OpenApiRouter::new()
         .routes(routes!(get_one))
+        .routes(routes!(get_channel))
+        .routes(routes!(get_weather))
         .routes(routes!(get_all_handler))
         .routes(routes!(create_one_handler))
         .routes(routes!(update_one_handler))
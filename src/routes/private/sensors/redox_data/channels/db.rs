@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, ToSchema)]
+#[sea_orm(table_name = "redox_channel")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub sensorprofile_id: Uuid,
+    pub name: String,
+    pub electrode_id: Option<String>,
+    pub depth_cm: i32,
+    pub column_name: Option<String>,
+    pub installed_on: Option<DateTime<Utc>>,
+    pub last_updated: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::routes::private::sensors::profile::db::Entity",
+        from = "Column::SensorprofileId",
+        to = "crate::routes::private::sensors::profile::db::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Sensorprofile,
+}
+
+impl Related<crate::routes::private::sensors::profile::db::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Sensorprofile.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,168 @@
+use super::db::Model;
+use crate::routes::private::sensors::profile::db::{self as ProfileDB, ProfileTypeEnum};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use crudcrate::{CRUDResource, ToCreateModel, ToUpdateModel, traits::MergeIntoActiveModel};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait,
+    Order, QueryOrder, QuerySelect, entity::prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// An electrode of a redox profile. Potentials are stored per channel and
+/// reported by the channel's depth.
+#[derive(ToSchema, Serialize, Deserialize, ToCreateModel, ToUpdateModel, Debug, Clone)]
+#[active_model = "super::db::ActiveModel"]
+pub struct RedoxChannel {
+    #[crudcrate(update_model = false, create_model = false, on_create = Uuid::new_v4())]
+    pub id: Uuid,
+    #[crudcrate(update_model = false)]
+    pub sensorprofile_id: Uuid,
+    /// Name used for the channel in ingested records, e.g. `ch1`
+    pub name: String,
+    /// Serial number or label of the electrode
+    pub electrode_id: Option<String>,
+    pub depth_cm: i32,
+    /// Column of the channel in logger files; the channel name when unset
+    pub column_name: Option<String>,
+    pub installed_on: Option<DateTime<Utc>>,
+    #[crudcrate(update_model = false, create_model = false, on_update = Utc::now(), on_create = Utc::now())]
+    pub last_updated: DateTime<Utc>,
+}
+
+impl From<Model> for RedoxChannel {
+    fn from(model: Model) -> Self {
+        Self {
+            id: model.id,
+            sensorprofile_id: model.sensorprofile_id,
+            name: model.name,
+            electrode_id: model.electrode_id,
+            depth_cm: model.depth_cm,
+            column_name: model.column_name,
+            installed_on: model.installed_on,
+            last_updated: model.last_updated,
+        }
+    }
+}
+
+/// Channels of a redox profile ordered by depth.
+pub async fn load_channels<C: ConnectionTrait>(
+    db: &C,
+    sensorprofile_id: Uuid,
+) -> Result<Vec<Model>, DbErr> {
+    super::db::Entity::find()
+        .filter(super::db::Column::SensorprofileId.eq(sensorprofile_id))
+        .order_by_asc(super::db::Column::DepthCm)
+        .order_by_asc(super::db::Column::Name)
+        .all(db)
+        .await
+}
+
+#[async_trait]
+impl CRUDResource for RedoxChannel {
+    type EntityType = super::db::Entity;
+    type ColumnType = super::db::Column;
+    type ActiveModelType = super::db::ActiveModel;
+    type CreateModel = RedoxChannelCreate;
+    type UpdateModel = RedoxChannelUpdate;
+
+    const ID_COLUMN: Self::ColumnType = super::db::Column::Id;
+    const RESOURCE_NAME_SINGULAR: &'static str = "redox channel";
+    const RESOURCE_NAME_PLURAL: &'static str = "redox channels";
+    const RESOURCE_DESCRIPTION: &'static str = "Electrode channels of a redox profile with their installation depth. Deleting a channel deletes its potentials.";
+
+    async fn get_all(
+        db: &DatabaseConnection,
+        condition: Condition,
+        order_column: Self::ColumnType,
+        order_direction: Order,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<Self>, DbErr> {
+        let models = Self::EntityType::find()
+            .filter(condition)
+            .order_by(order_column, order_direction)
+            .offset(offset)
+            .limit(limit)
+            .all(db)
+            .await?;
+        Ok(models.into_iter().map(RedoxChannel::from).collect())
+    }
+
+    async fn get_one(db: &DatabaseConnection, id: Uuid) -> Result<Self, DbErr> {
+        let model = Self::EntityType::find()
+            .filter(Self::ColumnType::Id.eq(id))
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound(format!(
+                "{} not found",
+                Self::RESOURCE_NAME_SINGULAR
+            )))?;
+        Ok(RedoxChannel::from(model))
+    }
+
+    async fn create(
+        db: &DatabaseConnection,
+        create_model: Self::CreateModel,
+    ) -> Result<Self, DbErr> {
+        if create_model.name.trim().is_empty() {
+            return Err(DbErr::Custom("Channel name must not be empty".into()));
+        }
+        let profile = ProfileDB::Entity::find_by_id(create_model.sensorprofile_id)
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Sensor profile not found".into()))?;
+        if profile.profile_type != ProfileTypeEnum::Redox {
+            return Err(DbErr::Custom(
+                "Sensor profile must be of type 'redox'".into(),
+            ));
+        }
+
+        let active_model: Self::ActiveModelType = create_model.into();
+        let result = Self::EntityType::insert(active_model).exec(db).await?;
+        Self::get_one(db, result.last_insert_id).await
+    }
+
+    async fn update(
+        db: &DatabaseConnection,
+        id: Uuid,
+        update_model: Self::UpdateModel,
+    ) -> Result<Self, DbErr> {
+        let db_obj: super::db::ActiveModel = super::db::Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound(format!(
+                "{} not found",
+                Self::RESOURCE_NAME_SINGULAR
+            )))?
+            .into();
+
+        let updated_obj: super::db::ActiveModel = update_model.merge_into_activemodel(db_obj);
+        if let ActiveValue::Set(name) = &updated_obj.name
+            && name.trim().is_empty()
+        {
+            return Err(DbErr::Custom("Channel name must not be empty".into()));
+        }
+        let response_obj = updated_obj.update(db).await?;
+        Self::get_one(db, response_obj.id).await
+    }
+
+    fn sortable_columns() -> Vec<(&'static str, Self::ColumnType)> {
+        vec![
+            ("name", Self::ColumnType::Name),
+            ("depth_cm", Self::ColumnType::DepthCm),
+            ("installed_on", Self::ColumnType::InstalledOn),
+            ("last_updated", Self::ColumnType::LastUpdated),
+        ]
+    }
+
+    fn filterable_columns() -> Vec<(&'static str, Self::ColumnType)> {
+        vec![
+            ("sensorprofile_id", Self::ColumnType::SensorprofileId),
+            ("name", Self::ColumnType::Name),
+            ("electrode_id", Self::ColumnType::ElectrodeId),
+        ]
+    }
+}
@@ -0,0 +1,47 @@
+use super::models::{RedoxChannel, RedoxChannelCreate, RedoxChannelUpdate};
+use crate::common::auth::Role;
+use axum_keycloak_auth::{
+    PassthroughMode, instance::KeycloakAuthInstance, layer::KeycloakAuthLayer,
+};
+use crudcrate::{CRUDResource, crud_handlers};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+crud_handlers!(RedoxChannel, RedoxChannelUpdate, RedoxChannelCreate);
+
+pub fn router(
+    db: &DatabaseConnection,
+    keycloak_auth_instance: Option<Arc<KeycloakAuthInstance>>,
+) -> OpenApiRouter
+where
+    RedoxChannel: CRUDResource,
+{
+    let mut mutating_router = OpenApiRouter::new()
+        .routes(routes!(get_one_handler))
+        .routes(routes!(get_all_handler))
+        .routes(routes!(create_one_handler))
+        .routes(routes!(update_one_handler))
+        .routes(routes!(delete_one_handler))
+        .routes(routes!(delete_many_handler))
+        .with_state(db.clone());
+
+    if let Some(instance) = keycloak_auth_instance {
+        mutating_router = mutating_router.layer(
+            KeycloakAuthLayer::<Role>::builder()
+                .instance(instance)
+                .passthrough_mode(PassthroughMode::Block)
+                .persist_raw_claims(false)
+                .expected_audiences(vec![String::from("account")])
+                .required_roles(vec![Role::Administrator])
+                .build(),
+        );
+    } else {
+        println!(
+            "Warning: Mutating routes of {} router are not protected",
+            RedoxChannel::RESOURCE_NAME_PLURAL
+        );
+    }
+
+    mutating_router
+}
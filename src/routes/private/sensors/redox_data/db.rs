@@ -4,6 +4,8 @@ use serde::Serialize;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
+/// Redox record of a profile. The hypertable is keyed by id and record time;
+/// ids are kept unique by a trigger, so records are addressed by id alone.
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, ToSchema)]
 #[sea_orm(table_name = "redox_data")]
 pub struct Model {
@@ -11,10 +13,6 @@ pub struct Model {
     pub id: Uuid,
     pub sensorprofile_id: Uuid,
     pub measured_on: DateTime<Utc>,
-    pub ch1_5cm_mv: Option<f64>,
-    pub ch2_15cm_mv: Option<f64>,
-    pub ch3_25cm_mv: Option<f64>,
-    pub ch4_35cm_mv: Option<f64>,
     pub temp_c: Option<f64>,
 }
 
@@ -0,0 +1,182 @@
+//! Conversion of electrode potentials to Eh against the standard hydrogen
+//! electrode (SHE) and classification of redox conditions.
+
+use crate::routes::private::sensors::profile::{
+    db::ReferenceElectrodeEnum, models::DepthAverageData,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Temperature assumed for readings without a logger temperature
+const DEFAULT_TEMPERATURE_C: f64 = 25.0;
+
+/// Scale of the returned potentials
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RedoxScale {
+    /// Raw potentials against the profile's reference electrode
+    #[default]
+    Reference,
+    /// Eh against the standard hydrogen electrode
+    She,
+}
+
+/// Potential of a reference electrode against SHE in mV. Linear approximations
+/// of the tabulated values between 0 and 50 °C.
+pub fn reference_potential_mv(electrode: ReferenceElectrodeEnum, temp_c: f64) -> f64 {
+    let (at_25c, per_kelvin) = match electrode {
+        ReferenceElectrodeEnum::AgAgclSatKcl => (197.0, -1.01),
+        ReferenceElectrodeEnum::AgAgcl3mKcl => (210.0, -0.73),
+        ReferenceElectrodeEnum::CalomelSatKcl => (241.0, -0.66),
+        ReferenceElectrodeEnum::She => (0.0, 0.0),
+    };
+    at_25c + per_kelvin * (temp_c - 25.0)
+}
+
+/// Nernst slope in mV per pH unit (59.16 mV at 25 °C).
+pub fn nernst_slope_mv(temp_c: f64) -> f64 {
+    0.198_4 * (temp_c + 273.15)
+}
+
+/// Conversion of raw potentials of one profile to Eh, optionally normalised to
+/// pH 7.
+#[derive(Debug, Clone, Copy)]
+pub struct EhConversion {
+    pub electrode: ReferenceElectrodeEnum,
+    pub ph: Option<f64>,
+}
+
+impl EhConversion {
+    pub fn apply(&self, potential_mv: f64, temp_c: Option<f64>) -> f64 {
+        let temp_c = temp_c.unwrap_or(DEFAULT_TEMPERATURE_C);
+        let eh = potential_mv + reference_potential_mv(self.electrode, temp_c);
+        match self.ph {
+            Some(ph) => eh + nernst_slope_mv(temp_c) * (ph - 7.0),
+            None => eh,
+        }
+    }
+}
+
+/// Share of time a depth spent in each redox class
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq)]
+pub struct RedoxClassFractions {
+    pub depth_cm: i32,
+    pub n: usize,
+    pub mean_eh_mv: f64,
+    pub min_eh_mv: f64,
+    pub max_eh_mv: f64,
+    /// Eh above `oxic_above_mv`
+    pub oxic: f64,
+    /// Eh between the two thresholds
+    pub suboxic: f64,
+    /// Eh below `anoxic_below_mv`
+    pub anoxic: f64,
+}
+
+/// Classify an Eh series and weight each reading by the time it represents,
+/// half the interval to its neighbours, so that gaps do not bias the result.
+pub fn class_fractions(
+    depth_cm: i32,
+    series: &[DepthAverageData],
+    oxic_above_mv: f64,
+    anoxic_below_mv: f64,
+) -> Option<RedoxClassFractions> {
+    let first = series.first()?;
+    let seconds = |a: &DepthAverageData, b: &DepthAverageData| {
+        let secs = (b.time_utc - a.time_utc).num_seconds();
+        i32::try_from(secs).map_or(f64::from(i32::MAX), f64::from)
+    };
+
+    let mut weights = vec![0.0; series.len()];
+    for (i, pair) in series.windows(2).enumerate() {
+        let half = seconds(&pair[0], &pair[1]) / 2.0;
+        weights[i] += half;
+        weights[i + 1] += half;
+    }
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        weights.fill(1.0);
+    }
+    let total: f64 = weights.iter().sum();
+
+    let (mut oxic, mut suboxic, mut anoxic, mut weighted_sum) = (0.0, 0.0, 0.0, 0.0);
+    let (mut min, mut max) = (first.y, first.y);
+    for (point, weight) in series.iter().zip(&weights) {
+        if point.y > oxic_above_mv {
+            oxic += weight;
+        } else if point.y < anoxic_below_mv {
+            anoxic += weight;
+        } else {
+            suboxic += weight;
+        }
+        weighted_sum += point.y * weight;
+        min = min.min(point.y);
+        max = max.max(point.y);
+    }
+
+    Some(RedoxClassFractions {
+        depth_cm,
+        n: series.len(),
+        mean_eh_mv: weighted_sum / total,
+        min_eh_mv: min,
+        max_eh_mv: max,
+        oxic: oxic / total,
+        suboxic: suboxic / total,
+        anoxic: anoxic / total,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn converts_to_she_with_temperature_and_ph() {
+        let raw = EhConversion {
+            electrode: ReferenceElectrodeEnum::AgAgclSatKcl,
+            ph: None,
+        };
+        assert!((raw.apply(100.0, Some(25.0)) - 297.0).abs() < 1e-9);
+        assert!((raw.apply(100.0, Some(15.0)) - 307.1).abs() < 1e-9);
+        assert!((raw.apply(100.0, None) - 297.0).abs() < 1e-9);
+
+        let normalised = EhConversion {
+            electrode: ReferenceElectrodeEnum::She,
+            ph: Some(5.0),
+        };
+        assert!((normalised.apply(300.0, Some(25.0)) - (300.0 - 2.0 * 59.16)).abs() < 0.05);
+    }
+
+    #[test]
+    fn class_fractions_are_time_weighted() {
+        let at = |hour| Utc.with_ymd_and_hms(2026, 6, 1, hour, 0, 0).unwrap();
+        // Hourly oxic readings, then one anoxic reading six hours later
+        let series = [
+            DepthAverageData {
+                time_utc: at(0),
+                y: 400.0,
+            },
+            DepthAverageData {
+                time_utc: at(1),
+                y: 350.0,
+            },
+            DepthAverageData {
+                time_utc: at(2),
+                y: 200.0,
+            },
+            DepthAverageData {
+                time_utc: at(8),
+                y: -100.0,
+            },
+        ];
+        let fractions = class_fractions(10, &series, 300.0, 100.0).unwrap();
+
+        assert_eq!(fractions.n, 4);
+        assert!((fractions.oxic - 1.5 / 8.0).abs() < 1e-9);
+        assert!((fractions.suboxic - 3.5 / 8.0).abs() < 1e-9);
+        assert!((fractions.anoxic - 3.0 / 8.0).abs() < 1e-9);
+        assert!((fractions.min_eh_mv + 100.0).abs() < 1e-9);
+        assert!(class_fractions(10, &[], 300.0, 100.0).is_none());
+    }
+}
@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use uuid::Uuid;
+
+/// Potential of one electrode channel in one redox record. The table is a
+/// hypertable keyed by channel and record time.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "redox_measurement")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub channel_id: Uuid,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub measured_on: DateTime<Utc>,
+    pub redox_data_id: Uuid,
+    pub potential_mv: f64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::routes::private::sensors::redox_data::db::Entity",
+        from = "Column::RedoxDataId",
+        to = "crate::routes::private::sensors::redox_data::db::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    RedoxData,
+    #[sea_orm(
+        belongs_to = "crate::routes::private::sensors::redox_data::channels::db::Entity",
+        from = "Column::ChannelId",
+        to = "crate::routes::private::sensors::redox_data::channels::db::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Channel,
+}
+
+impl Related<crate::routes::private::sensors::redox_data::channels::db::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Channel.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
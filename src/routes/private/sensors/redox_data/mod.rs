@@ -1,3 +1,7 @@
+pub mod channels;
 pub mod db;
+pub mod eh;
+pub mod measurements;
 pub mod models;
+pub mod parsers;
 pub mod views;
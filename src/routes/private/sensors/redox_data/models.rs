@@ -1,12 +1,24 @@
+use super::channels::{
+    db as ChannelDB,
+    models::{RedoxChannel, load_channels},
+};
 use super::db::Model;
+use super::eh::{EhConversion, RedoxClassFractions, RedoxScale, class_fractions};
+use super::measurements::db as MeasurementDB;
+use crate::routes::private::sensors::profile::{
+    db::{self as ProfileDB, ReferenceElectrodeEnum},
+    models::DepthAverageData,
+};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use crudcrate::{CRUDResource, ToCreateModel, ToUpdateModel, traits::MergeIntoActiveModel};
 use sea_orm::{
     ActiveModelTrait, ActiveValue, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait,
-    Order, QueryOrder, QuerySelect, entity::prelude::*,
+    Iterable, Order, QueryOrder, QuerySelect, Statement, TransactionTrait, entity::prelude::*,
+    sea_query::OnConflict,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
@@ -17,11 +29,10 @@ pub struct RedoxData {
     pub id: Uuid,
     pub sensorprofile_id: Uuid,
     pub measured_on: DateTime<Utc>,
-    pub ch1_5cm_mv: Option<f64>,
-    pub ch2_15cm_mv: Option<f64>,
-    pub ch3_25cm_mv: Option<f64>,
-    pub ch4_35cm_mv: Option<f64>,
     pub temp_c: Option<f64>,
+    /// Electrode potentials in mV keyed by the name of the profile's redox channel
+    #[crudcrate(non_db_attr = true, default = BTreeMap::new())]
+    pub potentials_mv: BTreeMap<String, f64>,
 }
 
 impl From<Model> for RedoxData {
@@ -30,15 +41,419 @@ impl From<Model> for RedoxData {
             id: model.id,
             sensorprofile_id: model.sensorprofile_id,
             measured_on: model.measured_on,
-            ch1_5cm_mv: model.ch1_5cm_mv,
-            ch2_15cm_mv: model.ch2_15cm_mv,
-            ch3_25cm_mv: model.ch3_25cm_mv,
-            ch4_35cm_mv: model.ch4_35cm_mv,
             temp_c: model.temp_c,
+            potentials_mv: BTreeMap::new(),
+        }
+    }
+}
+
+/// Redox potentials of a profile grouped by electrode depth.
+#[derive(ToSchema, Serialize, Deserialize, Debug, Clone)]
+pub struct RedoxSeries {
+    pub channels: Vec<RedoxChannel>,
+    pub reference_electrode: Option<ReferenceElectrodeEnum>,
+    pub scale: RedoxScale,
+    /// pH the Eh values were normalised from to pH 7
+    pub ph: Option<f64>,
+    pub resolution: RedoxResolution,
+    /// Potentials in mV grouped by depth in cm; channels at the same depth are averaged
+    pub redox_mv_by_depth_cm: HashMap<i32, Vec<DepthAverageData>>,
+    pub temperature_c: Vec<DepthAverageData>,
+}
+
+/// Channel ids of a redox profile keyed by channel name.
+pub async fn channel_ids<C: ConnectionTrait>(
+    db: &C,
+    sensorprofile_id: Uuid,
+) -> Result<HashMap<String, Uuid>, DbErr> {
+    Ok(load_channels(db, sensorprofile_id)
+        .await?
+        .into_iter()
+        .map(|c| (c.name, c.id))
+        .collect())
+}
+
+/// Reject potentials keyed by names that are not among the profile's
+/// `channels`, listing every unknown name.
+pub fn check_channel_names(
+    channels: &HashMap<String, Uuid>,
+    potentials_mv: &BTreeMap<String, f64>,
+) -> Result<(), DbErr> {
+    let unknown: Vec<&str> = potentials_mv
+        .keys()
+        .filter(|name| !channels.contains_key(*name))
+        .map(String::as_str)
+        .collect();
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    let mut known: Vec<&str> = channels.keys().map(String::as_str).collect();
+    known.sort_unstable();
+    Err(DbErr::Custom(format!(
+        "Unknown redox channels for this profile: {} (channels: {})",
+        unknown.join(", "),
+        known.join(", ")
+    )))
+}
+
+/// Write the potentials of a redox record, replacing existing values of the
+/// same channels. Channel names must be among the profile's `channels`.
+pub async fn save_potentials<C: ConnectionTrait>(
+    db: &C,
+    channels: &HashMap<String, Uuid>,
+    redox_data_id: Uuid,
+    measured_on: DateTime<Utc>,
+    potentials_mv: &BTreeMap<String, f64>,
+) -> Result<(), DbErr> {
+    if potentials_mv.is_empty() {
+        return Ok(());
+    }
+    check_channel_names(channels, potentials_mv)?;
+    let rows: Vec<MeasurementDB::ActiveModel> = potentials_mv
+        .iter()
+        .filter_map(|(name, potential_mv)| {
+            Some(MeasurementDB::ActiveModel {
+                channel_id: ActiveValue::Set(*channels.get(name)?),
+                measured_on: ActiveValue::Set(measured_on),
+                redox_data_id: ActiveValue::Set(redox_data_id),
+                potential_mv: ActiveValue::Set(*potential_mv),
+            })
+        })
+        .collect();
+    MeasurementDB::Entity::insert_many(rows)
+        .on_conflict(
+            OnConflict::columns([
+                MeasurementDB::Column::ChannelId,
+                MeasurementDB::Column::MeasuredOn,
+            ])
+            .update_columns([
+                MeasurementDB::Column::RedoxDataId,
+                MeasurementDB::Column::PotentialMv,
+            ])
+            .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+    Ok(())
+}
+
+/// Insert a redox record, replacing the temperature and the given channel
+/// potentials of an existing record of the same profile and time.
+pub async fn upsert_redox_record<C: ConnectionTrait>(
+    db: &C,
+    channels: &HashMap<String, Uuid>,
+    create_data: RedoxDataCreate,
+) -> Result<(), DbErr> {
+    use super::db::Column;
+
+    check_channel_names(channels, &create_data.potentials_mv)?;
+    let active_model = super::db::ActiveModel {
+        id: ActiveValue::Set(Uuid::new_v4()),
+        sensorprofile_id: ActiveValue::Set(create_data.sensorprofile_id),
+        measured_on: ActiveValue::Set(create_data.measured_on),
+        temp_c: ActiveValue::Set(create_data.temp_c),
+    };
+    let result = super::db::Entity::insert(active_model)
+        .on_conflict(
+            OnConflict::columns([Column::SensorprofileId, Column::MeasuredOn])
+                .update_columns(Column::iter().filter(|c| {
+                    !matches!(c, Column::Id | Column::SensorprofileId | Column::MeasuredOn)
+                }))
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+    save_potentials(
+        db,
+        channels,
+        result.last_insert_id,
+        create_data.measured_on,
+        &create_data.potentials_mv,
+    )
+    .await
+}
+
+/// Potentials of the given records keyed by record and channel name.
+async fn load_potentials(
+    db: &DatabaseConnection,
+    redox_data_ids: Vec<Uuid>,
+) -> Result<HashMap<Uuid, BTreeMap<String, f64>>, DbErr> {
+    let rows = MeasurementDB::Entity::find()
+        .filter(MeasurementDB::Column::RedoxDataId.is_in(redox_data_ids))
+        .find_also_related(ChannelDB::Entity)
+        .all(db)
+        .await?;
+
+    let mut potentials: HashMap<Uuid, BTreeMap<String, f64>> = HashMap::new();
+    for (measurement, channel) in rows {
+        if let Some(channel) = channel {
+            potentials
+                .entry(measurement.redox_data_id)
+                .or_default()
+                .insert(channel.name, measurement.potential_mv);
+        }
+    }
+    Ok(potentials)
+}
+
+/// Average readings sharing a depth and timestamp. Rows must be ordered by time.
+fn average_by_depth(
+    rows: impl IntoIterator<Item = (i32, DateTime<Utc>, f64)>,
+) -> HashMap<i32, Vec<DepthAverageData>> {
+    let mut sums: HashMap<i32, Vec<(DateTime<Utc>, f64, u32)>> = HashMap::new();
+    for (depth_cm, time_utc, value) in rows {
+        let series = sums.entry(depth_cm).or_default();
+        match series.last_mut() {
+            Some((last_time, sum, n)) if *last_time == time_utc => {
+                *sum += value;
+                *n += 1;
+            }
+            _ => series.push((time_utc, value, 1)),
+        }
+    }
+    sums.into_iter()
+        .map(|(depth_cm, series)| {
+            let averaged = series
+                .into_iter()
+                .map(|(time_utc, sum, n)| DepthAverageData {
+                    time_utc,
+                    y: sum / f64::from(n),
+                })
+                .collect();
+            (depth_cm, averaged)
+        })
+        .collect()
+}
+
+/// Resolve how the potentials of a profile are converted for the requested
+/// scale. Eh needs the profile's reference electrode.
+async fn eh_conversion(
+    db: &DatabaseConnection,
+    sensorprofile_id: Uuid,
+    scale: RedoxScale,
+    ph: Option<f64>,
+) -> Result<(Option<ReferenceElectrodeEnum>, Option<EhConversion>), DbErr> {
+    if let Some(ph) = ph
+        && !(0.0..=14.0).contains(&ph)
+    {
+        return Err(DbErr::Custom(format!(
+            "pH must be between 0 and 14, got {ph}"
+        )));
+    }
+    let profile = ProfileDB::Entity::find_by_id(sensorprofile_id)
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Sensor profile not found".into()))?;
+
+    match scale {
+        RedoxScale::Reference if ph.is_some() => {
+            Err(DbErr::Custom("pH normalisation requires scale=she".into()))
+        }
+        RedoxScale::Reference => Ok((profile.reference_electrode, None)),
+        RedoxScale::She => {
+            let electrode = profile.reference_electrode.ok_or(DbErr::Custom(
+                "Sensor profile has no reference electrode; set `reference_electrode` to convert to Eh".into(),
+            ))?;
+            Ok((Some(electrode), Some(EhConversion { electrode, ph })))
+        }
+    }
+}
+
+/// Time resolution of a redox series
+#[derive(ToSchema, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RedoxResolution {
+    #[default]
+    Raw,
+    Hourly,
+    Daily,
+}
+
+impl RedoxResolution {
+    /// Resolution for a date range, with the same thresholds as the temperature series
+    pub fn for_span_days(span_days: i64) -> Self {
+        if span_days <= 7 {
+            Self::Raw
+        } else if span_days <= 90 {
+            Self::Hourly
+        } else {
+            Self::Daily
+        }
+    }
+
+    /// Continuous aggregates of the potentials and of the logger temperature
+    fn aggregates(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            Self::Raw => None,
+            Self::Hourly => Some(("redox_measurement_hourly", "redox_data_hourly")),
+            Self::Daily => Some(("redox_measurement_daily", "redox_data_daily")),
         }
     }
 }
 
+/// Load the redox potentials of a profile grouped by electrode depth, with the
+/// logger temperature, for an optional date range. With `RedoxScale::She` each
+/// reading is converted to Eh using the temperature of its record, or of its
+/// bucket for aggregated resolutions.
+pub async fn load_redox_series(
+    db: &DatabaseConnection,
+    sensorprofile_id: Uuid,
+    date_from: Option<DateTime<Utc>>,
+    date_to: Option<DateTime<Utc>>,
+    resolution: RedoxResolution,
+    scale: RedoxScale,
+    ph: Option<f64>,
+) -> Result<RedoxSeries, DbErr> {
+    let (reference_electrode, conversion) = eh_conversion(db, sensorprofile_id, scale, ph).await?;
+    let channels = load_channels(db, sensorprofile_id).await?;
+
+    let (potential_sql, temperature_sql) = match resolution.aggregates() {
+        None => (
+            r"
+            SELECT c.depth_cm, m.measured_on AS time_utc, d.temp_c, m.potential_mv
+            FROM redox_measurement AS m
+            JOIN redox_channel AS c ON c.id = m.channel_id
+            JOIN redox_data AS d ON d.id = m.redox_data_id AND d.measured_on = m.measured_on
+            WHERE c.sensorprofile_id = $1
+              AND ($2::timestamptz IS NULL OR m.measured_on >= $2)
+              AND ($3::timestamptz IS NULL OR m.measured_on <= $3)
+            ORDER BY m.measured_on, c.depth_cm
+            "
+            .to_string(),
+            r"
+            SELECT measured_on AS time_utc, temp_c
+            FROM redox_data
+            WHERE sensorprofile_id = $1 AND temp_c IS NOT NULL
+              AND ($2::timestamptz IS NULL OR measured_on >= $2)
+              AND ($3::timestamptz IS NULL OR measured_on <= $3)
+            ORDER BY measured_on
+            "
+            .to_string(),
+        ),
+        Some((potential_view, temperature_view)) => (
+            format!(
+                r"
+                SELECT c.depth_cm, a.bucket AS time_utc, t.avg_temp_c AS temp_c,
+                       a.avg_potential_mv AS potential_mv
+                FROM {potential_view} AS a
+                JOIN redox_channel AS c ON c.id = a.channel_id
+                LEFT JOIN {temperature_view} AS t
+                  ON t.sensorprofile_id = c.sensorprofile_id AND t.bucket = a.bucket
+                WHERE c.sensorprofile_id = $1
+                  AND ($2::timestamptz IS NULL OR a.bucket >= $2)
+                  AND ($3::timestamptz IS NULL OR a.bucket <= $3)
+                ORDER BY a.bucket, c.depth_cm
+                "
+            ),
+            format!(
+                r"
+                SELECT bucket AS time_utc, avg_temp_c AS temp_c
+                FROM {temperature_view}
+                WHERE sensorprofile_id = $1 AND avg_temp_c IS NOT NULL
+                  AND ($2::timestamptz IS NULL OR bucket >= $2)
+                  AND ($3::timestamptz IS NULL OR bucket <= $3)
+                ORDER BY bucket
+                "
+            ),
+        ),
+    };
+    let query = |sql: String| {
+        Statement::from_sql_and_values(
+            db.get_database_backend(),
+            sql,
+            vec![sensorprofile_id.into(), date_from.into(), date_to.into()],
+        )
+    };
+
+    let rows = db.query_all(query(potential_sql)).await?;
+    let readings = rows.iter().filter_map(|row| {
+        let potential_mv = row.try_get::<f64>("", "potential_mv").ok()?;
+        let temp_c = row.try_get::<Option<f64>>("", "temp_c").ok()?;
+        Some((
+            row.try_get::<i32>("", "depth_cm").ok()?,
+            row.try_get::<DateTime<Utc>>("", "time_utc").ok()?,
+            conversion.map_or(potential_mv, |c| c.apply(potential_mv, temp_c)),
+        ))
+    });
+    let redox_mv_by_depth_cm = average_by_depth(readings);
+
+    let temperature_c = db
+        .query_all(query(temperature_sql))
+        .await?
+        .iter()
+        .filter_map(|row| {
+            Some(DepthAverageData {
+                time_utc: row.try_get("", "time_utc").ok()?,
+                y: row.try_get("", "temp_c").ok()?,
+            })
+        })
+        .collect();
+
+    Ok(RedoxSeries {
+        channels: channels.into_iter().map(RedoxChannel::from).collect(),
+        reference_electrode,
+        scale,
+        ph,
+        resolution,
+        redox_mv_by_depth_cm,
+        temperature_c,
+    })
+}
+
+/// Time spent in oxic, suboxic and anoxic conditions per electrode depth
+#[derive(ToSchema, Serialize, Deserialize, Debug, Clone)]
+pub struct RedoxClassSummary {
+    pub sensorprofile_id: Uuid,
+    pub reference_electrode: Option<ReferenceElectrodeEnum>,
+    pub ph: Option<f64>,
+    pub oxic_above_mv: f64,
+    pub anoxic_below_mv: f64,
+    pub depths: Vec<RedoxClassFractions>,
+}
+
+/// Classify the Eh series of a profile by depth. Thresholds apply to Eh
+/// against SHE, normalised to pH 7 when `ph` is given.
+pub async fn redox_class_summary(
+    db: &DatabaseConnection,
+    sensorprofile_id: Uuid,
+    date_from: Option<DateTime<Utc>>,
+    date_to: Option<DateTime<Utc>>,
+    ph: Option<f64>,
+    (oxic_above_mv, anoxic_below_mv): (f64, f64),
+) -> Result<RedoxClassSummary, DbErr> {
+    if anoxic_below_mv > oxic_above_mv {
+        return Err(DbErr::Custom(
+            "anoxic_below_mv must not exceed oxic_above_mv".into(),
+        ));
+    }
+    let series = load_redox_series(
+        db,
+        sensorprofile_id,
+        date_from,
+        date_to,
+        RedoxResolution::Raw,
+        RedoxScale::She,
+        ph,
+    )
+    .await?;
+
+    let mut depths: Vec<RedoxClassFractions> = series
+        .redox_mv_by_depth_cm
+        .iter()
+        .filter_map(|(depth_cm, eh)| class_fractions(*depth_cm, eh, oxic_above_mv, anoxic_below_mv))
+        .collect();
+    depths.sort_by_key(|d| d.depth_cm);
+
+    Ok(RedoxClassSummary {
+        sensorprofile_id,
+        reference_electrode: series.reference_electrode,
+        ph,
+        oxic_above_mv,
+        anoxic_below_mv,
+        depths,
+    })
+}
+
 #[async_trait]
 impl CRUDResource for RedoxData {
     type EntityType = super::db::Entity;
@@ -50,8 +465,7 @@ impl CRUDResource for RedoxData {
     const ID_COLUMN: Self::ColumnType = super::db::Column::Id;
     const RESOURCE_NAME_SINGULAR: &'static str = "redox data";
     const RESOURCE_NAME_PLURAL: &'static str = "redox data records";
-    const RESOURCE_DESCRIPTION: &'static str =
-        "Redox potential measurements at multiple depths (5, 15, 25, 35 cm).";
+    const RESOURCE_DESCRIPTION: &'static str = "Redox potential measurements of the electrode channels defined on a redox profile. Potentials are keyed by channel name; the channel determines the depth.";
 
     async fn get_all(
         db: &DatabaseConnection,
@@ -68,7 +482,17 @@ impl CRUDResource for RedoxData {
             .limit(limit)
             .all(db)
             .await?;
-        Ok(models.into_iter().map(RedoxData::from).collect())
+        let mut potentials = load_potentials(db, models.iter().map(|m| m.id).collect()).await?;
+        Ok(models
+            .into_iter()
+            .map(|model| {
+                let potentials_mv = potentials.remove(&model.id).unwrap_or_default();
+                RedoxData {
+                    potentials_mv,
+                    ..RedoxData::from(model)
+                }
+            })
+            .collect())
     }
 
     async fn get_one(db: &DatabaseConnection, id: Uuid) -> Result<Self, DbErr> {
@@ -80,7 +504,33 @@ impl CRUDResource for RedoxData {
                 "{} not found",
                 Self::RESOURCE_NAME_SINGULAR
             )))?;
-        Ok(RedoxData::from(model))
+        let mut redox_data = RedoxData::from(model);
+        redox_data.potentials_mv = load_potentials(db, vec![id])
+            .await?
+            .remove(&id)
+            .unwrap_or_default();
+        Ok(redox_data)
+    }
+
+    async fn create(
+        db: &DatabaseConnection,
+        create_model: Self::CreateModel,
+    ) -> Result<Self, DbErr> {
+        let txn = db.begin().await?;
+        let channels = channel_ids(&txn, create_model.sensorprofile_id).await?;
+        check_channel_names(&channels, &create_model.potentials_mv)?;
+        let active_model: Self::ActiveModelType = create_model.clone().into();
+        let result = Self::EntityType::insert(active_model).exec(&txn).await?;
+        save_potentials(
+            &txn,
+            &channels,
+            result.last_insert_id,
+            create_model.measured_on,
+            &create_model.potentials_mv,
+        )
+        .await?;
+        txn.commit().await?;
+        Self::get_one(db, result.last_insert_id).await
     }
 
     async fn update(
@@ -88,18 +538,56 @@ impl CRUDResource for RedoxData {
         id: Uuid,
         update_model: Self::UpdateModel,
     ) -> Result<Self, DbErr> {
-        let db_obj: super::db::ActiveModel = super::db::Entity::find_by_id(id)
+        let potentials_mv = update_model.potentials_mv.clone();
+        let existing = super::db::Entity::find_by_id(id)
             .one(db)
             .await?
             .ok_or(DbErr::RecordNotFound(format!(
                 "{} not found",
                 Self::RESOURCE_NAME_SINGULAR
-            )))?
-            .into();
-        let updated_obj: super::db::ActiveModel = update_model.merge_into_activemodel(db_obj);
-        let response_obj = updated_obj.update(db).await?;
-        let obj = Self::get_one(db, response_obj.id).await?;
-        Ok(obj)
+            )))?;
+        let (old_profile_id, old_measured_on) = (existing.sensorprofile_id, existing.measured_on);
+
+        // An empty map leaves the potentials untouched; otherwise it replaces them
+        let txn = db.begin().await?;
+        let updated_obj: super::db::ActiveModel =
+            update_model.merge_into_activemodel(existing.into());
+        let sensorprofile_id = *updated_obj.sensorprofile_id.as_ref();
+        // The stored potentials belong to channels of the old profile
+        if sensorprofile_id != old_profile_id && potentials_mv.is_empty() {
+            return Err(DbErr::Custom(
+                "Changing the sensor profile of a redox record requires its potentials_mv".into(),
+            ));
+        }
+        let channels = channel_ids(&txn, sensorprofile_id).await?;
+        check_channel_names(&channels, &potentials_mv)?;
+        let response_obj = updated_obj.update(&txn).await?;
+        if potentials_mv.is_empty() && response_obj.measured_on != old_measured_on {
+            MeasurementDB::Entity::update_many()
+                .col_expr(
+                    MeasurementDB::Column::MeasuredOn,
+                    Expr::value(response_obj.measured_on),
+                )
+                .filter(MeasurementDB::Column::RedoxDataId.eq(id))
+                .exec(&txn)
+                .await?;
+        }
+        if !potentials_mv.is_empty() {
+            MeasurementDB::Entity::delete_many()
+                .filter(MeasurementDB::Column::RedoxDataId.eq(id))
+                .exec(&txn)
+                .await?;
+            save_potentials(
+                &txn,
+                &channels,
+                id,
+                response_obj.measured_on,
+                &potentials_mv,
+            )
+            .await?;
+        }
+        txn.commit().await?;
+        Self::get_one(db, response_obj.id).await
     }
 
     fn sortable_columns() -> Vec<(&'static str, Self::ColumnType)> {
@@ -111,8 +599,54 @@ impl CRUDResource for RedoxData {
     }
 
     fn filterable_columns() -> Vec<(&'static str, Self::ColumnType)> {
-        vec![
-            ("sensorprofile_id", Self::ColumnType::SensorprofileId),
-        ]
+        vec![("sensorprofile_id", Self::ColumnType::SensorprofileId)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn channels_at_same_depth_are_averaged() {
+        let t0 = Utc.with_ymd_and_hms(2026, 6, 1, 0, 0, 0).unwrap();
+        let t1 = Utc.with_ymd_and_hms(2026, 6, 1, 1, 0, 0).unwrap();
+        let series = average_by_depth([
+            (10, t0, 300.0),
+            (10, t0, 200.0),
+            (30, t0, -50.0),
+            (10, t1, 240.0),
+        ]);
+
+        let shallow = &series[&10];
+        assert_eq!(shallow.len(), 2);
+        assert_eq!(shallow[0].time_utc, t0);
+        assert!((shallow[0].y - 250.0).abs() < 1e-9);
+        assert!((shallow[1].y - 240.0).abs() < 1e-9);
+        assert_eq!(series[&30].len(), 1);
+    }
+
+    #[test]
+    fn unknown_channel_names_are_all_listed() {
+        let channels = HashMap::from([
+            ("ch1".to_string(), Uuid::new_v4()),
+            ("ch2".to_string(), Uuid::new_v4()),
+        ]);
+        let known = BTreeMap::from([("ch1".to_string(), 310.0)]);
+        assert!(check_channel_names(&channels, &known).is_ok());
+
+        let mixed = BTreeMap::from([
+            ("ch1".to_string(), 310.0),
+            ("ch5".to_string(), 120.0),
+            ("ch9".to_string(), -40.0),
+        ]);
+        let Err(DbErr::Custom(message)) = check_channel_names(&channels, &mixed) else {
+            panic!("expected unknown channel error");
+        };
+        assert_eq!(
+            message,
+            "Unknown redox channels for this profile: ch5, ch9 (channels: ch1, ch2)"
+        );
     }
 }
@@ -0,0 +1,260 @@
+use crate::common::files::{DelimitedTable, is_toa5, read_delimited, read_toa5};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+/// Supported redox logger exports
+#[derive(ToSchema, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RedoxFileFormat {
+    /// Campbell Scientific TOA5 ASCII table
+    Toa5,
+    /// Delimited text with a header row (`,`, `;` or tab separated)
+    Csv,
+}
+
+impl RedoxFileFormat {
+    /// Guess the format from the file content
+    pub fn detect(text: &str) -> Self {
+        if is_toa5(text) { Self::Toa5 } else { Self::Csv }
+    }
+}
+
+/// Columns to read from a logger file
+#[derive(Debug, Clone, Default)]
+pub struct RedoxColumns {
+    /// Logger column and the name of the channel it belongs to
+    pub channels: Vec<(String, String)>,
+    /// Detected from usual names (`Temp_C`, `SoilTemp`, ...) when unset
+    pub temperature: Option<String>,
+    /// Detected from usual names (`TIMESTAMP`, `DateTime`, ...) when unset
+    pub timestamp: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RedoxReading {
+    pub time_utc: DateTime<Utc>,
+    pub temp_c: Option<f64>,
+    /// Potentials in mV keyed by channel name
+    pub potentials_mv: BTreeMap<String, f64>,
+}
+
+/// Result of parsing a logger file
+#[derive(Debug, Clone, Default)]
+pub struct ParsedRedoxFile {
+    pub readings: Vec<RedoxReading>,
+    /// Channels whose column was found in the file
+    pub channels: Vec<String>,
+    /// Mapped columns missing from the file
+    pub missing_columns: Vec<String>,
+}
+
+fn parse_timestamp(value: &str) -> Result<NaiveDateTime, String> {
+    const FORMATS: [&str; 7] = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y/%m/%d %H:%M:%S",
+        "%d.%m.%Y %H:%M:%S",
+        "%d.%m.%Y %H:%M",
+    ];
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.naive_utc());
+    }
+    FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .ok_or_else(|| format!("Invalid timestamp '{value}'"))
+}
+
+fn find_column(names: &[&str], name: &str) -> Option<usize> {
+    names.iter().position(|n| n.eq_ignore_ascii_case(name))
+}
+
+fn detect_timestamp_column(names: &[&str]) -> Option<usize> {
+    names.iter().position(|name| {
+        let name = name.to_lowercase();
+        ["timestamp", "datetime", "date_time", "time", "measured_on"].contains(&name.as_str())
+    })
+}
+
+/// Logger or soil temperature; the panel temperature (`PTemp`) is not used
+fn detect_temperature_column(names: &[&str]) -> Option<usize> {
+    names.iter().position(|name| {
+        let name = name.to_lowercase();
+        name.starts_with("temp") || name.starts_with("soiltemp") || name.starts_with("t_soil")
+    })
+}
+
+/// Read the data rows of a table. Values that do not parse (`NAN`, empty) are
+/// treated as missing.
+fn parse_rows(
+    table: &DelimitedTable,
+    columns: &RedoxColumns,
+    utc_offset_minutes: i32,
+) -> Result<ParsedRedoxFile, String> {
+    let names: Vec<&str> = table.names.iter().map(String::as_str).collect();
+    let time_col = match &columns.timestamp {
+        Some(name) => find_column(&names, name)
+            .ok_or_else(|| format!("Timestamp column '{name}' not found in file"))?,
+        None => detect_timestamp_column(&names).ok_or("No timestamp column found in file")?,
+    };
+    let temp_col = match &columns.temperature {
+        Some(name) => Some(
+            find_column(&names, name)
+                .ok_or_else(|| format!("Temperature column '{name}' not found in file"))?,
+        ),
+        None => detect_temperature_column(&names),
+    };
+
+    let mut parsed = ParsedRedoxFile::default();
+    let mut channel_cols = Vec::new();
+    for (column, channel) in &columns.channels {
+        match find_column(&names, column) {
+            Some(index) => {
+                channel_cols.push((index, channel));
+                parsed.channels.push(channel.clone());
+            }
+            None => parsed.missing_columns.push(column.clone()),
+        }
+    }
+    if channel_cols.is_empty() {
+        return Err(format!(
+            "None of the channel columns ({}) found in file; set `column_name` on the profile's redox channels",
+            columns
+                .channels
+                .iter()
+                .map(|(column, _)| column.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    let value = |fields: &csv::StringRecord, i: usize| {
+        fields
+            .get(i)
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|v| v.is_finite())
+    };
+    for fields in &table.rows {
+        let timestamp = fields.get(time_col).unwrap_or_default();
+        let naive = parse_timestamp(timestamp)?;
+        let potentials_mv: BTreeMap<String, f64> = channel_cols
+            .iter()
+            .filter_map(|(i, channel)| value(fields, *i).map(|v| ((*channel).clone(), v)))
+            .collect();
+        if potentials_mv.is_empty() {
+            continue;
+        }
+        parsed.readings.push(RedoxReading {
+            time_utc: naive.and_utc() - Duration::minutes(i64::from(utc_offset_minutes)),
+            temp_c: temp_col.and_then(|i| value(fields, i)),
+            potentials_mv,
+        });
+    }
+    Ok(parsed)
+}
+
+/// Parse a Campbell TOA5 file (environment, field names, units and processing
+/// header lines, then data).
+pub fn parse_toa5(
+    text: &str,
+    columns: &RedoxColumns,
+    utc_offset_minutes: i32,
+) -> Result<ParsedRedoxFile, String> {
+    parse_rows(&read_toa5(text)?, columns, utc_offset_minutes)
+}
+
+/// Parse a delimited file whose first non-empty line holds the column names.
+/// The delimiter is the most frequent of `,`, `;` and tab in that line.
+pub fn parse_csv(
+    text: &str,
+    columns: &RedoxColumns,
+    utc_offset_minutes: i32,
+) -> Result<ParsedRedoxFile, String> {
+    parse_rows(&read_delimited(text)?, columns, utc_offset_minutes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(pairs: &[(&str, &str)]) -> RedoxColumns {
+        RedoxColumns {
+            channels: pairs
+                .iter()
+                .map(|(column, channel)| ((*column).to_string(), (*channel).to_string()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_toa5_with_channel_mapping() {
+        let text = "\"TOA5\",\"Redox1\",\"CR1000X\",\"4321\",\"CR1000X.Std.05\",\"CPU:redox.CR1X\",\"1\",\"Redox\"\n\
+                    \"TIMESTAMP\",\"RECORD\",\"PTemp_C\",\"Temp_C\",\"Redox_mV(1)\",\"Redox_mV(2)\"\n\
+                    \"TS\",\"RN\",\"Deg C\",\"Deg C\",\"mV\",\"mV\"\n\
+                    \"\",\"\",\"Smp\",\"Smp\",\"Avg\",\"Avg\"\n\
+                    \"2026-05-01 02:00:00\",1,18.2,11.5,412.3,-85.1\n\
+                    \"2026-05-01 02:10:00\",2,18.3,11.4,\"NAN\",-86.0\n\
+                    \"2026-05-01 02:20:00\",3,18.3,11.4,\"NAN\",\"NAN\"\n";
+        let parsed = parse_toa5(
+            text,
+            &columns(&[
+                ("Redox_mV(1)", "e5"),
+                ("Redox_mV(2)", "e15"),
+                ("Redox_mV(3)", "e25"),
+            ]),
+            60,
+        )
+        .unwrap();
+
+        assert_eq!(parsed.readings.len(), 2);
+        assert_eq!(parsed.channels, vec!["e5", "e15"]);
+        assert_eq!(parsed.missing_columns, vec!["Redox_mV(3)"]);
+        let first = &parsed.readings[0];
+        assert_eq!(
+            first.time_utc,
+            DateTime::parse_from_rfc3339("2026-05-01T01:00:00Z").unwrap()
+        );
+        assert_eq!(first.temp_c, Some(11.5));
+        assert!((first.potentials_mv["e5"] - 412.3).abs() < 1e-9);
+        assert!(!parsed.readings[1].potentials_mv.contains_key("e5"));
+    }
+
+    #[test]
+    fn parses_semicolon_csv() {
+        let text = "Date Time;ch1;ch2;SoilTemp\n\
+                    01.05.2026 12:00;350;120;9.8\n\
+                    01.05.2026 12:30;348;;9.9\n";
+        let parsed = parse_csv(
+            text,
+            &RedoxColumns {
+                timestamp: Some("Date Time".into()),
+                ..columns(&[("ch1", "ch1"), ("CH2", "ch2")])
+            },
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(parsed.readings.len(), 2);
+        assert_eq!(parsed.readings[1].potentials_mv.len(), 1);
+        assert_eq!(parsed.readings[1].temp_c, Some(9.9));
+        let unmapped = RedoxColumns {
+            timestamp: Some("Date Time".into()),
+            ..columns(&[("ch9", "ch9")])
+        };
+        assert!(parse_csv(text, &unmapped, 0).is_err());
+    }
+
+    #[test]
+    fn quoted_delimiters_do_not_shift_columns() {
+        let text = "TIMESTAMP,Note,ch1\n\
+                    2026-05-01 12:00:00,\"cleaned, recalibrated\",350\n";
+        let parsed = parse_csv(text, &columns(&[("ch1", "ch1")]), 0).unwrap();
+        assert_eq!(parsed.readings.len(), 1);
+        assert!((parsed.readings[0].potentials_mv["ch1"] - 350.0).abs() < 1e-9);
+    }
+}
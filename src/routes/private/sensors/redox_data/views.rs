@@ -1,11 +1,24 @@
-use super::models::{RedoxData, RedoxDataCreate, RedoxDataUpdate};
+use super::channels::models::load_channels;
+use super::eh::RedoxScale;
+use super::models::{
+    RedoxClassSummary, RedoxData, RedoxDataCreate, RedoxDataUpdate, RedoxResolution, RedoxSeries,
+    channel_ids, load_redox_series, redox_class_summary, upsert_redox_record,
+};
+use super::parsers::{RedoxColumns, RedoxFileFormat, parse_csv, parse_toa5};
 use crate::common::auth::Role;
+use crate::common::errors::db_error_response;
+use crate::common::files::decode_base64_text;
+use crate::common::idempotency::{self, Claim};
+use crate::common::models::BatchQuery;
+use crate::routes::private::sensors::profile::db::{self as ProfileDB, ProfileTypeEnum};
 use axum_keycloak_auth::{
     PassthroughMode, instance::KeycloakAuthInstance, layer::KeycloakAuthLayer,
 };
+use chrono::{DateTime, Utc};
 use crudcrate::{CRUDResource, crud_handlers};
-use sea_orm::{ActiveModelTrait, ActiveValue, DatabaseConnection};
-use serde::Serialize;
+use sea_orm::{ConnectionTrait, DatabaseConnection, EntityTrait, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use utoipa::ToSchema;
 use utoipa_axum::{router::OpenApiRouter, routes};
@@ -13,58 +26,360 @@ use utoipa_axum::{router::OpenApiRouter, routes};
 crud_handlers!(RedoxData, RedoxDataUpdate, RedoxDataCreate);
 
 /// A single error from a batch ingest operation.
-#[derive(Serialize, ToSchema)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct BatchIngestError {
     pub index: usize,
     pub message: String,
 }
 
 /// Result of a batch ingest operation.
-#[derive(Serialize, ToSchema)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct BatchIngestResult {
+    /// Records inserted or replaced
     pub inserted: usize,
     pub errors: Vec<BatchIngestError>,
 }
 
+async fn ingest_redox_record<C: ConnectionTrait>(
+    db: &C,
+    create_data: RedoxDataCreate,
+) -> Result<(), DbErr> {
+    let channels = channel_ids(db, create_data.sensorprofile_id).await?;
+    upsert_redox_record(db, &channels, create_data).await
+}
+
 #[utoipa::path(
     post,
     path = "/batch",
     request_body = Vec<RedoxDataCreate>,
+    params(
+        ("atomic" = Option<bool>, Query, description = "Insert all entries in one transaction, rolling back on the first error"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when the same request was already processed with this key in the last 24 hours; reusing the key for a different request is rejected")
+    ),
     responses(
         (status = 200, description = "Batch create results.", body = BatchIngestResult),
+        (status = 400, description = "Invalid Idempotency-Key header"),
+        (status = 422, description = "Atomic batch rolled back; the failing entry is reported, or the Idempotency-Key was used with a different request", body = BatchIngestResult),
+        (status = 500, description = "Internal server error")
     ),
     summary = "Batch create redox data records",
-    description = "Accepts an array of redox data records. Each entry is inserted independently; errors are recorded per-entry without aborting the batch.",
+    description = "Accepts an array of redox data records. Potentials are keyed by the names of the profile's redox channels; unknown names fail the entry. Entries replace existing records with the same `sensorprofile_id` and `measured_on`, so re-sending a batch does not create duplicates. By default each entry is inserted independently and errors are recorded per entry without aborting the batch; with `atomic=true` the batch is all or nothing.",
     operation_id = "create_redox_data_batch",
 )]
 pub async fn create_redox_data_batch(
-    axum::extract::State(db): axum::extract::State<DatabaseConnection>,
-    axum::Json(requests): axum::Json<Vec<RedoxDataCreate>>,
-) -> axum::Json<BatchIngestResult> {
+    State(db): State<DatabaseConnection>,
+    Query(query): Query<BatchQuery>,
+    headers: HeaderMap,
+    Json(requests): Json<Vec<RedoxDataCreate>>,
+) -> Result<(StatusCode, Json<BatchIngestResult>), (StatusCode, Json<String>)> {
+    const SCOPE: &str = "redox_data/batch";
+
+    let key =
+        idempotency::key_from_headers(&headers).map_err(|e| (StatusCode::BAD_REQUEST, Json(e)))?;
+    // Holds the idempotency key until the response is stored; atomic batches
+    // also insert their entries in it
+    let txn = db.begin().await.map_err(db_error_response)?;
+    if let Some(key) = &key {
+        match idempotency::claim(&txn, SCOPE, key, &(query.atomic, &requests))
+            .await
+            .map_err(db_error_response)?
+        {
+            Claim::New => {}
+            Claim::Replay(stored) => return Ok((StatusCode::OK, Json(stored))),
+            Claim::Mismatch => {
+                return Err((
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    Json(idempotency::MISMATCH_MESSAGE.to_string()),
+                ));
+            }
+        }
+    }
+
     let mut inserted = 0usize;
     let mut errors = Vec::new();
 
-    for (index, create_data) in requests.into_iter().enumerate() {
-        let active_model = super::db::ActiveModel {
-            id: ActiveValue::Set(uuid::Uuid::new_v4()),
-            sensorprofile_id: ActiveValue::Set(create_data.sensorprofile_id),
-            measured_on: ActiveValue::Set(create_data.measured_on),
-            ch1_5cm_mv: ActiveValue::Set(create_data.ch1_5cm_mv),
-            ch2_15cm_mv: ActiveValue::Set(create_data.ch2_15cm_mv),
-            ch3_25cm_mv: ActiveValue::Set(create_data.ch3_25cm_mv),
-            ch4_35cm_mv: ActiveValue::Set(create_data.ch4_35cm_mv),
-            temp_c: ActiveValue::Set(create_data.temp_c),
-        };
-        match active_model.insert(&db).await {
-            Ok(_) => inserted += 1,
-            Err(e) => errors.push(BatchIngestError {
-                index,
-                message: format!("Failed to insert: {e}"),
-            }),
+    if query.atomic.unwrap_or(false) {
+        for (index, create_data) in requests.into_iter().enumerate() {
+            if let Err(e) = ingest_redox_record(&txn, create_data).await {
+                txn.rollback().await.map_err(db_error_response)?;
+                let errors = vec![BatchIngestError {
+                    index,
+                    message: format!("Failed to insert: {e}"),
+                }];
+                return Ok((
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    Json(BatchIngestResult {
+                        inserted: 0,
+                        errors,
+                    }),
+                ));
+            }
+            inserted += 1;
         }
+    } else {
+        for (index, create_data) in requests.into_iter().enumerate() {
+            // Each entry gets its own transaction so that a record is not kept
+            // when one of its potentials is rejected
+            let entry_txn = db.begin().await.map_err(db_error_response)?;
+            match ingest_redox_record(&entry_txn, create_data).await {
+                Ok(()) => {
+                    entry_txn.commit().await.map_err(db_error_response)?;
+                    inserted += 1;
+                }
+                Err(e) => errors.push(BatchIngestError {
+                    index,
+                    message: format!("Failed to insert: {e}"),
+                }),
+            }
+        }
+    }
+
+    let result = BatchIngestResult { inserted, errors };
+    if let Some(key) = &key {
+        idempotency::store(&txn, SCOPE, key, &result)
+            .await
+            .map_err(db_error_response)?;
+    }
+    txn.commit().await.map_err(db_error_response)?;
+    Ok((StatusCode::OK, Json(result)))
+}
+
+/// Request body for the redox logger file import endpoint.
+#[derive(Deserialize, ToSchema)]
+pub struct RedoxImportRequest {
+    pub sensorprofile_id: Uuid,
+    /// File content as a base64 data URL (or plain base64)
+    pub data_base64: String,
+    /// Detected from the content when omitted
+    pub format: Option<RedoxFileFormat>,
+    /// Offset of the logger clock from UTC in minutes
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+    /// Column holding the timestamp; detected when omitted
+    pub timestamp_column: Option<String>,
+    /// Column holding the logger or soil temperature; detected when omitted
+    pub temperature_column: Option<String>,
+}
+
+/// Result of a redox logger file import.
+#[derive(Serialize, ToSchema)]
+pub struct RedoxImportResult {
+    /// Records inserted or replaced; rows without any potential are skipped
+    pub imported: usize,
+    /// Channels found in the file
+    pub channels: Vec<String>,
+    /// Channel columns missing from the file
+    pub missing_columns: Vec<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/import",
+    request_body = RedoxImportRequest,
+    responses(
+        (status = 201, description = "Logger file imported.", body = RedoxImportResult),
+        (status = 404, description = "Sensor profile not found"),
+        (status = 422, description = "Invalid file, profile type or column mapping"),
+        (status = 500, description = "Internal server error")
+    ),
+    summary = "Import a redox logger file",
+    description = "Imports a Campbell Scientific TOA5 file or a delimited CSV export into a redox profile. Each redox channel of the profile is read from the column named by its `column_name`, or by its name when unset; channels without a column in the file are reported and skipped. Records replace existing ones with the same timestamp, and the import is rolled back as a whole on error.",
+    operation_id = "import_redox_data",
+)]
+pub async fn import_redox_data(
+    State(db): State<DatabaseConnection>,
+    Json(req): Json<RedoxImportRequest>,
+) -> Result<(StatusCode, Json<RedoxImportResult>), (StatusCode, Json<String>)> {
+    let db_error = |e: DbErr| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(format!("Database error: {e}")),
+        )
+    };
+    let profile = ProfileDB::Entity::find_by_id(req.sensorprofile_id)
+        .one(&db)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(format!("Sensor profile {} not found", req.sensorprofile_id)),
+            )
+        })?;
+    if profile.profile_type != ProfileTypeEnum::Redox {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json("Profile must be of type 'redox'".to_string()),
+        ));
     }
 
-    axum::Json(BatchIngestResult { inserted, errors })
+    let channels = load_channels(&db, profile.id).await.map_err(db_error)?;
+    if channels.is_empty() {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json("Profile has no redox channels".to_string()),
+        ));
+    }
+    let columns = RedoxColumns {
+        channels: channels
+            .iter()
+            .map(|c| {
+                let column = c.column_name.clone().unwrap_or_else(|| c.name.clone());
+                (column, c.name.clone())
+            })
+            .collect(),
+        temperature: req.temperature_column,
+        timestamp: req.timestamp_column,
+    };
+
+    let text = decode_base64_text(&req.data_base64)
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, Json(e)))?;
+
+    let format = req.format.unwrap_or_else(|| RedoxFileFormat::detect(&text));
+    let parsed = match format {
+        RedoxFileFormat::Toa5 => parse_toa5(&text, &columns, req.utc_offset_minutes),
+        RedoxFileFormat::Csv => parse_csv(&text, &columns, req.utc_offset_minutes),
+    }
+    .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, Json(e)))?;
+    if parsed.readings.is_empty() {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json("No readings found in file".to_string()),
+        ));
+    }
+
+    let channel_ids: HashMap<String, Uuid> = channels.into_iter().map(|c| (c.name, c.id)).collect();
+    let imported = parsed.readings.len();
+    let txn = db.begin().await.map_err(db_error)?;
+    for reading in parsed.readings {
+        let create_data = RedoxDataCreate {
+            sensorprofile_id: profile.id,
+            measured_on: reading.time_utc,
+            temp_c: reading.temp_c,
+            potentials_mv: reading.potentials_mv,
+        };
+        upsert_redox_record(&txn, &channel_ids, create_data)
+            .await
+            .map_err(db_error)?;
+    }
+    txn.commit().await.map_err(db_error)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(RedoxImportResult {
+            imported,
+            channels: parsed.channels,
+            missing_columns: parsed.missing_columns,
+        }),
+    ))
+}
+
+/// Default Eh thresholds (mV, SHE) between oxic, suboxic and anoxic conditions
+const OXIC_ABOVE_MV: f64 = 300.0;
+const ANOXIC_BELOW_MV: f64 = 100.0;
+
+#[derive(Deserialize, Debug)]
+pub struct RedoxSeriesQuery {
+    pub sensorprofile_id: Uuid,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub resolution: Option<RedoxResolution>,
+    pub scale: Option<RedoxScale>,
+    pub ph: Option<f64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RedoxSummaryQuery {
+    pub sensorprofile_id: Uuid,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub ph: Option<f64>,
+    pub oxic_above_mv: Option<f64>,
+    pub anoxic_below_mv: Option<f64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/series",
+    responses(
+        (status = 200, description = "Redox potentials grouped by depth.", body = RedoxSeries),
+        (status = 404, description = "Sensor profile not found"),
+        (status = 422, description = "Eh requested without a reference electrode or with an invalid pH"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("sensorprofile_id" = Uuid, Query, description = "Redox profile"),
+        ("start" = Option<String>, Query, description = "Start of date range (ISO 8601)"),
+        ("end" = Option<String>, Query, description = "End of date range (ISO 8601)"),
+        ("resolution" = Option<RedoxResolution>, Query, description = "`raw`, `hourly` or `daily`; chosen from the date range when omitted (raw without a complete range)"),
+        ("scale" = Option<RedoxScale>, Query, description = "`reference` (default) for raw potentials or `she` for Eh"),
+        ("ph" = Option<f64>, Query, description = "Normalise Eh from this soil pH to pH 7; requires `scale=she`")
+    ),
+    summary = "Get redox potentials by depth",
+    description = "Returns the potentials of a redox profile grouped by electrode depth in cm, averaging channels at the same depth, and the logger temperature. Hourly and daily resolutions read the continuous aggregates. With `scale=she` each reading is converted to Eh against the standard hydrogen electrode from the profile's reference electrode, corrected for the logger temperature (25 °C when missing). `ph` additionally normalises Eh to pH 7 with the Nernst slope.",
+    operation_id = "get_redox_series",
+)]
+pub async fn get_redox_series(
+    State(db): State<DatabaseConnection>,
+    Query(query): Query<RedoxSeriesQuery>,
+) -> Result<Json<RedoxSeries>, (StatusCode, Json<String>)> {
+    let resolution = query.resolution.unwrap_or(match (query.start, query.end) {
+        (Some(start), Some(end)) => RedoxResolution::for_span_days((end - start).num_days()),
+        _ => RedoxResolution::Raw,
+    });
+    load_redox_series(
+        &db,
+        query.sensorprofile_id,
+        query.start,
+        query.end,
+        resolution,
+        query.scale.unwrap_or_default(),
+        query.ph,
+    )
+    .await
+    .map(Json)
+    .map_err(db_error_response)
+}
+
+#[utoipa::path(
+    get,
+    path = "/summary",
+    responses(
+        (status = 200, description = "Redox class fractions per depth.", body = RedoxClassSummary),
+        (status = 404, description = "Sensor profile not found"),
+        (status = 422, description = "No reference electrode, invalid pH or thresholds"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("sensorprofile_id" = Uuid, Query, description = "Redox profile"),
+        ("start" = Option<String>, Query, description = "Start of date range (ISO 8601)"),
+        ("end" = Option<String>, Query, description = "End of date range (ISO 8601)"),
+        ("ph" = Option<f64>, Query, description = "Normalise Eh from this soil pH to pH 7 before classifying"),
+        ("oxic_above_mv" = Option<f64>, Query, description = "Eh above which conditions are oxic (default 300 mV)"),
+        ("anoxic_below_mv" = Option<f64>, Query, description = "Eh below which conditions are anoxic (default 100 mV)")
+    ),
+    summary = "Summarise redox classes by depth",
+    description = "Converts the potentials of a redox profile to Eh against the standard hydrogen electrode and returns, per electrode depth, the fraction of time spent in oxic, suboxic and anoxic conditions with the mean, minimum and maximum Eh. Each reading is weighted by half the interval to its neighbours so that irregular logging does not bias the fractions.",
+    operation_id = "summarise_redox_classes",
+)]
+pub async fn summarise_redox_classes(
+    State(db): State<DatabaseConnection>,
+    Query(query): Query<RedoxSummaryQuery>,
+) -> Result<Json<RedoxClassSummary>, (StatusCode, Json<String>)> {
+    redox_class_summary(
+        &db,
+        query.sensorprofile_id,
+        query.start,
+        query.end,
+        query.ph,
+        (
+            query.oxic_above_mv.unwrap_or(OXIC_ABOVE_MV),
+            query.anoxic_below_mv.unwrap_or(ANOXIC_BELOW_MV),
+        ),
+    )
+    .await
+    .map(Json)
+    .map_err(db_error_response)
 }
 
 pub fn router(
@@ -82,6 +397,9 @@ where
         .routes(routes!(delete_one_handler))
         .routes(routes!(delete_many_handler))
         .routes(routes!(create_redox_data_batch))
+        .routes(routes!(import_redox_data))
+        .routes(routes!(get_redox_series))
+        .routes(routes!(summarise_redox_classes))
         .with_state(db.clone());
 
     if let Some(instance) = keycloak_auth_instance {
@@ -87,3 +87,29 @@ pub fn process_sensor_data_base64(
     let data_objs = ingest_csv_data(&raw_data, sensor_id)?;
     Ok(data_objs)
 }
+
+/// Process a base64 logger export of a registered (non-TMS) sensor type into
+/// generic measurements, one per channel and timestamp.
+pub fn process_sensor_measurements_base64(
+    data_base64: &str,
+    sensor_type: &crate::routes::private::sensors::types::db::Model,
+    channels: &[crate::routes::private::sensors::types::channels::db::Model],
+) -> Result<Vec<crate::routes::private::sensors::types::parsers::ParsedMeasurement>, String> {
+    let text = crate::common::files::decode_base64_text(data_base64)?;
+    let config =
+        crate::routes::private::sensors::types::parsers::ParserConfig::for_sensor_type(
+            sensor_type,
+        )?;
+    let columns: Vec<(Uuid, usize)> = channels
+        .iter()
+        .map(|channel| {
+            usize::try_from(channel.column_index)
+                .map(|column| (channel.id, column))
+                .map_err(|_| format!("Invalid column index for channel '{}'", channel.name))
+        })
+        .collect::<Result<_, _>>()?;
+    if columns.is_empty() {
+        return Err(format!("Sensor type '{}' has no channels", sensor_type.name));
+    }
+    crate::routes::private::sensors::types::parsers::parse_delimited(&text, &config, &columns)
+}
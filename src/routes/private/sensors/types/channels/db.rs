@@ -0,0 +1,34 @@
+use sea_orm::entity::prelude::*;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "sensortype_channel")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub sensortype_id: Uuid,
+    pub name: String,
+    pub units: String,
+    pub column_index: i32,
+    pub description: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::routes::private::sensors::types::db::Entity",
+        from = "Column::SensortypeId",
+        to = "crate::routes::private::sensors::types::db::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    SensorType,
+}
+
+impl Related<crate::routes::private::sensors::types::db::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SensorType.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,28 @@
+use super::db::Model;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A measured quantity of a sensor type, e.g. `water_content` in m³/m³, read from
+/// the given (zero-based) column of the logger export.
+#[derive(ToSchema, Serialize, Deserialize, Clone, Debug)]
+pub struct SensorTypeChannel {
+    #[serde(default)]
+    pub id: Option<Uuid>,
+    pub name: String,
+    pub units: String,
+    pub column_index: i32,
+    pub description: Option<String>,
+}
+
+impl From<Model> for SensorTypeChannel {
+    fn from(model: Model) -> Self {
+        Self {
+            id: Some(model.id),
+            name: model.name,
+            units: model.units,
+            column_index: model.column_index,
+            description: model.description,
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(
+    Debug, Serialize, Deserialize, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "sensor_parser_enum")]
+pub enum SensorParserEnum {
+    /// TOMST TMS semicolon export, stored in the dedicated `sensordata` table
+    #[sea_orm(string_value = "tms")]
+    Tms,
+    #[sea_orm(string_value = "hobo_csv")]
+    HoboCsv,
+    #[sea_orm(string_value = "meter_zl6")]
+    MeterZl6,
+    #[sea_orm(string_value = "generic_csv")]
+    GenericCsv,
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "sensortype")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    #[sea_orm(unique)]
+    pub name: String,
+    pub manufacturer: Option<String>,
+    pub description: Option<String>,
+    pub parser: SensorParserEnum,
+    pub delimiter: Option<String>,
+    pub header_rows: Option<i32>,
+    pub timestamp_column: Option<i32>,
+    pub timestamp_format: Option<String>,
+    pub utc_offset_minutes: i32,
+    pub last_updated: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::channels::db::Entity")]
+    Channels,
+    #[sea_orm(has_many = "crate::routes::private::sensors::db::Entity")]
+    Sensors,
+}
+
+impl Related<super::channels::db::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Channels.def()
+    }
+}
+
+impl Related<crate::routes::private::sensors::db::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Sensors.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,5 @@
+pub mod channels;
+pub mod db;
+pub mod models;
+pub mod parsers;
+pub mod views;
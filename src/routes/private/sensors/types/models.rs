@@ -0,0 +1,282 @@
+use super::channels::models::SensorTypeChannel;
+use super::db::{self, SensorParserEnum};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use crudcrate::{CRUDResource, ToCreateModel, ToUpdateModel, traits::MergeIntoActiveModel};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait,
+    Order, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(ToSchema, Serialize, Deserialize, ToCreateModel, ToUpdateModel, Clone)]
+#[active_model = "super::db::ActiveModel"]
+pub struct SensorType {
+    #[crudcrate(update_model = false, create_model = false, on_create = Uuid::new_v4())]
+    pub id: Uuid,
+    pub name: String,
+    pub manufacturer: Option<String>,
+    pub description: Option<String>,
+    pub parser: SensorParserEnum,
+    pub delimiter: Option<String>,
+    pub header_rows: Option<i32>,
+    pub timestamp_column: Option<i32>,
+    pub timestamp_format: Option<String>,
+    pub utc_offset_minutes: i32,
+    #[crudcrate(update_model = false, create_model = false, on_update = chrono::Utc::now(), on_create = chrono::Utc::now())]
+    pub last_updated: DateTime<Utc>,
+    #[crudcrate(non_db_attr = true, default = vec![])]
+    pub channels: Vec<SensorTypeChannel>,
+}
+
+impl From<db::Model> for SensorType {
+    fn from(model: db::Model) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            manufacturer: model.manufacturer,
+            description: model.description,
+            parser: model.parser,
+            delimiter: model.delimiter,
+            header_rows: model.header_rows,
+            timestamp_column: model.timestamp_column,
+            timestamp_format: model.timestamp_format,
+            utc_offset_minutes: model.utc_offset_minutes,
+            last_updated: model.last_updated,
+            channels: vec![],
+        }
+    }
+}
+
+fn validate_channels(channels: &[SensorTypeChannel]) -> Result<(), DbErr> {
+    let mut names = std::collections::HashSet::new();
+    for channel in channels {
+        if channel.name.trim().is_empty() {
+            return Err(DbErr::Custom("Channel name must not be empty".into()));
+        }
+        if channel.column_index < 0 {
+            return Err(DbErr::Custom(format!(
+                "Channel '{}' has a negative column index",
+                channel.name
+            )));
+        }
+        if !names.insert(channel.name.as_str()) {
+            return Err(DbErr::Custom(format!(
+                "Duplicate channel name '{}'",
+                channel.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl CRUDResource for SensorType {
+    type EntityType = db::Entity;
+    type ColumnType = db::Column;
+    type ActiveModelType = db::ActiveModel;
+    type CreateModel = SensorTypeCreate;
+    type UpdateModel = SensorTypeUpdate;
+
+    const ID_COLUMN: Self::ColumnType = super::db::Column::Id;
+    const RESOURCE_NAME_PLURAL: &'static str = "sensor_types";
+    const RESOURCE_NAME_SINGULAR: &'static str = "sensor_type";
+    const RESOURCE_DESCRIPTION: &'static str = "A sensor type describes a logger model: the channels it records with their units, and the parser used to read its export files into the generic measurement table.";
+
+    async fn get_all(
+        db: &DatabaseConnection,
+        condition: Condition,
+        order_column: Self::ColumnType,
+        order_direction: Order,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<Self>, DbErr> {
+        let results: Vec<(db::Model, Vec<super::channels::db::Model>)> = Self::EntityType::find()
+            .filter(condition)
+            .order_by(order_column, order_direction)
+            .offset(offset)
+            .limit(limit)
+            .find_with_related(super::channels::db::Entity)
+            .all(db)
+            .await?;
+
+        Ok(results
+            .into_iter()
+            .map(|(model, mut channels)| {
+                channels.sort_by_key(|c| c.column_index);
+                let mut sensor_type: SensorType = model.into();
+                sensor_type.channels = channels.into_iter().map(Into::into).collect();
+                sensor_type
+            })
+            .collect())
+    }
+
+    async fn get_one(db: &DatabaseConnection, id: Uuid) -> Result<Self, DbErr> {
+        let model =
+            Self::EntityType::find_by_id(id)
+                .one(db)
+                .await?
+                .ok_or(DbErr::RecordNotFound(format!(
+                    "{} not found",
+                    Self::RESOURCE_NAME_SINGULAR
+                )))?;
+
+        let channels = super::channels::db::Entity::find()
+            .filter(super::channels::db::Column::SensortypeId.eq(id))
+            .order_by_asc(super::channels::db::Column::ColumnIndex)
+            .all(db)
+            .await?;
+
+        let mut sensor_type: SensorType = model.into();
+        sensor_type.channels = channels.into_iter().map(Into::into).collect();
+        Ok(sensor_type)
+    }
+
+    async fn create(
+        db: &DatabaseConnection,
+        create_model: Self::CreateModel,
+    ) -> Result<Self, DbErr> {
+        validate_channels(&create_model.channels)?;
+
+        let active_model: Self::ActiveModelType = create_model.clone().into();
+        let result = Self::EntityType::insert(active_model).exec(db).await?;
+        let sensortype_id = result.last_insert_id;
+
+        for channel in create_model.channels {
+            super::channels::db::Entity::insert(super::channels::db::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                sensortype_id: Set(sensortype_id),
+                name: Set(channel.name),
+                units: Set(channel.units),
+                column_index: Set(channel.column_index),
+                description: Set(channel.description),
+            })
+            .exec(db)
+            .await?;
+        }
+
+        match Self::get_one(db, sensortype_id).await {
+            Ok(obj) => Ok(obj),
+            Err(_) => Err(DbErr::RecordNotFound(format!(
+                "{} not created",
+                Self::RESOURCE_NAME_SINGULAR
+            ))),
+        }
+    }
+
+    async fn update(
+        db: &DatabaseConnection,
+        id: Uuid,
+        update_data: Self::UpdateModel,
+    ) -> Result<Self, DbErr> {
+        let new_channels = update_data.channels.clone();
+        validate_channels(&new_channels)?;
+
+        let existing: Self::ActiveModelType = Self::EntityType::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound(format!(
+                "{} not found",
+                Self::RESOURCE_NAME_SINGULAR
+            )))?
+            .into();
+        let updated = update_data
+            .merge_into_activemodel(existing)
+            .update(db)
+            .await?;
+
+        // An empty list leaves the channels untouched. Otherwise channels are matched
+        // by name so that the measurements of unchanged channels are kept, and
+        // channels missing from the list are removed (with their measurements).
+        if !new_channels.is_empty() {
+            let existing_channels = super::channels::db::Entity::find()
+                .filter(super::channels::db::Column::SensortypeId.eq(updated.id))
+                .all(db)
+                .await?;
+
+            for channel in &existing_channels {
+                if !new_channels.iter().any(|c| c.name == channel.name) {
+                    super::channels::db::Entity::delete_by_id(channel.id)
+                        .exec(db)
+                        .await?;
+                }
+            }
+
+            for channel in new_channels {
+                if let Some(existing) = existing_channels.iter().find(|c| c.name == channel.name) {
+                    let mut active: super::channels::db::ActiveModel = existing.clone().into();
+                    active.units = Set(channel.units);
+                    active.column_index = Set(channel.column_index);
+                    active.description = Set(channel.description);
+                    active.update(db).await?;
+                } else {
+                    super::channels::db::Entity::insert(super::channels::db::ActiveModel {
+                        id: Set(Uuid::new_v4()),
+                        sensortype_id: Set(updated.id),
+                        name: Set(channel.name),
+                        units: Set(channel.units),
+                        column_index: Set(channel.column_index),
+                        description: Set(channel.description),
+                    })
+                    .exec(db)
+                    .await?;
+                }
+            }
+        }
+
+        Self::get_one(db, updated.id).await
+    }
+
+    async fn delete(db: &DatabaseConnection, id: Uuid) -> Result<Uuid, DbErr> {
+        // Refuse to delete a type that sensors still refer to; their measurements
+        // would otherwise be removed through the channel cascade.
+        let sensors = crate::routes::private::sensors::db::Entity::find()
+            .filter(crate::routes::private::sensors::db::Column::SensortypeId.eq(id))
+            .all(db)
+            .await?;
+
+        if !sensors.is_empty() {
+            return Err(DbErr::Custom(
+                "Cannot delete sensor type that is used by sensors".into(),
+            ));
+        }
+
+        let res = <Self::EntityType as EntityTrait>::delete_by_id(id)
+            .exec(db)
+            .await?;
+
+        match res.rows_affected {
+            0 => Err(DbErr::RecordNotFound(format!(
+                "{} not found",
+                Self::RESOURCE_NAME_SINGULAR
+            ))),
+            _ => Ok(id),
+        }
+    }
+
+    async fn delete_many(db: &DatabaseConnection, ids: Vec<Uuid>) -> Result<Vec<Uuid>, DbErr> {
+        for id in &ids {
+            Self::delete(db, *id).await?;
+        }
+        Ok(ids)
+    }
+
+    fn sortable_columns() -> Vec<(&'static str, Self::ColumnType)> {
+        vec![
+            ("id", Self::ColumnType::Id),
+            ("name", Self::ColumnType::Name),
+            ("manufacturer", Self::ColumnType::Manufacturer),
+            ("last_updated", Self::ColumnType::LastUpdated),
+        ]
+    }
+
+    fn filterable_columns() -> Vec<(&'static str, Self::ColumnType)> {
+        vec![
+            ("name", Self::ColumnType::Name),
+            ("manufacturer", Self::ColumnType::Manufacturer),
+            ("description", Self::ColumnType::Description),
+        ]
+    }
+}
@@ -0,0 +1,196 @@
+use super::db::SensorParserEnum;
+use crate::common::files::read_records;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use uuid::Uuid;
+
+/// Values that loggers write for a missing reading
+const MISSING_VALUES: [&str; 6] = ["", "#N/A", "NaN", "nan", "NA", "-"];
+
+/// Layout of a delimited logger export. Each parser has defaults matching the
+/// vendor software export, which can be overridden per sensor type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParserConfig {
+    pub delimiter: u8,
+    pub header_rows: usize,
+    pub timestamp_column: usize,
+    pub timestamp_format: String,
+    pub utc_offset_minutes: i32,
+}
+
+impl ParserConfig {
+    /// Build the configuration for a sensor type, falling back to the parser's defaults
+    pub fn for_sensor_type(sensor_type: &super::db::Model) -> Result<Self, String> {
+        let (delimiter, header_rows, timestamp_column, timestamp_format) = match sensor_type.parser
+        {
+            // HOBOware: "Plot Title" line, then column headers, "#" index in column 0
+            SensorParserEnum::HoboCsv => (b',', 2, 1, "%m/%d/%y %I:%M:%S %p"),
+            // ZENTRA Utility: device row, port/sensor row, measurement/units row
+            SensorParserEnum::MeterZl6 => (b',', 3, 0, "%m/%d/%Y %I:%M %p"),
+            SensorParserEnum::GenericCsv => (b',', 1, 0, "%Y-%m-%d %H:%M:%S"),
+            SensorParserEnum::Tms => {
+                return Err("TMS files are ingested into the sensordata table".into());
+            }
+        };
+
+        let delimiter = match sensor_type.delimiter.as_deref() {
+            None | Some("") => delimiter,
+            Some("\\t" | "tab") => b'\t',
+            Some(value) => match value.as_bytes() {
+                [d] if d.is_ascii() => *d,
+                _ => return Err(format!("Invalid delimiter '{value}'")),
+            },
+        };
+
+        Ok(Self {
+            delimiter,
+            header_rows: sensor_type
+                .header_rows
+                .map_or(header_rows, |rows| usize::try_from(rows).unwrap_or(0)),
+            timestamp_column: sensor_type
+                .timestamp_column
+                .map_or(timestamp_column, |col| usize::try_from(col).unwrap_or(0)),
+            timestamp_format: sensor_type
+                .timestamp_format
+                .clone()
+                .unwrap_or_else(|| timestamp_format.to_string()),
+            utc_offset_minutes: sensor_type.utc_offset_minutes,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedMeasurement {
+    pub channel_id: Uuid,
+    pub time_utc: DateTime<Utc>,
+    pub value: f64,
+}
+
+fn parse_timestamp(value: &str, config: &ParserConfig) -> Result<DateTime<Utc>, String> {
+    // Formats carrying their own offset are taken as-is
+    if config.timestamp_format.contains("%z") || config.timestamp_format.contains("%:z") {
+        return DateTime::parse_from_str(value, &config.timestamp_format)
+            .map(|dt| dt.with_timezone(&Utc))
+            .map_err(|e| format!("Invalid timestamp '{value}': {e}"));
+    }
+    let naive = NaiveDateTime::parse_from_str(value, &config.timestamp_format)
+        .map_err(|e| format!("Invalid timestamp '{value}': {e}"))?;
+
+    // The logger clock runs at UTC + offset, so subtract the offset to get UTC
+    Ok(naive.and_utc() - Duration::minutes(i64::from(config.utc_offset_minutes)))
+}
+
+/// Parse a delimited logger export into one measurement per channel and row.
+///
+/// `channels` maps each registered channel to its zero-based column. Missing
+/// readings are skipped, as are rows without a timestamp (e.g. logger events).
+pub fn parse_delimited(
+    text: &str,
+    config: &ParserConfig,
+    channels: &[(Uuid, usize)],
+) -> Result<Vec<ParsedMeasurement>, String> {
+    let mut measurements = Vec::new();
+
+    for record in read_records(text, config.delimiter)? {
+        let line_no = record
+            .position()
+            .and_then(|position| usize::try_from(position.line()).ok())
+            .unwrap_or(0);
+        if line_no <= config.header_rows {
+            continue;
+        }
+        let Some(timestamp) = record
+            .get(config.timestamp_column)
+            .filter(|field| !field.is_empty())
+        else {
+            continue;
+        };
+        let time_utc =
+            parse_timestamp(timestamp, config).map_err(|e| format!("Line {line_no}: {e}"))?;
+
+        for (channel_id, column) in channels {
+            let Some(raw) = record.get(*column) else {
+                continue;
+            };
+            if MISSING_VALUES.contains(&raw) {
+                continue;
+            }
+            let value = raw.parse::<f64>().map_err(|_| {
+                format!("Line {line_no}: invalid value '{raw}' in column {column}")
+            })?;
+            measurements.push(ParsedMeasurement {
+                channel_id: *channel_id,
+                time_utc,
+                value,
+            });
+        }
+    }
+
+    Ok(measurements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hobo_config() -> ParserConfig {
+        ParserConfig {
+            delimiter: b',',
+            header_rows: 2,
+            timestamp_column: 1,
+            timestamp_format: "%m/%d/%y %I:%M:%S %p".to_string(),
+            utc_offset_minutes: 60,
+        }
+    }
+
+    #[test]
+    fn parses_hobo_export_with_offset_and_missing_values() {
+        let text = "\"Plot Title: 20731234\"\n\
+                    \"#\",\"Date Time, GMT+01:00\",\"Temp, °C\",\"RH, %\"\n\
+                    1,06/01/24 01:00:00 PM,21.5,55.2\n\
+                    2,06/01/24 01:15:00 PM,,54.9\n\
+                    3,,,\n";
+        let temp = Uuid::new_v4();
+        let rh = Uuid::new_v4();
+
+        let parsed = parse_delimited(text, &hobo_config(), &[(temp, 2), (rh, 3)]).unwrap();
+
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[0].channel_id, temp);
+        assert_eq!(
+            parsed[0].time_utc,
+            DateTime::parse_from_rfc3339("2024-06-01T12:00:00Z").unwrap()
+        );
+        assert!((parsed[0].value - 21.5).abs() < f64::EPSILON);
+        assert_eq!(parsed[2].channel_id, rh);
+    }
+
+    #[test]
+    fn quoted_fields_keep_delimiters() {
+        let text = "site;time;\"Temp; °C\"\n\
+                    \"Plot 1; north\";2024-06-01 12:00:00;\"21.5\"\n";
+        let config = ParserConfig {
+            delimiter: b';',
+            header_rows: 1,
+            timestamp_column: 1,
+            timestamp_format: "%Y-%m-%d %H:%M:%S".to_string(),
+            utc_offset_minutes: 0,
+        };
+        let temp = Uuid::new_v4();
+
+        let parsed = parse_delimited(text, &config, &[(temp, 2)]).unwrap();
+
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(
+            parsed[0].time_utc,
+            DateTime::parse_from_rfc3339("2024-06-01T12:00:00Z").unwrap()
+        );
+        assert!((parsed[0].value - 21.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn invalid_value_reports_line() {
+        let text = "\"Plot Title\"\nheader\n1,06/01/24 01:00:00 PM,abc\n";
+        let err = parse_delimited(text, &hobo_config(), &[(Uuid::new_v4(), 2)]).unwrap_err();
+        assert!(err.starts_with("Line 3"));
+    }
+}
@@ -102,6 +102,17 @@ pub async fn delete_sensor_data(
             )
         })?;
 
+    crate::routes::private::sensors::measurements::db::Entity::delete_many()
+        .filter(crate::routes::private::sensors::measurements::db::Column::SensorId.eq(id))
+        .exec(&db)
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json("Internal Server Error".to_string()),
+            )
+        })?;
+
     Ok(StatusCode::OK)
 }
 
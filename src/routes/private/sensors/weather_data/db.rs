@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, ToSchema)]
+#[sea_orm(table_name = "weather_data")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub sensorprofile_id: Uuid,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub time_utc: DateTime<Utc>,
+    pub air_temperature_c: Option<f64>,
+    pub precipitation_mm: Option<f64>,
+    pub snow_depth_cm: Option<f64>,
+    pub relative_humidity_pct: Option<f64>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::routes::private::sensors::profile::db::Entity",
+        from = "Column::SensorprofileId",
+        to = "crate::routes::private::sensors::profile::db::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Sensorprofile,
+}
+
+impl Related<crate::routes::private::sensors::profile::db::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Sensorprofile.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,4 @@
+pub mod db;
+pub mod models;
+pub mod parsers;
+pub mod views;
@@ -0,0 +1,446 @@
+use super::parsers::WeatherReading;
+use crate::routes::private::sensors::profile::db::{self as ProfileDB, ProfileTypeEnum};
+use crate::routes::private::sensors::profile::models::SensorProfile;
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    ActiveValue, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    Statement,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// One station record, either raw or a continuous aggregate bucket
+#[derive(ToSchema, Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct WeatherDataPoint {
+    pub time_utc: DateTime<Utc>,
+    pub air_temperature_c: Option<f64>,
+    pub precipitation_mm: Option<f64>,
+    pub snow_depth_cm: Option<f64>,
+    pub relative_humidity_pct: Option<f64>,
+}
+
+/// Weather station time series at the resolution chosen from the date range
+#[derive(ToSchema, Serialize, Deserialize, Debug, Clone)]
+pub struct WeatherSeries {
+    pub sensorprofile_id: Uuid,
+    pub name: String,
+    pub resolution: String,
+    pub data: Vec<WeatherDataPoint>,
+}
+
+/// Station data resampled onto the timestamps of a sensor profile response.
+/// Every vector has one entry per element of `time_utc`; `None` marks gaps.
+#[derive(ToSchema, Serialize, Deserialize, Debug, Clone)]
+pub struct WeatherAlignedSeries {
+    pub sensorprofile_id: Uuid,
+    pub station_id: Uuid,
+    pub station_name: String,
+    pub resolution: String,
+    pub time_utc: Vec<DateTime<Utc>>,
+    pub air_temperature_c: Vec<Option<f64>>,
+    pub precipitation_mm: Vec<Option<f64>>,
+    pub snow_depth_cm: Vec<Option<f64>>,
+    pub relative_humidity_pct: Vec<Option<f64>>,
+}
+
+/// Resolution label used for a date span, matching `SensorProfile` responses.
+pub fn resolution_for_span(span_days: i64) -> &'static str {
+    if span_days <= 7 {
+        "raw"
+    } else if span_days <= 90 {
+        "hourly"
+    } else {
+        "6h"
+    }
+}
+
+/// Longest interval a single axis timestamp may collect station records from.
+fn max_window(resolution: &str) -> Duration {
+    match resolution {
+        "6h" => Duration::hours(6),
+        _ => Duration::hours(1),
+    }
+}
+
+#[derive(Default)]
+struct Accumulator {
+    temperature: (f64, u32),
+    precipitation: Option<f64>,
+    snow_depth: (f64, u32),
+    humidity: (f64, u32),
+}
+
+impl Accumulator {
+    fn add(&mut self, point: &WeatherDataPoint) {
+        fn push(acc: &mut (f64, u32), value: Option<f64>) {
+            if let Some(v) = value {
+                acc.0 += v;
+                acc.1 += 1;
+            }
+        }
+        push(&mut self.temperature, point.air_temperature_c);
+        push(&mut self.snow_depth, point.snow_depth_cm);
+        push(&mut self.humidity, point.relative_humidity_pct);
+        if let Some(p) = point.precipitation_mm {
+            *self.precipitation.get_or_insert(0.0) += p;
+        }
+    }
+
+    fn mean((sum, count): (f64, u32)) -> Option<f64> {
+        (count > 0).then(|| sum / f64::from(count))
+    }
+}
+
+/// Resample station records onto `axis` (sorted ascending). Each axis timestamp
+/// takes the records from itself up to the next timestamp, but at most
+/// `window` ahead, like the bucket start labels of the continuous aggregates.
+/// Precipitation is summed, the other variables are averaged.
+pub fn align_to_axis(
+    sensorprofile_id: Uuid,
+    station: &ProfileDB::Model,
+    resolution: &str,
+    axis: Vec<DateTime<Utc>>,
+    points: &[WeatherDataPoint],
+) -> WeatherAlignedSeries {
+    let window = max_window(resolution);
+    let mut accumulators: Vec<Accumulator> = axis.iter().map(|_| Accumulator::default()).collect();
+
+    let mut p = points.partition_point(|pt| axis.first().is_some_and(|t| pt.time_utc < *t));
+    for (i, start) in axis.iter().enumerate() {
+        let limit = axis
+            .get(i + 1)
+            .map_or(*start + window, |next| (*next).min(*start + window));
+        while p < points.len() && points[p].time_utc < *start {
+            p += 1;
+        }
+        while p < points.len() && points[p].time_utc < limit {
+            accumulators[i].add(&points[p]);
+            p += 1;
+        }
+    }
+
+    WeatherAlignedSeries {
+        sensorprofile_id,
+        station_id: station.id,
+        station_name: station.name.clone(),
+        resolution: resolution.to_string(),
+        air_temperature_c: accumulators
+            .iter()
+            .map(|a| Accumulator::mean(a.temperature))
+            .collect(),
+        precipitation_mm: accumulators.iter().map(|a| a.precipitation).collect(),
+        snow_depth_cm: accumulators
+            .iter()
+            .map(|a| Accumulator::mean(a.snow_depth))
+            .collect(),
+        relative_humidity_pct: accumulators
+            .iter()
+            .map(|a| Accumulator::mean(a.humidity))
+            .collect(),
+        time_utc: axis,
+    }
+}
+
+/// Weather station used for a profile: the requested one, otherwise the nearest
+/// weather profile, preferring stations in the same area.
+pub async fn resolve_station(
+    db: &DatabaseConnection,
+    profile_id: Uuid,
+    station_id: Option<Uuid>,
+) -> Result<ProfileDB::Model, DbErr> {
+    if let Some(station_id) = station_id {
+        let station = ProfileDB::Entity::find_by_id(station_id)
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Weather station not found".into()))?;
+        if station.profile_type != ProfileTypeEnum::Weather {
+            return Err(DbErr::Custom(
+                "Station profile must be of type 'weather'".into(),
+            ));
+        }
+        return Ok(station);
+    }
+
+    let sql = r"
+        SELECT w.id
+        FROM sensorprofile AS w
+        JOIN sensorprofile AS p ON p.id = $1
+        WHERE w.profile_type = 'weather'
+        ORDER BY (w.area_id = p.area_id) DESC, st_distance(w.geom, p.geom) NULLS LAST, w.name
+        LIMIT 1
+    ";
+    let stmt =
+        Statement::from_sql_and_values(db.get_database_backend(), sql, vec![profile_id.into()]);
+    let row = db
+        .query_one(stmt)
+        .await?
+        .ok_or(DbErr::Custom("No weather station profile found".into()))?;
+    let station_id: Uuid = row.try_get("", "id")?;
+    ProfileDB::Entity::find_by_id(station_id)
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Weather station not found".into()))
+}
+
+/// Load the records of a station, from the raw table or the continuous aggregate
+/// matching `resolution`.
+pub async fn load_points(
+    db: &DatabaseConnection,
+    station_id: Uuid,
+    resolution: &str,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Result<Vec<WeatherDataPoint>, DbErr> {
+    const AGGREGATE_COLUMNS: &str = "avg_air_temperature_c AS air_temperature_c, \
+        sum_precipitation_mm AS precipitation_mm, \
+        avg_snow_depth_cm AS snow_depth_cm, \
+        avg_relative_humidity_pct AS relative_humidity_pct";
+
+    let (source, time_col, columns) = match resolution {
+        "hourly" => ("weather_data_hourly", "bucket", AGGREGATE_COLUMNS),
+        "6h" => ("weather_data_6h", "bucket", AGGREGATE_COLUMNS),
+        _ => (
+            "weather_data",
+            "time_utc",
+            "air_temperature_c, precipitation_mm, snow_depth_cm, relative_humidity_pct",
+        ),
+    };
+
+    let mut conditions = Vec::new();
+    let mut params: Vec<sea_orm::Value> = vec![station_id.into()];
+    if let Some(s) = start {
+        params.push(s.into());
+        conditions.push(format!(" AND {time_col} >= ${}", params.len()));
+    }
+    if let Some(e) = end {
+        params.push(e.into());
+        conditions.push(format!(" AND {time_col} <= ${}", params.len()));
+    }
+    let conditions = conditions.concat();
+
+    let sql = format!(
+        r"
+        SELECT {time_col} AS time_utc, {columns}
+        FROM {source}
+        WHERE sensorprofile_id = $1{conditions}
+        ORDER BY {time_col}
+        "
+    );
+    let stmt = Statement::from_sql_and_values(db.get_database_backend(), &sql, params);
+    let rows = db.query_all(stmt).await?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(WeatherDataPoint {
+                time_utc: row.try_get("", "time_utc")?,
+                air_temperature_c: row.try_get("", "air_temperature_c")?,
+                precipitation_mm: row.try_get("", "precipitation_mm")?,
+                snow_depth_cm: row.try_get("", "snow_depth_cm")?,
+                relative_humidity_pct: row.try_get("", "relative_humidity_pct")?,
+            })
+        })
+        .collect()
+}
+
+/// Station time series with the resolution chosen from the requested range, or
+/// from the stored data range if no complete range is given.
+pub async fn get_series(
+    db: &DatabaseConnection,
+    station_id: Uuid,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Result<WeatherSeries, DbErr> {
+    let station = ProfileDB::Entity::find_by_id(station_id)
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Weather station not found".into()))?;
+    if station.profile_type != ProfileTypeEnum::Weather {
+        return Err(DbErr::Custom(
+            "Sensor profile must be of type 'weather'".into(),
+        ));
+    }
+
+    let span_days = if let (Some(s), Some(e)) = (start, end) {
+        (e - s).num_days()
+    } else {
+        let sql = r"
+            SELECT MIN(time_utc) AS min_time, MAX(time_utc) AS max_time
+            FROM weather_data
+            WHERE sensorprofile_id = $1
+        ";
+        let stmt =
+            Statement::from_sql_and_values(db.get_database_backend(), sql, vec![station_id.into()]);
+        let row = db.query_one(stmt).await?;
+        let data_from: Option<DateTime<Utc>> =
+            row.as_ref().and_then(|r| r.try_get("", "min_time").ok());
+        let data_to: Option<DateTime<Utc>> =
+            row.as_ref().and_then(|r| r.try_get("", "max_time").ok());
+        match (start.or(data_from), end.or(data_to)) {
+            (Some(f), Some(t)) => (t - f).num_days(),
+            _ => 365,
+        }
+    };
+    let resolution = resolution_for_span(span_days);
+
+    Ok(WeatherSeries {
+        sensorprofile_id: station.id,
+        name: station.name,
+        resolution: resolution.to_string(),
+        data: load_points(db, station_id, resolution, start, end).await?,
+    })
+}
+
+/// Station data aligned on the time axis of the TMS data that
+/// `SensorProfile::get_one_with_date_range` returns for the same range.
+pub async fn get_aligned_to_profile(
+    db: &DatabaseConnection,
+    profile_id: Uuid,
+    station_id: Option<Uuid>,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Result<WeatherAlignedSeries, DbErr> {
+    let profile = SensorProfile::get_one_with_date_range(db, profile_id, start, end).await?;
+    let resolution = profile.resolution.as_deref().unwrap_or("raw");
+    let station = resolve_station(db, profile_id, station_id).await?;
+
+    let mut axis: Vec<DateTime<Utc>> = profile
+        .temperature_by_depth_cm
+        .values()
+        .flatten()
+        .map(|d| d.time_utc)
+        .collect();
+    axis.sort_unstable();
+    axis.dedup();
+
+    let (Some(first), Some(last)) = (axis.first().copied(), axis.last().copied()) else {
+        return Ok(align_to_axis(profile_id, &station, resolution, axis, &[]));
+    };
+    let points = load_points(
+        db,
+        station.id,
+        resolution,
+        Some(first),
+        Some(last + max_window(resolution)),
+    )
+    .await?;
+    Ok(align_to_axis(
+        profile_id, &station, resolution, axis, &points,
+    ))
+}
+
+/// Store parsed station readings, skipping timestamps that already exist.
+/// Returns the number of inserted readings.
+pub async fn insert_readings(
+    db: &DatabaseConnection,
+    profile_id: Uuid,
+    readings: Vec<WeatherReading>,
+) -> Result<u64, DbErr> {
+    const CHUNK_SIZE: usize = 1000;
+
+    let active_models: Vec<super::db::ActiveModel> = readings
+        .into_iter()
+        .map(|r| super::db::ActiveModel {
+            sensorprofile_id: ActiveValue::Set(profile_id),
+            time_utc: ActiveValue::Set(r.time_utc),
+            air_temperature_c: ActiveValue::Set(r.air_temperature_c),
+            precipitation_mm: ActiveValue::Set(r.precipitation_mm),
+            snow_depth_cm: ActiveValue::Set(r.snow_depth_cm),
+            relative_humidity_pct: ActiveValue::Set(r.relative_humidity_pct),
+        })
+        .collect();
+
+    let mut inserted = 0u64;
+    for chunk in active_models.chunks(CHUNK_SIZE) {
+        inserted += super::db::Entity::insert_many(chunk.to_vec())
+            .on_conflict(
+                sea_orm::sea_query::OnConflict::columns([
+                    super::db::Column::SensorprofileId,
+                    super::db::Column::TimeUtc,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await?;
+    }
+    Ok(inserted)
+}
+
+/// Delete station readings of a profile within an optional time range.
+pub async fn delete_readings(
+    db: &DatabaseConnection,
+    profile_id: Uuid,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Result<u64, DbErr> {
+    let mut query =
+        super::db::Entity::delete_many().filter(super::db::Column::SensorprofileId.eq(profile_id));
+    if let Some(s) = start {
+        query = query.filter(super::db::Column::TimeUtc.gte(s));
+    }
+    if let Some(e) = end {
+        query = query.filter(super::db::Column::TimeUtc.lte(e));
+    }
+    Ok(query.exec(db).await?.rows_affected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .to_utc()
+            + Duration::minutes(minutes)
+    }
+
+    fn station() -> ProfileDB::Model {
+        ProfileDB::Model {
+            id: Uuid::nil(),
+            name: "DAV".into(),
+            description: None,
+            area_id: Uuid::nil(),
+            profile_type: ProfileTypeEnum::Weather,
+            coord_x: None,
+            coord_y: None,
+            coord_z: None,
+            coord_srid: None,
+            soil_type_vwc: None,
+            volume_ml: None,
+            area_cm2: None,
+            instrument_model: None,
+            chamber_id_external: None,
+            position: None,
+            logger_depth_m: None,
+            baro_profile_id: None,
+            reference_electrode: None,
+            last_updated: at(0),
+        }
+    }
+
+    #[test]
+    fn aligns_ten_minute_records_on_fifteen_minute_axis() {
+        let points: Vec<WeatherDataPoint> = [0_i32, 10, 20, 30, 40]
+            .iter()
+            .map(|&m| WeatherDataPoint {
+                time_utc: at(i64::from(m)),
+                air_temperature_c: Some(f64::from(m)),
+                precipitation_mm: Some(0.5),
+                ..Default::default()
+            })
+            .collect();
+        let aligned = align_to_axis(
+            Uuid::nil(),
+            &station(),
+            "raw",
+            vec![at(0), at(15), at(120)],
+            &points,
+        );
+
+        assert_eq!(aligned.air_temperature_c, vec![Some(5.0), Some(30.0), None]);
+        assert_eq!(aligned.precipitation_mm, vec![Some(1.0), Some(1.5), None]);
+        assert_eq!(aligned.snow_depth_cm, vec![None, None, None]);
+    }
+}
@@ -0,0 +1,340 @@
+use crate::common::files::{is_toa5, read_toa5};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+/// Supported weather station exports
+#[derive(ToSchema, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WeatherFileFormat {
+    /// IDAWEB order or Swiss open data CSV (`;` separated, UTC)
+    Meteoswiss,
+    /// Campbell Scientific TOA5 ASCII table
+    Toa5,
+}
+
+impl WeatherFileFormat {
+    /// Guess the format from the file content
+    pub fn detect(text: &str) -> Self {
+        if is_toa5(text) {
+            Self::Toa5
+        } else {
+            Self::Meteoswiss
+        }
+    }
+}
+
+/// Names of the TOA5 columns holding each variable. Unset variables are
+/// detected from common datalogger program names (e.g. `AirTC_Avg`, `Rain_mm_Tot`).
+#[derive(ToSchema, Deserialize, Debug, Clone, Default)]
+pub struct Toa5ColumnMapping {
+    pub air_temperature_c: Option<String>,
+    pub precipitation_mm: Option<String>,
+    pub snow_depth_cm: Option<String>,
+    pub relative_humidity_pct: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct WeatherReading {
+    pub time_utc: DateTime<Utc>,
+    pub air_temperature_c: Option<f64>,
+    pub precipitation_mm: Option<f64>,
+    pub snow_depth_cm: Option<f64>,
+    pub relative_humidity_pct: Option<f64>,
+}
+
+impl WeatherReading {
+    fn is_empty(&self) -> bool {
+        self.air_temperature_c.is_none()
+            && self.precipitation_mm.is_none()
+            && self.snow_depth_cm.is_none()
+            && self.relative_humidity_pct.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Variable {
+    AirTemperature,
+    Precipitation,
+    SnowDepth,
+    RelativeHumidity,
+}
+
+fn set_variable(reading: &mut WeatherReading, variable: Variable, value: f64) {
+    match variable {
+        Variable::AirTemperature => reading.air_temperature_c = Some(value),
+        Variable::Precipitation => reading.precipitation_mm = Some(value),
+        Variable::SnowDepth => reading.snow_depth_cm = Some(value),
+        Variable::RelativeHumidity => reading.relative_humidity_pct = Some(value),
+    }
+}
+
+/// IDAWEB parameter codes: 2 m air temperature, precipitation, snow depth
+/// (cm) and 2 m relative humidity, at any time resolution suffix.
+fn meteoswiss_variable(code: &str) -> Option<Variable> {
+    let code = code.trim().to_lowercase();
+    if code.starts_with("tre200") {
+        Some(Variable::AirTemperature)
+    } else if code.starts_with("rre150") {
+        Some(Variable::Precipitation)
+    } else if code.starts_with("hto") {
+        Some(Variable::SnowDepth)
+    } else if code.starts_with("ure200") {
+        Some(Variable::RelativeHumidity)
+    } else {
+        None
+    }
+}
+
+/// IDAWEB writes `YYYYMMDD[HH[MM]]`, the open data files `DD.MM.YYYY HH:MM`.
+/// Both are in UTC.
+fn parse_meteoswiss_time(value: &str) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+    let naive = match value.len() {
+        8 => NaiveDate::parse_from_str(value, "%Y%m%d")
+            .map(|d| d.and_hms_opt(0, 0, 0).unwrap_or_default()),
+        10 => NaiveDateTime::parse_from_str(&format!("{value}00"), "%Y%m%d%H%M"),
+        12 => NaiveDateTime::parse_from_str(value, "%Y%m%d%H%M"),
+        _ => NaiveDateTime::parse_from_str(value, "%d.%m.%Y %H:%M"),
+    }
+    .map_err(|e| format!("Invalid timestamp '{value}': {e}"))?;
+    Ok(naive.and_utc())
+}
+
+/// Parse a Swiss weather service CSV. Legend lines before the `stn;time;...` (IDAWEB) or
+/// `station_abbr;reference_timestamp;...` (open data) header are skipped.
+/// Files with several stations need `station_code` to select one.
+pub fn parse_meteoswiss(
+    text: &str,
+    station_code: Option<&str>,
+) -> Result<Vec<WeatherReading>, String> {
+    let mut lines = text.lines();
+    let header = lines
+        .by_ref()
+        .find(|line| {
+            let lower = line.trim_start().to_lowercase();
+            lower.starts_with("stn;") || lower.starts_with("station_abbr;")
+        })
+        .ok_or("No 'stn;time' header found in MeteoSwiss file")?;
+
+    let columns: Vec<(usize, Variable)> = header
+        .split(';')
+        .enumerate()
+        .skip(2)
+        .filter_map(|(i, code)| meteoswiss_variable(code).map(|v| (i, v)))
+        .collect();
+    if columns.is_empty() {
+        return Err("No supported parameter (tre200*, rre150*, hto*, ure200*) in file".into());
+    }
+
+    let mut readings = Vec::new();
+    let mut stations: Vec<String> = Vec::new();
+    for line in lines {
+        let fields: Vec<&str> = line.trim().split(';').collect();
+        if fields.len() < 2 || fields[0].is_empty() {
+            continue;
+        }
+        let station = fields[0].trim();
+        if let Some(code) = station_code {
+            if !station.eq_ignore_ascii_case(code) {
+                continue;
+            }
+        } else if !stations.iter().any(|s| s == station) {
+            stations.push(station.to_string());
+        }
+
+        let mut reading = WeatherReading {
+            time_utc: parse_meteoswiss_time(fields[1])?,
+            ..Default::default()
+        };
+        for (i, variable) in &columns {
+            // Missing values are written as '-' (IDAWEB) or left empty (open data)
+            if let Some(value) = fields.get(*i).and_then(|v| v.trim().parse::<f64>().ok()) {
+                set_variable(&mut reading, *variable, value);
+            }
+        }
+        if !reading.is_empty() {
+            readings.push(reading);
+        }
+    }
+
+    if stations.len() > 1 {
+        return Err(format!(
+            "File contains several stations ({}); select one with station_code",
+            stations.join(", ")
+        ));
+    }
+    Ok(readings)
+}
+
+/// Column matching a usual datalogger field name for a variable, if not mapped explicitly
+fn detect_toa5_column(names: &[&str], variable: Variable) -> Option<usize> {
+    names.iter().position(|name| {
+        let name = name.to_lowercase();
+        match variable {
+            Variable::AirTemperature => name.starts_with("airtc") || name.starts_with("t_air"),
+            Variable::Precipitation => name.starts_with("rain") || name.starts_with("precip"),
+            Variable::SnowDepth => name.starts_with("snow") || name.starts_with("dt_"),
+            Variable::RelativeHumidity => name == "rh" || name.starts_with("rh_"),
+        }
+    })
+}
+
+/// Parse a Campbell TOA5 file (environment, field names, units and processing
+/// header lines, then data). Snow depth given in m is converted to cm.
+pub fn parse_toa5(
+    text: &str,
+    mapping: &Toa5ColumnMapping,
+    utc_offset_minutes: i32,
+) -> Result<Vec<WeatherReading>, String> {
+    let table = read_toa5(text)?;
+    let names: Vec<&str> = table.names.iter().map(String::as_str).collect();
+    let time_col = table
+        .column("TIMESTAMP")
+        .ok_or("No TIMESTAMP column in TOA5 file")?;
+
+    let mut columns: Vec<(usize, Variable, f64)> = Vec::new();
+    for (variable, mapped) in [
+        (Variable::AirTemperature, &mapping.air_temperature_c),
+        (Variable::Precipitation, &mapping.precipitation_mm),
+        (Variable::SnowDepth, &mapping.snow_depth_cm),
+        (Variable::RelativeHumidity, &mapping.relative_humidity_pct),
+    ] {
+        let index = match mapped {
+            Some(name) => Some(
+                names
+                    .iter()
+                    .position(|n| n == name)
+                    .ok_or_else(|| format!("Column '{name}' not found in TOA5 file"))?,
+            ),
+            None => detect_toa5_column(&names, variable),
+        };
+        if let Some(index) = index {
+            let scale = if variable == Variable::SnowDepth
+                && table
+                    .units
+                    .get(index)
+                    .is_some_and(|u| u.eq_ignore_ascii_case("m"))
+            {
+                100.0
+            } else {
+                1.0
+            };
+            columns.push((index, variable, scale));
+        }
+    }
+    if columns.is_empty() {
+        return Err("No weather variables found in TOA5 file; provide a column mapping".into());
+    }
+
+    let mut readings = Vec::new();
+    for fields in &table.rows {
+        let timestamp = fields.get(time_col).unwrap_or_default();
+        let naive = NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S")
+            .or_else(|_| NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S%.f"))
+            .map_err(|e| format!("Invalid timestamp '{timestamp}': {e}"))?;
+
+        let mut reading = WeatherReading {
+            time_utc: naive.and_utc() - Duration::minutes(i64::from(utc_offset_minutes)),
+            ..Default::default()
+        };
+        for (i, variable, scale) in &columns {
+            // "NAN" fails to parse and is treated as missing
+            if let Some(value) = fields
+                .get(*i)
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|v| v.is_finite())
+            {
+                set_variable(&mut reading, *variable, value * scale);
+            }
+        }
+        if !reading.is_empty() {
+            readings.push(reading);
+        }
+    }
+    Ok(readings)
+}
+
+/// Merge readings sharing a timestamp (e.g. separate parameter files), keeping
+/// the first value of each variable.
+pub fn merge_by_time(readings: Vec<WeatherReading>) -> Vec<WeatherReading> {
+    let mut merged: HashMap<DateTime<Utc>, WeatherReading> = HashMap::new();
+    for reading in readings {
+        let entry = merged
+            .entry(reading.time_utc)
+            .or_insert_with(|| WeatherReading {
+                time_utc: reading.time_utc,
+                ..Default::default()
+            });
+        entry.air_temperature_c = entry.air_temperature_c.or(reading.air_temperature_c);
+        entry.precipitation_mm = entry.precipitation_mm.or(reading.precipitation_mm);
+        entry.snow_depth_cm = entry.snow_depth_cm.or(reading.snow_depth_cm);
+        entry.relative_humidity_pct = entry
+            .relative_humidity_pct
+            .or(reading.relative_humidity_pct);
+    }
+    let mut readings: Vec<WeatherReading> = merged.into_values().collect();
+    readings.sort_by_key(|r| r.time_utc);
+    readings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_idaweb_order() {
+        let text = "\nstn;time;tre200s0;rre150z0;htoauts0\n\
+                    DAV;202401010000;-3.2;0.0;85\n\
+                    DAV;202401010010;-3.4;-;86\n";
+        let readings = parse_meteoswiss(text, None).unwrap();
+        assert_eq!(readings.len(), 2);
+        assert_eq!(readings[0].air_temperature_c, Some(-3.2));
+        assert_eq!(readings[1].precipitation_mm, None);
+        assert_eq!(readings[1].snow_depth_cm, Some(86.0));
+        assert_eq!(
+            readings[1].time_utc,
+            DateTime::parse_from_rfc3339("2024-01-01T00:10:00Z").unwrap()
+        );
+    }
+
+    #[test]
+    fn idaweb_requires_station_for_multiple_stations() {
+        let text = "stn;time;tre200h0\nDAV;2024010100;-3.2\nSIA;2024010100;-8.1\n";
+        assert!(parse_meteoswiss(text, None).is_err());
+        let readings = parse_meteoswiss(text, Some("sia")).unwrap();
+        assert_eq!(readings.len(), 1);
+        assert_eq!(readings[0].air_temperature_c, Some(-8.1));
+    }
+
+    #[test]
+    fn parses_toa5_with_snow_depth_in_m() {
+        let text = "\"TOA5\",\"Alp1\",\"CR1000\",\"1234\",\"CR1000.Std.32\",\"CPU:met.CR1\",\"1\",\"Table10\"\n\
+                    \"TIMESTAMP\",\"RECORD\",\"AirTC_Avg\",\"Rain_mm_Tot\",\"DT_Avg\"\n\
+                    \"TS\",\"RN\",\"Deg C\",\"mm\",\"m\"\n\
+                    \"\",\"\",\"Avg\",\"Tot\",\"Avg\"\n\
+                    \"2024-01-01 01:10:00\",1,-2.5,0.2,0.45\n\
+                    \"2024-01-01 01:20:00\",2,\"NAN\",0,0.46\n";
+        let readings = parse_toa5(text, &Toa5ColumnMapping::default(), 60).unwrap();
+        assert_eq!(readings.len(), 2);
+        assert_eq!(
+            readings[0].time_utc,
+            DateTime::parse_from_rfc3339("2024-01-01T00:10:00Z").unwrap()
+        );
+        assert!((readings[0].snow_depth_cm.unwrap() - 45.0).abs() < 1e-9);
+        assert_eq!(readings[1].air_temperature_c, None);
+    }
+
+    #[test]
+    fn toa5_quoted_fields_may_contain_commas() {
+        let text = "\"TOA5\",\"Alp, upper\",\"CR1000\",\"1234\",\"CR1000.Std.32\",\"CPU:met.CR1\",\"1\",\"Table10\"\n\
+                    \"TIMESTAMP\",\"RECORD\",\"Status\",\"AirTC_Avg\"\n\
+                    \"TS\",\"RN\",\"\",\"Deg C\"\n\
+                    \"\",\"\",\"Smp\",\"Avg\"\n\
+                    \"2024-01-01 00:10:00\",1,\"ok, heated\",-2.5\n";
+        let readings = parse_toa5(text, &Toa5ColumnMapping::default(), 0).unwrap();
+        assert_eq!(readings.len(), 1);
+        assert_eq!(readings[0].air_temperature_c, Some(-2.5));
+    }
+}
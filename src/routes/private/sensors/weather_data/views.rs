@@ -0,0 +1,221 @@
+use super::models::{WeatherSeries, delete_readings, get_series, insert_readings};
+use super::parsers::{
+    Toa5ColumnMapping, WeatherFileFormat, merge_by_time, parse_meteoswiss, parse_toa5,
+};
+use crate::common::auth::Role;
+use crate::common::errors::db_error_response;
+use crate::common::files::decode_base64_text;
+use crate::common::models::DateRangeQuery;
+use crate::routes::private::sensors::profile::db::{self as ProfileDB, ProfileTypeEnum};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use axum_keycloak_auth::{
+    PassthroughMode, instance::KeycloakAuthInstance, layer::KeycloakAuthLayer,
+};
+use sea_orm::{DatabaseConnection, EntityTrait};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+/// Request body for the weather station file import endpoint.
+#[derive(Deserialize, ToSchema)]
+pub struct WeatherImportRequest {
+    pub sensorprofile_id: uuid::Uuid,
+    /// File content as a base64 data URL (or plain base64)
+    pub data_base64: String,
+    /// Detected from the content when omitted
+    pub format: Option<WeatherFileFormat>,
+    /// Station abbreviation (e.g. `DAV`), required for multi-station files
+    pub station_code: Option<String>,
+    /// Offset of the logger clock from UTC in minutes (TOA5 only; IDAWEB data is UTC)
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+    /// TOA5 field names per variable, overriding the detected columns
+    #[serde(default)]
+    pub columns: Toa5ColumnMapping,
+}
+
+/// Result of a weather station file import.
+#[derive(Serialize, ToSchema)]
+pub struct WeatherImportResult {
+    pub parsed: usize,
+    pub inserted: u64,
+    pub skipped_duplicates: u64,
+}
+
+#[utoipa::path(
+    post,
+    path = "/import",
+    request_body = WeatherImportRequest,
+    responses(
+        (status = 201, description = "Station file imported.", body = WeatherImportResult),
+        (status = 404, description = "Sensor profile not found"),
+        (status = 422, description = "Invalid file or profile type"),
+        (status = 500, description = "Internal server error")
+    ),
+    summary = "Import a weather station file",
+    description = "Imports a MeteoSwiss IDAWEB/open data CSV or a Campbell Scientific TOA5 file into a weather profile. Air temperature, precipitation, snow depth and relative humidity are read; readings already stored for the same timestamp are skipped.",
+    operation_id = "import_weather_data",
+)]
+pub async fn import_weather_data(
+    State(db): State<DatabaseConnection>,
+    Json(req): Json<WeatherImportRequest>,
+) -> impl IntoResponse {
+    let profile = match ProfileDB::Entity::find_by_id(req.sensorprofile_id)
+        .one(&db)
+        .await
+    {
+        Ok(Some(p)) => p,
+        Ok(None) => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(format!("Sensor profile {} not found", req.sensorprofile_id)),
+            ));
+        }
+        Err(e) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(format!("Database error: {e}")),
+            ));
+        }
+    };
+
+    if profile.profile_type != ProfileTypeEnum::Weather {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json("Profile must be of type 'weather'".to_string()),
+        ));
+    }
+
+    // IDAWEB orders are delivered as ISO-8859-1
+    let text = decode_base64_text(&req.data_base64)
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, Json(e)))?;
+
+    let format = req
+        .format
+        .unwrap_or_else(|| WeatherFileFormat::detect(&text));
+    let readings = match format {
+        WeatherFileFormat::Meteoswiss => parse_meteoswiss(&text, req.station_code.as_deref()),
+        WeatherFileFormat::Toa5 => parse_toa5(&text, &req.columns, req.utc_offset_minutes),
+    }
+    .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, Json(e)))?;
+
+    if readings.is_empty() {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json("No readings found in file".to_string()),
+        ));
+    }
+
+    let readings = merge_by_time(readings);
+    let parsed = readings.len();
+    let inserted = insert_readings(&db, profile.id, readings)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(format!("Database error: {e}")),
+            )
+        })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(WeatherImportResult {
+            parsed,
+            inserted,
+            skipped_duplicates: parsed as u64 - inserted,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{sensorprofile_id}",
+    responses(
+        (status = 200, description = "Weather station series", body = WeatherSeries),
+        (status = 404, description = "Sensor profile not found"),
+        (status = 422, description = "Profile is not a weather station"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("sensorprofile_id" = Uuid, description = "Weather station profile ID"),
+        ("start" = Option<String>, Query, description = "Start of date range (ISO 8601)"),
+        ("end" = Option<String>, Query, description = "End of date range (ISO 8601)")
+    ),
+    summary = "Get weather station data",
+    description = "Returns the readings of a weather station profile. The resolution (raw, hourly or 6h) is chosen from the requested date range.",
+    operation_id = "get_weather_data",
+)]
+pub async fn get_weather_data(
+    State(db): State<DatabaseConnection>,
+    Path(sensorprofile_id): Path<uuid::Uuid>,
+    Query(query): Query<DateRangeQuery>,
+) -> Result<Json<WeatherSeries>, (StatusCode, Json<String>)> {
+    get_series(&db, sensorprofile_id, query.start, query.end)
+        .await
+        .map(Json)
+        .map_err(db_error_response)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{sensorprofile_id}",
+    responses(
+        (status = 200, description = "Number of deleted readings", body = u64),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("sensorprofile_id" = Uuid, description = "Weather station profile ID"),
+        ("start" = Option<String>, Query, description = "Only delete readings from this time (ISO 8601)"),
+        ("end" = Option<String>, Query, description = "Only delete readings up to this time (ISO 8601)")
+    ),
+    summary = "Delete weather station data",
+    description = "Deletes the readings of a weather station profile, optionally limited to a date range, e.g. before re-importing a corrected file.",
+    operation_id = "delete_weather_data",
+)]
+pub async fn delete_weather_data(
+    State(db): State<DatabaseConnection>,
+    Path(sensorprofile_id): Path<uuid::Uuid>,
+    Query(query): Query<DateRangeQuery>,
+) -> Result<Json<u64>, (StatusCode, Json<String>)> {
+    delete_readings(&db, sensorprofile_id, query.start, query.end)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(format!("Database error: {e}")),
+            )
+        })
+}
+
+pub fn router(
+    db: &DatabaseConnection,
+    keycloak_auth_instance: Option<Arc<KeycloakAuthInstance>>,
+) -> OpenApiRouter {
+    let mut mutating_router = OpenApiRouter::new()
+        .routes(routes!(import_weather_data))
+        .routes(routes!(get_weather_data, delete_weather_data))
+        .with_state(db.clone());
+
+    if let Some(instance) = keycloak_auth_instance {
+        mutating_router = mutating_router.layer(
+            KeycloakAuthLayer::<Role>::builder()
+                .instance(instance)
+                .passthrough_mode(PassthroughMode::Block)
+                .persist_raw_claims(false)
+                .expected_audiences(vec![String::from("account")])
+                .required_roles(vec![Role::Administrator])
+                .build(),
+        );
+    } else {
+        println!("Warning: Mutating routes of weather data router are not protected");
+    }
+
+    mutating_router
+}
@@ -1,6 +1,9 @@
 use crate::common::geometry::Geometry;
-use crate::routes::private::sensors::profile::db::ProfileTypeEnum;
+use crate::routes::private::sensors::profile::db::{ProfileTypeEnum, ReferenceElectrodeEnum};
 use crate::routes::private::sensors::profile::models::DepthAverageData;
+use crate::routes::private::sensors::redox_data::{
+    channels::models::RedoxChannel, eh::RedoxScale, models::RedoxResolution,
+};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeSet, HashMap};
@@ -140,6 +143,13 @@ pub struct FluxDataPoint {
     pub r2_ch4: Option<f64>,
     pub r2_h2o: Option<f64>,
     pub swc: Option<f64>,
+    /// QC flags per gas, only present when flagged values are included
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qc_flagged_co2: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qc_flagged_ch4: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qc_flagged_h2o: Option<bool>,
 }
 
 #[derive(ToSchema, Serialize, Deserialize)]
@@ -150,21 +160,35 @@ pub struct SensorProfileFlux {
     pub flux_data: Vec<FluxDataPoint>,
 }
 
-/// Public redox data time series response
+/// Public redox data response with potentials grouped by electrode depth
 #[derive(ToSchema, Serialize, Deserialize)]
-pub struct RedoxDataPoint {
+pub struct SensorProfileRedox {
+    pub id: Uuid,
+    pub name: String,
+    pub geom: HashMap<i32, Geometry>,
+    pub channels: Vec<RedoxChannel>,
+    pub reference_electrode: Option<ReferenceElectrodeEnum>,
+    pub scale: RedoxScale,
+    pub ph: Option<f64>,
+    pub resolution: RedoxResolution,
+    pub redox_mv_by_depth_cm: HashMap<i32, Vec<DepthAverageData>>,
+    pub temperature_c: Vec<DepthAverageData>,
+}
+
+/// Public groundwater level time series response
+#[derive(ToSchema, Serialize, Deserialize)]
+pub struct GroundwaterDataPoint {
     pub measured_on: DateTime<Utc>,
-    pub ch1_5cm_mv: Option<f64>,
-    pub ch2_15cm_mv: Option<f64>,
-    pub ch3_25cm_mv: Option<f64>,
-    pub ch4_35cm_mv: Option<f64>,
+    pub water_column_m: Option<f64>,
+    pub depth_below_surface_m: Option<f64>,
+    pub water_level_masl: Option<f64>,
     pub temp_c: Option<f64>,
 }
 
 #[derive(ToSchema, Serialize, Deserialize)]
-pub struct SensorProfileRedox {
+pub struct SensorProfileGroundwater {
     pub id: Uuid,
     pub name: String,
     pub geom: HashMap<i32, Geometry>,
-    pub redox_data: Vec<RedoxDataPoint>,
+    pub groundwater_data: Vec<GroundwaterDataPoint>,
 }
@@ -1,6 +1,11 @@
+use crate::common::errors::db_error_response;
 use crate::common::geometry::Geometry;
 use crate::routes::private::sensors::flux_data::db as FluxDB;
-use crate::routes::private::sensors::redox_data::db as RedoxDB;
+use crate::routes::private::sensors::groundwater_data::db as GroundwaterDB;
+use crate::routes::private::sensors::redox_data::{
+    eh::RedoxScale,
+    models::{RedoxResolution, load_redox_series},
+};
 use crate::routes::public::website_access::{check_sensor_access, validate_slug};
 use axum::{
     Json,
@@ -21,6 +26,12 @@ pub struct SensorQueryParams {
     pub website: Option<String>,
     pub start: Option<DateTime<Utc>>,
     pub end: Option<DateTime<Utc>>,
+    /// Flux only: keep QC-flagged values and mark them instead of dropping them
+    pub include_flagged: Option<bool>,
+    /// Redox only: return raw potentials or Eh against the standard hydrogen electrode
+    pub scale: Option<RedoxScale>,
+    /// Redox only: normalise Eh from this soil pH to pH 7
+    pub ph: Option<f64>,
 }
 
 #[derive(Serialize, Debug, Clone, ToSchema)]
@@ -40,8 +51,10 @@ pub fn router(db: &DatabaseConnection) -> OpenApiRouter {
     OpenApiRouter::new()
         .routes(routes!(get_one_temperature))
         .routes(routes!(get_one_moisture))
+        .routes(routes!(get_one_channel))
         .routes(routes!(get_one_flux))
         .routes(routes!(get_one_redox))
+        .routes(routes!(get_one_groundwater))
         .routes(routes!(get_soil_types))
         .with_state(db.clone())
 }
@@ -214,6 +227,100 @@ pub async fn get_one_moisture(
     Ok((StatusCode::OK, Json(response)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{id}/channels/{channel}",
+    responses(
+        (status = 200, description = "Sensor profile found.", body = crate::routes::public::sensors::models::SensorProfile),
+        (status = 404, description = "Sensor profile or channel not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    summary = "Get sensor - channel data (public)",
+    description = "Returns the sensor and the data of a channel registered on its sensor type (e.g. HOBO or METER loggers).",
+    operation_id = "get_one_sensor_profile_channel_public",
+)]
+pub async fn get_one_channel(
+    State(db): State<DatabaseConnection>,
+    Path((id, channel)): Path<(Uuid, String)>,
+    Query(params): Query<SensorQueryParams>,
+) -> impl IntoResponse {
+    let Some(website_slug) = params.website else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json("Missing required query parameter: 'website'".to_string()),
+        ));
+    };
+
+    if !validate_slug(&website_slug) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json("Invalid website slug".to_string()),
+        ));
+    }
+
+    let (profile_model, website_from, website_to) =
+        match check_sensor_access(&db, id, &website_slug).await {
+            Ok(Some(result)) => result,
+            Ok(None) => {
+                return Err((
+                    StatusCode::NOT_FOUND,
+                    Json("Sensor profile not found".to_string()),
+                ))
+            }
+            Err(_) => {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json("Internal server error".to_string()),
+                ))
+            }
+        };
+
+    let profile: crate::routes::private::sensors::profile::models::SensorProfile =
+        profile_model.into();
+
+    let units = match crate::routes::private::sensors::profile::models::SensorProfile::channel_units(
+        &db, id, &channel,
+    )
+    .await
+    {
+        Ok(Some(units)) => units,
+        Ok(None) => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json("Channel not found".to_string()),
+            ))
+        }
+        Err(_) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json("Internal server error".to_string()),
+            ))
+        }
+    };
+
+    let (date_from, date_to, span_days) = effective_date_range(
+        &db, id, website_from, website_to, params.start, params.end,
+    )
+    .await;
+
+    let (resolution, _) = resolution_for_span(span_days);
+    let aggregate_table =
+        crate::routes::private::sensors::profile::models::SensorProfile::channel_aggregate_table(
+            resolution,
+        );
+
+    let depth_data = profile
+        .load_channel_series_by_depth_cm(&db, &channel, aggregate_table, date_from, date_to)
+        .await
+        .unwrap_or_default();
+
+    let response = super::models::SensorProfile::from_depth_map(
+        profile.id, &profile.name, resolution, &units, depth_data,
+    );
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
 #[utoipa::path(
     get,
     path = "/{id}/flux",
@@ -222,8 +329,13 @@ pub async fn get_one_moisture(
         (status = 404, description = "Sensor profile not found"),
         (status = 500, description = "Internal server error")
     ),
+    params(
+        ("id" = Uuid, description = "Sensor profile ID"),
+        ("website" = String, Query, description = "Website slug"),
+        ("include_flagged" = Option<bool>, Query, description = "Keep QC-flagged flux values and mark them (default: drop them)")
+    ),
     summary = "Get sensor - flux data (public)",
-    description = "Returns the sensor profile and its gas flux time series data.",
+    description = "Returns the sensor profile and its gas flux time series data. Flux values flagged by quality control are omitted, and measurements without any remaining value are dropped, unless `include_flagged` is set.",
     operation_id = "get_one_sensor_profile_flux_public",
 )]
 pub async fn get_one_flux(
@@ -286,19 +398,38 @@ pub async fn get_one_flux(
         .await
         .unwrap_or_default();
 
+    let include_flagged = params.include_flagged.unwrap_or(false);
+    let unless_flagged = |value: Option<f64>, flagged: Option<bool>| {
+        if include_flagged || flagged != Some(true) {
+            value
+        } else {
+            None
+        }
+    };
     let flux_data: Vec<super::models::FluxDataPoint> = flux_records
         .into_iter()
-        .map(|r| super::models::FluxDataPoint {
-            measured_on: r.measured_on,
-            replicate: r.replicate,
-            setting: r.setting,
-            flux_co2_umol_m2_s: r.flux_co2_umol_m2_s,
-            flux_ch4_nmol_m2_s: r.flux_ch4_nmol_m2_s,
-            flux_h2o_umol_m2_s: r.flux_h2o_umol_m2_s,
-            r2_co2: r.r2_co2,
-            r2_ch4: r.r2_ch4,
-            r2_h2o: r.r2_h2o,
-            swc: r.swc,
+        .filter_map(|r| {
+            let point = super::models::FluxDataPoint {
+                measured_on: r.measured_on,
+                replicate: r.replicate,
+                setting: r.setting,
+                flux_co2_umol_m2_s: unless_flagged(r.flux_co2_umol_m2_s, r.qc_flagged_co2),
+                flux_ch4_nmol_m2_s: unless_flagged(r.flux_ch4_nmol_m2_s, r.qc_flagged_ch4),
+                flux_h2o_umol_m2_s: unless_flagged(r.flux_h2o_umol_m2_s, r.qc_flagged_h2o),
+                r2_co2: unless_flagged(r.r2_co2, r.qc_flagged_co2),
+                r2_ch4: unless_flagged(r.r2_ch4, r.qc_flagged_ch4),
+                r2_h2o: unless_flagged(r.r2_h2o, r.qc_flagged_h2o),
+                swc: r.swc,
+                qc_flagged_co2: r.qc_flagged_co2.filter(|_| include_flagged),
+                qc_flagged_ch4: r.qc_flagged_ch4.filter(|_| include_flagged),
+                qc_flagged_h2o: r.qc_flagged_h2o.filter(|_| include_flagged),
+            };
+            let any_flagged = [r.qc_flagged_co2, r.qc_flagged_ch4, r.qc_flagged_h2o]
+                .contains(&Some(true));
+            let all_dropped = point.flux_co2_umol_m2_s.is_none()
+                && point.flux_ch4_nmol_m2_s.is_none()
+                && point.flux_h2o_umol_m2_s.is_none();
+            (!(any_flagged && all_dropped)).then_some(point)
         })
         .collect();
 
@@ -318,10 +449,11 @@ pub async fn get_one_flux(
     responses(
         (status = 200, description = "Sensor profile with redox data.", body = super::models::SensorProfileRedox),
         (status = 404, description = "Sensor profile not found"),
+        (status = 422, description = "Eh requested without a reference electrode or with an invalid pH"),
         (status = 500, description = "Internal server error")
     ),
     summary = "Get sensor - redox data (public)",
-    description = "Returns the sensor profile and its redox potential time series data.",
+    description = "Returns the sensor profile, its electrode channels and the redox potential time series grouped by electrode depth in cm. Channels at the same depth are averaged. The resolution (raw, hourly or daily) is chosen from the date range like for the temperature series. With `scale=she` potentials are converted to Eh against the standard hydrogen electrode using the profile's reference electrode and the logger temperature; `ph` additionally normalises Eh to pH 7.",
     operation_id = "get_one_sensor_profile_redox_public",
 )]
 pub async fn get_one_redox(
@@ -368,39 +500,128 @@ pub async fn get_one_redox(
     }
     .to_hashmap(vec![4326]);
 
-    let mut redox_query = RedoxDB::Entity::find()
-        .filter(RedoxDB::Column::SensorprofileId.eq(id));
+    let (date_from, date_to, span_days) =
+        effective_date_range(&db, id, date_from, date_to, params.start, params.end).await;
+    let resolution = RedoxResolution::for_span_days(span_days);
+    let scale = params.scale.unwrap_or_default();
+    let series = load_redox_series(&db, id, date_from, date_to, resolution, scale, params.ph)
+        .await
+        .map_err(db_error_response)?;
+
+    let response = super::models::SensorProfileRedox {
+        id: profile.id,
+        name: profile.name,
+        geom,
+        channels: series.channels,
+        reference_electrode: series.reference_electrode,
+        scale: series.scale,
+        ph: series.ph,
+        resolution: series.resolution,
+        redox_mv_by_depth_cm: series.redox_mv_by_depth_cm,
+        temperature_c: series.temperature_c,
+    };
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/groundwater",
+    responses(
+        (status = 200, description = "Sensor profile with groundwater level data.", body = super::models::SensorProfileGroundwater),
+        (status = 404, description = "Sensor profile not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    summary = "Get sensor - groundwater level data (public)",
+    description = "Returns the groundwater profile and its barometrically compensated water level time series.",
+    operation_id = "get_one_sensor_profile_groundwater_public",
+)]
+pub async fn get_one_groundwater(
+    State(db): State<DatabaseConnection>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<SensorQueryParams>,
+) -> impl IntoResponse {
+    let Some(website_slug) = params.website else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json("Missing required query parameter: 'website'".to_string()),
+        ));
+    };
+
+    if !validate_slug(&website_slug) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json("Invalid website slug".to_string()),
+        ));
+    }
+
+    let (profile, website_from, website_to) =
+        match check_sensor_access(&db, id, &website_slug).await {
+            Ok(Some(result)) => result,
+            Ok(None) => {
+                return Err((
+                    StatusCode::NOT_FOUND,
+                    Json("Sensor profile not found".to_string()),
+                ))
+            }
+            Err(_) => {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json("Internal server error".to_string()),
+                ))
+            }
+        };
+
+    let geom = Geometry {
+        srid: profile.coord_srid.unwrap_or_default(),
+        x: profile.coord_x.unwrap_or_default(),
+        y: profile.coord_y.unwrap_or_default(),
+        z: profile.coord_z.unwrap_or_default(),
+    }
+    .to_hashmap(vec![4326]);
+
+    // Requested range, clamped to the website's visibility window
+    let date_from = match (website_from, params.start) {
+        (Some(w), Some(s)) => Some(w.max(s)),
+        (w, s) => w.or(s),
+    };
+    let date_to = match (website_to, params.end) {
+        (Some(w), Some(e)) => Some(w.min(e)),
+        (w, e) => w.or(e),
+    };
+
+    let mut query = GroundwaterDB::Entity::find()
+        .filter(GroundwaterDB::Column::SensorprofileId.eq(id));
 
     if let Some(df) = date_from {
-        redox_query = redox_query.filter(RedoxDB::Column::MeasuredOn.gte(df));
+        query = query.filter(GroundwaterDB::Column::MeasuredOn.gte(df));
     }
     if let Some(dt) = date_to {
-        redox_query = redox_query.filter(RedoxDB::Column::MeasuredOn.lte(dt));
+        query = query.filter(GroundwaterDB::Column::MeasuredOn.lte(dt));
     }
 
-    let redox_records = redox_query
-        .order_by_asc(RedoxDB::Column::MeasuredOn)
+    let records = query
+        .order_by_asc(GroundwaterDB::Column::MeasuredOn)
         .all(&db)
         .await
         .unwrap_or_default();
 
-    let redox_data: Vec<super::models::RedoxDataPoint> = redox_records
+    let groundwater_data: Vec<super::models::GroundwaterDataPoint> = records
         .into_iter()
-        .map(|r| super::models::RedoxDataPoint {
+        .map(|r| super::models::GroundwaterDataPoint {
             measured_on: r.measured_on,
-            ch1_5cm_mv: r.ch1_5cm_mv,
-            ch2_15cm_mv: r.ch2_15cm_mv,
-            ch3_25cm_mv: r.ch3_25cm_mv,
-            ch4_35cm_mv: r.ch4_35cm_mv,
+            water_column_m: r.water_column_m,
+            depth_below_surface_m: r.depth_below_surface_m,
+            water_level_masl: r.water_level_masl,
             temp_c: r.temp_c,
         })
         .collect();
 
-    let response = super::models::SensorProfileRedox {
+    let response = super::models::SensorProfileGroundwater {
         id: profile.id,
         name: profile.name,
         geom,
-        redox_data,
+        groundwater_data,
     };
 
     Ok((StatusCode::OK, Json(response)))